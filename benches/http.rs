@@ -59,6 +59,7 @@ fn benchmark_http(c: &mut Criterion) {
                                 encoding: (None::<FramingConfig>, TextSerializerConfig::default())
                                     .into(),
                                 request: Default::default(),
+                                load_shedding: Default::default(),
                                 tls: Default::default(),
                                 acknowledgements: Default::default(),
                             },
@@ -92,12 +92,34 @@ where
         HttpClient::new_with_custom_client(tls_settings, proxy_config, &mut Client::builder())
     }
 
+    /// Creates a client which gives up on establishing a connection after `connect_timeout`,
+    /// before any TLS handshake, regardless of how long requests are otherwise allowed to take.
+    pub fn new_with_connect_timeout(
+        tls_settings: impl Into<MaybeTlsSettings>,
+        proxy_config: &ProxyConfig,
+        connect_timeout: Option<Duration>,
+    ) -> Result<HttpClient<B>, HttpError> {
+        let proxy_connector =
+            build_proxy_connector_with_timeout(tls_settings.into(), proxy_config, connect_timeout)?;
+        Ok(HttpClient::from_connector(
+            proxy_connector,
+            &mut Client::builder(),
+        ))
+    }
+
     pub fn new_with_custom_client(
         tls_settings: impl Into<MaybeTlsSettings>,
         proxy_config: &ProxyConfig,
         client_builder: &mut client::Builder,
     ) -> Result<HttpClient<B>, HttpError> {
         let proxy_connector = build_proxy_connector(tls_settings.into(), proxy_config)?;
+        Ok(HttpClient::from_connector(proxy_connector, client_builder))
+    }
+
+    fn from_connector(
+        proxy_connector: HttpProxyConnector,
+        client_builder: &mut client::Builder,
+    ) -> HttpClient<B> {
         let client = client_builder.build(proxy_connector.clone());
 
         let app_name = crate::get_app_name();
@@ -105,11 +127,11 @@ where
         let user_agent = HeaderValue::from_str(&format!("{}/{}", app_name, version))
             .expect("Invalid header value for user-agent!");
 
-        Ok(HttpClient {
+        HttpClient {
             client,
             user_agent,
             proxy_connector,
-        })
+        }
     }
 
     pub fn send(
@@ -177,12 +199,20 @@ where
 pub fn build_proxy_connector(
     tls_settings: MaybeTlsSettings,
     proxy_config: &ProxyConfig,
+) -> Result<ProxyConnector<HttpsConnector<HttpConnector>>, HttpError> {
+    build_proxy_connector_with_timeout(tls_settings, proxy_config, None)
+}
+
+fn build_proxy_connector_with_timeout(
+    tls_settings: MaybeTlsSettings,
+    proxy_config: &ProxyConfig,
+    connect_timeout: Option<Duration>,
 ) -> Result<ProxyConnector<HttpsConnector<HttpConnector>>, HttpError> {
     // Create dedicated TLS connector for the proxied connection with user TLS settings.
     let tls = tls_connector_builder(&tls_settings)
         .context(BuildTlsConnectorSnafu)?
         .build();
-    let https = build_tls_connector(tls_settings)?;
+    let https = build_tls_connector_with_timeout(tls_settings, connect_timeout)?;
     let mut proxy = ProxyConnector::new(https).unwrap();
     // Make proxy connector aware of user TLS settings by setting the TLS connector:
     // https://github.com/vectordotdev/vector/issues/13683
@@ -195,9 +225,17 @@ pub fn build_proxy_connector(
 
 pub fn build_tls_connector(
     tls_settings: MaybeTlsSettings,
+) -> Result<HttpsConnector<HttpConnector>, HttpError> {
+    build_tls_connector_with_timeout(tls_settings, None)
+}
+
+fn build_tls_connector_with_timeout(
+    tls_settings: MaybeTlsSettings,
+    connect_timeout: Option<Duration>,
 ) -> Result<HttpsConnector<HttpConnector>, HttpError> {
     let mut http = HttpConnector::new();
     http.enforce_http(false);
+    http.set_connect_timeout(connect_timeout);
 
     let tls = tls_connector_builder(&tls_settings).context(BuildTlsConnectorSnafu)?;
     let mut https = HttpsConnector::with_connector(http, tls).context(MakeHttpsConnectorSnafu)?;
@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use metrics::{register_histogram, Histogram};
+use metrics::{register_histogram, Histogram, Label};
 
 #[derive(Clone, Copy)]
 pub struct AdaptiveConcurrencyLimitData {
@@ -12,15 +12,38 @@ pub struct AdaptiveConcurrencyLimitData {
     pub past_rtt_deviation: Duration,
 }
 
+/// The labels of the adaptive concurrency metrics of a controller, which are tagged by endpoint when
+/// the controller limits a single endpoint of a distributed service.
+fn endpoint_labels(endpoint: &Option<String>) -> Vec<Label> {
+    endpoint
+        .iter()
+        .map(|endpoint| Label::new("endpoint", endpoint.clone()))
+        .collect()
+}
+
 registered_event! {
-    AdaptiveConcurrencyLimit => {
+    AdaptiveConcurrencyLimit {
+        endpoint: Option<String>,
+    } => {
         // These are histograms, as they may have a number of different
         // values over each reporting interval, and each of those values
         // is valuable for diagnosis.
-        limit: Histogram = register_histogram!("adaptive_concurrency_limit"),
-        reached_limit: Histogram = register_histogram!("adaptive_concurrency_reached_limit"),
-        back_pressure: Histogram = register_histogram!("adaptive_concurrency_back_pressure"),
-        past_rtt_mean: Histogram = register_histogram!("adaptive_concurrency_past_rtt_mean"),
+        limit: Histogram = register_histogram!(
+            "adaptive_concurrency_limit",
+            endpoint_labels(&self.endpoint)
+        ),
+        reached_limit: Histogram = register_histogram!(
+            "adaptive_concurrency_reached_limit",
+            endpoint_labels(&self.endpoint)
+        ),
+        back_pressure: Histogram = register_histogram!(
+            "adaptive_concurrency_back_pressure",
+            endpoint_labels(&self.endpoint)
+        ),
+        past_rtt_mean: Histogram = register_histogram!(
+            "adaptive_concurrency_past_rtt_mean",
+            endpoint_labels(&self.endpoint)
+        ),
     }
 
     fn emit(&self, data: AdaptiveConcurrencyLimitData) {
@@ -35,8 +58,13 @@ registered_event! {
 }
 
 registered_event! {
-    AdaptiveConcurrencyInFlight => {
-        in_flight: Histogram = register_histogram!("adaptive_concurrency_in_flight"),
+    AdaptiveConcurrencyInFlight {
+        endpoint: Option<String>,
+    } => {
+        in_flight: Histogram = register_histogram!(
+            "adaptive_concurrency_in_flight",
+            endpoint_labels(&self.endpoint)
+        ),
     }
 
     fn emit(&self, in_flight: u64) {
@@ -45,8 +73,13 @@ registered_event! {
 }
 
 registered_event! {
-    AdaptiveConcurrencyObservedRtt => {
-        observed_rtt: Histogram = register_histogram!("adaptive_concurrency_observed_rtt"),
+    AdaptiveConcurrencyObservedRtt {
+        endpoint: Option<String>,
+    } => {
+        observed_rtt: Histogram = register_histogram!(
+            "adaptive_concurrency_observed_rtt",
+            endpoint_labels(&self.endpoint)
+        ),
     }
 
     fn emit(&self, rtt: Duration) {
@@ -55,8 +88,13 @@ registered_event! {
 }
 
 registered_event! {
-    AdaptiveConcurrencyAveragedRtt => {
-        averaged_rtt: Histogram = register_histogram!("adaptive_concurrency_averaged_rtt"),
+    AdaptiveConcurrencyAveragedRtt {
+        endpoint: Option<String>,
+    } => {
+        averaged_rtt: Histogram = register_histogram!(
+            "adaptive_concurrency_averaged_rtt",
+            endpoint_labels(&self.endpoint)
+        ),
     }
 
     fn emit(&self, rtt: Duration) {
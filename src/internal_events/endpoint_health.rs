@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use metrics::{register_counter, Counter};
+
+/// The health state of an endpoint, as tracked by its circuit breaker.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EndpointHealthState {
+    /// The endpoint is healthy and receiving requests.
+    Healthy,
+    /// The endpoint was unhealthy and is being tested with a single request.
+    Probation,
+    /// The endpoint is unhealthy and not receiving requests.
+    Unhealthy,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EndpointHealthStateDwell {
+    pub state: EndpointHealthState,
+    pub duration: Duration,
+}
+
+registered_event! {
+    EndpointHealthStateDuration {
+        endpoint: String,
+    } => {
+        healthy: Counter = register_counter!(
+            "endpoint_health_state_duration_milliseconds_total",
+            "endpoint" => self.endpoint.clone(),
+            "state" => "healthy"
+        ),
+        probation: Counter = register_counter!(
+            "endpoint_health_state_duration_milliseconds_total",
+            "endpoint" => self.endpoint.clone(),
+            "state" => "probation"
+        ),
+        unhealthy: Counter = register_counter!(
+            "endpoint_health_state_duration_milliseconds_total",
+            "endpoint" => self.endpoint.clone(),
+            "state" => "unhealthy"
+        ),
+    }
+
+    fn emit(&self, data: EndpointHealthStateDwell) {
+        let counter = match data.state {
+            EndpointHealthState::Healthy => &self.healthy,
+            EndpointHealthState::Probation => &self.probation,
+            EndpointHealthState::Unhealthy => &self.unhealthy,
+        };
+        counter.increment(data.duration.as_millis() as u64);
+    }
+}
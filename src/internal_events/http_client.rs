@@ -85,6 +85,24 @@ impl<'a> InternalEvent for GotHttpWarning<'a> {
     }
 }
 
+#[derive(Debug)]
+pub struct HttpResponseBodyTooLarge {
+    pub limit: usize,
+}
+
+impl InternalEvent for HttpResponseBodyTooLarge {
+    fn emit(self) {
+        warn!(
+            message = "HTTP response body exceeds the size limit; discarding it.",
+            limit = self.limit,
+            error_type = error_type::REQUEST_FAILED,
+            stage = error_stage::PROCESSING,
+            internal_log_rate_limit = true,
+        );
+        counter!("http_client_response_body_too_large_total", 1);
+    }
+}
+
 /// Newtype placeholder to provide a formatter for the request and response body.
 struct FormatBody<'a, B>(&'a B);
 
@@ -0,0 +1,19 @@
+use metrics::counter;
+use vector_lib::internal_event::InternalEvent;
+
+#[derive(Debug)]
+pub struct RequestShed {
+    /// The load shedding policy which dropped the request.
+    pub policy: &'static str,
+}
+
+impl InternalEvent for RequestShed {
+    fn emit(self) {
+        debug!(
+            message = "Request dropped to shed load.",
+            policy = self.policy,
+            internal_log_rate_limit = true,
+        );
+        counter!("requests_shed_total", 1, "policy" => self.policy);
+    }
+}
@@ -43,6 +43,7 @@ mod dnstap;
 #[cfg(feature = "sources-docker_logs")]
 mod docker_logs;
 mod encoding_transcode;
+mod endpoint_health;
 #[cfg(feature = "sources-eventstoredb_metrics")]
 mod eventstoredb_metrics;
 #[cfg(feature = "sources-exec")]
@@ -76,6 +77,7 @@ mod journald;
 mod kafka;
 #[cfg(feature = "sources-kubernetes_logs")]
 mod kubernetes_logs;
+mod load_shedding;
 #[cfg(feature = "transforms-log_to_metric")]
 mod log_to_metric;
 mod logplex;
@@ -109,6 +111,7 @@ mod redis;
 #[cfg(feature = "transforms-reduce")]
 mod reduce;
 mod remap;
+mod retry;
 mod sample;
 #[cfg(feature = "sinks-sematext")]
 mod sematext_metrics;
@@ -265,5 +268,6 @@ pub(crate) use self::websocket::*;
 pub(crate) use self::windows::*;
 pub use self::{
     adaptive_concurrency::*, batch::*, common::*, conditions::*, encoding_transcode::*,
-    heartbeat::*, http::*, open::*, process::*, socket::*, tcp::*, template::*, udp::*,
+    endpoint_health::*, heartbeat::*, http::*, load_shedding::*, open::*, process::*, retry::*,
+    socket::*, tcp::*, template::*, udp::*,
 };
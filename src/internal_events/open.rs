@@ -31,6 +31,17 @@ impl InternalEvent for EndpointsActive {
     }
 }
 
+#[derive(Debug)]
+pub struct RequestsInFlight {
+    pub count: usize,
+}
+
+impl InternalEvent for RequestsInFlight {
+    fn emit(self) {
+        gauge!("in_flight_requests", self.count as f64);
+    }
+}
+
 #[derive(Clone)]
 pub struct OpenGauge {
     gauge: Arc<AtomicUsize>,
@@ -53,7 +64,6 @@ impl OpenGauge {
         }
     }
 
-    #[cfg(all(feature = "sources-utils-net-unix", unix))]
     pub fn any_open(&self) -> bool {
         self.gauge.load(Ordering::Acquire) != 0
     }
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use metrics::{counter, histogram};
+use vector_lib::internal_event::InternalEvent;
+
+/// The attempt numbers above which attempts share their `attempt_number` tag, so that requests
+/// retried indefinitely don't add a tag value for each of their attempts.
+const MAX_ATTEMPT_NUMBER_TAG: usize = 10;
+
+fn attempt_number_tag(attempt: usize) -> String {
+    if attempt >= MAX_ATTEMPT_NUMBER_TAG {
+        format!("{MAX_ATTEMPT_NUMBER_TAG}+")
+    } else {
+        attempt.to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestRetry<'a> {
+    /// The number of the attempt about to be made, counting the original request as the first.
+    pub attempt: usize,
+    pub backoff: Duration,
+    /// The kind of failure being retried: `error`, `response` or `timeout`.
+    pub error_kind: &'static str,
+    pub reason: &'a str,
+}
+
+impl InternalEvent for RequestRetry<'_> {
+    fn emit(self) {
+        let delay_ms = self.backoff.as_millis() as u64;
+        match self.error_kind {
+            "response" => warn!(
+                message = "Retrying after response.",
+                attempt = self.attempt,
+                delay_ms,
+                reason = %self.reason,
+                internal_log_rate_limit = true,
+            ),
+            "timeout" => warn!(
+                message = "Request timed out. If this happens often while the events are actually reaching their destination, try decreasing `batch.max_bytes` and/or using `compression` if applicable. Alternatively `request.timeout_secs` can be increased.",
+                attempt = self.attempt,
+                delay_ms,
+                internal_log_rate_limit = true,
+            ),
+            _ => warn!(
+                message = "Retrying after error.",
+                attempt = self.attempt,
+                delay_ms,
+                error = %self.reason,
+                internal_log_rate_limit = true,
+            ),
+        }
+        counter!(
+            "request_retries_total", 1,
+            "attempt_number" => attempt_number_tag(self.attempt),
+        );
+        histogram!("retry_backoff_duration_seconds", self.backoff.as_secs_f64());
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestRetrySucceeded {
+    /// The number of attempts made, counting the original request as the first.
+    pub attempts: usize,
+    /// The total amount of time spent waiting between attempts.
+    pub total_backoff: Duration,
+}
+
+impl InternalEvent for RequestRetrySucceeded {
+    fn emit(self) {
+        info!(
+            message = "Request succeeded after retrying.",
+            attempts = self.attempts,
+            total_backoff_ms = self.total_backoff.as_millis() as u64,
+            internal_log_rate_limit = true,
+        );
+        counter!("request_retry_successes_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestRetriesRemaining {
+    /// The number of times the request may still be retried after a failure.
+    pub remaining_attempts: usize,
+}
+
+impl InternalEvent for RequestRetriesRemaining {
+    fn emit(self) {
+        histogram!("request_retries_remaining", self.remaining_attempts as f64);
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestRetriesExhausted {
+    /// The number of attempts made, counting the original request as the first.
+    pub attempts: usize,
+}
+
+impl InternalEvent for RequestRetriesExhausted {
+    fn emit(self) {
+        trace!(
+            message = "Request retries exhausted.",
+            attempts = self.attempts
+        );
+        counter!("retry_exhausted_total", 1);
+    }
+}
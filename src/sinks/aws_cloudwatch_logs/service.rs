@@ -25,11 +25,8 @@ use indexmap::IndexMap;
 use snafu::{ResultExt, Snafu};
 use tokio::sync::oneshot;
 use tower::{
-    buffer::Buffer,
-    limit::{ConcurrencyLimit, RateLimit},
-    retry::Retry,
-    timeout::Timeout,
-    Service, ServiceBuilder, ServiceExt,
+    buffer::Buffer, limit::ConcurrencyLimit, retry::Retry, timeout::Timeout, Service,
+    ServiceBuilder, ServiceExt,
 };
 use vector_lib::stream::DriverResponse;
 use vector_lib::{
@@ -42,12 +39,14 @@ use crate::sinks::{
         config::CloudwatchLogsSinkConfig, config::Retention, request, retry::CloudwatchRetryLogic,
         sink::BatchCloudwatchRequest, CloudwatchKey,
     },
-    util::{retries::FibonacciRetryPolicy, EncodedLength, TowerRequestSettings},
+    util::{
+        retries::FibonacciRetryPolicy, service::MaybeRateLimit, EncodedLength, TowerRequestSettings,
+    },
 };
 
 type Svc = Buffer<
     ConcurrencyLimit<
-        RateLimit<
+        MaybeRateLimit<
             Retry<
                 FibonacciRetryPolicy<CloudwatchRetryLogic<()>>,
                 Buffer<Timeout<CloudwatchLogsSvc>, Vec<InputLogEvent>>,
@@ -140,7 +139,7 @@ impl CloudwatchLogsPartitionSvc {
         config: CloudwatchLogsSinkConfig,
         client: CloudwatchLogsClient,
     ) -> crate::Result<Self> {
-        let request_settings = config.request.tower.into_settings();
+        let request_settings = config.request.tower.try_into_settings()?;
 
         let headers = config
             .request
@@ -197,10 +196,7 @@ impl Service<BatchCloudwatchRequest> for CloudwatchLogsPartitionSvc {
             let svc = ServiceBuilder::new()
                 .buffer(1)
                 .concurrency_limit(1)
-                .rate_limit(
-                    self.request_settings.rate_limit_num,
-                    self.request_settings.rate_limit_duration,
-                )
+                .option_layer(self.request_settings.rate_limit_layer())
                 .retry(
                     self.request_settings
                         .retry_policy(CloudwatchRetryLogic::new()),
@@ -35,7 +35,7 @@ use crate::{
     tls::TlsConfig,
 };
 
-use super::util::service::TowerRequestConfigDefaults;
+use super::util::service::{RateLimitNum, TowerRequestConfigDefaults};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct CloudWatchMetricsDefaultBatchSettings;
@@ -50,7 +50,7 @@ impl SinkBatchSettings for CloudWatchMetricsDefaultBatchSettings {
 pub struct CloudWatchMetricsTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for CloudWatchMetricsTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 150;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(150);
 }
 
 /// Configuration for the `aws_cloudwatch_metrics` sink.
@@ -226,7 +226,7 @@ impl CloudWatchMetricsSvc {
     ) -> crate::Result<VectorSink> {
         let default_namespace = config.default_namespace.clone();
         let batch = config.batch.into_batch_settings()?;
-        let request_settings = config.request.into_settings();
+        let request_settings = config.request.try_into_settings()?;
 
         let service = CloudWatchMetricsSvc { client };
         let buffer = PartitionBuffer::new(MetricsBuffer::new(batch.size));
@@ -54,8 +54,8 @@ async fn firehose_put_records_without_partition_key() {
         encoding: JsonSerializerConfig::default().into(), // required for ES destination w/ localstack
         compression: Compression::None,
         request: TowerRequestConfig {
-            timeout_secs: 10,
-            retry_attempts: 0,
+            timeout_secs: Some(10),
+            retry_attempts: Some(0),
             ..Default::default()
         },
         tls: None,
@@ -155,8 +155,8 @@ async fn firehose_put_records_with_partition_key() {
         encoding: JsonSerializerConfig::default().into(), // required for ES destination w/ localstack
         compression: Compression::None,
         request: TowerRequestConfig {
-            timeout_secs: 10,
-            retry_attempts: 0,
+            timeout_secs: Some(10),
+            retry_attempts: Some(0),
             ..Default::default()
         },
         tls: None,
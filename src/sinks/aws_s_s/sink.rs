@@ -1,6 +1,6 @@
 use super::{client::Client, request_builder::SSRequestBuilder, service::SSService};
 use crate::sinks::aws_s_s::retry::SSRetryLogic;
-use crate::sinks::prelude::*;
+use crate::sinks::{prelude::*, util::TowerRequestSettings};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct SqsSinkDefaultBatchSettings;
@@ -19,7 +19,7 @@ where
 {
     request_builder: SSRequestBuilder,
     service: SSService<C, E>,
-    request: TowerRequestConfig,
+    request: TowerRequestSettings,
 }
 
 impl<C, E> SSSink<C, E>
@@ -35,12 +35,12 @@ where
         Ok(SSSink {
             request_builder,
             service: SSService::new(publisher),
-            request,
+            request: request.try_into_settings()?,
         })
     }
 
     async fn run_inner(self: Box<Self>, input: BoxStream<'_, Event>) -> Result<(), ()> {
-        let request = self.request.into_settings();
+        let request = self.request.clone();
         let retry_logic: SSRetryLogic<E> = super::retry::SSRetryLogic::new();
         let service = tower::ServiceBuilder::new()
             .settings(request, retry_logic)
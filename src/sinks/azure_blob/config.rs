@@ -7,7 +7,7 @@ use vector_lib::configurable::configurable_component;
 use vector_lib::sensitive_string::SensitiveString;
 
 use super::request_builder::AzureBlobRequestOptions;
-use crate::sinks::util::service::TowerRequestConfigDefaults;
+use crate::sinks::util::service::{RateLimitNum, TowerRequestConfigDefaults};
 use crate::{
     codecs::{Encoder, EncodingConfigWithFraming, SinkType},
     config::{AcknowledgementsConfig, DataType, GenerateConfig, Input, SinkConfig, SinkContext},
@@ -29,7 +29,7 @@ use crate::{
 pub struct AzureBlobTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for AzureBlobTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 250;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(250);
 }
 
 /// Configuration for the `azure_blob` sink.
@@ -210,7 +210,7 @@ const DEFAULT_FILENAME_APPEND_UUID: bool = true;
 
 impl AzureBlobSinkConfig {
     pub fn build_processor(&self, client: Arc<ContainerClient>) -> crate::Result<VectorSink> {
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
         let service = ServiceBuilder::new()
             .settings(request_limits, AzureBlobRetryLogic)
             .service(AzureBlobService::new(client));
@@ -182,7 +182,7 @@ impl AzureMonitorLogsConfig {
 
         let retry_logic =
             HttpStatusRetryLogic::new(|res: &AzureMonitorLogsResponse| res.http_status);
-        let request_settings = self.request.into_settings();
+        let request_settings = self.request.try_into_settings()?;
         let service = ServiceBuilder::new()
             .settings(request_settings, retry_logic)
             .service(service);
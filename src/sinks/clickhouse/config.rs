@@ -135,7 +135,7 @@ impl SinkConfig for ClickhouseConfig {
         let service: HttpService<ClickhouseServiceRequestBuilder, PartitionKey> =
             HttpService::new(client.clone(), clickhouse_service_request_builder);
 
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
 
         let service = ServiceBuilder::new()
             .settings(request_limits, ClickhouseRetryLogic::default())
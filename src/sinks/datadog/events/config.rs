@@ -75,7 +75,7 @@ impl DatadogEventsConfig {
         );
 
         let request_opts = self.request;
-        let request_settings = request_opts.into_settings();
+        let request_settings = request_opts.try_into_settings()?;
         let retry_logic = HttpStatusRetryLogic::new(|req: &DatadogEventsResponse| req.http_status);
 
         let service = ServiceBuilder::new()
@@ -254,7 +254,7 @@ impl DatadogMetricsConfig {
         let batcher_settings = self.batch.into_batcher_settings()?;
 
         // TODO: revisit our concurrency and batching defaults
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
 
         let endpoint_configuration = self.generate_metrics_endpoint_configuration(dd_common)?;
         let service = ServiceBuilder::new()
@@ -133,7 +133,7 @@ impl DatadogTracesConfig {
         client: HttpClient,
     ) -> crate::Result<VectorSink> {
         let default_api_key: Arc<str> = Arc::from(dd_common.default_api_key.inner());
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
         let endpoints = self.generate_traces_endpoint_configuration(dd_common)?;
 
         let batcher_settings = self
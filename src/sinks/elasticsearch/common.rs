@@ -91,7 +91,7 @@ impl ElasticsearchCommon {
 
         let mode = config.common_mode()?;
 
-        let tower_request = config.request.tower.into_settings();
+        let tower_request = config.request.tower.try_into_settings()?;
 
         let mut query_params = config.query.clone().unwrap_or_default();
         query_params.insert(
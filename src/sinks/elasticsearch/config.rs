@@ -22,7 +22,7 @@ use crate::{
             ElasticsearchCommonMode, ElasticsearchMode,
         },
         util::{
-            http::RequestConfig, service::HealthConfig, BatchConfig, Compression,
+            auth::Auth, http::RequestConfig, service::HealthConfig, BatchConfig, Compression,
             RealtimeSizeBasedDefaultBatchSettings,
         },
         Healthcheck, VectorSink,
@@ -479,9 +479,15 @@ impl SinkConfig for ElasticsearchConfig {
 
         let client = HttpClient::new(common.tls_settings.clone(), cx.proxy())?;
 
-        let request_limits = self.request.tower.into_settings();
+        let request_limits = self.request.tower.try_into_settings()?;
 
-        let health_config = self.endpoint_health.clone().unwrap_or_default();
+        let mut health_config = self.endpoint_health.clone().unwrap_or_default();
+        // Probes can't be signed, so they are only sent with basic auth.
+        let probe_auth = match &common.auth {
+            Some(Auth::Basic(auth)) => Some(auth.clone()),
+            _ => None,
+        };
+        health_config.probe_with(client.clone(), probe_auth);
 
         let services = commons
             .iter()
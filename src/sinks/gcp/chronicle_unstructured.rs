@@ -22,7 +22,7 @@ use vector_lib::{
 };
 use vrl::value::Kind;
 
-use crate::sinks::util::service::TowerRequestConfigDefaults;
+use crate::sinks::util::service::{RateLimitNum, TowerRequestConfigDefaults};
 use crate::{
     codecs::{self, EncodingConfig},
     config::{GenerateConfig, SinkConfig, SinkContext},
@@ -102,7 +102,7 @@ impl SinkBatchSettings for ChronicleUnstructuredDefaultBatchSettings {
 pub struct ChronicleUnstructuredTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for ChronicleUnstructuredTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 1_000;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(1_000);
 }
 /// Configuration for the `gcp_chronicle_unstructured` sink.
 #[configurable_component(sink(
@@ -242,7 +242,7 @@ impl ChronicleUnstructuredConfig {
     ) -> crate::Result<VectorSink> {
         use crate::sinks::util::service::ServiceBuilderExt;
 
-        let request = self.request.into_settings();
+        let request = self.request.try_into_settings()?;
 
         let batch_settings = self.batch.into_batcher_settings()?;
 
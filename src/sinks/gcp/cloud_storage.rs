@@ -15,7 +15,7 @@ use vector_lib::event::{EventFinalizers, Finalizable};
 use vector_lib::{request_metadata::RequestMetadata, TimeZone};
 
 use crate::sinks::util::metadata::RequestMetadataBuilder;
-use crate::sinks::util::service::TowerRequestConfigDefaults;
+use crate::sinks::util::service::{RateLimitNum, TowerRequestConfigDefaults};
 use crate::{
     codecs::{Encoder, EncodingConfigWithFraming, SinkType, Transformer},
     config::{AcknowledgementsConfig, DataType, GenerateConfig, Input, SinkConfig, SinkContext},
@@ -53,7 +53,7 @@ pub enum GcsHealthcheckError {
 pub struct GcsTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for GcsTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 1_000;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(1_000);
 }
 
 /// Configuration for the `gcp_cloud_storage` sink.
@@ -253,7 +253,7 @@ impl GcsSinkConfig {
         auth: GcpAuthenticator,
         cx: SinkContext,
     ) -> crate::Result<VectorSink> {
-        let request = self.request.into_settings();
+        let request = self.request.try_into_settings()?;
 
         let batch_settings = self.batch.into_batcher_settings()?;
 
@@ -9,7 +9,7 @@ use crate::{
         prelude::*,
         util::{
             http::{http_response_retry_logic, HttpService},
-            service::TowerRequestConfigDefaults,
+            service::{RateLimitNum, TowerRequestConfigDefaults},
             BoxedRawValue, RealtimeSizeBasedDefaultBatchSettings,
         },
     },
@@ -36,7 +36,7 @@ enum HealthcheckError {
 pub struct StackdriverTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for StackdriverTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 1_000;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(1_000);
 }
 
 /// Configuration for the `gcp_stackdriver_logs` sink.
@@ -219,7 +219,7 @@ impl SinkConfig for StackdriverConfig {
             .limit_max_bytes(MAX_BATCH_PAYLOAD_SIZE)?
             .into_batcher_settings()?;
 
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
 
         let tls_settings = TlsSettings::from_options(&self.tls)?;
         let client = HttpClient::new(tls_settings, cx.proxy())?;
@@ -16,7 +16,7 @@ use crate::{
             http::{
                 http_response_retry_logic, HttpRequest, HttpService, HttpServiceRequestBuilder,
             },
-            service::TowerRequestConfigDefaults,
+            service::{RateLimitNum, TowerRequestConfigDefaults},
         },
         HTTPRequestBuilderSnafu,
     },
@@ -27,7 +27,7 @@ use snafu::ResultExt;
 pub struct StackdriverMetricsTowerRequestConfigDefaults;
 
 impl TowerRequestConfigDefaults for StackdriverMetricsTowerRequestConfigDefaults {
-    const RATE_LIMIT_NUM: u64 = 1_000;
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(1_000);
 }
 
 /// Configuration for the `gcp_stackdriver_metrics` sink.
@@ -111,7 +111,7 @@ impl SinkConfig for StackdriverConfig {
             },
         };
 
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
 
         let uri: Uri = format!(
             "{}/v3/projects/{}/timeSeries",
@@ -116,10 +116,11 @@ impl_generate_config_from_default!(GreptimeDBConfig);
 #[async_trait::async_trait]
 impl SinkConfig for GreptimeDBConfig {
     async fn build(&self, _cx: SinkContext) -> crate::Result<(VectorSink, Healthcheck)> {
-        let request_settings = self.request.into_settings();
-        let service = ServiceBuilder::new()
-            .settings(request_settings, GreptimeDBRetryLogic)
-            .service(service::GreptimeDBService::try_new(self)?);
+        let request_settings = self.request.try_into_settings()?;
+        let service = request_settings.service(
+            GreptimeDBRetryLogic,
+            service::GreptimeDBService::try_new(self)?,
+        );
         let sink = sink::GreptimeDBSink {
             service,
             batch_settings: self.batch.into_batcher_settings()?,
@@ -1,6 +1,9 @@
 //! Configuration for the `http` sink.
 
-use http::{header::AUTHORIZATION, HeaderName, HeaderValue, Method, Request, StatusCode};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{header::AUTHORIZATION, HeaderName, HeaderValue, Method, Request, Response, StatusCode};
 use hyper::Body;
 use indexmap::IndexMap;
 use vector_lib::codecs::{
@@ -15,7 +18,10 @@ use crate::{
     sinks::{
         prelude::*,
         util::{
-            http::{http_response_retry_logic, HttpService, RequestConfig},
+            http::{http_response_retry_logic, HttpResponse, HttpService, RequestConfig},
+            retries::StatusCodeRetryLogic,
+            service::LoadSheddingConfig,
+            status_codes::{StatusCodeRange, StatusCodeRangeError, StatusCodeRanges},
             RealtimeSizeBasedDefaultBatchSettings, UriSerde,
         },
     },
@@ -86,7 +92,10 @@ pub struct HttpSinkConfig {
 
     #[configurable(derived)]
     #[serde(default)]
-    pub request: RequestConfig,
+    pub request: HttpSinkRequestConfig,
+
+    #[configurable(derived)]
+    pub load_shedding: Option<LoadSheddingConfig>,
 
     #[configurable(derived)]
     pub tls: Option<TlsConfig>,
@@ -100,6 +109,71 @@ pub struct HttpSinkConfig {
     pub acknowledgements: AcknowledgementsConfig,
 }
 
+/// Outbound HTTP request settings of the `http` sink.
+#[configurable_component]
+#[derive(Clone, Debug, Default)]
+pub struct HttpSinkRequestConfig {
+    #[serde(flatten)]
+    pub http: RequestConfig,
+
+    /// The time to wait for a connection to be established.
+    ///
+    /// This only bounds connecting to the endpoint, before any TLS handshake or data is sent, so
+    /// that unreachable endpoints are given up on sooner than requests which are slow to respond.
+    /// The request as a whole is still bounded by `timeout_secs`, which this must not exceed. If
+    /// not set, connecting is only bounded by `timeout_secs`.
+    #[configurable(metadata(docs::type_unit = "milliseconds"))]
+    #[configurable(metadata(docs::human_name = "Connect Timeout"))]
+    #[configurable(metadata(docs::examples = 500))]
+    pub connect_timeout_ms: Option<u64>,
+
+    /// The maximum size of a response body to accept, in bytes.
+    ///
+    /// Requests whose response body is larger than this fail without being retried, instead of
+    /// buffering the whole response body in memory. If not set, response bodies are not limited.
+    #[configurable(metadata(docs::type_unit = "bytes"))]
+    pub max_response_body_bytes: Option<usize>,
+
+    /// The HTTP status codes of responses to retry, as single status codes such as `429`, or as
+    /// ranges of them such as `"500-599"`.
+    ///
+    /// If set, responses are retried if, and only if, their status code is in this list. Otherwise,
+    /// responses with a `429` or `5xx` status code are retried, except for `501`. Ranges must not
+    /// overlap.
+    #[configurable(metadata(docs::examples = "retry_on_response_codes_examples()"))]
+    pub retry_on_response_codes: Option<Vec<StatusCodeRange>>,
+}
+
+fn retry_on_response_codes_examples() -> Vec<StatusCodeRange> {
+    vec![StatusCodeRange::single(429), "500-599".parse().unwrap()]
+}
+
+impl HttpSinkRequestConfig {
+    /// The timeout of each attempt to connect to the endpoint, if one is configured.
+    ///
+    /// Fails if it exceeds the request timeout.
+    fn connect_timeout(&self) -> crate::Result<Option<Duration>> {
+        match self.connect_timeout_ms {
+            Some(ms) if Duration::from_millis(ms) > self.http.tower.timeout() => {
+                Err("`request.connect_timeout_ms` must not exceed `request.timeout_secs`".into())
+            }
+            connect_timeout_ms => Ok(connect_timeout_ms.map(Duration::from_millis)),
+        }
+    }
+
+    /// Wraps `logic` so that it retries the responses listed in `retry_on_response_codes`, if
+    /// set.
+    ///
+    /// Fails if the ranges of `retry_on_response_codes` overlap.
+    fn retry_logic<L>(&self, logic: L) -> Result<StatusCodeRetryLogic<L>, StatusCodeRangeError> {
+        let ranges = match &self.retry_on_response_codes {
+            Some(ranges) => Some(StatusCodeRanges::new(ranges)?),
+            None => None,
+        };
+        Ok(StatusCodeRetryLogic::from_ranges(logic, ranges))
+    }
+}
+
 /// HTTP method.
 ///
 /// A subset of the HTTP methods described in [RFC 9110, section 9.1][rfc9110] are supported.
@@ -154,7 +228,11 @@ impl From<HttpMethod> for Method {
 impl HttpSinkConfig {
     fn build_http_client(&self, cx: &SinkContext) -> crate::Result<HttpClient> {
         let tls = TlsSettings::from_options(&self.tls)?;
-        Ok(HttpClient::new(tls, cx.proxy())?)
+        Ok(HttpClient::new_with_connect_timeout(
+            tls,
+            cx.proxy(),
+            self.request.connect_timeout()?,
+        )?)
     }
 
     pub(super) fn build_encoder(&self) -> crate::Result<Encoder<Framer>> {
@@ -234,7 +312,7 @@ impl SinkConfig for HttpSinkConfig {
         let encoder = self.build_encoder()?;
         let transformer = self.encoding.transformer();
 
-        let mut request = self.request.clone();
+        let mut request = self.request.http.clone();
         request.add_old_option(self.headers.clone());
 
         let headers = validate_headers(&request.headers, self.auth.is_some())?;
@@ -285,12 +363,31 @@ impl SinkConfig for HttpSinkConfig {
             content_encoding,
         );
 
-        let service = HttpService::new(client, http_sink_request_builder);
+        let mut service = HttpService::new(client, http_sink_request_builder);
+        if let Some(limit) = self.request.max_response_body_bytes {
+            service = service.with_max_response_body_bytes(limit);
+        }
+
+        let request_limits = self.request.http.tower.try_into_settings()?;
 
-        let request_limits = self.request.tower.into_settings();
+        // Dropped requests are reported as delivered, without any events or bytes having been sent.
+        let load_shed = self
+            .load_shedding
+            .map(|config| {
+                config.layer(|| HttpResponse {
+                    http_response: Response::new(Bytes::new()),
+                    events_byte_size: GroupedCountByteSize::new_untagged(),
+                    raw_byte_size: 0,
+                })
+            })
+            .transpose()?;
 
         let service = ServiceBuilder::new()
-            .settings(request_limits, http_response_retry_logic())
+            .option_layer(load_shed)
+            .settings(
+                request_limits,
+                self.request.retry_logic(http_response_retry_logic())?,
+            )
             .service(service);
 
         let sink = HttpSink::new(service, batch_settings, request_builder);
@@ -325,7 +422,8 @@ impl ValidatableComponent for HttpSinkConfig {
             headers: None,
             compression: Compression::default(),
             batch: BatchConfig::default(),
-            request: RequestConfig::default(),
+            request: HttpSinkRequestConfig::default(),
+            load_shedding: None,
             tls: None,
             acknowledgements: AcknowledgementsConfig::default(),
             payload_prefix: String::new(),
@@ -58,6 +58,7 @@ fn default_cfg(encoding: EncodingConfigWithFraming) -> HttpSinkConfig {
         payload_suffix: Default::default(),
         batch: Default::default(),
         request: Default::default(),
+        load_shedding: Default::default(),
         tls: Default::default(),
         acknowledgements: Default::default(),
     }
@@ -123,7 +124,7 @@ fn http_validates_normal_headers() {
         "#;
     let config: HttpSinkConfig = toml::from_str(config).unwrap();
 
-    assert!(validate_headers(&config.request.headers, false).is_ok());
+    assert!(validate_headers(&config.request.http.headers, false).is_ok());
 }
 
 #[test]
@@ -137,7 +138,7 @@ fn http_catches_bad_header_names() {
     let config: HttpSinkConfig = toml::from_str(config).unwrap();
 
     assert_downcast_matches!(
-        validate_headers(&config.request.headers, false).unwrap_err(),
+        validate_headers(&config.request.http.headers, false).unwrap_err(),
         HeaderValidationError,
         HeaderValidationError::InvalidHeaderName { .. }
     );
@@ -197,6 +198,43 @@ async fn http_headers_auth_conflict() {
     _ = config.build(cx).await.unwrap();
 }
 
+#[tokio::test]
+async fn http_rejects_invalid_request_settings() {
+    for request in [
+        "request.max_buffered_requests = 0",
+        r#"request.buffer_mode = { type = "bounded", capacity = 0 }"#,
+        "request.max_in_flight_bytes = 0",
+        "request.timeout_secs = 1\nrequest.connect_timeout_ms = 2000",
+        r#"load_shedding.policy = { type = "sample", drop_rate = 0.5 }"#,
+    ] {
+        let config = format!(
+            r#"
+                uri = "http://localhost/"
+                encoding.codec = "text"
+                {request}
+            "#
+        );
+        let config: HttpSinkConfig = toml::from_str(&config).unwrap();
+
+        let error = config.build(SinkContext::default()).await.err();
+        assert!(error.is_some(), "`{request}` was accepted");
+    }
+}
+
+#[tokio::test]
+async fn http_accepts_a_single_retry_attempt() {
+    let config: HttpSinkConfig = toml::from_str(
+        r#"
+            uri = "http://localhost/"
+            encoding.codec = "text"
+            request.retry_attempts = 1
+        "#,
+    )
+    .unwrap();
+
+    config.build(SinkContext::default()).await.unwrap();
+}
+
 #[tokio::test]
 async fn http_happy_path_post() {
     run_sink(
@@ -375,6 +413,33 @@ async fn fails_on_permanent_error() {
     .await;
 }
 
+#[tokio::test]
+async fn sheds_load_when_allowed_to_lose_data() {
+    let num_lines = 1000;
+
+    let (in_addr, sink) = build_sink(
+        r#"
+        load_shedding.allow_data_loss = true
+        load_shedding.policy = { type = "sample", drop_rate = 1.0 }
+    "#,
+    )
+    .await;
+
+    let (rx, trigger, server) = build_test_server(in_addr);
+    tokio::spawn(server);
+
+    let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+    let (_input_lines, events) = random_lines_with_stream(100, num_lines, Some(batch));
+    sink.run(events).await.unwrap();
+    drop(trigger);
+
+    // Dropped requests are acknowledged as if they were delivered.
+    assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+
+    let output_lines = get_received_gzip(rx, |_| unreachable!("There should be no lines")).await;
+    assert!(output_lines.is_empty());
+}
+
 #[tokio::test]
 async fn json_gzip_compression() {
     json_compression("gzip").await;
@@ -168,7 +168,7 @@ impl SinkConfig for InfluxDbLogsConfig {
         let healthcheck = self.healthcheck(client.clone())?;
 
         let batch = self.batch.into_batch_settings()?;
-        let request = self.request.into_settings();
+        let request = self.request.try_into_settings()?;
 
         let settings = influxdb_settings(
             self.influxdb1_settings.clone(),
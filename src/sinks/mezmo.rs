@@ -160,7 +160,7 @@ impl SinkConfig for MezmoConfig {
         &self,
         cx: SinkContext,
     ) -> crate::Result<(super::VectorSink, super::Healthcheck)> {
-        let request_settings = self.request.into_settings();
+        let request_settings = self.request.try_into_settings()?;
         let batch_settings = self.batch.into_batch_settings()?;
         let client = HttpClient::new(None, cx.proxy())?;
 
@@ -3,7 +3,7 @@
 
 use snafu::Snafu;
 
-use crate::nats::NatsConfigError;
+use crate::{nats::NatsConfigError, sinks::util::service::RequestConfigError};
 
 mod config;
 #[cfg(feature = "nats-integration-tests")]
@@ -23,6 +23,8 @@ enum NatsError {
     },
     #[snafu(display("NATS Config Error: {}", source))]
     Config { source: NatsConfigError },
+    #[snafu(display("invalid request settings: {}", source))]
+    Request { source: RequestConfigError },
     #[snafu(display("NATS Connect Error: {}", source))]
     Connect { source: async_nats::ConnectError },
     #[snafu(display("NATS Server Error: {}", source))]
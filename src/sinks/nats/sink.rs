@@ -2,13 +2,13 @@ use std::sync::Arc;
 
 use snafu::ResultExt;
 
-use crate::sinks::prelude::*;
+use crate::sinks::{prelude::*, util::TowerRequestSettings};
 
 use super::{
-    config::{NatsSinkConfig, NatsTowerRequestConfigDefaults},
+    config::NatsSinkConfig,
     request_builder::{NatsEncoder, NatsRequestBuilder},
     service::{NatsResponse, NatsService},
-    EncodingSnafu, NatsError,
+    EncodingSnafu, NatsError, RequestSnafu,
 };
 
 pub(super) struct NatsEvent {
@@ -17,7 +17,7 @@ pub(super) struct NatsEvent {
 }
 
 pub(super) struct NatsSink {
-    request: TowerRequestConfig<NatsTowerRequestConfigDefaults>,
+    request: TowerRequestSettings,
     transformer: Transformer,
     encoder: Encoder<()>,
     connection: Arc<async_nats::Client>,
@@ -46,7 +46,7 @@ impl NatsSink {
         let transformer = config.encoding.transformer();
         let serializer = config.encoding.build().context(EncodingSnafu)?;
         let encoder = Encoder::<()>::new(serializer);
-        let request = config.request;
+        let request = config.request.try_into_settings().context(RequestSnafu)?;
         let subject = config.subject;
 
         Ok(NatsSink {
@@ -59,7 +59,7 @@ impl NatsSink {
     }
 
     async fn run_inner(self: Box<Self>, input: BoxStream<'_, Event>) -> Result<(), ()> {
-        let request = self.request.into_settings();
+        let request = self.request.clone();
 
         let request_builder = NatsRequestBuilder {
             encoder: NatsEncoder {
@@ -68,11 +68,12 @@ impl NatsSink {
             },
         };
 
-        let service = ServiceBuilder::new()
-            .settings(request, NatsRetryLogic)
-            .service(NatsService {
+        let service = request.service(
+            NatsRetryLogic,
+            NatsService {
                 connection: Arc::clone(&self.connection),
-            });
+            },
+        );
 
         input
             .filter_map(|event| std::future::ready(self.make_nats_event(event)))
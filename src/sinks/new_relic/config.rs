@@ -138,7 +138,7 @@ impl SinkConfig for NewRelicConfig {
             .limit_max_events(self.batch.max_events.unwrap_or(100))?
             .into_batcher_settings()?;
 
-        let request_limits = self.request.into_settings();
+        let request_limits = self.request.try_into_settings()?;
         let tls_settings = TlsSettings::from_options(&None)?;
         let client = HttpClient::new(tls_settings, &cx.proxy)?;
         let credentials = Arc::from(NewRelicCredentials::from(self));
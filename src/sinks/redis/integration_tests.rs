@@ -9,7 +9,7 @@ use vector_lib::{
 
 use super::config::{DataTypeConfig, ListOption, Method, RedisSinkConfig};
 use crate::{
-    sinks::prelude::*,
+    sinks::{prelude::*, util::RateLimitNum},
     test_util::{
         components::{
             assert_data_volume_sink_compliance, assert_sink_compliance, DATA_VOLUME_SINK_TAGS,
@@ -44,7 +44,7 @@ async fn redis_sink_list_lpush() {
         }),
         batch: BatchConfig::default(),
         request: TowerRequestConfig {
-            rate_limit_num: u64::MAX,
+            rate_limit_num: Some(RateLimitNum::Unlimited),
             ..Default::default()
         },
         acknowledgements: Default::default(),
@@ -108,7 +108,7 @@ async fn redis_sink_list_rpush() {
         }),
         batch: BatchConfig::default(),
         request: TowerRequestConfig {
-            rate_limit_num: u64::MAX,
+            rate_limit_num: Some(RateLimitNum::Unlimited),
             ..Default::default()
         },
         acknowledgements: Default::default(),
@@ -186,7 +186,7 @@ async fn redis_sink_channel() {
         list_option: None,
         batch: BatchConfig::default(),
         request: TowerRequestConfig {
-            rate_limit_num: u64::MAX,
+            rate_limit_num: Some(RateLimitNum::Unlimited),
             ..Default::default()
         },
         acknowledgements: Default::default(),
@@ -262,7 +262,7 @@ async fn redis_sink_channel_data_volume_tags() {
         list_option: None,
         batch: BatchConfig::default(),
         request: TowerRequestConfig {
-            rate_limit_num: u64::MAX,
+            rate_limit_num: Some(RateLimitNum::Unlimited),
             ..Default::default()
         },
         acknowledgements: Default::default(),
@@ -2,17 +2,20 @@ use std::future;
 
 use redis::{aio::ConnectionManager, RedisError};
 
-use crate::sinks::{prelude::*, util::retries::RetryAction};
+use crate::sinks::{
+    prelude::*,
+    util::{retries::RetryAction, TowerRequestSettings},
+};
 
 use super::{
-    config::{DataTypeConfig, RedisSinkConfig, RedisTowerRequestConfigDefaults},
+    config::{DataTypeConfig, RedisSinkConfig},
     request_builder::request_builder,
     service::{RedisResponse, RedisService},
     RedisEvent,
 };
 
 pub(super) struct RedisSink {
-    request: TowerRequestConfig<RedisTowerRequestConfigDefaults>,
+    request: TowerRequestSettings,
     encoder: crate::codecs::Encoder<()>,
     transformer: crate::codecs::Transformer,
     conn: ConnectionManager,
@@ -34,7 +37,7 @@ impl RedisSink {
         let serializer = config.encoding.build()?;
         let encoder = Encoder::<()>::new(serializer);
         let key = config.key.clone();
-        let request = config.request;
+        let request = config.request.try_into_settings()?;
 
         Ok(RedisSink {
             request,
@@ -67,16 +70,14 @@ impl RedisSink {
     }
 
     async fn run_inner(self: Box<Self>, input: BoxStream<'_, Event>) -> Result<(), ()> {
-        let request = self.request.into_settings();
+        let request = self.request.clone();
 
         let service = RedisService {
             conn: self.conn.clone(),
             data_type: self.data_type,
         };
 
-        let service = ServiceBuilder::new()
-            .settings(request, RedisRetryLogic)
-            .service(service);
+        let service = request.service(RedisRetryLogic, service);
 
         let mut encoder = self.encoder.clone();
         let transformer = self.transformer.clone();
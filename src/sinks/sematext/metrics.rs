@@ -166,7 +166,7 @@ impl SematextMetricsService {
         client: HttpClient,
     ) -> Result<VectorSink> {
         let batch = config.batch.into_batch_settings()?;
-        let request = config.request.into_settings();
+        let request = config.request.try_into_settings()?;
         let http_service = HttpBatchService::new(client, create_build_request(endpoint));
         let sematext_service = SematextMetricsService {
             config,
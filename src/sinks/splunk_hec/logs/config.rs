@@ -240,7 +240,7 @@ impl HecLogsSinkConfig {
             compression: self.compression,
         };
 
-        let request_settings = self.request.into_settings();
+        let request_settings = self.request.try_into_settings()?;
         let http_request_builder = Arc::new(HttpRequestBuilder::new(
             self.endpoint.clone(),
             self.endpoint_target,
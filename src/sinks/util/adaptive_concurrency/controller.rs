@@ -5,10 +5,11 @@ use std::{
 };
 
 use tokio::sync::OwnedSemaphorePermit;
-use tower::timeout::error::Elapsed;
 use vector_lib::internal_event::{InternalEventHandle as _, Registered};
 
-use super::{instant_now, semaphore::ShrinkableSemaphore, AdaptiveConcurrencySettings};
+use super::{
+    instant_now, semaphore::ShrinkableSemaphore, AdaptiveConcurrencySettings, ConcurrencyController,
+};
 #[cfg(test)]
 use crate::test_util::stats::{TimeHistogram, TimeWeightedSum};
 use crate::{
@@ -17,7 +18,10 @@ use crate::{
         AdaptiveConcurrencyAveragedRtt, AdaptiveConcurrencyInFlight, AdaptiveConcurrencyLimit,
         AdaptiveConcurrencyLimitData, AdaptiveConcurrencyObservedRtt,
     },
-    sinks::util::retries::{RetryAction, RetryLogic},
+    sinks::util::{
+        retries::{RetryAction, RetryLogic},
+        service::is_timeout,
+    },
     stats::{EwmaVar, Mean, MeanVariance},
 };
 
@@ -29,6 +33,7 @@ pub(super) struct Controller<L> {
     concurrency: Option<usize>,
     settings: AdaptiveConcurrencySettings,
     logic: L,
+    started: Instant,
     pub(super) inner: Arc<Mutex<Inner>>,
     #[cfg(test)]
     pub(super) stats: Arc<Mutex<ControllerStatistics>>,
@@ -44,10 +49,60 @@ pub(super) struct Inner {
     pub(super) current_limit: usize,
     in_flight: usize,
     past_rtt: EwmaVar,
+    min_rtt: MinRtt,
+    /// The limit set by the timeout AIMD controller, including fractional increases.
+    timeout_aimd_limit: f64,
     next_update: Instant,
     current_rtt: Mean,
     had_back_pressure: bool,
     reached_limit: bool,
+    probe: Option<Probe>,
+}
+
+/// How long the lowest observed RTT is remembered for by the gradient controller.
+const MIN_RTT_WINDOW: Duration = Duration::from_secs(30);
+
+/// The lowest RTT observed over the current and the previous window of [`MIN_RTT_WINDOW`].
+///
+/// Only remembering it for so long lets the gradient controller follow a service whose RTT rose
+/// for good, rather than holding its limit down against an RTT it no longer reaches.
+#[derive(Debug, Default)]
+struct MinRtt {
+    previous: Option<f64>,
+    current: Option<f64>,
+    window_end: Option<Instant>,
+}
+
+impl MinRtt {
+    fn update(&mut self, rtt: f64, now: Instant) {
+        match self.window_end {
+            Some(window_end) if now < window_end => {}
+            _ => {
+                self.previous = self.current.take();
+                self.window_end = Some(now + MIN_RTT_WINDOW);
+            }
+        }
+        self.current = Some(self.current.map_or(rtt, |current| current.min(rtt)));
+    }
+
+    fn get(&self) -> Option<f64> {
+        match (self.previous, self.current) {
+            (Some(previous), Some(current)) => Some(previous.min(current)),
+            (previous, current) => previous.or(current),
+        }
+    }
+}
+
+/// A pre-flight probe of a higher concurrency limit, run before actually raising the limit.
+#[derive(Debug)]
+struct Probe {
+    /// The number of probe requests admitted so far.
+    admitted: usize,
+    /// The number of admitted probe requests which have completed.
+    completed: usize,
+    /// The RTT above which a probe request is considered to have failed.
+    max_rtt: f64,
+    failed: bool,
 }
 
 #[cfg(test)]
@@ -64,6 +119,7 @@ impl<L> Controller<L> {
         concurrency: Option<usize>,
         settings: AdaptiveConcurrencySettings,
         logic: L,
+        endpoint: Option<String>,
     ) -> Self {
         // If a `concurrency` is specified, it becomes both the
         // current limit and the maximum, effectively bypassing all the
@@ -75,24 +131,66 @@ impl<L> Controller<L> {
             concurrency,
             settings,
             logic,
+            started: instant_now(),
             inner: Arc::new(Mutex::new(Inner {
                 current_limit,
                 in_flight: 0,
                 past_rtt: EwmaVar::new(settings.ewma_alpha),
+                min_rtt: MinRtt::default(),
+                timeout_aimd_limit: current_limit as f64,
                 next_update: instant_now(),
                 current_rtt: Default::default(),
                 had_back_pressure: false,
                 reached_limit: false,
+                probe: None,
             })),
             #[cfg(test)]
             stats: Arc::new(Mutex::new(ControllerStatistics::default())),
-            limit: register!(AdaptiveConcurrencyLimit),
-            in_flight: register!(AdaptiveConcurrencyInFlight),
-            observed_rtt: register!(AdaptiveConcurrencyObservedRtt),
-            averaged_rtt: register!(AdaptiveConcurrencyAveragedRtt),
+            limit: register!(AdaptiveConcurrencyLimit {
+                endpoint: endpoint.clone()
+            }),
+            in_flight: register!(AdaptiveConcurrencyInFlight {
+                endpoint: endpoint.clone()
+            }),
+            observed_rtt: register!(AdaptiveConcurrencyObservedRtt {
+                endpoint: endpoint.clone()
+            }),
+            averaged_rtt: register!(AdaptiveConcurrencyAveragedRtt { endpoint }),
         }
     }
 
+    /// The highest the limit may currently be raised to.
+    ///
+    /// During slow start, this grows by one per second from the initial limit.
+    fn max_limit(&self) -> usize {
+        let max_limit = self.settings.max_concurrency_limit;
+        let elapsed = instant_now().saturating_duration_since(self.started);
+        if elapsed.as_secs() >= self.settings.slow_start_secs {
+            return max_limit;
+        }
+
+        let ramp = self.settings.initial_concurrency + elapsed.as_secs() as usize;
+        ramp.min(max_limit)
+    }
+
+    /// The current concurrency limit.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub(super) fn current_limit(&self) -> usize {
+        self.inner
+            .lock()
+            .expect("Controller mutex is poisoned")
+            .current_limit
+    }
+
+    /// Sets the current concurrency limit, clamped between 1 and the highest limit currently
+    /// allowed. The controller keeps adjusting it from there.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub(super) fn seed_limit(&self, limit: usize) {
+        let mut inner = self.inner.lock().expect("Controller mutex is poisoned");
+        self.set_limit(&mut inner, limit);
+        inner.timeout_aimd_limit = inner.current_limit as f64;
+    }
+
     /// An estimate of current load on service managed by this controller.
     ///
     /// 0.0 is no load, while 1.0 is max load.
@@ -109,7 +207,8 @@ impl<L> Controller<L> {
         Arc::clone(&self.semaphore).acquire()
     }
 
-    pub(super) fn start_request(&self) {
+    /// Records the start of a request, returning whether it is part of a probe.
+    pub(super) fn start_request(&self) -> bool {
         let mut inner = self.inner.lock().expect("Controller mutex is poisoned");
 
         #[cfg(test)]
@@ -118,18 +217,35 @@ impl<L> Controller<L> {
             stats.in_flight.add(inner.in_flight, instant_now());
         }
 
+        // Requests beyond the current limit can only be admitted by a probe.
+        let beyond_limit = inner.in_flight >= inner.current_limit;
+        let is_probe = match &mut inner.probe {
+            Some(probe) if beyond_limit && probe.admitted < self.settings.probe_requests => {
+                probe.admitted += 1;
+                true
+            }
+            _ => false,
+        };
+
         inner.in_flight += 1;
         if inner.in_flight >= inner.current_limit {
             inner.reached_limit = true;
         }
 
         self.in_flight.emit(inner.in_flight as u64);
+        is_probe
     }
 
     /// Adjust the controller to a response, based on type of response
     /// given (backpressure or not) and if it should be used as a valid
     /// RTT measurement.
-    fn adjust_to_response_inner(&self, start: Instant, is_back_pressure: bool, use_rtt: bool) {
+    fn adjust_to_response_inner(
+        &self,
+        start: Instant,
+        is_probe: bool,
+        is_back_pressure: bool,
+        use_rtt: bool,
+    ) {
         let now = instant_now();
         let mut inner = self.inner.lock().expect("Controller mutex is poisoned");
 
@@ -157,8 +273,20 @@ impl<L> Controller<L> {
         inner.in_flight -= 1;
         self.in_flight.emit(inner.in_flight as u64);
 
+        if is_probe {
+            self.complete_probe_request(&mut inner, use_rtt && !is_back_pressure, rtt);
+        }
+
+        // Only manage the concurrency if `concurrency` was set to "adaptive"
+        if self.settings.controller == ConcurrencyController::TimeoutAimd
+            && self.concurrency.is_none()
+        {
+            self.adjust_timeout_aimd(&mut inner, is_back_pressure, use_rtt);
+        }
+
         if use_rtt {
             inner.current_rtt.update(rtt);
+            inner.min_rtt.update(rtt, now);
         }
         let current_rtt = inner.current_rtt.average();
 
@@ -222,19 +350,69 @@ impl<L> Controller<L> {
         let past_rtt_deviation = past_rtt.variance.sqrt();
         let threshold = past_rtt_deviation * self.settings.rtt_deviation_scale;
 
+        match self.settings.controller {
+            ConcurrencyController::RttAimd => {
+                self.adjust_rtt_aimd(inner, past_rtt, threshold, current_rtt);
+            }
+            ConcurrencyController::Gradient => self.adjust_gradient(inner, current_rtt),
+            // The limit is adjusted on each response instead.
+            ConcurrencyController::TimeoutAimd => {}
+        }
+        self.limit.emit(AdaptiveConcurrencyLimitData {
+            concurrency: inner.current_limit as u64,
+            reached_limit: inner.reached_limit,
+            had_back_pressure: inner.had_back_pressure,
+            current_rtt: current_rtt.map(Duration::from_secs_f64),
+            past_rtt: Duration::from_secs_f64(past_rtt.mean),
+            past_rtt_deviation: Duration::from_secs_f64(past_rtt_deviation),
+        });
+    }
+
+    /// Raises the limit by one while `current_rtt` stays within the usual range of the past RTT,
+    /// and lowers it by the decrease ratio otherwise.
+    fn adjust_rtt_aimd(
+        &self,
+        inner: &mut Inner,
+        past_rtt: MeanVariance,
+        threshold: f64,
+        current_rtt: Option<f64>,
+    ) {
+        // Probe requests are only admitted beyond the current limit, so a probe started while
+        // traffic fell short of it may never complete. It is given up on by the next update once
+        // none of its requests are in flight, rather than holding on to its extra permits.
+        if inner
+            .probe
+            .as_ref()
+            .is_some_and(|probe| probe.completed == probe.admitted)
+        {
+            inner.probe = None;
+            self.semaphore.forget_permits(self.settings.probe_requests);
+        }
+
         // Normal quick responses trigger an increase in the
         // concurrency limit. Note that we only check this if we had
         // requests to go beyond the current limit to prevent
         // increasing the limit beyond what we have evidence for.
-        if inner.current_limit < self.settings.max_concurrency_limit
+        if inner.current_limit < self.max_limit()
             && inner.reached_limit
             && !inner.had_back_pressure
             && current_rtt.is_some()
             && current_rtt.unwrap() <= past_rtt.mean
         {
-            // Increase (additive) the current concurrency limit
-            self.semaphore.add_permits(1);
-            inner.current_limit += 1;
+            if self.settings.probe_requests == 0 {
+                // Increase (additive) the current concurrency limit
+                self.semaphore.add_permits(1);
+                inner.current_limit += 1;
+            } else if inner.probe.is_none() {
+                // Let a few extra requests through to probe the higher limit before raising it
+                self.semaphore.add_permits(self.settings.probe_requests);
+                inner.probe = Some(Probe {
+                    admitted: 0,
+                    completed: 0,
+                    max_rtt: past_rtt.mean + threshold,
+                    failed: false,
+                });
+            }
         }
         // Back pressure responses, either explicit or implicit due
         // to increasing response times, trigger a decrease in the
@@ -248,14 +426,78 @@ impl<L> Controller<L> {
             self.semaphore.forget_permits(to_forget);
             inner.current_limit -= to_forget;
         }
-        self.limit.emit(AdaptiveConcurrencyLimitData {
-            concurrency: inner.current_limit as u64,
-            reached_limit: inner.reached_limit,
-            had_back_pressure: inner.had_back_pressure,
-            current_rtt: current_rtt.map(Duration::from_secs_f64),
-            past_rtt: Duration::from_secs_f64(past_rtt.mean),
-            past_rtt_deviation: Duration::from_secs_f64(past_rtt_deviation),
-        });
+    }
+
+    /// Scales the limit by the ratio between the lowest observed RTT and `current_rtt`, leaving
+    /// a margin of the square root of the limit for requests to queue up.
+    fn adjust_gradient(&self, inner: &mut Inner, current_rtt: Option<f64>) {
+        let limit = inner.current_limit;
+        let new_limit = if inner.had_back_pressure {
+            (limit as f64 * self.settings.decrease_ratio) as usize
+        } else if let (Some(current_rtt), Some(min_rtt)) = (current_rtt, inner.min_rtt.get()) {
+            let gradient = (min_rtt / current_rtt).clamp(0.5, 1.0);
+            let new_limit = (limit as f64 * gradient + (limit as f64).sqrt()) as usize;
+            // As with increasing the limit additively, only raise it with evidence that it is
+            // being reached.
+            if inner.reached_limit {
+                new_limit
+            } else {
+                new_limit.min(limit)
+            }
+        } else {
+            limit
+        };
+
+        self.set_limit(inner, new_limit);
+    }
+
+    /// Raises the limit by `timeout_increase_by` after a successful response which was sent while
+    /// the limit was reached, and scales it by `timeout_decrease_ratio` after back pressure.
+    fn adjust_timeout_aimd(&self, inner: &mut Inner, is_back_pressure: bool, processed: bool) {
+        if is_back_pressure {
+            inner.timeout_aimd_limit *= self.settings.timeout_decrease_ratio;
+        } else if processed && inner.reached_limit {
+            inner.timeout_aimd_limit += self.settings.timeout_increase_by;
+        } else {
+            return;
+        }
+
+        let max_limit = self.max_limit() as f64;
+        inner.timeout_aimd_limit = inner.timeout_aimd_limit.clamp(1.0, max_limit);
+        self.set_limit(inner, inner.timeout_aimd_limit as usize);
+    }
+
+    fn set_limit(&self, inner: &mut Inner, new_limit: usize) {
+        let limit = inner.current_limit;
+        let new_limit = new_limit.clamp(1, self.max_limit());
+        if new_limit > limit {
+            self.semaphore.add_permits(new_limit - limit);
+        } else {
+            self.semaphore.forget_permits(limit - new_limit);
+        }
+        inner.current_limit = new_limit;
+    }
+
+    /// Records the completion of a probe request, raising the limit once all of them have
+    /// completed successfully.
+    fn complete_probe_request(&self, inner: &mut Inner, processed: bool, rtt: f64) {
+        let Some(probe) = inner.probe.as_mut() else {
+            return;
+        };
+
+        probe.failed |= !processed || rtt > probe.max_rtt;
+        probe.completed += 1;
+        if probe.completed == self.settings.probe_requests {
+            let failed = probe.failed;
+            inner.probe = None;
+
+            self.semaphore.forget_permits(self.settings.probe_requests);
+            if !failed && inner.current_limit < self.max_limit() {
+                // Increase (additive) the current concurrency limit
+                self.semaphore.add_permits(1);
+                inner.current_limit += 1;
+            }
+        }
     }
 }
 
@@ -266,6 +508,7 @@ where
     pub(super) fn adjust_to_response(
         &self,
         start: Instant,
+        is_probe: bool,
         response: &Result<L::Response, crate::Error>,
     ) {
         // It would be better to avoid generating the string in Retry(_)
@@ -279,7 +522,7 @@ where
             Err(error) => {
                 if let Some(error) = error.downcast_ref::<L::Error>() {
                     self.logic.is_retriable_error(error)
-                } else if error.downcast_ref::<Elapsed>().is_some() {
+                } else if is_timeout(error) {
                     true
                 } else if error.downcast_ref::<HttpError>().is_some() {
                     // HTTP protocol-level errors are not backpressure
@@ -296,6 +539,6 @@ where
         };
         // Only adjust to the RTT when the request was successfully processed.
         let use_rtt = matches!(response_action, Ok(RetryAction::Successful));
-        self.adjust_to_response_inner(start, is_back_pressure, use_rtt)
+        self.adjust_to_response_inner(start, is_probe, is_back_pressure, use_rtt)
     }
 }
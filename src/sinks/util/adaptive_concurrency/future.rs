@@ -11,7 +11,7 @@ use std::{
 use pin_project::pin_project;
 use tokio::sync::OwnedSemaphorePermit;
 
-use super::{controller::Controller, instant_now};
+use super::controller::Controller;
 use crate::sinks::util::retries::RetryLogic;
 
 /// Future for the `AdaptiveConcurrencyLimit` service.
@@ -22,9 +22,10 @@ use crate::sinks::util::retries::RetryLogic;
 /// semaphore permit that is used to control concurrency such that the
 /// semaphore is returned when this future is dropped.
 ///
-/// Note that this future must be awaited immediately (such as by
-/// spawning it) to prevent extraneous delays from causing discrepancies
-/// in the measurements.
+/// The round trip time of the request is measured from `start`, which
+/// is when the inner service was called, so this future must be awaited
+/// immediately (such as by spawning it) to prevent extraneous delays
+/// from causing discrepancies in the measurements.
 #[pin_project]
 pub struct ResponseFuture<F, L> {
     #[pin]
@@ -33,6 +34,7 @@ pub struct ResponseFuture<F, L> {
     _permit: OwnedSemaphorePermit,
     controller: Arc<Controller<L>>,
     start: Instant,
+    is_probe: bool,
 }
 
 impl<F, L> ResponseFuture<F, L> {
@@ -40,12 +42,15 @@ impl<F, L> ResponseFuture<F, L> {
         inner: F,
         _permit: OwnedSemaphorePermit,
         controller: Arc<Controller<L>>,
+        start: Instant,
+        is_probe: bool,
     ) -> Self {
         Self {
             inner,
             _permit,
             controller,
-            start: instant_now(),
+            start,
+            is_probe,
         }
     }
 }
@@ -61,7 +66,9 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let future = self.project();
         let output = ready!(future.inner.poll(cx)).map_err(Into::into);
-        future.controller.adjust_to_response(*future.start, &output);
+        future
+            .controller
+            .adjust_to_response(*future.start, *future.is_probe, &output);
         Poll::Ready(output)
     }
 }
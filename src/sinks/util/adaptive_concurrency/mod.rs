@@ -19,12 +19,15 @@ fn instant_now() -> std::time::Instant {
 
 /// Configuration of adaptive concurrency parameters.
 ///
+/// Each parameter is set individually under `request.adaptive_concurrency`, for example
+/// `request.adaptive_concurrency.ewma_alpha`, and the ones left unset keep their default.
+///
 /// These parameters typically do not require changes from the default, and incorrect values can lead to meta-stable or
 /// unstable performance and sink behavior. Proceed with caution.
 // The defaults for these values were chosen after running several simulations on a test service that had
 // various responses to load. The values are the best balances found between competing outcomes.
 #[configurable_component]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct AdaptiveConcurrencySettings {
     /// The initial concurrency limit to use. If not specified, the initial limit will be 1 (no concurrency).
@@ -32,6 +35,7 @@ pub struct AdaptiveConcurrencySettings {
     /// It is recommended to set this value to your service's average limit if you're seeing that it takes a
     /// long time to ramp up adaptive concurrency after a restart. You can find this value by looking at the
     /// `adaptive_concurrency_limit` metric.
+    #[configurable(metadata(docs::human_name = "Initial Concurrency"))]
     #[configurable(validation(range(min = 1)))]
     #[serde(default = "default_initial_concurrency")]
     pub(super) initial_concurrency: usize,
@@ -42,6 +46,7 @@ pub struct AdaptiveConcurrencySettings {
     /// when latency increases.
     ///
     /// Note that the new limit is rounded down after applying this ratio.
+    #[configurable(metadata(docs::human_name = "Decrease Ratio"))]
     #[configurable(validation(range(min = 0.0, max = 1.0)))]
     #[serde(default = "default_decrease_ratio")]
     pub(super) decrease_ratio: f64,
@@ -53,6 +58,7 @@ pub struct AdaptiveConcurrencySettings {
     /// ARC uses an exponentially weighted moving average (EWMA) of past RTT measurements as a reference to compare with
     /// the current RTT. Smaller values cause this reference to adjust more slowly, which may be useful if a service has
     /// unusually high response variability.
+    #[configurable(metadata(docs::human_name = "EWMA Alpha"))]
     #[configurable(validation(range(min = 0.0, max = 1.0)))]
     #[serde(default = "default_ewma_alpha")]
     pub(super) ewma_alpha: f64,
@@ -65,6 +71,7 @@ pub struct AdaptiveConcurrencySettings {
     /// those values are. We use that deviation when comparing the past RTT average to the current measurements, so we
     /// can ignore increases in RTT that are within an expected range. This factor is used to scale up the deviation to
     /// an appropriate range.  Larger values cause the algorithm to ignore larger increases in the RTT.
+    #[configurable(metadata(docs::human_name = "RTT Deviation Scale"))]
     #[configurable(validation(range(min = 0.0)))]
     #[serde(default = "default_rtt_deviation_scale")]
     pub(super) rtt_deviation_scale: f64,
@@ -72,9 +79,99 @@ pub struct AdaptiveConcurrencySettings {
     /// The maximum concurrency limit.
     ///
     /// The adaptive request concurrency limit will not go above this bound. This is put in place as a safeguard.
+    #[configurable(metadata(docs::human_name = "Max Concurrency Limit"))]
     #[configurable(validation(range(min = 1)))]
     #[serde(default = "default_max_concurrency_limit")]
     pub(super) max_concurrency_limit: usize,
+
+    /// The number of extra requests used to probe a higher concurrency limit before raising it.
+    ///
+    /// When set, rather than raising the limit as soon as the measurements allow it, up to this many requests are let
+    /// through beyond the current limit first. The limit is only raised if all of them succeed without their RTT
+    /// being considered anomalous, and is left as is otherwise. If set to `0`, the limit is raised without probing.
+    #[configurable(metadata(docs::human_name = "Probe Requests"))]
+    #[serde(default)]
+    pub(super) probe_requests: usize,
+
+    /// The number of seconds after start during which the concurrency limit is raised slowly.
+    ///
+    /// During this time, the limit is raised by at most one per second, regardless of how quickly the controller
+    /// would otherwise raise it, so that a backend which has yet to warm up is not flooded with requests. If set
+    /// to `0`, the controller raises the limit as it sees fit from the start.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Slow Start"))]
+    #[serde(default)]
+    pub(super) slow_start_secs: u64,
+
+    #[configurable(derived)]
+    #[serde(default)]
+    pub(super) controller: ConcurrencyController,
+
+    /// The amount by which the `timeout_aimd` controller raises the limit after each successful
+    /// response.
+    ///
+    /// Fractional amounts accumulate until they add up to a whole request.
+    #[configurable(metadata(docs::human_name = "Timeout AIMD Increase"))]
+    #[serde(default = "default_timeout_increase_by")]
+    pub(super) timeout_increase_by: f64,
+
+    /// The fraction of the current limit the `timeout_aimd` controller sets the new limit to after
+    /// a response which timed out or signaled back pressure.
+    ///
+    /// Valid values are greater than `0` and less than `1`.
+    #[configurable(metadata(docs::human_name = "Timeout AIMD Decrease Ratio"))]
+    #[configurable(validation(range(min = 0.0, max = 1.0)))]
+    #[serde(default = "default_timeout_decrease_ratio")]
+    pub(super) timeout_decrease_ratio: f64,
+}
+
+/// What a service values most, for picking adaptive concurrency settings without tuning them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServiceTier {
+    /// Throughput matters more than the latency of each request, as with bulk ingestion
+    /// endpoints. See [`AdaptiveConcurrencySettings::aggressive`].
+    BestEffort,
+
+    /// Neither throughput nor latency is favored. See [`AdaptiveConcurrencySettings::new`].
+    Balanced,
+
+    /// The latency of each request matters more than throughput, as with services shared with
+    /// interactive traffic. See [`AdaptiveConcurrencySettings::conservative`].
+    LowLatency,
+}
+
+/// The algorithm used to adjust the concurrency limit.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyController {
+    /// Raises the limit by one while the RTT stays within its usual range, and lowers it by the
+    /// decrease ratio once the RTT rises beyond it or the service applies back pressure.
+    #[default]
+    RttAimd,
+
+    /// Scales the limit by the ratio between the lowest RTT observed over the last 30 to 60
+    /// seconds and the current RTT, along with a margin of the square root of the limit.
+    ///
+    /// This converges faster than `rtt_aimd` for services whose RTT follows their load closely,
+    /// but is more sensitive to RTT variability.
+    Gradient,
+
+    /// Raises the limit by `timeout_increase_by` after each successful response, and scales it by
+    /// `timeout_decrease_ratio` after each response which times out or signals back pressure,
+    /// regardless of the RTT.
+    ///
+    /// This is better suited than `rtt_aimd` to services whose RTT does not reflect their load,
+    /// such as bulk uploads.
+    TimeoutAimd,
+}
+
+const fn default_timeout_increase_by() -> f64 {
+    1.0
+}
+
+const fn default_timeout_decrease_ratio() -> f64 {
+    0.5
 }
 
 const fn default_initial_concurrency() -> usize {
@@ -97,14 +194,142 @@ const fn default_max_concurrency_limit() -> usize {
     200
 }
 
-impl Default for AdaptiveConcurrencySettings {
-    fn default() -> Self {
+impl AdaptiveConcurrencySettings {
+    /// Creates the default settings, which the setters below can then adjust.
+    ///
+    /// Unlike [`Default::default`], this can be used to define tuned settings as a constant.
+    pub const fn new() -> Self {
         Self {
             initial_concurrency: default_initial_concurrency(),
             decrease_ratio: default_decrease_ratio(),
             ewma_alpha: default_ewma_alpha(),
             rtt_deviation_scale: default_rtt_deviation_scale(),
             max_concurrency_limit: default_max_concurrency_limit(),
+            probe_requests: 0,
+            slow_start_secs: 0,
+            controller: ConcurrencyController::RttAimd,
+            timeout_increase_by: default_timeout_increase_by(),
+            timeout_decrease_ratio: default_timeout_decrease_ratio(),
+        }
+    }
+
+    /// Settings which keep the load on the service low, at the cost of throughput.
+    ///
+    /// - The limit starts at `1` and is capped at `50`, so the service is never sent more than it
+    ///   was shown to handle.
+    /// - The limit is raised slowly: by at most one per second during the first 30 seconds, and
+    ///   only after 2 probe requests beyond it succeed afterwards.
+    /// - RTT increases of more than 1.5 deviations are considered anomalous, and halve the limit.
+    ///
+    /// This suits services which slow down well before they fail, or which are shared with
+    /// latency sensitive traffic. It takes the longest to reach the capacity of the service after
+    /// a restart.
+    pub const fn conservative() -> Self {
+        Self::new()
+            .max_concurrency_limit(50)
+            .decrease_ratio(0.5)
+            .rtt_deviation_scale(1.5)
+            .probe_requests(2)
+            .slow_start_secs(30)
+    }
+
+    /// Settings which keep the throughput high, at the cost of a higher load on the service.
+    ///
+    /// - The limit starts at `10` and is capped at `500`, so that large services are saturated
+    ///   quickly.
+    /// - The RTT reference adjusts slowly, with an EWMA alpha of `0.2`, and RTT increases of up to
+    ///   3.5 deviations are considered expected, so occasional slow responses are ignored.
+    /// - Anomalous RTTs only lower the limit by 5%.
+    ///
+    /// This suits services with variable latency which scale with their load, such as bulk
+    /// ingestion endpoints. Services which degrade under load may be overloaded before the limit
+    /// is lowered enough.
+    pub const fn aggressive() -> Self {
+        Self::new()
+            .initial_concurrency(10)
+            .max_concurrency_limit(500)
+            .decrease_ratio(0.95)
+            .ewma_alpha(0.2)
+            .rtt_deviation_scale(3.5)
+    }
+
+    /// The settings suiting services of the given tier.
+    pub const fn from_service_tier(tier: ServiceTier) -> Self {
+        match tier {
+            ServiceTier::BestEffort => Self::aggressive(),
+            ServiceTier::Balanced => Self::new(),
+            ServiceTier::LowLatency => Self::conservative(),
         }
     }
+
+    /// Sets the initial concurrency limit.
+    pub const fn initial_concurrency(mut self, initial_concurrency: usize) -> Self {
+        self.initial_concurrency = initial_concurrency;
+        self
+    }
+
+    /// Sets the fraction of the current value to set the new concurrency limit to when decreasing it.
+    pub const fn decrease_ratio(mut self, decrease_ratio: f64) -> Self {
+        self.decrease_ratio = decrease_ratio;
+        self
+    }
+
+    /// Sets the weighting of new RTT measurements compared to older ones.
+    pub const fn ewma_alpha(mut self, ewma_alpha: f64) -> Self {
+        self.ewma_alpha = ewma_alpha;
+        self
+    }
+
+    /// Sets the scale of RTT deviations which are not considered anomalous.
+    pub const fn rtt_deviation_scale(mut self, rtt_deviation_scale: f64) -> Self {
+        self.rtt_deviation_scale = rtt_deviation_scale;
+        self
+    }
+
+    /// Sets the maximum concurrency limit.
+    pub const fn max_concurrency_limit(mut self, max_concurrency_limit: usize) -> Self {
+        self.max_concurrency_limit = max_concurrency_limit;
+        self
+    }
+
+    /// Sets the number of extra requests used to probe a higher concurrency limit.
+    pub const fn probe_requests(mut self, probe_requests: usize) -> Self {
+        self.probe_requests = probe_requests;
+        self
+    }
+
+    /// Sets the number of seconds after start during which the concurrency limit is raised slowly.
+    pub const fn slow_start_secs(mut self, slow_start_secs: u64) -> Self {
+        self.slow_start_secs = slow_start_secs;
+        self
+    }
+
+    /// Sets the algorithm used to adjust the concurrency limit.
+    pub const fn controller(mut self, controller: ConcurrencyController) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    /// Sets the amount by which the `timeout_aimd` controller raises the limit.
+    pub const fn timeout_increase_by(mut self, timeout_increase_by: f64) -> Self {
+        self.timeout_increase_by = timeout_increase_by;
+        self
+    }
+
+    /// Sets the fraction of the current limit the `timeout_aimd` controller lowers the limit to.
+    pub const fn timeout_decrease_ratio(mut self, timeout_decrease_ratio: f64) -> Self {
+        self.timeout_decrease_ratio = timeout_decrease_ratio;
+        self
+    }
+
+    /// Whether the initial concurrency limit is above the maximum concurrency limit.
+    pub(crate) const fn initial_exceeds_max(&self) -> bool {
+        self.initial_concurrency > self.max_concurrency_limit
+    }
+}
+
+impl Default for AdaptiveConcurrencySettings {
+    fn default() -> Self {
+        Self::new()
+    }
 }
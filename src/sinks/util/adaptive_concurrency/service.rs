@@ -10,7 +10,9 @@ use futures::future::BoxFuture;
 use tokio::sync::OwnedSemaphorePermit;
 use tower::{load::Load, Service};
 
-use super::{controller::Controller, future::ResponseFuture, AdaptiveConcurrencySettings};
+use super::{
+    controller::Controller, future::ResponseFuture, instant_now, AdaptiveConcurrencySettings,
+};
 use crate::sinks::util::retries::RetryLogic;
 
 /// Enforces a limit on the concurrent number of requests the underlying
@@ -35,15 +37,32 @@ impl<S, L> AdaptiveConcurrencyLimit<S, L> {
         logic: L,
         concurrency: Option<usize>,
         options: AdaptiveConcurrencySettings,
+        endpoint: Option<String>,
     ) -> Self {
         AdaptiveConcurrencyLimit {
             inner,
-            controller: Arc::new(Controller::new(concurrency, options, logic)),
+            controller: Arc::new(Controller::new(concurrency, options, logic, endpoint)),
             state: State::Empty,
         }
     }
 }
 
+#[cfg(any(test, feature = "test-utils"))]
+impl<S, L> AdaptiveConcurrencyLimit<S, L> {
+    /// The current concurrency limit, shared with the clones of this service.
+    pub fn current_limit(&self) -> usize {
+        self.controller.current_limit()
+    }
+
+    /// Sets the current concurrency limit, so that tests can start from a known limit.
+    ///
+    /// The limit is clamped between 1 and the highest limit currently allowed, and keeps being
+    /// adjusted from there.
+    pub fn seed_limit(&self, limit: usize) {
+        self.controller.seed_limit(limit);
+    }
+}
+
 impl<S, L, Request> Service<Request> for AdaptiveConcurrencyLimit<S, L>
 where
     S: Service<Request>,
@@ -77,12 +96,22 @@ where
             _ => panic!("Maximum requests in-flight; poll_ready must be called first"),
         };
 
-        self.controller.start_request();
+        let is_probe = self.controller.start_request();
+
+        // The round trip time is measured from here, once the request holds a permit, so that the
+        // time it spent queued behind the concurrency limit is not mistaken for backend latency.
+        let start = instant_now();
 
         // Call the inner service
         let future = self.inner.call(request);
 
-        ResponseFuture::new(future, permit, Arc::clone(&self.controller))
+        ResponseFuture::new(
+            future,
+            permit,
+            Arc::clone(&self.controller),
+            start,
+            is_probe,
+        )
     }
 }
 
@@ -129,7 +158,7 @@ mod tests {
     };
 
     use snafu::Snafu;
-    use tokio::time::{advance, pause};
+    use tokio::time::{advance, pause, resume};
     use tokio_test::{assert_pending, assert_ready_ok};
     use tower_test::{
         assert_request_eq,
@@ -141,7 +170,7 @@ mod tests {
     use super::{
         super::{
             controller::{ControllerStatistics, Inner},
-            AdaptiveConcurrencyLimitLayer,
+            AdaptiveConcurrencyLimitLayer, ConcurrencyController, ServiceTier,
         },
         *,
     };
@@ -178,15 +207,8 @@ mod tests {
     }
 
     impl TestService {
-        fn start() -> Self {
-            let layer = AdaptiveConcurrencyLimitLayer::new(
-                None,
-                AdaptiveConcurrencySettings {
-                    decrease_ratio: 0.5,
-                    ..Default::default()
-                },
-                TestRetryLogic,
-            );
+        fn start(settings: AdaptiveConcurrencySettings) -> Self {
+            let layer = AdaptiveConcurrencyLimitLayer::new(None, settings, TestRetryLogic);
             let (service, handle) = mock::spawn_layer(layer);
             let controller = Arc::clone(&service.get_ref().controller);
             let inner = Arc::clone(&controller.inner);
@@ -205,7 +227,18 @@ mod tests {
             F: FnOnce(Self) -> Ret,
             Ret: Future<Output = ()>,
         {
-            let svc = Self::start();
+            Self::run_with(AdaptiveConcurrencySettings::new().decrease_ratio(0.5), doit).await
+        }
+
+        async fn run_with<F, Ret>(
+            settings: AdaptiveConcurrencySettings,
+            doit: F,
+        ) -> ControllerStatistics
+        where
+            F: FnOnce(Self) -> Ret,
+            Ret: Future<Output = ()>,
+        {
+            let svc = Self::start(settings);
             //let inner = svc.inner.clone();
             let stats = Arc::clone(&svc.stats);
             pause();
@@ -346,4 +379,299 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn queueing_does_not_inflate_rtt() {
+        let stats = TestService::run(|mut svc| async move {
+            for _ in 0..2 {
+                // The next request starts waiting for a permit as soon as this one is sent, and
+                // only gets one after waiting for 4s.
+                let req = svc.send(false).await;
+                advance(Duration::from_secs(1)).await;
+                req.respond().await;
+                advance(Duration::from_secs(3)).await;
+            }
+
+            // The measured latency stays constant, so the limit is increased.
+            assert_eq!(svc.inner().current_limit, 2);
+        })
+        .await;
+
+        let observed_rtt = stats.observed_rtt.stats().unwrap();
+        assert_eq!(observed_rtt.mean, 1.0);
+        assert_eq!(observed_rtt.max, 1.0);
+    }
+
+    const fn probing_settings() -> AdaptiveConcurrencySettings {
+        AdaptiveConcurrencySettings::new().probe_requests(1)
+    }
+
+    #[tokio::test]
+    async fn increases_limit_after_successful_probe() {
+        TestService::run_with(probing_settings(), |mut svc| async move {
+            for _ in 0..2 {
+                let req = svc.send(false).await;
+                advance(Duration::from_secs(1)).await;
+                req.respond().await;
+            }
+
+            // Rather than being increased, the limit is probed with an extra request
+            assert_eq!(svc.inner().current_limit, 1);
+            let req = svc.send(true).await;
+            let probe = svc.send(false).await;
+            advance(Duration::from_secs(1)).await;
+            req.respond().await;
+            assert_eq!(svc.inner().current_limit, 1);
+            probe.respond().await;
+
+            assert_eq!(svc.inner().current_limit, 2);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn keeps_limit_after_slow_probe() {
+        TestService::run_with(probing_settings(), |mut svc| async move {
+            for _ in 0..2 {
+                let req = svc.send(false).await;
+                advance(Duration::from_secs(1)).await;
+                req.respond().await;
+            }
+
+            assert_eq!(svc.inner().current_limit, 1);
+            let req = svc.send(true).await;
+            let probe = svc.send(false).await;
+            advance(Duration::from_secs(1)).await;
+            req.respond().await;
+            advance(Duration::from_secs(2)).await;
+            probe.respond().await;
+
+            // The extra permit used by the probe is taken back as well
+            assert_eq!(svc.inner().current_limit, 1);
+            svc.send(false).await;
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_probes_without_traffic_beyond_the_limit() {
+        TestService::run_with(probing_settings(), |mut svc| async move {
+            for _ in 0..2 {
+                let req = svc.send(false).await;
+                advance(Duration::from_secs(1)).await;
+                req.respond().await;
+            }
+
+            // The probe's extra permit is left unused until the next update, where a slower
+            // response keeps a new probe from being started.
+            let req = svc.send(true).await;
+            advance(Duration::from_secs(2)).await;
+            req.respond().await;
+
+            assert_eq!(svc.inner().current_limit, 1);
+            svc.send(false).await;
+        })
+        .await;
+    }
+
+    /// Sends rounds of as many concurrent requests as the limit allows, each round taking the RTT
+    /// returned by `rtt` for its index and limit, and returns the limit after each round.
+    async fn limits_after_rounds(
+        settings: AdaptiveConcurrencySettings,
+        rounds: usize,
+        rtt: impl Fn(usize, usize) -> u64,
+    ) -> Vec<usize> {
+        let mut limits = Vec::with_capacity(rounds);
+        let recorded = &mut limits;
+        TestService::run_with(settings, |mut svc| async move {
+            for round in 0..rounds {
+                let limit = svc.inner().current_limit;
+                let mut reqs = Vec::with_capacity(limit);
+                for i in 0..limit {
+                    reqs.push(svc.send(i < limit - 1).await);
+                }
+                advance(Duration::from_secs(rtt(round, limit))).await;
+                for req in reqs {
+                    req.respond().await;
+                }
+                recorded.push(svc.inner().current_limit);
+            }
+        })
+        .await;
+
+        // Let the clock be paused again by the next run.
+        resume();
+        limits
+    }
+
+    /// Sends rounds of as many concurrent requests as the limit allows, each round taking the
+    /// given RTT, and returns how many times the limit was decreased after the first ten rounds.
+    async fn count_decreases(ewma_alpha: f64, rtts: &[u64]) -> usize {
+        let mut decreases = 0;
+        let counted = &mut decreases;
+        let settings = AdaptiveConcurrencySettings::new().ewma_alpha(ewma_alpha);
+        TestService::run_with(settings, |mut svc| async move {
+            for (round, &rtt) in rtts.iter().enumerate() {
+                let limit = svc.inner().current_limit;
+                let mut reqs = Vec::with_capacity(limit);
+                for i in 0..limit {
+                    reqs.push(svc.send(i < limit - 1).await);
+                }
+                advance(Duration::from_secs(rtt)).await;
+                for req in reqs {
+                    req.respond().await;
+                }
+
+                if round >= 10 && svc.inner().current_limit < limit {
+                    *counted += 1;
+                }
+            }
+        })
+        .await;
+
+        // Let the clock be paused again by the next run.
+        resume();
+        decreases
+    }
+
+    #[tokio::test]
+    async fn smoother_rtt_reference_stabilizes_limit_under_jitter() {
+        // A steady service whose RTT regularly doubles for a single round.
+        let rtts = [[1; 10].as_slice(), &[1, 1, 2, 1, 1, 1].repeat(8)].concat();
+
+        // The more weight new measurements are given, the sooner the RTT reference forgets about
+        // past spikes, and the more often the next spike is considered anomalous.
+        let smooth = count_decreases(0.2, &rtts).await;
+        let reactive = count_decreases(0.8, &rtts).await;
+        assert!(
+            smooth < reactive,
+            "{smooth} decreases with a smooth reference, {reactive} with a reactive one"
+        );
+    }
+
+    #[tokio::test]
+    async fn gradient_controller_converges_faster() {
+        let run = |controller| {
+            let settings = AdaptiveConcurrencySettings::new().controller(controller);
+            limits_after_rounds(settings, 25, |_, _| 1)
+        };
+        let rounds_to_reach = |limits: &[usize]| limits.iter().position(|&limit| limit >= 20);
+
+        // With a constant RTT, the gradient controller raises the limit by more than one at a time.
+        let rtt_aimd = run(ConcurrencyController::RttAimd).await;
+        let gradient = run(ConcurrencyController::Gradient).await;
+        assert!(rounds_to_reach(&gradient).unwrap() < rounds_to_reach(&rtt_aimd).unwrap());
+
+        // It lowers the limit once the RTT rises with it, and holds it around that point.
+        let settings =
+            AdaptiveConcurrencySettings::new().controller(ConcurrencyController::Gradient);
+        let limits =
+            limits_after_rounds(settings, 40, |_, limit| if limit > 10 { 2 } else { 1 }).await;
+        assert!(limits[10..].iter().all(|limit| (5..=20).contains(limit)));
+    }
+
+    #[tokio::test]
+    async fn gradient_controller_follows_a_lasting_rtt_rise() {
+        let settings =
+            AdaptiveConcurrencySettings::new().controller(ConcurrencyController::Gradient);
+        // The RTT triples for good after ten rounds.
+        let limits =
+            limits_after_rounds(settings, 60, |round, _| if round < 10 { 1 } else { 3 }).await;
+
+        // The limit is first cut against the lowest RTT, then raised again once that is forgotten.
+        let lowest = *limits[10..30].iter().min().unwrap();
+        assert!(
+            *limits.last().unwrap() > 2 * lowest,
+            "limits {limits:?} did not recover"
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_start_limits_ramp_up() {
+        let settings = AdaptiveConcurrencySettings::new()
+            .controller(ConcurrencyController::Gradient)
+            .slow_start_secs(10);
+        let limits = limits_after_rounds(settings, 25, |_, _| 1).await;
+
+        // Each round takes a second, so the limit can only have been raised once per round so far.
+        for (round, &limit) in limits.iter().take(10).enumerate() {
+            assert!(limit <= round + 2, "limit {limit} after round {round}");
+        }
+        // Past the slow start, the controller raises the limit as quickly as it would otherwise.
+        assert!(limits.iter().any(|&limit| limit >= 20));
+    }
+
+    #[tokio::test]
+    async fn aggressive_settings_ramp_up_faster_than_conservative_ones() {
+        let conservative =
+            limits_after_rounds(AdaptiveConcurrencySettings::conservative(), 10, |_, _| 1).await;
+        let aggressive =
+            limits_after_rounds(AdaptiveConcurrencySettings::aggressive(), 10, |_, _| 1).await;
+
+        for (conservative, aggressive) in conservative.iter().zip(&aggressive) {
+            assert!(aggressive > conservative);
+        }
+        assert_eq!(
+            AdaptiveConcurrencySettings::from_service_tier(ServiceTier::Balanced),
+            AdaptiveConcurrencySettings::new()
+        );
+    }
+
+    const fn timeout_aimd_settings() -> AdaptiveConcurrencySettings {
+        AdaptiveConcurrencySettings::new()
+            .controller(ConcurrencyController::TimeoutAimd)
+            .timeout_increase_by(1.0)
+            .timeout_decrease_ratio(0.5)
+    }
+
+    #[tokio::test]
+    async fn timeout_aimd_controller_ignores_rtt() {
+        // A steady service whose RTT triples for a single round.
+        let rtt = |round, _| if round == 12 { 3 } else { 1 };
+
+        // Unlike the RTT based controller, the limit is only ever raised.
+        let rtt_aimd = limits_after_rounds(AdaptiveConcurrencySettings::new(), 20, rtt).await;
+        assert!(rtt_aimd.windows(2).any(|limits| limits[1] < limits[0]));
+        let timeout_aimd = limits_after_rounds(timeout_aimd_settings(), 20, rtt).await;
+        assert!(timeout_aimd.windows(2).all(|limits| limits[1] >= limits[0]));
+    }
+
+    #[tokio::test]
+    async fn seeded_limit_is_adjusted_from() {
+        TestService::run_with(timeout_aimd_settings(), |mut svc| async move {
+            svc.service.get_ref().seed_limit(4);
+            assert_eq!(svc.service.get_ref().current_limit(), 4);
+
+            let first = svc.send(true).await;
+            let _second = svc.send(true).await;
+            let _third = svc.send(true).await;
+            let _fourth = svc.send(false).await;
+            first.defer().await;
+            assert_eq!(svc.service.get_ref().current_limit(), 2);
+
+            // The limit is capped by the maximum concurrency limit.
+            svc.service.get_ref().seed_limit(usize::MAX);
+            assert_eq!(svc.service.get_ref().current_limit(), 200);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn timeout_aimd_controller_adjusts_on_each_response() {
+        TestService::run_with(timeout_aimd_settings(), |mut svc| async move {
+            let req = svc.send(false).await;
+            advance(Duration::from_secs(1)).await;
+            req.respond().await;
+            assert_eq!(svc.inner().current_limit, 2);
+
+            let first = svc.send(true).await;
+            let second = svc.send(false).await;
+            advance(Duration::from_secs(1)).await;
+            first.respond().await;
+            assert_eq!(svc.inner().current_limit, 3);
+            second.defer().await;
+            assert_eq!(svc.inner().current_limit, 1);
+        })
+        .await;
+    }
 }
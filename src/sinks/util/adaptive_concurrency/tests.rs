@@ -36,7 +36,8 @@ use crate::{
     sinks::{
         util::{
             retries::{JitterMode, RetryLogic},
-            BatchSettings, Concurrency, EncodedEvent, EncodedLength, TowerRequestConfig, VecBuffer,
+            BatchSettings, Concurrency, EncodedEvent, EncodedLength, RateLimitNum,
+            TowerRequestConfig, VecBuffer,
         },
         Healthcheck, VectorSink,
     },
@@ -414,10 +415,10 @@ async fn run_test(params: TestParams) -> TestResults {
 
     let test_config = TestConfig {
         request: TowerRequestConfig {
-            concurrency: params.concurrency,
-            rate_limit_num: 9999,
-            timeout_secs: 1,
-            retry_jitter_mode: JitterMode::None,
+            concurrency: Some(params.concurrency),
+            rate_limit_num: Some(RateLimitNum::Limited(9999)),
+            timeout_secs: Some(1),
+            retry_jitter_mode: Some(JitterMode::None),
             ..Default::default()
         },
         params,
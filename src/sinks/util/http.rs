@@ -9,11 +9,14 @@ use std::{
     time::Duration,
 };
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
 use futures::{future::BoxFuture, Sink};
 use headers::HeaderName;
 use http::{header, HeaderValue, Request, Response, StatusCode};
-use hyper::{body, Body};
+use hyper::{
+    body::{self, HttpBody},
+    Body,
+};
 use indexmap::IndexMap;
 use pin_project::pin_project;
 use snafu::{ResultExt, Snafu};
@@ -24,7 +27,7 @@ use vector_lib::stream::batcher::limiter::ItemBatchSize;
 use vector_lib::{ByteSizeOf, EstimatedJsonEncodedSizeOf};
 
 use super::{
-    retries::{RetryAction, RetryLogic},
+    retries::{HasStatusCode, RetryAction, RetryErrorCategory, RetryLogic},
     sink::{self, Response as _},
     uri, Batch, EncodedEvent, Partition, TowerBatchedSink, TowerPartitionSink, TowerRequestConfig,
     TowerRequestSettings,
@@ -32,7 +35,9 @@ use super::{
 use crate::{
     event::Event,
     http::{HttpClient, HttpError},
-    internal_events::{EndpointBytesSent, SinkRequestBuildError},
+    internal_events::{
+        http_client::HttpResponseBodyTooLarge, EndpointBytesSent, SinkRequestBuildError,
+    },
     sinks::prelude::*,
 };
 
@@ -367,6 +372,7 @@ where
 pub struct HttpBatchService<F, B = Bytes> {
     inner: HttpClient<Body>,
     request_builder: Arc<dyn Fn(B) -> F + Send + Sync>,
+    max_response_body_bytes: Option<usize>,
 }
 
 impl<F, B> HttpBatchService<F, B> {
@@ -377,8 +383,41 @@ impl<F, B> HttpBatchService<F, B> {
         HttpBatchService {
             inner,
             request_builder: Arc::new(Box::new(request_builder)),
+            max_response_body_bytes: None,
         }
     }
+
+    /// Fails requests whose response body is larger than `limit` bytes, rather than buffering it.
+    pub fn with_max_response_body_bytes(mut self, limit: usize) -> Self {
+        self.max_response_body_bytes = Some(limit);
+        self
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display("Response body exceeds the limit of {} bytes", limit))]
+pub struct ResponseBodyTooLargeError {
+    limit: usize,
+}
+
+/// Reads a response body, failing as soon as it grows beyond `limit` bytes.
+async fn read_body_limited(mut body: Body, limit: usize) -> crate::Result<Bytes> {
+    if body.size_hint().lower() > limit as u64 {
+        emit!(HttpResponseBodyTooLarge { limit });
+        return Err(ResponseBodyTooLargeError { limit }.into());
+    }
+
+    let mut buffer = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buffer.len() + chunk.len() > limit {
+            emit!(HttpResponseBodyTooLarge { limit });
+            return Err(ResponseBodyTooLargeError { limit }.into());
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+
+    Ok(buffer.freeze())
 }
 
 impl<F, B> Service<B> for HttpBatchService<F, B>
@@ -397,6 +436,7 @@ where
     fn call(&mut self, body: B) -> Self::Future {
         let request_builder = Arc::clone(&self.request_builder);
         let http_client = self.inner.clone();
+        let max_response_body_bytes = self.max_response_body_bytes;
 
         Box::pin(async move {
             let request = request_builder(body).await.map_err(|error| {
@@ -425,11 +465,14 @@ where
             }
 
             let (parts, body) = response.into_parts();
-            let mut body = body::aggregate(body).await?;
-            Ok(hyper::Response::from_parts(
-                parts,
-                body.copy_to_bytes(body.remaining()),
-            ))
+            let body = match max_response_body_bytes {
+                Some(limit) => read_body_limited(body, limit).await?,
+                None => {
+                    let mut body = body::aggregate(body).await?;
+                    body.copy_to_bytes(body.remaining())
+                }
+            };
+            Ok(hyper::Response::from_parts(parts, body))
         })
     }
 }
@@ -439,10 +482,17 @@ impl<F, B> Clone for HttpBatchService<F, B> {
         Self {
             inner: self.inner.clone(),
             request_builder: Arc::clone(&self.request_builder),
+            max_response_body_bytes: self.max_response_body_bytes,
         }
     }
 }
 
+impl<T> HasStatusCode for http::Response<T> {
+    fn status_code(&self) -> u16 {
+        self.status().as_u16()
+    }
+}
+
 impl<T: fmt::Debug> sink::Response for http::Response<T> {
     fn is_successful(&self) -> bool {
         self.status().is_success()
@@ -479,6 +529,29 @@ impl RetryLogic for HttpRetryLogic {
             _ => RetryAction::DontRetry(format!("response status: {}", status).into()),
         }
     }
+
+    fn error_category(&self, error: &Self::Error) -> RetryErrorCategory {
+        http_error_category(error)
+    }
+
+    fn response_category(&self, response: &Self::Response) -> RetryErrorCategory {
+        status_category(response.status())
+    }
+}
+
+fn http_error_category(error: &HttpError) -> RetryErrorCategory {
+    match error {
+        HttpError::CallRequest { .. } => RetryErrorCategory::Connection,
+        _ => RetryErrorCategory::Other,
+    }
+}
+
+fn status_category(status: StatusCode) -> RetryErrorCategory {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => RetryErrorCategory::Throttled,
+        _ if status.is_server_error() => RetryErrorCategory::Server,
+        _ => RetryErrorCategory::Other,
+    }
 }
 
 /// A more generic version of `HttpRetryLogic` that accepts anything that can be converted
@@ -529,6 +602,14 @@ where
             _ => RetryAction::DontRetry(format!("Http status: {}", status).into()),
         }
     }
+
+    fn error_category(&self, error: &Self::Error) -> RetryErrorCategory {
+        http_error_category(error)
+    }
+
+    fn response_category(&self, response: &T) -> RetryErrorCategory {
+        status_category((self.func)(response))
+    }
 }
 
 impl<F, T> Clone for HttpStatusRetryLogic<F, T>
@@ -668,6 +749,12 @@ pub struct HttpResponse {
     pub raw_byte_size: usize,
 }
 
+impl HasStatusCode for HttpResponse {
+    fn status_code(&self) -> u16 {
+        self.http_response.status_code()
+    }
+}
+
 impl DriverResponse for HttpResponse {
     fn event_status(&self) -> EventStatus {
         if self.http_response.is_successful() {
@@ -739,6 +826,12 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Fails requests whose response body is larger than `limit` bytes, rather than buffering it.
+    pub fn with_max_response_body_bytes(mut self, limit: usize) -> Self {
+        self.batch_service = self.batch_service.with_max_response_body_bytes(limit);
+        self
+    }
 }
 
 impl<B, T: Send + 'static> Service<HttpRequest<T>> for HttpService<B, T>
@@ -858,4 +951,42 @@ mod test {
         let (body, _rest) = rx.into_future().await;
         assert_eq!(body.unwrap(), "hello");
     }
+
+    #[tokio::test]
+    async fn util_http_limits_response_body_size() {
+        let addr = next_addr();
+
+        let uri = format!("http://{}:{}/", addr.ip(), addr.port())
+            .parse::<Uri>()
+            .unwrap();
+
+        let proxy = ProxyConfig::default();
+        let client = HttpClient::new(None, &proxy).unwrap();
+        let mut service = HttpBatchService::new(client, move |body: Bytes| {
+            Box::pin(ready(
+                http::Request::post(&uri).body(body).map_err(Into::into),
+            ))
+        })
+        .with_max_response_body_bytes(1024);
+
+        // The response body never ends, so it can only be read if it is limited.
+        let new_service = make_service_fn(move |_| async move {
+            Ok::<_, std::convert::Infallible>(service_fn(|_| async move {
+                let chunks = futures::stream::repeat_with(|| {
+                    Ok::<_, std::convert::Infallible>(Bytes::from(vec![0; 100]))
+                });
+                Ok::<_, crate::Error>(Response::new(Body::wrap_stream(chunks)))
+            }))
+        });
+
+        tokio::spawn(async move {
+            if let Err(error) = Server::bind(&addr).serve(new_service).await {
+                eprintln!("Server error: {}", error);
+            }
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let error = service.call(Bytes::from("hello")).await.unwrap_err();
+        assert!(error.downcast_ref::<ResponseBodyTooLargeError>().is_some());
+    }
 }
@@ -19,6 +19,7 @@ pub mod sink;
 pub mod snappy;
 pub mod socket_bytes_sink;
 pub mod statistic;
+pub mod status_codes;
 pub mod tcp;
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test;
@@ -45,11 +46,12 @@ pub use builder::SinkBuilderExt;
 pub use compressor::Compressor;
 pub use normalizer::Normalizer;
 pub use request_builder::{IncrementalRequestBuilder, RequestBuilder};
+pub use retries::IoErrorRetryLogic;
 pub use service::{
-    Concurrency, ServiceBuilderExt, TowerBatchedSink, TowerPartitionSink, TowerRequestConfig,
-    TowerRequestLayer, TowerRequestSettings,
+    Concurrency, RateLimitNum, ServiceBuilderExt, TowerBatchedSink, TowerBiLayer,
+    TowerPartitionSink, TowerRequestConfig, TowerRequestLayer, TowerRequestSettings,
 };
-pub use sink::{BatchSink, PartitionBatchSink, StreamSink};
+pub use sink::{BackpressureSignal, BatchSink, PartitionBatchSink, PartitionRate, StreamSink};
 use snafu::Snafu;
 pub use uri::UriSerde;
 use vector_lib::{json_size::JsonSize, TimeZone};
@@ -1,18 +1,70 @@
 use std::{
     borrow::Cow,
+    cell::RefCell,
     cmp,
     future::Future,
+    marker::PhantomData,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::Duration,
 };
 
 use futures::FutureExt;
-use tokio::time::{sleep, Sleep};
-use tower::{retry::Policy, timeout::error::Elapsed};
-use vector_lib::configurable::configurable_component;
+use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use tokio::task::futures::TaskLocalFuture;
+use tower::retry::Policy;
+use vector_lib::configurable::{
+    configurable_component,
+    schema::{SchemaGenerator, SchemaObject},
+    Configurable, GenerateError, Metadata, ToValue,
+};
+
+use crate::{
+    internal_events::{
+        RequestRetriesExhausted, RequestRetriesRemaining, RequestRetry, RequestRetrySucceeded,
+    },
+    sinks::util::{
+        service::{
+            count_retry, current_deadline, is_timeout, record_retries_exhausted, record_retry,
+            Clock, ClockSleep, GlobalTowerRequestConfigDefaults, RetryableErrorsConfig, TokioClock,
+            TowerRequestConfigDefaults,
+        },
+        status_codes::StatusCodeRanges,
+    },
+    Error,
+};
+
+tokio::task_local! {
+    static INITIAL_BATCH: ();
+    static BATCH_BYTES: usize;
+}
+
+/// Sends the first batch of a partition, so that the retry policy applies its initial batch
+/// settings, if any, to it.
+pub(crate) fn send_initial_batch<F: Future>(call: impl FnOnce() -> F) -> TaskLocalFuture<(), F> {
+    let future = INITIAL_BATCH.sync_scope((), call);
+    INITIAL_BATCH.scope((), future)
+}
+
+fn is_initial_batch() -> bool {
+    INITIAL_BATCH.try_with(|_| ()).is_ok()
+}
+
+/// Sends a batch of `byte_size` bytes, so that the retry policy applies its large batch settings,
+/// if any, to it.
+pub(crate) fn send_batch<F: Future>(
+    byte_size: usize,
+    call: impl FnOnce() -> F,
+) -> TaskLocalFuture<usize, F> {
+    let future = BATCH_BYTES.sync_scope(byte_size, call);
+    BATCH_BYTES.scope(byte_size, future)
+}
 
-use crate::Error;
+fn current_batch_bytes() -> Option<usize> {
+    BATCH_BYTES.try_with(|byte_size| *byte_size).ok()
+}
 
 pub enum RetryAction {
     /// Indicate that this request should be retried with a reason
@@ -31,6 +83,12 @@ pub trait RetryLogic: Clone + Send + Sync + 'static {
     /// implementors to specify what kinds of errors can be retried.
     fn is_retriable_error(&self, error: &Self::Error) -> bool;
 
+    /// When the Service call returns an `Err` response that is retried, this function allows
+    /// implementors to give a short reason for the retry, which is reported alongside it.
+    fn retry_reason(&self, error: &Self::Error) -> Cow<'static, str> {
+        error.to_string().into()
+    }
+
     /// When the Service call returns an `Ok` response, this function allows
     /// implementors to specify additional logic to determine if the success response
     /// is actually an error. This is particularly useful when the downstream service
@@ -41,11 +99,383 @@ pub trait RetryLogic: Clone + Send + Sync + 'static {
         // Treat the default as the request is successful
         RetryAction::Successful
     }
+
+    /// When the Service call returns an `Err` response for a request that is not idempotent, this
+    /// function allows implementors to specify which errors guarantee that the request never
+    /// reached the downstream service, such as failing to establish a connection, and can
+    /// therefore still be retried safely.
+    fn is_connection_error(&self, _error: &Self::Error) -> bool {
+        false
+    }
+
+    /// When the Service call returns an `Err` response, this function allows implementors to
+    /// classify the error, so that operators can narrow down which errors are retried with the
+    /// `retryable_errors` request option.
+    fn error_category(&self, error: &Self::Error) -> RetryErrorCategory {
+        if self.is_connection_error(error) {
+            RetryErrorCategory::Connection
+        } else {
+            RetryErrorCategory::Other
+        }
+    }
+
+    /// When `should_retry_response` asks for an `Ok` response to be retried, this function allows
+    /// implementors to classify the failure it reports, the same way as `error_category`.
+    fn response_category(&self, _response: &Self::Response) -> RetryErrorCategory {
+        RetryErrorCategory::Other
+    }
+
+    /// When a request is about to be retried, this function allows implementors to run side
+    /// effects beforehand, such as refreshing an expired authentication token.
+    ///
+    /// It is called once the retry is decided, before its backoff, with the number of the attempt
+    /// about to be made, starting at `2` for the first retry, and the error or response the
+    /// previous attempt failed with. Requests retried after a failure other than `Self::Error`,
+    /// such as a timeout, are passed neither.
+    ///
+    /// This can't change the request being retried, which is resent as it was first sent. Side
+    /// effects only reach the retry through the service sending it, such as a token the service
+    /// reads whenever it signs a request.
+    fn on_retry(
+        &self,
+        _attempt: usize,
+        _error: Option<&Self::Error>,
+        _response: Option<&Self::Response>,
+    ) {
+    }
+}
+
+/// A category of failed requests, which the `retryable_errors` request option narrows retries by.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryErrorCategory {
+    /// The request timed out.
+    Timeout,
+
+    /// The connection to the downstream service failed, such as by being refused or reset.
+    Connection,
+
+    /// The downstream service failed to process the request, such as with a `5xx` HTTP status code.
+    Server,
+
+    /// The downstream service asked for requests to be slowed down, such as with a `429` HTTP
+    /// status code.
+    Throttled,
+
+    /// Any other failure.
+    Other,
+}
+
+impl RetryErrorCategory {
+    const ALL: [Self; 5] = [
+        Self::Timeout,
+        Self::Connection,
+        Self::Server,
+        Self::Throttled,
+        Self::Other,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of categories of failed requests.
+///
+/// This is configured as a list of categories.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RetryErrorCategories(u8);
+
+impl RetryErrorCategories {
+    pub const fn contains(self, category: RetryErrorCategory) -> bool {
+        self.0 & category.bit() != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = RetryErrorCategory> {
+        RetryErrorCategory::ALL
+            .into_iter()
+            .filter(move |category| self.contains(*category))
+    }
+}
+
+impl FromIterator<RetryErrorCategory> for RetryErrorCategories {
+    fn from_iter<I: IntoIterator<Item = RetryErrorCategory>>(categories: I) -> Self {
+        Self(
+            categories
+                .into_iter()
+                .fold(0, |bits, category| bits | category.bit()),
+        )
+    }
+}
+
+impl Serialize for RetryErrorCategories {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for category in self.iter() {
+            seq.serialize_element(&category)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RetryErrorCategories {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<RetryErrorCategory>::deserialize(deserializer)
+            .map(|categories| categories.into_iter().collect())
+    }
+}
+
+// Configured the same way as the list of categories it is deserialized from.
+impl Configurable for RetryErrorCategories {
+    fn metadata() -> Metadata {
+        Vec::<RetryErrorCategory>::metadata()
+    }
+
+    fn validate_metadata(metadata: &Metadata) -> Result<(), GenerateError> {
+        Vec::<RetryErrorCategory>::validate_metadata(metadata)
+    }
+
+    fn generate_schema(gen: &RefCell<SchemaGenerator>) -> Result<SchemaObject, GenerateError> {
+        Vec::<RetryErrorCategory>::generate_schema(gen)
+    }
+}
+
+impl ToValue for RetryErrorCategories {
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("Could not convert retry error categories to JSON")
+    }
+}
+
+/// Classifies requests by whether they can safely be sent more than once.
+pub trait Idempotency {
+    /// Returns `true` if sending this request more than once has the same effect as sending it once.
+    fn is_idempotent(&self) -> bool;
+}
+
+/// A retry logic which retries every `std::io::Error` and treats every response as successful.
+///
+/// This is meant for quickly setting up sinks whose service fails with I/O errors only, without
+/// having to write a dedicated [`RetryLogic`] for them.
+pub struct IoErrorRetryLogic<Resp> {
+    _response: PhantomData<fn() -> Resp>,
+}
+
+impl<Resp> IoErrorRetryLogic<Resp> {
+    pub const fn new() -> Self {
+        Self {
+            _response: PhantomData,
+        }
+    }
+}
+
+impl<Resp> Default for IoErrorRetryLogic<Resp> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Derived impls would needlessly require `Resp: Clone` and `Resp: Debug`.
+impl<Resp> Clone for IoErrorRetryLogic<Resp> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<Resp> std::fmt::Debug for IoErrorRetryLogic<Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoErrorRetryLogic").finish()
+    }
+}
+
+impl<Resp: Send + 'static> RetryLogic for IoErrorRetryLogic<Resp> {
+    type Error = std::io::Error;
+    type Response = Resp;
+
+    fn is_retriable_error(&self, _error: &Self::Error) -> bool {
+        true
+    }
+
+    fn should_retry_response(&self, _response: &Self::Response) -> RetryAction {
+        RetryAction::Successful
+    }
+}
+
+/// Responses which carry an HTTP status code.
+pub trait HasStatusCode {
+    /// Returns the HTTP status code of this response.
+    fn status_code(&self) -> u16;
+}
+
+/// A retry logic which retries exactly the responses whose status code is in a configured list.
+///
+/// Errors, and responses whose status code is not listed, are classified by the wrapped retry
+/// logic, except that such responses are never retried. Without a list of status codes, the
+/// wrapped retry logic is used as is.
+#[derive(Clone, Debug)]
+pub struct StatusCodeRetryLogic<L> {
+    inner: L,
+    status_codes: Option<StatusCodeRanges>,
+}
+
+impl<L> StatusCodeRetryLogic<L> {
+    pub fn new(inner: L, status_codes: Option<&[u16]>) -> Self {
+        Self::from_ranges(
+            inner,
+            status_codes.map(|codes| StatusCodeRanges::default().with_codes(codes)),
+        )
+    }
+
+    /// Creates a retry logic retrying the responses whose status code is in `ranges`, if any.
+    pub const fn from_ranges(inner: L, ranges: Option<StatusCodeRanges>) -> Self {
+        Self {
+            inner,
+            status_codes: ranges,
+        }
+    }
+}
+
+impl<L> RetryLogic for StatusCodeRetryLogic<L>
+where
+    L: RetryLogic,
+    L::Response: HasStatusCode,
+{
+    type Error = L::Error;
+    type Response = L::Response;
+
+    fn is_retriable_error(&self, error: &Self::Error) -> bool {
+        self.inner.is_retriable_error(error)
+    }
+
+    fn retry_reason(&self, error: &Self::Error) -> Cow<'static, str> {
+        self.inner.retry_reason(error)
+    }
+
+    fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+        let Some(status_codes) = &self.status_codes else {
+            return self.inner.should_retry_response(response);
+        };
+
+        let status = response.status_code();
+        if status_codes.contains(status) {
+            return RetryAction::Retry(format!("response status: {status}").into());
+        }
+        match self.inner.should_retry_response(response) {
+            RetryAction::Retry(reason) => RetryAction::DontRetry(reason),
+            action => action,
+        }
+    }
+
+    fn is_connection_error(&self, error: &Self::Error) -> bool {
+        self.inner.is_connection_error(error)
+    }
+
+    fn error_category(&self, error: &Self::Error) -> RetryErrorCategory {
+        self.inner.error_category(error)
+    }
+
+    fn response_category(&self, response: &Self::Response) -> RetryErrorCategory {
+        self.inner.response_category(response)
+    }
+
+    fn on_retry(
+        &self,
+        attempt: usize,
+        error: Option<&Self::Error>,
+        response: Option<&Self::Response>,
+    ) {
+        self.inner.on_retry(attempt, error, response);
+    }
+}
+
+/// A source of authentication tokens which can be told to replace its current one.
+pub trait TokenProvider: Send + 'static {
+    /// Replaces the current token, such as after a request was rejected with it.
+    fn refresh(&mut self);
+}
+
+/// A retry logic which refreshes the token of a [`TokenProvider`] before each retry, so that
+/// requests failing because their token expired are retried with a new one.
+///
+/// The retried request itself is left as is, so this only helps services which read the token
+/// from the provider whenever they send a request, rather than requests carrying their token.
+///
+/// Requests are classified by the wrapped retry logic.
+pub struct TokenRefreshRetryLogic<L, P> {
+    inner: L,
+    provider: Arc<Mutex<P>>,
+}
+
+impl<L, P> TokenRefreshRetryLogic<L, P> {
+    pub const fn new(inner: L, provider: Arc<Mutex<P>>) -> Self {
+        Self { inner, provider }
+    }
+}
+
+// Derived impls would needlessly require `P: Clone` and `P: Debug`.
+impl<L: Clone, P> Clone for TokenRefreshRetryLogic<L, P> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone(), Arc::clone(&self.provider))
+    }
+}
+
+impl<L: std::fmt::Debug, P> std::fmt::Debug for TokenRefreshRetryLogic<L, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenRefreshRetryLogic")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<L, P> RetryLogic for TokenRefreshRetryLogic<L, P>
+where
+    L: RetryLogic,
+    P: TokenProvider,
+{
+    type Error = L::Error;
+    type Response = L::Response;
+
+    fn is_retriable_error(&self, error: &Self::Error) -> bool {
+        self.inner.is_retriable_error(error)
+    }
+
+    fn retry_reason(&self, error: &Self::Error) -> Cow<'static, str> {
+        self.inner.retry_reason(error)
+    }
+
+    fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+        self.inner.should_retry_response(response)
+    }
+
+    fn is_connection_error(&self, error: &Self::Error) -> bool {
+        self.inner.is_connection_error(error)
+    }
+
+    fn error_category(&self, error: &Self::Error) -> RetryErrorCategory {
+        self.inner.error_category(error)
+    }
+
+    fn response_category(&self, response: &Self::Response) -> RetryErrorCategory {
+        self.inner.response_category(response)
+    }
+
+    fn on_retry(
+        &self,
+        attempt: usize,
+        error: Option<&Self::Error>,
+        response: Option<&Self::Response>,
+    ) {
+        self.provider
+            .lock()
+            .expect("token provider poisoned")
+            .refresh();
+        self.inner.on_retry(attempt, error, response);
+    }
 }
 
 /// The jitter mode to use for retry backoff behavior.
 #[configurable_component]
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum JitterMode {
     /// No jitter.
     None,
@@ -62,19 +492,36 @@ pub enum JitterMode {
     Full,
 }
 
+/// Retry attempts from which requests are retried without limit, as they are by default.
+const UNBOUNDED_RETRY_ATTEMPTS: usize = GlobalTowerRequestConfigDefaults::RETRY_ATTEMPTS;
+
 #[derive(Debug, Clone)]
 pub struct FibonacciRetryPolicy<L> {
+    attempt: usize,
+    total_backoff: Duration,
     remaining_attempts: usize,
+    unbounded: bool,
     previous_duration: Duration,
     current_duration: Duration,
     jitter_mode: JitterMode,
     current_jitter_duration: Duration,
     max_duration: Duration,
     logic: L,
+    initial_batch: Option<Box<FibonacciRetryPolicy<L>>>,
+    large_batch: Option<LargeBatchRetry>,
+    any_error: bool,
+    retryable_errors: RetryableErrorsConfig,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LargeBatchRetry {
+    min_bytes: usize,
+    retry_attempts: usize,
 }
 
 pub struct RetryPolicyFuture<L: RetryLogic> {
-    delay: Pin<Box<Sleep>>,
+    delay: ClockSleep,
     policy: FibonacciRetryPolicy<L>,
 }
 
@@ -87,14 +534,72 @@ impl<L: RetryLogic> FibonacciRetryPolicy<L> {
         jitter_mode: JitterMode,
     ) -> Self {
         FibonacciRetryPolicy {
+            attempt: 1,
+            total_backoff: Duration::ZERO,
             remaining_attempts,
+            unbounded: remaining_attempts >= UNBOUNDED_RETRY_ATTEMPTS,
             previous_duration: Duration::from_secs(0),
             current_duration: initial_backoff,
             jitter_mode,
             current_jitter_duration: Self::add_full_jitter(initial_backoff),
             max_duration,
             logic,
+            initial_batch: None,
+            large_batch: None,
+            any_error: false,
+            retryable_errors: RetryableErrorsConfig::default(),
+            clock: Arc::new(TokioClock),
+        }
+    }
+
+    /// Uses `policy` instead of this one to retry the first batch of each partition.
+    ///
+    /// This only has an effect for sinks which track the batches sent for each partition, such as
+    /// ordered partition batch sinks.
+    pub fn with_initial_batch(mut self, policy: FibonacciRetryPolicy<L>) -> Self {
+        self.initial_batch = Some(Box::new(policy));
+        self
+    }
+
+    /// Retries batches of at least `min_bytes` bytes at most `retry_attempts` times, if that is
+    /// fewer than this policy otherwise allows.
+    ///
+    /// This only has an effect for sinks which send their batches through a batch sink, as those
+    /// track the size of each batch.
+    pub const fn with_large_batch(mut self, min_bytes: usize, retry_attempts: usize) -> Self {
+        self.large_batch = Some(LargeBatchRetry {
+            min_bytes,
+            retry_attempts,
+        });
+        self
+    }
+
+    /// Retries every error, including the ones the retry logic deems non-retriable and the ones it
+    /// does not know about.
+    ///
+    /// Errors such as authentication failures are then retried until retries are exhausted, so
+    /// this is not meant for production use.
+    pub const fn with_any_error(mut self) -> Self {
+        self.any_error = true;
+        self
+    }
+
+    /// Only retries the categories of failures permitted by `retryable_errors`, on top of the
+    /// decisions of the retry logic.
+    pub const fn with_retryable_errors(mut self, retryable_errors: RetryableErrorsConfig) -> Self {
+        self.retryable_errors = retryable_errors;
+        self
+    }
+
+    /// Waits out backoffs and checks request deadlines against `clock` rather than the tokio
+    /// clock, including for the policy retrying initial batches, if any.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        if let Some(initial_batch) = self.initial_batch.as_deref_mut() {
+            initial_batch.clock = Arc::clone(&clock);
         }
+        self.clock = clock;
+        self
     }
 
     fn add_full_jitter(d: Duration) -> Duration {
@@ -109,13 +614,22 @@ impl<L: RetryLogic> FibonacciRetryPolicy<L> {
         );
 
         FibonacciRetryPolicy {
+            attempt: self.attempt + 1,
+            total_backoff: self.total_backoff + self.backoff(),
             remaining_attempts: self.remaining_attempts - 1,
+            unbounded: self.unbounded,
             previous_duration: self.current_duration,
             current_duration: next_duration,
             current_jitter_duration: Self::add_full_jitter(next_duration),
             jitter_mode: self.jitter_mode,
             max_duration: self.max_duration,
             logic: self.logic.clone(),
+            // Whether the request is an initial batch is settled on its first retry.
+            initial_batch: None,
+            large_batch: self.large_batch,
+            any_error: self.any_error,
+            retryable_errors: self.retryable_errors,
+            clock: Arc::clone(&self.clock),
         }
     }
 
@@ -126,39 +640,113 @@ impl<L: RetryLogic> FibonacciRetryPolicy<L> {
         }
     }
 
-    fn build_retry(&self) -> RetryPolicyFuture<L> {
-        let policy = self.advance();
-        let delay = Box::pin(sleep(self.backoff()));
+    /// Whether the request currently being processed has used up its retries.
+    fn retries_exhausted(&self) -> bool {
+        let large_batch_exhausted = self.large_batch.map_or(false, |large_batch| {
+            current_batch_bytes().map_or(false, |bytes| bytes >= large_batch.min_bytes)
+                && self.attempt > large_batch.retry_attempts
+        });
+        self.remaining_attempts == 0 || large_batch_exhausted
+    }
 
-        debug!(message = "Retrying request.", delay_ms = %self.backoff().as_millis());
-        RetryPolicyFuture { delay, policy }
+    /// Whether `result` is a failure the request currently being processed would be retried for,
+    /// if it had retries left, while it has none.
+    fn exhausts_retries<Res>(&self, result: Result<&Res, &Error>) -> bool
+    where
+        L: RetryLogic<Response = Res>,
+    {
+        let retried = match result {
+            Ok(response) => {
+                self.logic.should_retry_response(response).is_retryable()
+                    && self
+                        .retryable_errors
+                        .permits(self.logic.response_category(response))
+            }
+            Err(_) => true,
+        };
+        retried && self.retries_exhausted()
     }
-}
 
-impl<Req, Res, L> Policy<Req, Res, Error> for FibonacciRetryPolicy<L>
-where
-    Req: Clone,
-    L: RetryLogic<Response = Res>,
-{
-    type Future = RetryPolicyFuture<L>;
+    /// The policy retrying the request currently being processed.
+    fn current(&self) -> &Self {
+        match self.initial_batch.as_deref() {
+            Some(initial_batch) if is_initial_batch() => initial_batch,
+            _ => self,
+        }
+    }
+
+    /// Whether waiting out the next backoff would run past the deadline of the request currently
+    /// being processed, if it has one.
+    fn exceeds_deadline(&self) -> bool {
+        current_deadline().map_or(false, |deadline| {
+            self.clock.now() + self.backoff() >= deadline
+        })
+    }
+
+    fn build_retry(
+        &self,
+        error_kind: &'static str,
+        reason: &str,
+        error: Option<&L::Error>,
+        response: Option<&L::Response>,
+    ) -> RetryPolicyFuture<L> {
+        let policy = self.advance();
+        self.logic.on_retry(policy.attempt, error, response);
+        let delay = self.clock.sleep(self.backoff());
+
+        emit!(RequestRetry {
+            attempt: policy.attempt,
+            backoff: self.backoff(),
+            error_kind,
+            reason,
+        });
+        record_retry(self.backoff(), error_kind);
+        count_retry();
+        RetryPolicyFuture { delay, policy }
+    }
 
     // NOTE: in the error cases- `Error` and `EventsDropped` internal events are emitted by the
     // driver, so only need to log here.
-    fn retry(&self, _: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+    fn retry_result<Res>(&self, result: Result<&Res, &Error>) -> Option<RetryPolicyFuture<L>>
+    where
+        L: RetryLogic<Response = Res>,
+    {
         match result {
             Ok(response) => match self.logic.should_retry_response(response) {
                 RetryAction::Retry(reason) => {
-                    if self.remaining_attempts == 0 {
+                    let category = self.logic.response_category(response);
+                    if !self.retryable_errors.permits(category) {
+                        error!(
+                            message = "OK/retry response but its category is not retryable; dropping the request.",
+                            reason = ?reason,
+                            ?category,
+                            internal_log_rate_limit = true,
+                        );
+                        return None;
+                    }
+
+                    if self.retries_exhausted() {
                         error!(
                             message = "OK/retry response but retries exhausted; dropping the request.",
                             reason = ?reason,
                             internal_log_rate_limit = true,
                         );
+                        emit!(RequestRetriesExhausted {
+                            attempts: self.attempt
+                        });
+                        return None;
+                    }
+
+                    if self.exceeds_deadline() {
+                        error!(
+                            message = "OK/retry response but request deadline reached; dropping the request.",
+                            reason = ?reason,
+                            internal_log_rate_limit = true,
+                        );
                         return None;
                     }
 
-                    warn!(message = "Retrying after response.", reason = %reason, internal_log_rate_limit = true);
-                    Some(self.build_retry())
+                    Some(self.build_retry("response", &reason, None, Some(response)))
                 }
 
                 RetryAction::DontRetry(reason) => {
@@ -166,18 +754,54 @@ where
                     None
                 }
 
-                RetryAction::Successful => None,
+                RetryAction::Successful => {
+                    if self.attempt > 1 {
+                        emit!(RequestRetrySucceeded {
+                            attempts: self.attempt,
+                            total_backoff: self.total_backoff,
+                        });
+                    }
+                    None
+                }
             },
             Err(error) => {
-                if self.remaining_attempts == 0 {
+                if self.retries_exhausted() {
                     error!(message = "Retries exhausted; dropping the request.", %error, internal_log_rate_limit = true);
+                    emit!(RequestRetriesExhausted {
+                        attempts: self.attempt
+                    });
+                    record_retries_exhausted(self.attempt);
+                    return None;
+                }
+
+                if self.exceeds_deadline() {
+                    error!(message = "Request deadline reached; dropping the request.", %error, internal_log_rate_limit = true);
+                    return None;
+                }
+
+                let category = match error.downcast_ref::<L::Error>() {
+                    Some(expected) => self.logic.error_category(expected),
+                    None if is_timeout(error) => RetryErrorCategory::Timeout,
+                    None => RetryErrorCategory::Other,
+                };
+                if !self.retryable_errors.permits(category) {
+                    error!(
+                        message = "Error category is not retryable; dropping the request.",
+                        %error,
+                        ?category,
+                        internal_log_rate_limit = true,
+                    );
                     return None;
                 }
 
                 if let Some(expected) = error.downcast_ref::<L::Error>() {
-                    if self.logic.is_retriable_error(expected) {
-                        warn!(message = "Retrying after error.", error = %expected, internal_log_rate_limit = true);
-                        Some(self.build_retry())
+                    if self.any_error || self.logic.is_retriable_error(expected) {
+                        Some(self.build_retry(
+                            "error",
+                            &self.logic.retry_reason(expected),
+                            Some(expected),
+                            None,
+                        ))
                     } else {
                         error!(
                             message = "Non-retriable error; dropping the request.",
@@ -186,12 +810,10 @@ where
                         );
                         None
                     }
-                } else if error.downcast_ref::<Elapsed>().is_some() {
-                    warn!(
-                        message = "Request timed out. If this happens often while the events are actually reaching their destination, try decreasing `batch.max_bytes` and/or using `compression` if applicable. Alternatively `request.timeout_secs` can be increased.",
-                        internal_log_rate_limit = true
-                    );
-                    Some(self.build_retry())
+                } else if is_timeout(error) {
+                    Some(self.build_retry("timeout", "request timed out", None, None))
+                } else if self.any_error {
+                    Some(self.build_retry("error", &error.to_string(), None, None))
                 } else {
                     error!(
                         message = "Unexpected error type; dropping the request.",
@@ -203,6 +825,18 @@ where
             }
         }
     }
+}
+
+impl<Req, Res, L> Policy<Req, Res, Error> for FibonacciRetryPolicy<L>
+where
+    Req: Clone,
+    L: RetryLogic<Response = Res>,
+{
+    type Future = RetryPolicyFuture<L>;
+
+    fn retry(&self, _: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+        self.current().retry_result(result)
+    }
 
     fn clone_request(&self, request: &Req) -> Option<Req> {
         Some(request.clone())
@@ -213,27 +847,441 @@ where
 // therefore this safe.
 impl<L: RetryLogic> Unpin for RetryPolicyFuture<L> {}
 
-impl<L: RetryLogic> Future for RetryPolicyFuture<L> {
-    type Output = FibonacciRetryPolicy<L>;
+mod sealed {
+    pub trait Sealed {}
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        std::task::ready!(self.delay.poll_unpin(cx));
-        Poll::Ready(self.policy.clone())
-    }
+    impl<L> Sealed for super::FibonacciRetryPolicy<L> {}
+    impl<L> Sealed for super::IdempotencyAwareRetryPolicy<L> {}
+    impl<P, D> Sealed for super::DeadLetter<P, D> {}
 }
 
-impl RetryAction {
-    pub const fn is_retryable(&self) -> bool {
-        matches!(self, RetryAction::Retry(_))
-    }
+/// Exposes the state of the retry policies of this module to middleware.
+pub trait RetryPolicyExt: sealed::Sealed {
+    /// The responses of the requests retried by the policy.
+    type Response;
 
-    pub const fn is_not_retryable(&self) -> bool {
-        matches!(self, RetryAction::DontRetry(_))
-    }
+    /// The number of times the request currently being processed may still be retried, or `None`
+    /// if it is retried without limit.
+    fn remaining_attempts(&self) -> Option<usize>;
 
-    pub const fn is_successful(&self) -> bool {
-        matches!(self, RetryAction::Successful)
-    }
+    /// Whether `response` is a failure, whether or not the policy retries it.
+    fn is_failure(&self, response: &Self::Response) -> bool;
+
+    /// The number of attempts made for the request currently being processed, counting the
+    /// original request as the first, if the policy gives up on it after `result` for lack of
+    /// retries left.
+    fn exhausted_after(&self, result: Result<&Self::Response, &Error>) -> Option<usize>;
+}
+
+impl<L: RetryLogic> RetryPolicyExt for FibonacciRetryPolicy<L> {
+    type Response = L::Response;
+
+    fn remaining_attempts(&self) -> Option<usize> {
+        (!self.unbounded).then_some(self.remaining_attempts)
+    }
+
+    fn is_failure(&self, response: &Self::Response) -> bool {
+        !self.logic.should_retry_response(response).is_successful()
+    }
+
+    fn exhausted_after(&self, result: Result<&Self::Response, &Error>) -> Option<usize> {
+        let policy = self.current();
+        policy.exhausts_retries(result).then_some(policy.attempt)
+    }
+}
+
+impl<L: RetryLogic> RetryPolicyExt for IdempotencyAwareRetryPolicy<L> {
+    type Response = L::Response;
+
+    fn remaining_attempts(&self) -> Option<usize> {
+        self.inner.remaining_attempts()
+    }
+
+    fn is_failure(&self, response: &Self::Response) -> bool {
+        self.inner.is_failure(response)
+    }
+
+    fn exhausted_after(&self, result: Result<&Self::Response, &Error>) -> Option<usize> {
+        self.inner.exhausted_after(result)
+    }
+}
+
+/// Records the number of retries left to a request after each of its failures, and the requests
+/// which ran out of retries.
+pub trait RetryMetrics: Clone + Send + Sync + 'static {
+    fn record_remaining_attempts(&self, remaining_attempts: usize);
+
+    /// Records that a request failed for the last time after `attempts` attempts, as it had no
+    /// retries left.
+    fn record_retries_exhausted(&self, attempts: usize);
+}
+
+/// Reports the retries left to requests as an internal metric.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InternalRetryMetrics;
+
+impl RetryMetrics for InternalRetryMetrics {
+    fn record_remaining_attempts(&self, remaining_attempts: usize) {
+        emit!(RequestRetriesRemaining { remaining_attempts });
+    }
+
+    fn record_retries_exhausted(&self, _attempts: usize) {
+        // The retry policies of this module already emit `RequestRetriesExhausted` themselves.
+    }
+}
+
+/// Records the retries left to requests, for tests.
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Clone, Debug, Default)]
+pub struct MetricsSink {
+    remaining_attempts: Arc<std::sync::Mutex<Vec<usize>>>,
+    retries_exhausted: Arc<std::sync::Mutex<Vec<usize>>>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl MetricsSink {
+    /// The retries left recorded so far, in order.
+    pub fn remaining_attempts(&self) -> Vec<usize> {
+        self.remaining_attempts
+            .lock()
+            .expect("metrics sink poisoned")
+            .clone()
+    }
+
+    /// The attempts made by the requests which ran out of retries so far, in order.
+    pub fn retries_exhausted(&self) -> Vec<usize> {
+        self.retries_exhausted
+            .lock()
+            .expect("metrics sink poisoned")
+            .clone()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl RetryMetrics for MetricsSink {
+    fn record_remaining_attempts(&self, remaining_attempts: usize) {
+        self.remaining_attempts
+            .lock()
+            .expect("metrics sink poisoned")
+            .push(remaining_attempts);
+    }
+
+    fn record_retries_exhausted(&self, attempts: usize) {
+        self.retries_exhausted
+            .lock()
+            .expect("metrics sink poisoned")
+            .push(attempts);
+    }
+}
+
+/// Observes the retries left to requests, and the requests which run out of them, through the
+/// wrapped retry policy.
+#[derive(Clone, Debug)]
+pub struct RetryObserverLayer<M> {
+    metrics: M,
+}
+
+impl<M: RetryMetrics> RetryObserverLayer<M> {
+    pub const fn new(metrics: M) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<P, M: RetryMetrics> tower::Layer<P> for RetryObserverLayer<M> {
+    type Service = RetryObserver<P, M>;
+
+    fn layer(&self, inner: P) -> Self::Service {
+        RetryObserver {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// A retry policy which records the retries left to a request after each of its failures, that is
+/// after each error and each response which is retried.
+///
+/// Retried requests are recorded once their backoff has passed, with the attempts left to the
+/// policy which retries them. Requests which are not retried after an error have no attempts left.
+/// Nothing is recorded for requests which are retried without limit.
+#[derive(Clone, Debug)]
+pub struct RetryObserver<P, M> {
+    inner: P,
+    metrics: M,
+}
+
+pub struct RetryObserverFuture<F, M> {
+    inner: F,
+    metrics: M,
+}
+
+impl<Req, Res, P, M> Policy<Req, Res, Error> for RetryObserver<P, M>
+where
+    P: Policy<Req, Res, Error> + RetryPolicyExt,
+    P::Future: Unpin,
+    M: RetryMetrics,
+{
+    type Future = RetryObserverFuture<P::Future, M>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+        match self.inner.retry(req, result) {
+            Some(inner) => Some(RetryObserverFuture {
+                inner,
+                metrics: self.metrics.clone(),
+            }),
+            None => {
+                if let Some(attempts) = self.inner.exhausted_after(result) {
+                    self.metrics.record_retries_exhausted(attempts);
+                }
+                if result.is_err() && self.inner.remaining_attempts().is_some() {
+                    self.metrics.record_remaining_attempts(0);
+                }
+                None
+            }
+        }
+    }
+
+    fn clone_request(&self, request: &Req) -> Option<Req> {
+        self.inner.clone_request(request)
+    }
+}
+
+impl<F, M> Future for RetryObserverFuture<F, M>
+where
+    F: Future + Unpin,
+    F::Output: RetryPolicyExt,
+    M: RetryMetrics,
+{
+    type Output = RetryObserver<F::Output, M>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = std::task::ready!(self.inner.poll_unpin(cx));
+        if let Some(remaining_attempts) = inner.remaining_attempts() {
+            self.metrics.record_remaining_attempts(remaining_attempts);
+        }
+        Poll::Ready(RetryObserver {
+            inner,
+            metrics: self.metrics.clone(),
+        })
+    }
+}
+
+/// Receives the requests a retry policy gave up on, along with their last failure.
+///
+/// This is implemented for closures taking the request and its last result, which is either a
+/// failed response or an error.
+pub trait DeadLetterHandler<Req, Res>: Clone + Send + Sync + 'static {
+    fn give_up(&self, request: &Req, failure: Result<&Res, &Error>);
+}
+
+impl<Req, Res, F> DeadLetterHandler<Req, Res> for F
+where
+    F: Fn(&Req, Result<&Res, &Error>) + Clone + Send + Sync + 'static,
+{
+    fn give_up(&self, request: &Req, failure: Result<&Res, &Error>) {
+        self(request, failure)
+    }
+}
+
+/// Hands the requests the wrapped retry policy gives up on to a [`DeadLetterHandler`].
+#[derive(Clone, Debug)]
+pub struct DeadLetterLayer<D> {
+    handler: D,
+}
+
+impl<D> DeadLetterLayer<D> {
+    pub const fn new(handler: D) -> Self {
+        Self { handler }
+    }
+}
+
+impl<P, D: Clone> tower::Layer<P> for DeadLetterLayer<D> {
+    type Service = DeadLetter<P, D>;
+
+    fn layer(&self, inner: P) -> Self::Service {
+        DeadLetter {
+            inner,
+            handler: self.handler.clone(),
+        }
+    }
+}
+
+/// A retry policy which hands each request it gives up on to a handler, so that sinks can route
+/// them to a dead letter destination rather than lose them.
+///
+/// A request is given up on when it fails and is not retried, be it for a non-retriable failure,
+/// for lack of retries left or for reaching its deadline. The handler is called once per request,
+/// with its last failure, before the failure is returned to the caller. Requests which succeed,
+/// even after being retried, are not handed to it.
+#[derive(Clone, Debug)]
+pub struct DeadLetter<P, D> {
+    inner: P,
+    handler: D,
+}
+
+pub struct DeadLetterFuture<F, D> {
+    inner: F,
+    handler: D,
+}
+
+impl<Req, Res, P, D> Policy<Req, Res, Error> for DeadLetter<P, D>
+where
+    P: Policy<Req, Res, Error> + RetryPolicyExt<Response = Res>,
+    P::Future: Unpin,
+    D: DeadLetterHandler<Req, Res> + Unpin,
+{
+    type Future = DeadLetterFuture<P::Future, D>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+        match self.inner.retry(req, result) {
+            Some(inner) => Some(DeadLetterFuture {
+                inner,
+                handler: self.handler.clone(),
+            }),
+            None => {
+                let failed = match result {
+                    Ok(response) => self.inner.is_failure(response),
+                    Err(_) => true,
+                };
+                if failed {
+                    self.handler.give_up(req, result);
+                }
+                None
+            }
+        }
+    }
+
+    fn clone_request(&self, request: &Req) -> Option<Req> {
+        self.inner.clone_request(request)
+    }
+}
+
+impl<P: RetryPolicyExt, D> RetryPolicyExt for DeadLetter<P, D> {
+    type Response = P::Response;
+
+    fn remaining_attempts(&self) -> Option<usize> {
+        self.inner.remaining_attempts()
+    }
+
+    fn is_failure(&self, response: &Self::Response) -> bool {
+        self.inner.is_failure(response)
+    }
+
+    fn exhausted_after(&self, result: Result<&Self::Response, &Error>) -> Option<usize> {
+        self.inner.exhausted_after(result)
+    }
+}
+
+impl<F, D> Future for DeadLetterFuture<F, D>
+where
+    F: Future + Unpin,
+    D: Clone + Unpin,
+{
+    type Output = DeadLetter<F::Output, D>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = std::task::ready!(self.inner.poll_unpin(cx));
+        Poll::Ready(DeadLetter {
+            inner,
+            handler: self.handler.clone(),
+        })
+    }
+}
+
+/// A retry policy which only retries requests that are not idempotent when it is certain they
+/// were never processed.
+///
+/// Idempotent requests are retried exactly as [`FibonacciRetryPolicy`] would. Requests that are not
+/// idempotent are only retried after errors classified by [`RetryLogic::is_connection_error`], as
+/// any other failure, including timeouts, leaves open the possibility that the request was
+/// already processed downstream.
+#[derive(Debug, Clone)]
+pub struct IdempotencyAwareRetryPolicy<L> {
+    inner: FibonacciRetryPolicy<L>,
+}
+
+pub struct IdempotencyAwareRetryPolicyFuture<L: RetryLogic>(RetryPolicyFuture<L>);
+
+impl<L: RetryLogic> IdempotencyAwareRetryPolicy<L> {
+    pub const fn new(inner: FibonacciRetryPolicy<L>) -> Self {
+        Self { inner }
+    }
+
+    fn may_have_been_processed<Res>(&self, result: Result<&Res, &Error>) -> bool
+    where
+        L: RetryLogic<Response = Res>,
+    {
+        match result {
+            Ok(response) => self
+                .inner
+                .logic
+                .should_retry_response(response)
+                .is_retryable(),
+            Err(error) => !error
+                .downcast_ref::<L::Error>()
+                .map_or(false, |error| self.inner.logic.is_connection_error(error)),
+        }
+    }
+}
+
+impl<Req, Res, L> Policy<Req, Res, Error> for IdempotencyAwareRetryPolicy<L>
+where
+    Req: Clone + Idempotency,
+    L: RetryLogic<Response = Res>,
+{
+    type Future = IdempotencyAwareRetryPolicyFuture<L>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+        if !req.is_idempotent() && self.may_have_been_processed(result) {
+            error!(
+                message =
+                    "Request is not idempotent and may have been processed; dropping the request.",
+                internal_log_rate_limit = true,
+            );
+            return None;
+        }
+
+        self.inner
+            .retry(req, result)
+            .map(IdempotencyAwareRetryPolicyFuture)
+    }
+
+    fn clone_request(&self, request: &Req) -> Option<Req> {
+        Some(request.clone())
+    }
+}
+
+impl<L: RetryLogic> Future for IdempotencyAwareRetryPolicyFuture<L> {
+    type Output = IdempotencyAwareRetryPolicy<L>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0
+            .poll_unpin(cx)
+            .map(|inner| IdempotencyAwareRetryPolicy { inner })
+    }
+}
+
+impl<L: RetryLogic> Future for RetryPolicyFuture<L> {
+    type Output = FibonacciRetryPolicy<L>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        std::task::ready!(self.delay.poll_unpin(cx));
+        Poll::Ready(self.policy.clone())
+    }
+}
+
+impl RetryAction {
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self, RetryAction::Retry(_))
+    }
+
+    pub const fn is_not_retryable(&self) -> bool {
+        matches!(self, RetryAction::DontRetry(_))
+    }
+
+    pub const fn is_successful(&self) -> bool {
+        matches!(self, RetryAction::Successful)
+    }
 }
 
 // `tokio-retry` crate
@@ -322,11 +1370,20 @@ mod tests {
 
     use tokio::time;
     use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
-    use tower::retry::RetryLayer;
+    use tower::{retry::RetryLayer, timeout::error::Elapsed, Layer};
     use tower_test::{assert_request_eq, mock};
+    use vector_lib::event_test_util;
 
     use super::*;
-    use crate::test_util::trace_init;
+    use crate::{
+        event::metric::MetricValue,
+        metrics,
+        sinks::util::{
+            service::{GlobalTowerRequestConfigDefaults, ManualClock},
+            TowerRequestConfig,
+        },
+        test_util::trace_init,
+    };
 
     #[tokio::test]
     async fn service_error_retry() {
@@ -360,6 +1417,40 @@ mod tests {
         assert_eq!(fut.await.unwrap(), "world");
     }
 
+    #[tokio::test]
+    async fn backoffs_are_waited_out_on_the_policy_clock() {
+        let clock = ManualClock::new();
+        let policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        )
+        .with_clock(clock.clone());
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+
+        for _ in 0..4 {
+            assert_request_eq!(handle, "hello").send_error(Error(true));
+            assert_pending!(fut.poll());
+
+            let backoff = *clock.sleeps().last().unwrap();
+            clock.advance(backoff);
+            assert_pending!(fut.poll());
+        }
+
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+        assert_eq!(
+            clock.sleeps(),
+            [1, 1, 2, 3].map(Duration::from_secs).to_vec()
+        );
+    }
+
     #[tokio::test]
     async fn service_error_no_retry() {
         trace_init();
@@ -382,68 +1473,620 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn timeout_error() {
+    async fn retry_observer_records_remaining_attempts() {
         trace_init();
 
         time::pause();
 
         let policy = FibonacciRetryPolicy::new(
-            5,
+            2,
             Duration::from_secs(1),
             Duration::from_secs(10),
             SvcRetryLogic,
             JitterMode::None,
         );
+        let metrics = MetricsSink::default();
+        let policy = RetryObserverLayer::new(metrics.clone()).layer(policy);
 
         let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
 
         assert_ready_ok!(svc.poll_ready());
-
         let mut fut = task::spawn(svc.call("hello"));
-        assert_request_eq!(handle, "hello").send_error(Elapsed::new());
+
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_secs(2)).await;
         assert_pending!(fut.poll());
+        assert_eq!(metrics.remaining_attempts(), [1]);
 
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_pending!(fut.poll());
         time::advance(Duration::from_secs(2)).await;
         assert_pending!(fut.poll());
+        assert_eq!(metrics.remaining_attempts(), [1, 0]);
 
-        assert_request_eq!(handle, "hello").send_response("world");
-        assert_eq!(fut.await.unwrap(), "world");
+        // The last failure is not retried, leaving no attempts.
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_ready_err!(fut.poll());
+        assert_eq!(metrics.remaining_attempts(), [1, 0, 0]);
     }
 
-    #[test]
-    fn backoff_grows_to_max() {
-        let mut policy = FibonacciRetryPolicy::new(
-            10,
+    #[tokio::test]
+    async fn retry_observer_ignores_unbounded_retries() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FibonacciRetryPolicy::new(
+            UNBOUNDED_RETRY_ATTEMPTS,
             Duration::from_secs(1),
             Duration::from_secs(10),
             SvcRetryLogic,
             JitterMode::None,
         );
-        assert_eq!(Duration::from_secs(1), policy.backoff());
+        let metrics = MetricsSink::default();
+        let policy = RetryObserverLayer::new(metrics.clone()).layer(policy);
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(1), policy.backoff());
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(2), policy.backoff());
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(3), policy.backoff());
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(5), policy.backoff());
+        assert_request_eq!(handle, "hello").send_error(Error(false));
+        assert_ready_err!(fut.poll());
+        assert!(metrics.remaining_attempts().is_empty());
+    }
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(8), policy.backoff());
+    #[tokio::test]
+    async fn retry_observer_records_exhausted_retries() {
+        trace_init();
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(10), policy.backoff());
+        time::pause();
 
-        policy = policy.advance();
-        assert_eq!(Duration::from_secs(10), policy.backoff());
-    }
+        let policy = FibonacciRetryPolicy::new(
+            1,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            StatusRetryLogic,
+            JitterMode::None,
+        );
+        let metrics = MetricsSink::default();
+        let policy = RetryObserverLayer::new(metrics.clone()).layer(policy);
 
-    #[test]
+        let (mut svc, mut handle) = mock::spawn_layer::<_, Status, _>(RetryLayer::new(policy));
+
+        // A response which is retried until no retries are left.
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("response"));
+        assert_request_eq!(handle, "response").send_response(Status(503));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "response").send_response(Status(503));
+        assert_eq!(assert_ready_ok!(fut.poll()).0, 503);
+        assert_eq!(metrics.retries_exhausted(), [2]);
+
+        // An error which is retried until no retries are left.
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("error"));
+        assert_request_eq!(handle, "error").send_error(Error(true));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "error").send_error(Error(true));
+        assert_ready_err!(fut.poll());
+        assert_eq!(metrics.retries_exhausted(), [2, 2]);
+
+        // Failures which are not retried don't exhaust the retries.
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("rejected"));
+        assert_request_eq!(handle, "rejected").send_response(Status(400));
+        assert_ready_ok!(fut.poll());
+        assert_eq!(metrics.retries_exhausted(), [2, 2]);
+    }
+
+    #[tokio::test]
+    async fn dead_letter_receives_requests_given_up_on() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FibonacciRetryPolicy::new(
+            1,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            StatusRetryLogic,
+            JitterMode::None,
+        );
+        let dead_letters = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler = {
+            let dead_letters = Arc::clone(&dead_letters);
+            move |request: &&'static str, failure: Result<&Status, &crate::Error>| {
+                let status = failure.ok().map(|status| status.0);
+                dead_letters.lock().unwrap().push((*request, status));
+            }
+        };
+        let policy = DeadLetterLayer::new(handler).layer(policy);
+
+        let (mut svc, mut handle) = mock::spawn_layer::<_, Status, _>(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+        let fut = svc.call("accepted");
+        assert_request_eq!(handle, "accepted").send_response(Status(200));
+        assert!(fut.await.is_ok());
+
+        assert_ready_ok!(svc.poll_ready());
+        let fut = svc.call("rejected");
+        assert_request_eq!(handle, "rejected").send_response(Status(400));
+        assert!(fut.await.is_ok());
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("failing"));
+        assert_request_eq!(handle, "failing").send_error(Error(true));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "failing").send_error(Error(true));
+        assert_ready_err!(fut.poll());
+
+        // Only the requests which failed for good are handed over, once each.
+        assert_eq!(
+            *dead_letters.lock().unwrap(),
+            [("rejected", Some(400)), ("failing", None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn any_error_retries_non_retriable_errors() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        )
+        .with_any_error();
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(Error(false));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "hello").send_error(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "unauthorized",
+        ));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
+    #[tokio::test]
+    async fn retryable_errors_narrow_retries() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        )
+        .with_retryable_errors(RetryableErrorsConfig {
+            allow: Some([RetryErrorCategory::Timeout].into_iter().collect()),
+            deny: RetryErrorCategories::default(),
+        });
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        // Timeouts are retried.
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(Elapsed::new());
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+
+        // Connection errors are not, even though the retry logic deems them retriable.
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_ready_err!(fut.poll());
+    }
+
+    #[test]
+    fn retryable_errors_permit_categories() {
+        let deny_connection = RetryableErrorsConfig {
+            allow: None,
+            deny: [RetryErrorCategory::Connection].into_iter().collect(),
+        };
+        assert!(deny_connection.permits(RetryErrorCategory::Timeout));
+        assert!(!deny_connection.permits(RetryErrorCategory::Connection));
+
+        let allow_server = RetryableErrorsConfig {
+            allow: Some(
+                [RetryErrorCategory::Server, RetryErrorCategory::Throttled]
+                    .into_iter()
+                    .collect(),
+            ),
+            deny: [RetryErrorCategory::Throttled].into_iter().collect(),
+        };
+        assert!(allow_server.permits(RetryErrorCategory::Server));
+        assert!(!allow_server.permits(RetryErrorCategory::Throttled));
+        assert!(!allow_server.permits(RetryErrorCategory::Other));
+    }
+
+    #[tokio::test]
+    async fn timeout_error() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        );
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(Elapsed::new());
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
+    #[tokio::test]
+    async fn idempotency_aware_retry() {
+        trace_init();
+
+        time::pause();
+
+        let policy = IdempotencyAwareRetryPolicy::new(FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        ));
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        // Idempotent requests are retried after a timeout.
+        let request = Request {
+            body: "hello",
+            idempotent: true,
+        };
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call(request));
+        assert_request_eq!(handle, request).send_error(Elapsed::new());
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+
+        assert_request_eq!(handle, request).send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+
+        // Non-idempotent requests are not, since they may have been processed before timing out.
+        let request = Request {
+            body: "hello",
+            idempotent: false,
+        };
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call(request));
+        assert_request_eq!(handle, request).send_error(Elapsed::new());
+        assert_ready_err!(fut.poll());
+
+        // They are still retried after connection errors, which guarantee they never got through.
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call(request));
+        assert_request_eq!(handle, request).send_error(Error(true));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+
+        assert_request_eq!(handle, request).send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
+    #[tokio::test]
+    async fn io_error_retry_logic() {
+        time::pause();
+
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+        let policy: FibonacciRetryPolicy<IoErrorRetryLogic<&'static str>> =
+            settings.retry_policy(IoErrorRetryLogic::new());
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "broken pipe",
+        ));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
+    async fn retries_made(byte_size: usize) -> usize {
+        let mut policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        )
+        .with_large_batch(1000, 1);
+        let error: crate::Error = Box::new(Error(true));
+
+        send_batch(byte_size, || async move {
+            let mut retries = 0;
+            while let Some(retry) = Policy::<_, &str, _>::retry(&policy, &"hello", Err(&error)) {
+                policy = retry.await;
+                retries += 1;
+            }
+            retries
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn retry_success_is_reported_after_retries_only() {
+        time::pause();
+        event_test_util::clear_recorded_events();
+
+        let mut policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        );
+        assert!(Policy::<_, &str, _>::retry(&policy, &"hello", Ok(&"world")).is_none());
+        assert!(event_test_util::contains_name_once("RequestRetrySucceeded").is_err());
+
+        let error: crate::Error = Box::new(Error(true));
+        for _ in 0..3 {
+            policy = Policy::<_, &str, _>::retry(&policy, &"hello", Err(&error))
+                .unwrap()
+                .await;
+        }
+        assert_eq!(policy.attempt, 4);
+        assert_eq!(policy.total_backoff, Duration::from_secs(4));
+
+        assert!(Policy::<_, &str, _>::retry(&policy, &"hello", Ok(&"world")).is_none());
+        event_test_util::contains_name_once("RequestRetrySucceeded").unwrap();
+    }
+
+    /// Records the attempts each retry is made for, and counts the token refreshes.
+    #[derive(Debug, Clone, Default)]
+    struct RecordingRetryLogic {
+        attempts: Arc<Mutex<Vec<(usize, bool)>>>,
+    }
+
+    impl RetryLogic for RecordingRetryLogic {
+        type Error = Error;
+        type Response = &'static str;
+
+        fn is_retriable_error(&self, error: &Self::Error) -> bool {
+            error.0
+        }
+
+        fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+            match *response {
+                "retry" => RetryAction::Retry("retry".into()),
+                _ => RetryAction::Successful,
+            }
+        }
+
+        fn on_retry(
+            &self,
+            attempt: usize,
+            error: Option<&Self::Error>,
+            _response: Option<&Self::Response>,
+        ) {
+            self.attempts
+                .lock()
+                .unwrap()
+                .push((attempt, error.is_some()));
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingTokenProvider {
+        refreshes: usize,
+    }
+
+    impl TokenProvider for CountingTokenProvider {
+        fn refresh(&mut self) {
+            self.refreshes += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn on_retry_is_called_before_each_retry() {
+        time::pause();
+
+        let logic = RecordingRetryLogic::default();
+        let provider = Arc::new(Mutex::new(CountingTokenProvider::default()));
+        let mut policy = FibonacciRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            TokenRefreshRetryLogic::new(logic.clone(), Arc::clone(&provider)),
+            JitterMode::None,
+        );
+
+        let error: crate::Error = Box::new(Error(true));
+        policy = Policy::<_, &str, _>::retry(&policy, &"hello", Err(&error))
+            .unwrap()
+            .await;
+        policy = Policy::<_, &str, _>::retry(&policy, &"hello", Ok(&"retry"))
+            .unwrap()
+            .await;
+        assert!(Policy::<_, &str, _>::retry(&policy, &"hello", Ok(&"ok")).is_none());
+
+        // Non-retriable errors aren't retried, so the hook isn't called for them.
+        let fatal: crate::Error = Box::new(Error(false));
+        assert!(Policy::<_, &str, _>::retry(&policy, &"hello", Err(&fatal)).is_none());
+
+        assert_eq!(*logic.attempts.lock().unwrap(), [(2, true), (3, false)]);
+        assert_eq!(provider.lock().unwrap().refreshes, 2);
+    }
+
+    #[tokio::test]
+    async fn retry_metrics_are_emitted_until_retries_are_exhausted() {
+        time::pause();
+        metrics::init_test();
+
+        let mut policy = FibonacciRetryPolicy::new(
+            3,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        );
+        let error: crate::Error = Box::new(Error(true));
+        while let Some(retry) = Policy::<_, &str, _>::retry(&policy, &"hello", Err(&error)) {
+            policy = retry.await;
+        }
+
+        let metrics = metrics::Controller::get()
+            .unwrap()
+            .capture_metrics()
+            .into_iter()
+            .filter(|metric| {
+                metric.name() == "request_retries_total" || metric.name().starts_with("retry_")
+            })
+            .collect::<Vec<_>>();
+        let counter = |name: &str, attempt: Option<&str>| {
+            metrics
+                .iter()
+                .filter(|metric| {
+                    metric.name() == name
+                        && metric.tag_value("attempt_number").as_deref() == attempt
+                })
+                .map(|metric| match metric.value() {
+                    MetricValue::Counter { value } => *value,
+                    value => panic!("{name} is not a counter: {value:?}"),
+                })
+                .sum::<f64>()
+        };
+
+        for attempt in ["2", "3", "4"] {
+            assert_eq!(counter("request_retries_total", Some(attempt)), 1.0);
+        }
+        assert_eq!(counter("retry_exhausted_total", None), 1.0);
+        let backoffs = metrics
+            .iter()
+            .find(|metric| metric.name() == "retry_backoff_duration_seconds")
+            .unwrap();
+        assert!(matches!(
+            backoffs.value(),
+            MetricValue::AggregatedHistogram { count: 3, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn large_batches_are_retried_less() {
+        time::pause();
+
+        assert_eq!(retries_made(10).await, 5);
+        assert_eq!(retries_made(1000).await, 1);
+    }
+
+    #[test]
+    fn status_code_retry_logic_retries_listed_status_codes() {
+        let logic = StatusCodeRetryLogic::new(StatusRetryLogic, Some(&[429, 503]));
+
+        assert!(logic.should_retry_response(&Status(429)).is_retryable());
+        assert!(logic.should_retry_response(&Status(503)).is_retryable());
+        assert!(logic.should_retry_response(&Status(400)).is_not_retryable());
+        assert!(logic.should_retry_response(&Status(500)).is_not_retryable());
+        assert!(logic.should_retry_response(&Status(200)).is_successful());
+    }
+
+    #[test]
+    fn status_code_retry_logic_defaults_to_inner_logic() {
+        let logic = StatusCodeRetryLogic::new(StatusRetryLogic, None);
+
+        assert!(logic.should_retry_response(&Status(429)).is_not_retryable());
+        assert!(logic.should_retry_response(&Status(500)).is_retryable());
+        assert!(logic.should_retry_response(&Status(200)).is_successful());
+    }
+
+    #[test]
+    fn backoff_grows_to_max() {
+        let mut policy = FibonacciRetryPolicy::new(
+            10,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+            JitterMode::None,
+        );
+        assert_eq!(Duration::from_secs(1), policy.backoff());
+        assert_eq!(1, policy.attempt);
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(1), policy.backoff());
+        assert_eq!(2, policy.attempt);
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(2), policy.backoff());
+        assert_eq!(3, policy.attempt);
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(3), policy.backoff());
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(5), policy.backoff());
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(8), policy.backoff());
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(10), policy.backoff());
+
+        policy = policy.advance();
+        assert_eq!(Duration::from_secs(10), policy.backoff());
+    }
+
+    #[test]
     fn backoff_grows_to_max_with_jitter() {
         let max_duration = Duration::from_secs(10);
         let mut policy = FibonacciRetryPolicy::new(
@@ -496,6 +2139,51 @@ mod tests {
         fn is_retriable_error(&self, error: &Self::Error) -> bool {
             error.0
         }
+
+        fn is_connection_error(&self, error: &Self::Error) -> bool {
+            error.0
+        }
+    }
+
+    /// Retries server errors only.
+    #[derive(Debug, Clone)]
+    struct StatusRetryLogic;
+
+    impl RetryLogic for StatusRetryLogic {
+        type Error = Error;
+        type Response = Status;
+
+        fn is_retriable_error(&self, error: &Self::Error) -> bool {
+            error.0
+        }
+
+        fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+            match response.0 {
+                200..=299 => RetryAction::Successful,
+                500..=599 => RetryAction::Retry("server error".into()),
+                _ => RetryAction::DontRetry("client error".into()),
+            }
+        }
+    }
+
+    struct Status(u16);
+
+    impl HasStatusCode for Status {
+        fn status_code(&self) -> u16 {
+            self.0
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Request {
+        body: &'static str,
+        idempotent: bool,
+    }
+
+    impl Idempotency for Request {
+        fn is_idempotent(&self) -> bool {
+            self.idempotent
+        }
     }
 
     #[derive(Debug)]
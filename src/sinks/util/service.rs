@@ -1,23 +1,85 @@
-use std::{hash::Hash, marker::PhantomData, pin::Pin, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap, hash::Hash, marker::PhantomData, ops::BitOr, pin::Pin, sync::Arc,
+    time::Duration,
+};
 
-use futures_util::stream::{self, BoxStream};
+use futures_util::{
+    future,
+    stream::{BoxStream, StreamExt},
+};
+use serde::{Serialize, Serializer};
 use serde_with::serde_as;
+use snafu::Snafu;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tower::{
-    balance::p2c::Balance,
-    buffer::{Buffer, BufferLayer},
+    buffer::Buffer,
     discover::Change,
     layer::{util::Stack, Layer},
-    limit::RateLimit,
+    limit::{RateLimit, RateLimitLayer},
     retry::Retry,
     timeout::Timeout,
+    util::Either,
     Service, ServiceBuilder,
 };
 use vector_lib::configurable::configurable_component;
 
+#[cfg(any(test, feature = "test-utils"))]
+pub use crate::sinks::util::service::clock::ManualClock;
+#[cfg(any(test, feature = "test-utils"))]
+pub use crate::sinks::util::service::health::{AlwaysHealthy, AlwaysUnhealthy};
+#[cfg(feature = "hot-reload")]
+pub use crate::sinks::util::service::live::{
+    LiveRateLimit, LiveRateLimitLayer, LiveTowerRequestSettings,
+};
+#[cfg(any(test, feature = "test-utils"))]
+pub use crate::sinks::util::service::mock::{MockService, TestError};
+pub(crate) use crate::sinks::util::service::{
+    attempts::count_retry,
+    deadline::current_deadline,
+    error::{is_timeout, record_retries_exhausted},
+    instrument::record_retry,
+};
 pub use crate::sinks::util::service::{
-    concurrency::Concurrency,
-    health::{HealthConfig, HealthLogic, HealthService},
-    map::Map,
+    attempts::{Attempted, CountAttempts, CountAttemptsLayer},
+    balance::{
+        LeastLoadedBalance, LoadBalanceStrategy, LoadBalancer, RandomBalance, RoundRobinBalance,
+    },
+    buffer::BufferMode,
+    burst_limit::{BurstLimit, BurstLimitFuture, BurstLimitLayer},
+    clock::{Clock, ClockSleep, TokioClock},
+    coalesce::{Coalesce, CoalesceLayer, CoalescedError, Coalescible},
+    concurrency::{Concurrency, ConcurrencyError},
+    deadline::{
+        AttemptTimeout, AttemptTimeoutFuture, AttemptTimeoutLayer, Deadline, DeadlineFuture,
+        DeadlineLayer, DynamicTimeout, DynamicTimeoutLayer, HasTimeout, RequestTimeout,
+        RequestTimeoutLayer, WithTimeout,
+    },
+    discovery::{DrainHandle, EndpointController, HealthDrivenDiscovery, Readiness, Unavailable},
+    distributed::{CloneableDiscoveryService, DebugDistributedService},
+    env::EnvConfigError,
+    error::{RetryExhaustion, RetryExhaustionLayer, SinkServiceError},
+    failover::{Failover, FailoverPool},
+    health::{
+        DegradedWeight, HealthConfig, HealthLogic, HealthService, HealthStatus, HealthTransition,
+        HealthWatch, HttpHealthLogic, ProbingHealthLogic,
+    },
+    in_flight::{InFlight, InFlightFuture, InFlightLayer, ShutdownHandle},
+    in_flight_bytes::{
+        InFlightBytes, InFlightBytesFuture, InFlightBytesLayer, RequestSize, RequestSizeLayer,
+    },
+    instrument::{AttemptSpan, AttemptSpanLayer, Tracing, TracingLayer},
+    load_shedding::{
+        LoadShed, LoadShedLayer, LoadSheddingConfig, LoadSheddingError, LoadSheddingPolicy,
+    },
+    map::{
+        KeepResponse, Map, MapErr, MapErrFuture, MapFuture, MapLayer, MapResponse, ResponseMap,
+        TryMap,
+    },
+    partial_success::{
+        Delivery, PartialDelivery, PartialSuccess, PartialSuccessLayer, PartialSuccessMode,
+    },
+    rate_limit::{RateLimitNum, TokenBucketRateLimit, TokenBucketRateLimitLayer},
+    startup_jitter::{StartupJitter, StartupJitterLayer},
 };
 use crate::{
     internal_events::OpenGauge,
@@ -25,37 +87,155 @@ use crate::{
         adaptive_concurrency::{
             AdaptiveConcurrencyLimit, AdaptiveConcurrencyLimitLayer, AdaptiveConcurrencySettings,
         },
-        retries::{FibonacciRetryPolicy, JitterMode, RetryLogic},
-        service::map::MapLayer,
+        retries::{
+            ExponentialBackoff, FibonacciRetryPolicy, IdempotencyAwareRetryPolicy, JitterMode,
+            RetryErrorCategories, RetryErrorCategory, RetryLogic,
+        },
+        service::{
+            discovery::EndpointUpdate,
+            health::HealthCounters,
+            map::{MapErrLayer, TryMapLayer},
+        },
         sink::Response,
         Batch, BatchSink, Partition, PartitionBatchSink,
     },
 };
 
+mod attempts;
+mod balance;
+mod buffer;
+mod burst_limit;
+mod clock;
+mod coalesce;
 mod concurrency;
+mod deadline;
+mod discovery;
+mod distributed;
+mod env;
+mod error;
+mod failover;
 mod health;
+mod in_flight;
+mod in_flight_bytes;
+mod instrument;
+#[cfg(feature = "hot-reload")]
+mod live;
+mod load_shedding;
 mod map;
+#[cfg(any(test, feature = "test-utils"))]
+mod mock;
 pub mod net;
-
-pub type Svc<S, L> =
-    RateLimit<AdaptiveConcurrencyLimit<Retry<FibonacciRetryPolicy<L>, Timeout<S>>, L>>;
+mod partial_success;
+mod rate_limit;
+mod startup_jitter;
+
+pub type Svc<S, L> = MaybeTokenBucketRateLimit<
+    MaybeRateLimit<
+        AdaptiveConcurrencyLimit<
+            MaybeBurstLimit<
+                MaybeInFlightBytes<
+                    InFlight<
+                        MaybeStartupJitter<
+                            MaybeDeadline<
+                                Tracing<
+                                    RetryExhaustion<
+                                        Retry<
+                                            FibonacciRetryPolicy<L>,
+                                            AttemptSpan<AttemptTimeout<S>>,
+                                        >,
+                                    >,
+                                >,
+                            >,
+                        >,
+                    >,
+                >,
+            >,
+            L,
+        >,
+    >,
+>;
+/// A service which is rate limited, unless the rate limit is `unlimited`.
+pub type MaybeRateLimit<S> = Either<RateLimit<S>, S>;
+/// A service which is rate limited by a token bucket, when its rate limit allows bursts.
+pub type MaybeTokenBucketRateLimit<S> = Either<TokenBucketRateLimit<S>, S>;
+/// A service which can exceed its steady concurrency for bursts, when a burst concurrency is set.
+pub type MaybeBurstLimit<S> = Either<BurstLimit<S>, S>;
+/// A service which limits the bytes in flight, when `max_in_flight_bytes` is set.
+pub type MaybeInFlightBytes<S> = Either<InFlightBytes<S>, S>;
+/// A service which delays its first request, when there is a startup jitter to delay it by.
+pub type MaybeStartupJitter<S> = Either<StartupJitter<S>, S>;
+/// A service which bounds the total time of each request, when a request deadline is set.
+pub type MaybeDeadline<S> = Either<Deadline<S>, S>;
 pub type TowerBatchedSink<S, B, RL> = BatchSink<Svc<S, RL>, B>;
 pub type TowerPartitionSink<S, B, RL, K> = PartitionBatchSink<Svc<S, RL>, B, K>;
 
 // Distributed service types
-pub type DistributedService<S, RL, HL, K, Req> = RateLimit<
-    Retry<FibonacciRetryPolicy<RL>, Buffer<Balance<DiscoveryService<S, RL, HL, K>, Req>, Req>>,
+pub type DistributedService<S, RL, HL, K, Req> = MaybeRateLimit<
+    Retry<FibonacciRetryPolicy<RL>, Buffer<LoadBalancer<DiscoveryService<S, RL, HL, K>, Req>, Req>>,
 >;
 pub type DiscoveryService<S, RL, HL, K> =
     BoxStream<'static, Result<Change<K, SingleDistributedService<S, RL, HL>>, crate::Error>>;
 pub type SingleDistributedService<S, RL, HL> =
-    AdaptiveConcurrencyLimit<HealthService<Timeout<S>, HL>, RL>;
+    DegradedWeight<AdaptiveConcurrencyLimit<HealthService<Timeout<S>, HL>, RL>>;
 
 pub trait ServiceBuilderExt<L> {
     fn map<R1, R2, F>(self, f: F) -> ServiceBuilder<Stack<MapLayer<R1, R2>, L>>
     where
         F: Fn(R1) -> R2 + Send + Sync + 'static;
 
+    /// Transforms each request with `f` before it is sent, failing the requests it can't
+    /// transform with its error, converted into a `crate::Error`.
+    fn try_map<R1, R2, E, F>(self, f: F) -> ServiceBuilder<Stack<TryMapLayer<R1, R2, E>, L>>
+    where
+        F: Fn(R1) -> Result<R2, E> + Send + Sync + 'static;
+
+    /// Transforms the errors of the service into a `crate::Error` with `f`.
+    ///
+    /// Unlike `ServiceBuilder::map_err`, the resulting service can be named in type aliases.
+    fn map_error<E, F>(self, f: F) -> ServiceBuilder<Stack<MapErrLayer<E>, L>>
+    where
+        F: Fn(E) -> crate::Error + Send + Sync + 'static;
+
+    /// Coalesces identical requests made while one of them is in flight.
+    ///
+    /// This should be added before the request settings, so that coalesced requests share a
+    /// single concurrency slot and retry sequence.
+    fn coalesce<Request>(self) -> ServiceBuilder<Stack<CoalesceLayer<Request>, L>>;
+
+    /// Applies the timeout of each request implementing [`HasTimeout`] to its attempts, in place
+    /// of the configured `timeout_secs`.
+    ///
+    /// This must be added before the request settings.
+    fn request_timeouts(self) -> ServiceBuilder<Stack<RequestTimeoutLayer, L>>;
+
+    /// Unwraps [`WithTimeout`] requests, applying their timeout to their attempts in place of the
+    /// configured `timeout_secs`.
+    ///
+    /// This must be added before the request settings.
+    fn dynamic_timeout(self) -> ServiceBuilder<Stack<DynamicTimeoutLayer, L>>;
+
+    /// Counts each request as its encoded size towards `max_in_flight_bytes`, in place of the
+    /// configured `default_request_size_bytes`.
+    ///
+    /// This must be added before the request settings.
+    fn request_sizes(self) -> ServiceBuilder<Stack<RequestSizeLayer, L>>;
+
+    /// Responds with the number of attempts made for each request along with its response, as an
+    /// [`Attempted`].
+    ///
+    /// This must be added before the request settings.
+    fn count_attempts(self) -> ServiceBuilder<Stack<CountAttemptsLayer, L>>;
+
+    /// Times out each attempt after `timeout`, rather than the request as a whole.
+    ///
+    /// This must be added after `retry`, so that every retry starts a timeout of its own. The
+    /// request settings already time out each attempt after `timeout_secs`, so this is only
+    /// needed by services which add their own retry layer.
+    fn timeout_per_attempt(
+        self,
+        timeout: Duration,
+    ) -> ServiceBuilder<Stack<AttemptTimeoutLayer, L>>;
+
     fn settings<RL, Request>(
         self,
         settings: TowerRequestSettings,
@@ -71,6 +251,47 @@ impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
         self.layer(MapLayer::new(Arc::new(f)))
     }
 
+    fn try_map<R1, R2, E, F>(self, f: F) -> ServiceBuilder<Stack<TryMapLayer<R1, R2, E>, L>>
+    where
+        F: Fn(R1) -> Result<R2, E> + Send + Sync + 'static,
+    {
+        self.layer(TryMapLayer::new(Arc::new(f)))
+    }
+
+    fn map_error<E, F>(self, f: F) -> ServiceBuilder<Stack<MapErrLayer<E>, L>>
+    where
+        F: Fn(E) -> crate::Error + Send + Sync + 'static,
+    {
+        self.layer(MapErrLayer::new(Arc::new(f)))
+    }
+
+    fn coalesce<Request>(self) -> ServiceBuilder<Stack<CoalesceLayer<Request>, L>> {
+        self.layer(CoalesceLayer::new())
+    }
+
+    fn request_timeouts(self) -> ServiceBuilder<Stack<RequestTimeoutLayer, L>> {
+        self.layer(RequestTimeoutLayer)
+    }
+
+    fn dynamic_timeout(self) -> ServiceBuilder<Stack<DynamicTimeoutLayer, L>> {
+        self.layer(DynamicTimeoutLayer)
+    }
+
+    fn request_sizes(self) -> ServiceBuilder<Stack<RequestSizeLayer, L>> {
+        self.layer(RequestSizeLayer)
+    }
+
+    fn count_attempts(self) -> ServiceBuilder<Stack<CountAttemptsLayer, L>> {
+        self.layer(CountAttemptsLayer)
+    }
+
+    fn timeout_per_attempt(
+        self,
+        timeout: Duration,
+    ) -> ServiceBuilder<Stack<AttemptTimeoutLayer, L>> {
+        self.layer(AttemptTimeoutLayer::new(timeout))
+    }
+
     fn settings<RL, Request>(
         self,
         settings: TowerRequestSettings,
@@ -88,7 +309,7 @@ pub trait TowerRequestConfigDefaults {
     const CONCURRENCY: Concurrency = Concurrency::Adaptive;
     const TIMEOUT_SECS: u64 = 60;
     const RATE_LIMIT_DURATION_SECS: u64 = 1;
-    const RATE_LIMIT_NUM: u64 = i64::max_value() as u64; // i64 avoids TOML deserialize issue
+    const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Unlimited;
     const RETRY_ATTEMPTS: usize = isize::max_value() as usize; // isize avoids TOML deserialize issue
     const RETRY_MAX_DURATION_SECS: u64 = 30;
     const RETRY_INITIAL_BACKOFF_SECS: u64 = 1;
@@ -104,15 +325,60 @@ impl TowerRequestConfigDefaults for GlobalTowerRequestConfigDefaults {}
 /// Various settings can be configured, such as concurrency and rate limits, timeouts, retry behavior, etc.
 ///
 /// Note that the retry backoff policy follows the Fibonacci sequence.
+///
+/// Options which are not set take the default of the sink.
 #[serde_as]
 #[configurable_component]
 #[configurable(metadata(docs::advanced))]
 #[derive(Clone, Copy, Debug)]
 pub struct TowerRequestConfig<D: TowerRequestConfigDefaults = GlobalTowerRequestConfigDefaults> {
     #[configurable(derived)]
-    #[serde(default = "default_concurrency::<D>")]
-    #[serde(skip_serializing_if = "concurrency_is_default::<D>")]
-    pub concurrency: Concurrency,
+    pub concurrency: Option<Concurrency>,
+
+    /// The maximum number of requests allowed in flight during short bursts.
+    ///
+    /// Only applies when `concurrency` is fixed. Sustained load is limited to `concurrency`, but
+    /// time spent with fewer requests in flight earns credits, one per
+    /// `concurrency_burst_refill_secs`, each of which admits a request beyond `concurrency`, up to
+    /// this limit. If not set, `concurrency` is never exceeded.
+    #[configurable(metadata(docs::type_unit = "requests"))]
+    #[configurable(metadata(docs::human_name = "Burst Concurrency"))]
+    pub concurrency_burst: Option<usize>,
+
+    /// The maximum number of requests allowed in flight for each partition.
+    ///
+    /// Only applies to sinks which batch events into partitions, such as per tenant, to keep a single
+    /// busy partition from using up all of the concurrency. `concurrency` still limits the number of
+    /// requests in flight across all partitions. If not set, partitions are not limited individually.
+    #[configurable(metadata(docs::type_unit = "requests"))]
+    #[configurable(metadata(docs::human_name = "Partition Concurrency"))]
+    pub partition_concurrency: Option<usize>,
+
+    /// The time spent below `concurrency` that earns a request beyond it during bursts.
+    ///
+    /// If not set, a request is earned every second.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Burst Concurrency Refill"))]
+    pub concurrency_burst_refill_secs: Option<u64>,
+
+    /// The maximum total size of the requests allowed in flight, in bytes.
+    ///
+    /// This limits memory use when batches are large or vary in size, independently of the number
+    /// of requests allowed in flight. No more requests are sent while this limit is reached, so a
+    /// single request larger than the limit is still sent, once on its own. If not set, requests
+    /// are not limited by their size.
+    #[configurable(metadata(docs::type_unit = "bytes"))]
+    #[configurable(metadata(docs::human_name = "Max In-Flight Bytes"))]
+    #[configurable(validation(range(min = 1)))]
+    pub max_in_flight_bytes: Option<usize>,
+
+    /// The size requests are counted as towards `max_in_flight_bytes` when their sink does not
+    /// report their encoded size.
+    ///
+    /// If not set, such requests are counted as 1 MiB.
+    #[configurable(metadata(docs::type_unit = "bytes"))]
+    #[configurable(metadata(docs::human_name = "Default Request Size"))]
+    pub default_request_size_bytes: Option<usize>,
 
     /// The time a request can take before being aborted.
     ///
@@ -120,144 +386,866 @@ pub struct TowerRequestConfig<D: TowerRequestConfigDefaults = GlobalTowerRequest
     /// create orphaned requests, pile on retries, and result in duplicate data downstream.
     #[configurable(metadata(docs::type_unit = "seconds"))]
     #[configurable(metadata(docs::human_name = "Timeout"))]
-    #[serde(default = "default_timeout_secs::<D>")]
-    pub timeout_secs: u64,
+    pub timeout_secs: Option<u64>,
 
     /// The time window used for the `rate_limit_num` option.
     #[configurable(metadata(docs::type_unit = "seconds"))]
     #[configurable(metadata(docs::human_name = "Rate Limit Duration"))]
-    #[serde(default = "default_rate_limit_duration_secs::<D>")]
-    pub rate_limit_duration_secs: u64,
+    pub rate_limit_duration_secs: Option<u64>,
 
     /// The maximum number of requests allowed within the `rate_limit_duration_secs` time window.
+    ///
+    /// Set to `unlimited` to not rate limit requests at all.
     #[configurable(metadata(docs::type_unit = "requests"))]
     #[configurable(metadata(docs::human_name = "Rate Limit Number"))]
-    #[serde(default = "default_rate_limit_num::<D>")]
-    pub rate_limit_num: u64,
+    pub rate_limit_num: Option<RateLimitNum>,
+
+    /// Whether to offset the `rate_limit_duration_secs` time window by a random amount of time.
+    ///
+    /// The time windows of the rate limit start from the first request it admits, so identically
+    /// configured sinks which start together also roll their windows over together, sending their
+    /// requests in synchronized bursts. When enabled, the first request is held back by a random
+    /// fraction of the time window, once, so that the windows of each sink are out of phase with
+    /// one another. This has no effect if requests are not rate limited. By default, the windows
+    /// are not offset.
+    pub rate_limit_jitter: Option<bool>,
+
+    /// The number of requests which can be sent at once on top of `rate_limit_num`.
+    ///
+    /// When set, requests are rate limited with a token bucket holding `rate_limit_num` plus this
+    /// many tokens, rather than with a fixed time window. Unused tokens accumulate up to that
+    /// amount, so that short bursts of requests can be sent at once, while the rate limit is still
+    /// upheld over time as tokens are refilled at a steady rate of `rate_limit_num` per
+    /// `rate_limit_duration_secs`. This has no effect if requests are not rate limited.
+    #[configurable(metadata(docs::type_unit = "requests"))]
+    #[configurable(metadata(docs::human_name = "Rate Limit Burst"))]
+    pub rate_limit_burst: Option<u64>,
 
     /// The maximum number of retries to make for failed requests.
     #[configurable(metadata(docs::type_unit = "retries"))]
-    #[serde(default = "default_retry_attempts::<D>")]
-    pub retry_attempts: usize,
+    pub retry_attempts: Option<usize>,
 
     /// The maximum amount of time to wait between retries.
     #[configurable(metadata(docs::type_unit = "seconds"))]
     #[configurable(metadata(docs::human_name = "Max Retry Duration"))]
-    #[serde(default = "default_retry_max_duration_secs::<D>")]
-    pub retry_max_duration_secs: u64,
+    pub retry_max_duration_secs: Option<u64>,
 
     /// The amount of time to wait before attempting the first retry for a failed request.
     ///
     /// After the first retry has failed, the fibonacci sequence is used to select future backoffs.
     #[configurable(metadata(docs::type_unit = "seconds"))]
     #[configurable(metadata(docs::human_name = "Retry Initial Backoff"))]
-    #[serde(default = "default_retry_initial_backoff_secs::<D>")]
-    pub retry_initial_backoff_secs: u64,
+    pub retry_initial_backoff_secs: Option<u64>,
 
     #[configurable(derived)]
-    #[serde(default)]
-    pub retry_jitter_mode: JitterMode,
+    pub retry_jitter_mode: Option<JitterMode>,
 
     #[configurable(derived)]
-    #[serde(default)]
-    pub adaptive_concurrency: AdaptiveConcurrencySettings,
+    pub initial_batch_retry: Option<InitialBatchRetryConfig>,
+
+    #[configurable(derived)]
+    pub large_batch_retry: Option<LargeBatchRetryConfig>,
+
+    /// The maximum amount of time a request can take in total, including all of its retries.
+    ///
+    /// Once the deadline is reached, the request is aborted and no further retries are attempted.
+    /// The `timeout_secs` option still bounds each individual attempt. If not set, there is no
+    /// overall deadline.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Request Deadline"))]
+    pub request_deadline_secs: Option<u64>,
+
+    /// Whether `timeout_secs` bounds the request as a whole, including all of its retries.
+    ///
+    /// When enabled, each retry attempt is only given the time remaining out of `timeout_secs`
+    /// since the request was first attempted, rather than a full `timeout_secs` of its own, and no
+    /// further attempts are made once that time has run out. This has no effect if
+    /// `request_deadline_secs` is set, as that already bounds the request as a whole.
+    pub propagate_deadline: Option<bool>,
+
+    /// The maximum amount of time to delay the first request by.
+    ///
+    /// The first request is delayed by a random amount of time up to this value, so that many
+    /// sinks starting at the same time, such as during a rolling deployment, do not all send
+    /// their first request at once. This is separate from the jitter applied to retries. By
+    /// default, the first request is not delayed.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Startup Jitter"))]
+    pub startup_jitter_secs: Option<u64>,
+
+    /// The maximum number of requests to queue up in front of the endpoints of sinks which
+    /// distribute requests across several of them.
+    ///
+    /// Requests are queued here before an endpoint is picked for them, so this bounds the number of
+    /// requests waiting on top of the ones already admitted by the concurrency limit of each
+    /// endpoint. Raising it allows more requests to be pipelined, at the cost of memory, while
+    /// lowering it applies backpressure sooner. It must be at least `1`.
+    ///
+    /// If not set, the sink's own default is used. This has no effect on sinks which send to a
+    /// single endpoint, as those do not queue requests beyond the concurrency limit.
+    #[configurable(metadata(docs::type_unit = "requests"))]
+    #[configurable(metadata(docs::human_name = "Max Buffered Requests"))]
+    #[configurable(validation(range(min = 1)))]
+    #[serde(alias = "buffer_size")]
+    pub max_buffered_requests: Option<usize>,
+
+    /// How to queue up requests in front of the endpoints of sinks which distribute requests
+    /// across several of them.
+    ///
+    /// This takes the place of `max_buffered_requests`, which is shorthand for a bounded buffer
+    /// of that capacity, and takes precedence over it if both are set. If neither is set, the
+    /// buffer is bounded by the sink's own default.
+    #[configurable(derived)]
+    pub buffer_mode: Option<BufferMode>,
+
+    /// The strategy used to pick the endpoint each request is sent to, for sinks which distribute
+    /// requests across several endpoints.
+    #[configurable(derived)]
+    #[serde(alias = "balance_policy")]
+    pub load_balance_strategy: Option<LoadBalanceStrategy>,
+
+    /// Whether to retry every error, regardless of whether the sink considers it retriable.
+    ///
+    /// This is not recommended for production, as errors which can never succeed, such as
+    /// authentication failures, are then retried until `retry_attempts` is exhausted.
+    pub retry_on_any_error: Option<bool>,
+
+    #[configurable(derived)]
+    pub retryable_errors: Option<RetryableErrorsConfig>,
+
+    #[configurable(derived)]
+    pub adaptive_concurrency: Option<AdaptiveConcurrencySettings>,
 
     #[serde(skip)]
     pub _d: PhantomData<D>,
 }
 
-const fn default_concurrency<D: TowerRequestConfigDefaults>() -> Concurrency {
-    D::CONCURRENCY
-}
+/// Retry behavior for the first batch sent for each partition.
+///
+/// Sinks which send the batches of each partition in order can retry the first batch of a
+/// partition differently from the ones that follow it, for example to retry more persistently while
+/// the destination for a new partition is still being created. Options that are not set fall back
+/// to the corresponding top-level retry options.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InitialBatchRetryConfig {
+    /// The maximum number of retries to make for the first batch of a partition.
+    #[configurable(metadata(docs::type_unit = "retries"))]
+    pub retry_attempts: Option<usize>,
 
-fn concurrency_is_default<D: TowerRequestConfigDefaults>(concurrency: &Concurrency) -> bool {
-    *concurrency == D::CONCURRENCY
-}
+    /// The maximum amount of time to wait between retries of the first batch of a partition.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Max Retry Duration"))]
+    pub retry_max_duration_secs: Option<u64>,
 
-const fn default_timeout_secs<D: TowerRequestConfigDefaults>() -> u64 {
-    D::TIMEOUT_SECS
+    /// The amount of time to wait before the first retry of the first batch of a partition.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Retry Initial Backoff"))]
+    pub retry_initial_backoff_secs: Option<u64>,
 }
 
-const fn default_rate_limit_duration_secs<D: TowerRequestConfigDefaults>() -> u64 {
-    D::RATE_LIMIT_DURATION_SECS
+/// Retry behavior for large batches.
+///
+/// Large batches are more costly to send again, and more likely to fail again if they failed
+/// because of their size. Batches of at least `min_bytes` bytes are retried at most
+/// `retry_attempts` times, if that is fewer than the top-level `retry_attempts` option allows.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LargeBatchRetryConfig {
+    /// The size, in bytes, from which a batch is considered large.
+    #[configurable(metadata(docs::type_unit = "bytes"))]
+    pub min_bytes: usize,
+
+    /// The maximum number of retries to make for a large batch.
+    #[configurable(metadata(docs::type_unit = "retries"))]
+    pub retry_attempts: usize,
 }
 
-const fn default_rate_limit_num<D: TowerRequestConfigDefaults>() -> u64 {
-    D::RATE_LIMIT_NUM
+/// Which categories of failed requests to retry.
+///
+/// Sinks classify their failures into categories, and only failures in a category permitted here
+/// are retried, if the sink considers them retriable in the first place. By default, every
+/// category is permitted.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RetryableErrorsConfig {
+    /// The only categories of failures to retry.
+    ///
+    /// If not set, failures of every category not listed in `deny` are retried.
+    #[configurable(metadata(docs::examples = "timeout"))]
+    pub allow: Option<RetryErrorCategories>,
+
+    /// The categories of failures never to retry.
+    #[configurable(metadata(docs::examples = "connection"))]
+    #[serde(default)]
+    pub deny: RetryErrorCategories,
 }
 
-const fn default_retry_attempts<D: TowerRequestConfigDefaults>() -> usize {
-    D::RETRY_ATTEMPTS
+impl RetryableErrorsConfig {
+    /// Whether failures of `category` may be retried.
+    pub const fn permits(&self, category: RetryErrorCategory) -> bool {
+        let allowed = match self.allow {
+            Some(allow) => allow.contains(category),
+            None => true,
+        };
+        allowed && !self.deny.contains(category)
+    }
 }
 
-const fn default_retry_max_duration_secs<D: TowerRequestConfigDefaults>() -> u64 {
-    D::RETRY_MAX_DURATION_SECS
+const fn default_concurrency_burst_refill_secs() -> u64 {
+    1
 }
 
-const fn default_retry_initial_backoff_secs<D: TowerRequestConfigDefaults>() -> u64 {
-    D::RETRY_INITIAL_BACKOFF_SECS
+const fn default_request_size_bytes() -> usize {
+    1024 * 1024
 }
 
 impl<D: TowerRequestConfigDefaults> Default for TowerRequestConfig<D> {
     fn default() -> Self {
         Self {
-            concurrency: default_concurrency::<D>(),
-            timeout_secs: default_timeout_secs::<D>(),
-            rate_limit_duration_secs: default_rate_limit_duration_secs::<D>(),
-            rate_limit_num: default_rate_limit_num::<D>(),
-            retry_attempts: default_retry_attempts::<D>(),
-            retry_max_duration_secs: default_retry_max_duration_secs::<D>(),
-            retry_initial_backoff_secs: default_retry_initial_backoff_secs::<D>(),
-            adaptive_concurrency: AdaptiveConcurrencySettings::default(),
-            retry_jitter_mode: JitterMode::default(),
+            concurrency: None,
+            concurrency_burst: None,
+            partition_concurrency: None,
+            concurrency_burst_refill_secs: None,
+            max_in_flight_bytes: None,
+            default_request_size_bytes: None,
+            timeout_secs: None,
+            rate_limit_duration_secs: None,
+            rate_limit_num: None,
+            rate_limit_jitter: None,
+            rate_limit_burst: None,
+            retry_attempts: None,
+            retry_max_duration_secs: None,
+            retry_initial_backoff_secs: None,
+            adaptive_concurrency: None,
+            retry_jitter_mode: None,
+            initial_batch_retry: None,
+            large_batch_retry: None,
+            request_deadline_secs: None,
+            propagate_deadline: None,
+            startup_jitter_secs: None,
+            max_buffered_requests: None,
+            buffer_mode: None,
+            load_balance_strategy: None,
+            retry_on_any_error: None,
+            retryable_errors: None,
 
             _d: PhantomData,
         }
     }
 }
 
+/// Merges a user config, on the left, with sink defaults, on the right, into settings.
+///
+/// See [`TowerRequestConfig::or`] for how the two are merged.
+///
+/// ```rust
+/// use vector::sinks::util::TowerRequestConfig;
+///
+/// let user: TowerRequestConfig = toml::from_str("retry_attempts = 3").unwrap();
+/// let defaults = TowerRequestConfig {
+///     timeout_secs: Some(10),
+///     retry_attempts: Some(5),
+///     ..Default::default()
+/// };
+///
+/// let settings = user | defaults;
+/// assert_eq!(settings.retry_attempts, 3);
+/// assert_eq!(settings.timeout.as_secs(), 10);
+/// ```
+impl<D, E> BitOr<TowerRequestConfig<E>> for TowerRequestConfig<D>
+where
+    D: TowerRequestConfigDefaults,
+    E: TowerRequestConfigDefaults,
+{
+    type Output = TowerRequestSettings;
+
+    fn bitor(self, defaults: TowerRequestConfig<E>) -> Self::Output {
+        self.or(defaults).into_settings()
+    }
+}
+
+#[derive(Debug, Snafu, PartialEq, Eq)]
+pub enum RequestConfigError {
+    #[snafu(display("`timeout_secs` must be greater than zero"))]
+    InvalidTimeout,
+    #[snafu(display("`rate_limit_duration_secs` must be greater than zero"))]
+    InvalidRateLimitDuration,
+    #[snafu(display("`max_buffered_requests` must be greater than zero"))]
+    InvalidMaxBufferedRequests,
+    #[snafu(display("`buffer_mode.capacity` must be greater than zero"))]
+    InvalidBufferCapacity,
+    #[snafu(display("`max_in_flight_bytes` must be greater than zero"))]
+    InvalidMaxInFlightBytes,
+    #[snafu(display("`rate_limit_num` must be greater than zero"))]
+    InvalidRateLimitNum,
+    #[snafu(display("`concurrency` must be greater than zero"))]
+    InvalidConcurrency,
+}
+
 impl<D: TowerRequestConfigDefaults> TowerRequestConfig<D> {
-    pub const fn into_settings(&self) -> TowerRequestSettings {
-        // the unwrap() calls below are safe because the final defaults are always Some<>
+    /// Validates the configuration and resolves it into settings.
+    ///
+    /// Returns the first error reported by [`TowerRequestSettings::validate`].
+    pub fn try_into_settings(&self) -> Result<TowerRequestSettings, RequestConfigError> {
+        // `into_settings` raises a zero concurrency limit, so it has to be caught beforehand.
+        if matches!(
+            self.concurrency().parse_concurrency(),
+            Err(ConcurrencyError::Zero)
+        ) {
+            return Err(RequestConfigError::InvalidConcurrency);
+        }
+        let settings = self.into_settings();
+        match settings.validate() {
+            Ok(()) => Ok(settings),
+            Err(mut errors) => Err(errors.swap_remove(0)),
+        }
+    }
+
+    /// Resolves the configuration into settings without validating it first.
+    ///
+    /// Prefer [`Self::try_into_settings`], which rejects values which can never work.
+    pub fn into_settings(&self) -> TowerRequestSettings {
+        let concurrency = match self.concurrency().parse_concurrency() {
+            Ok(concurrency) => concurrency,
+            // A limit of zero would never admit a request, so it is raised to the lowest usable
+            // limit. `Self::try_into_settings` rejects it instead.
+            Err(error) => {
+                warn!(
+                    message = "Raising the concurrency limit to 1.",
+                    %error,
+                    internal_log_rate_limit = true,
+                );
+                Some(1)
+            }
+        };
+        let timeout = self.timeout();
         TowerRequestSettings {
-            concurrency: self.concurrency.parse_concurrency(),
-            timeout: Duration::from_secs(self.timeout_secs),
-            rate_limit_duration: Duration::from_secs(self.rate_limit_duration_secs),
-            rate_limit_num: self.rate_limit_num,
-            retry_attempts: self.retry_attempts,
-            retry_max_duration: Duration::from_secs(self.retry_max_duration_secs),
-            retry_initial_backoff: Duration::from_secs(self.retry_initial_backoff_secs),
-            adaptive_concurrency: self.adaptive_concurrency,
-            retry_jitter_mode: self.retry_jitter_mode,
+            concurrency,
+            concurrency_burst: self.concurrency_burst,
+            partition_concurrency: self.partition_concurrency,
+            concurrency_burst_refill: Duration::from_secs(
+                self.concurrency_burst_refill_secs
+                    .unwrap_or_else(default_concurrency_burst_refill_secs),
+            ),
+            max_in_flight_bytes: self.max_in_flight_bytes,
+            default_request_size_bytes: self
+                .default_request_size_bytes
+                .unwrap_or_else(default_request_size_bytes),
+            timeout,
+            rate_limit_duration: Duration::from_secs(
+                self.rate_limit_duration_secs
+                    .unwrap_or(D::RATE_LIMIT_DURATION_SECS),
+            ),
+            rate_limit_num: self.rate_limit_num.unwrap_or(D::RATE_LIMIT_NUM).limit(),
+            rate_limit_jitter: self.rate_limit_jitter.unwrap_or_default(),
+            rate_limit_burst: self.rate_limit_burst,
+            retry_attempts: self.retry_attempts(),
+            retry_max_duration: Duration::from_secs(self.retry_max_duration_secs()),
+            retry_initial_backoff: Duration::from_secs(self.retry_initial_backoff_secs()),
+            adaptive_concurrency: self.adaptive_concurrency.unwrap_or_default(),
+            retry_jitter_mode: self.retry_jitter_mode.unwrap_or_default(),
+            initial_batch_retry: self
+                .initial_batch_retry
+                .map(|config| self.initial_batch_retry_settings(config)),
+            large_batch_retry: self.large_batch_retry,
+            request_deadline: match self.request_deadline_secs {
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None if self.propagate_deadline.unwrap_or_default() => Some(timeout),
+                None => None,
+            },
+            startup_jitter: Duration::from_secs(self.startup_jitter_secs.unwrap_or_default()),
+            max_buffered_requests: self.max_buffered_requests,
+            buffer_mode: self.buffer_mode,
+            load_balance_strategy: self.load_balance_strategy.unwrap_or_default(),
+            retry_on_any_error: self.retry_on_any_error.unwrap_or_default(),
+            retryable_errors: self.retryable_errors.unwrap_or_default(),
+            shutdown: None,
+        }
+    }
+
+    /// The time each attempt can take before being aborted.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(D::TIMEOUT_SECS))
+    }
+
+    fn concurrency(&self) -> Concurrency {
+        self.concurrency.unwrap_or(D::CONCURRENCY)
+    }
+
+    fn retry_attempts(&self) -> usize {
+        self.retry_attempts.unwrap_or(D::RETRY_ATTEMPTS)
+    }
+
+    fn retry_max_duration_secs(&self) -> u64 {
+        self.retry_max_duration_secs
+            .unwrap_or(D::RETRY_MAX_DURATION_SECS)
+    }
+
+    fn retry_initial_backoff_secs(&self) -> u64 {
+        self.retry_initial_backoff_secs
+            .unwrap_or(D::RETRY_INITIAL_BACKOFF_SECS)
+    }
+
+    fn initial_batch_retry_settings(
+        &self,
+        config: InitialBatchRetryConfig,
+    ) -> InitialBatchRetrySettings {
+        InitialBatchRetrySettings {
+            retry_attempts: config
+                .retry_attempts
+                .unwrap_or_else(|| self.retry_attempts()),
+            retry_max_duration: Duration::from_secs(
+                config
+                    .retry_max_duration_secs
+                    .unwrap_or_else(|| self.retry_max_duration_secs()),
+            ),
+            retry_initial_backoff: Duration::from_secs(
+                config
+                    .retry_initial_backoff_secs
+                    .unwrap_or_else(|| self.retry_initial_backoff_secs()),
+            ),
+        }
+    }
+
+    /// Replaces the options this config does not set with the ones of `defaults`.
+    ///
+    /// Layered configs, such as sink, pipeline and global configs, are merged by chaining calls
+    /// from the most to the least specific, as in `sink.or(pipeline).or(global)`, so that each
+    /// option is taken from the first config which sets it. Options set by none of them take the
+    /// default of this config.
+    pub fn or<E: TowerRequestConfigDefaults>(self, defaults: TowerRequestConfig<E>) -> Self {
+        // Both configure the same buffer, so neither default applies once either is set.
+        let (max_buffered_requests, buffer_mode) =
+            if self.max_buffered_requests.is_some() || self.buffer_mode.is_some() {
+                (self.max_buffered_requests, self.buffer_mode)
+            } else {
+                (defaults.max_buffered_requests, defaults.buffer_mode)
+            };
+        Self {
+            concurrency: self.concurrency.or(defaults.concurrency),
+            concurrency_burst: self.concurrency_burst.or(defaults.concurrency_burst),
+            partition_concurrency: self
+                .partition_concurrency
+                .or(defaults.partition_concurrency),
+            concurrency_burst_refill_secs: self
+                .concurrency_burst_refill_secs
+                .or(defaults.concurrency_burst_refill_secs),
+            max_in_flight_bytes: self.max_in_flight_bytes.or(defaults.max_in_flight_bytes),
+            default_request_size_bytes: self
+                .default_request_size_bytes
+                .or(defaults.default_request_size_bytes),
+            timeout_secs: self.timeout_secs.or(defaults.timeout_secs),
+            rate_limit_duration_secs: self
+                .rate_limit_duration_secs
+                .or(defaults.rate_limit_duration_secs),
+            rate_limit_num: self.rate_limit_num.or(defaults.rate_limit_num),
+            rate_limit_jitter: self.rate_limit_jitter.or(defaults.rate_limit_jitter),
+            rate_limit_burst: self.rate_limit_burst.or(defaults.rate_limit_burst),
+            retry_attempts: self.retry_attempts.or(defaults.retry_attempts),
+            retry_max_duration_secs: self
+                .retry_max_duration_secs
+                .or(defaults.retry_max_duration_secs),
+            retry_initial_backoff_secs: self
+                .retry_initial_backoff_secs
+                .or(defaults.retry_initial_backoff_secs),
+            adaptive_concurrency: self.adaptive_concurrency.or(defaults.adaptive_concurrency),
+            retry_jitter_mode: self.retry_jitter_mode.or(defaults.retry_jitter_mode),
+            initial_batch_retry: self.initial_batch_retry.or(defaults.initial_batch_retry),
+            large_batch_retry: self.large_batch_retry.or(defaults.large_batch_retry),
+            request_deadline_secs: self
+                .request_deadline_secs
+                .or(defaults.request_deadline_secs),
+            propagate_deadline: self.propagate_deadline.or(defaults.propagate_deadline),
+            startup_jitter_secs: self.startup_jitter_secs.or(defaults.startup_jitter_secs),
+            max_buffered_requests,
+            buffer_mode,
+            load_balance_strategy: self
+                .load_balance_strategy
+                .or(defaults.load_balance_strategy),
+            retry_on_any_error: self.retry_on_any_error.or(defaults.retry_on_any_error),
+            retryable_errors: self.retryable_errors.or(defaults.retryable_errors),
+            _d: PhantomData,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TowerRequestSettings {
     pub concurrency: Option<usize>,
+    pub concurrency_burst: Option<usize>,
+    pub concurrency_burst_refill: Duration,
+    pub max_in_flight_bytes: Option<usize>,
+    pub default_request_size_bytes: usize,
+    pub partition_concurrency: Option<usize>,
     pub timeout: Duration,
     pub rate_limit_duration: Duration,
-    pub rate_limit_num: u64,
+    pub rate_limit_num: Option<u64>,
+    pub rate_limit_jitter: bool,
+    pub rate_limit_burst: Option<u64>,
     pub retry_attempts: usize,
     pub retry_max_duration: Duration,
     pub retry_initial_backoff: Duration,
     pub adaptive_concurrency: AdaptiveConcurrencySettings,
     pub retry_jitter_mode: JitterMode,
+    pub initial_batch_retry: Option<InitialBatchRetrySettings>,
+    pub large_batch_retry: Option<LargeBatchRetryConfig>,
+    pub request_deadline: Option<Duration>,
+    pub startup_jitter: Duration,
+    pub max_buffered_requests: Option<usize>,
+    pub buffer_mode: Option<BufferMode>,
+    pub load_balance_strategy: LoadBalanceStrategy,
+    pub retry_on_any_error: bool,
+    pub retryable_errors: RetryableErrorsConfig,
+    pub(crate) shutdown: Option<ShutdownHandle>,
+}
+
+/// Settings are serialized as the configuration they resolve from, so that they can be dumped in
+/// the same format sinks are configured with.
+impl Serialize for TowerRequestSettings {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_config().serialize(serializer)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitialBatchRetrySettings {
+    pub retry_attempts: usize,
+    pub retry_max_duration: Duration,
+    pub retry_initial_backoff: Duration,
 }
 
 impl TowerRequestSettings {
+    /// Resolves settings from `config`, merged with `defaults`, whenever it changes, so that
+    /// changes sent to `config` at runtime are picked up without rebuilding the sink.
+    #[cfg(feature = "hot-reload")]
+    pub fn live_reload(
+        config: tokio::sync::watch::Receiver<TowerRequestConfig>,
+        defaults: TowerRequestConfig,
+    ) -> LiveTowerRequestSettings {
+        LiveTowerRequestSettings::new(config, defaults)
+    }
+
+    /// The configuration these settings resolve from, with every option set explicitly.
+    ///
+    /// Durations are rounded down to whole seconds, as they are configured in seconds.
+    pub fn to_config(&self) -> TowerRequestConfig {
+        TowerRequestConfig {
+            concurrency: Some(match self.concurrency {
+                Some(limit) => Concurrency::Fixed(limit),
+                None => Concurrency::Adaptive,
+            }),
+            concurrency_burst: self.concurrency_burst,
+            partition_concurrency: self.partition_concurrency,
+            concurrency_burst_refill_secs: Some(self.concurrency_burst_refill.as_secs()),
+            max_in_flight_bytes: self.max_in_flight_bytes,
+            default_request_size_bytes: Some(self.default_request_size_bytes),
+            timeout_secs: Some(self.timeout.as_secs()),
+            rate_limit_duration_secs: Some(self.rate_limit_duration.as_secs()),
+            rate_limit_num: Some(match self.rate_limit_num {
+                Some(num) => RateLimitNum::Limited(num),
+                None => RateLimitNum::Unlimited,
+            }),
+            rate_limit_jitter: Some(self.rate_limit_jitter),
+            rate_limit_burst: self.rate_limit_burst,
+            retry_attempts: Some(self.retry_attempts),
+            retry_max_duration_secs: Some(self.retry_max_duration.as_secs()),
+            retry_initial_backoff_secs: Some(self.retry_initial_backoff.as_secs()),
+            retry_jitter_mode: Some(self.retry_jitter_mode),
+            initial_batch_retry: self
+                .initial_batch_retry
+                .map(|settings| InitialBatchRetryConfig {
+                    retry_attempts: Some(settings.retry_attempts),
+                    retry_max_duration_secs: Some(settings.retry_max_duration.as_secs()),
+                    retry_initial_backoff_secs: Some(settings.retry_initial_backoff.as_secs()),
+                }),
+            large_batch_retry: self.large_batch_retry,
+            // The deadline is already resolved, whether it was propagated from the timeout or not.
+            request_deadline_secs: self.request_deadline.map(|deadline| deadline.as_secs()),
+            propagate_deadline: Some(false),
+            startup_jitter_secs: Some(self.startup_jitter.as_secs()),
+            max_buffered_requests: self.max_buffered_requests,
+            buffer_mode: self.buffer_mode,
+            load_balance_strategy: Some(self.load_balance_strategy),
+            retry_on_any_error: Some(self.retry_on_any_error),
+            retryable_errors: Some(self.retryable_errors),
+            adaptive_concurrency: Some(self.adaptive_concurrency),
+            _d: PhantomData,
+        }
+    }
+
+    /// Checks these settings for values which can never work, returning every error found.
+    pub fn validate(&self) -> Result<(), Vec<RequestConfigError>> {
+        let errors = [
+            (self.timeout.is_zero(), RequestConfigError::InvalidTimeout),
+            (
+                self.rate_limit_duration.is_zero(),
+                RequestConfigError::InvalidRateLimitDuration,
+            ),
+            (
+                self.max_buffered_requests == Some(0),
+                RequestConfigError::InvalidMaxBufferedRequests,
+            ),
+            (
+                self.max_in_flight_bytes == Some(0),
+                RequestConfigError::InvalidMaxInFlightBytes,
+            ),
+            (
+                self.buffer_mode == Some(BufferMode::Bounded { capacity: 0 }),
+                RequestConfigError::InvalidBufferCapacity,
+            ),
+            (
+                self.rate_limit_num == Some(0),
+                RequestConfigError::InvalidRateLimitNum,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(invalid, error)| invalid.then_some(error))
+        .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Whether the concurrency limit is managed by adaptive concurrency.
+    pub const fn is_adaptive_concurrency_enabled(&self) -> bool {
+        self.concurrency.is_none()
+    }
+
+    /// Whether adaptive concurrency settings were configured even though the concurrency limit is
+    /// not managed by adaptive concurrency, so that they have no effect.
+    pub fn ignores_adaptive_concurrency(&self) -> bool {
+        self.concurrency.is_some()
+            && self.adaptive_concurrency != AdaptiveConcurrencySettings::default()
+    }
+
+    /// The adaptive concurrency settings, warning that they are ignored if they were configured
+    /// along with a fixed concurrency limit, or that the initial limit is above the maximum.
+    fn adaptive_concurrency_settings(&self) -> AdaptiveConcurrencySettings {
+        if self.ignores_adaptive_concurrency() {
+            warn!(
+                message = "Ignoring `request.adaptive_concurrency` settings, as they only apply when `request.concurrency` is `adaptive`.",
+                concurrency = ?self.concurrency,
+                internal_log_rate_limit = true,
+            );
+        } else if self.adaptive_concurrency.initial_exceeds_max() {
+            warn!(
+                message = "`request.adaptive_concurrency.initial_concurrency` exceeds `request.adaptive_concurrency.max_concurrency_limit`, so the concurrency limit starts above its maximum.",
+                initial_concurrency = self.adaptive_concurrency.initial_concurrency,
+                max_concurrency_limit = self.adaptive_concurrency.max_concurrency_limit,
+                internal_log_rate_limit = true,
+            );
+        }
+        self.adaptive_concurrency
+    }
+
+    /// The fixed concurrency limit, unless it is managed by adaptive concurrency.
+    pub const fn fixed_concurrency_limit(&self) -> Option<usize> {
+        self.concurrency
+    }
+
+    /// The rate limit to enforce, unless requests are not rate limited.
+    pub fn rate_limit_layer(&self) -> Option<RateLimitLayer> {
+        self.rate_limit_num
+            .map(|num| RateLimitLayer::new(num, self.rate_limit_duration))
+    }
+
+    /// The rate limit to enforce with a token bucket, when requests can burst above the rate
+    /// limit.
+    pub fn token_bucket_rate_limit_layer(&self) -> Option<TokenBucketRateLimitLayer> {
+        self.rate_limit_num
+            .zip(self.rate_limit_burst)
+            .map(|(num, burst)| {
+                TokenBucketRateLimitLayer::new(num, self.rate_limit_duration, burst)
+            })
+    }
+
+    /// The random delay of the first request, which also offsets the phase of the rate limit when
+    /// `rate_limit_jitter` is enabled, unless there is nothing to delay it by.
+    fn startup_jitter_layer(&self) -> Option<StartupJitterLayer> {
+        let phase = match self.rate_limit_num {
+            Some(_) if self.rate_limit_jitter => self.rate_limit_duration,
+            _ => Duration::ZERO,
+        };
+        (!self.startup_jitter.is_zero() || !phase.is_zero())
+            .then(|| StartupJitterLayer::new(self.startup_jitter).with_rate_limit_phase(phase))
+    }
+
+    /// How to queue requests in front of the endpoints of distributed services, which is bounded
+    /// by `buffer_bound` unless configured otherwise.
+    pub fn buffer_mode(&self, buffer_bound: usize) -> BufferMode {
+        if self.max_buffered_requests.is_some() && self.buffer_mode.is_some() {
+            warn!(
+                message =
+                    "Ignoring `request.max_buffered_requests`, as `request.buffer_mode` is set.",
+                internal_log_rate_limit = true,
+            );
+        }
+        self.buffer_mode.unwrap_or(BufferMode::Bounded {
+            capacity: self.max_buffered_requests.unwrap_or(buffer_bound),
+        })
+    }
+
+    /// The concurrency limit to enforce on top of [`BurstLimit`], which is the burst concurrency
+    /// when there is one.
+    fn burst_concurrency(&self) -> Option<usize> {
+        match (self.concurrency, self.concurrency_burst) {
+            (Some(steady), Some(burst)) => Some(steady.max(burst)),
+            (concurrency, _) => concurrency,
+        }
+    }
+
     pub fn retry_policy<L: RetryLogic>(&self, logic: L) -> FibonacciRetryPolicy<L> {
-        FibonacciRetryPolicy::new(
+        if self.retry_on_any_error {
+            warn!(
+                message = "Retrying every error, including non-retriable ones such as authentication failures. Setting `request.retry_on_any_error` is not recommended for production.",
+                internal_log_rate_limit = true,
+            );
+        }
+        if self.retry_initial_backoff > self.retry_max_duration {
+            warn!(
+                message = "`request.retry_initial_backoff_secs` exceeds `request.retry_max_duration_secs`, so only the first retry waits longer than `request.retry_max_duration_secs`.",
+                retry_initial_backoff_secs = self.retry_initial_backoff.as_secs(),
+                retry_max_duration_secs = self.retry_max_duration.as_secs(),
+                internal_log_rate_limit = true,
+            );
+        }
+
+        let configure = |policy: FibonacciRetryPolicy<L>| {
+            let policy = match self.large_batch_retry {
+                Some(large_batch) => {
+                    policy.with_large_batch(large_batch.min_bytes, large_batch.retry_attempts)
+                }
+                None => policy,
+            };
+            let policy = policy.with_retryable_errors(self.retryable_errors);
+            if self.retry_on_any_error {
+                policy.with_any_error()
+            } else {
+                policy
+            }
+        };
+        let policy = configure(FibonacciRetryPolicy::new(
             self.retry_attempts,
             self.retry_initial_backoff,
             self.retry_max_duration,
-            logic,
+            logic.clone(),
             self.retry_jitter_mode,
+        ));
+
+        match self.initial_batch_retry {
+            Some(initial_batch) => policy.with_initial_batch(configure(FibonacciRetryPolicy::new(
+                initial_batch.retry_attempts,
+                initial_batch.retry_initial_backoff,
+                initial_batch.retry_max_duration,
+                logic,
+                self.retry_jitter_mode,
+            ))),
+            None => policy,
+        }
+    }
+
+    /// Builds a retry policy for sinks mixing idempotent and non-idempotent requests.
+    ///
+    /// See [`IdempotencyAwareRetryPolicy`] for how the two kinds of requests are treated.
+    pub fn idempotency_aware_retry_policy<L: RetryLogic>(
+        &self,
+        logic: L,
+    ) -> IdempotencyAwareRetryPolicy<L> {
+        IdempotencyAwareRetryPolicy::new(self.retry_policy(logic))
+    }
+
+    /// A layer retrying partially delivered requests according to `mode`, with the same number of
+    /// attempts and backoff as other retries.
+    ///
+    /// It is meant to be applied to a sink's service before these settings, by sinks whose
+    /// downstream service accepts or rejects the items of a request individually.
+    pub fn partial_success_layer(&self, mode: PartialSuccessMode) -> PartialSuccessLayer {
+        let initial_backoff_millis = self.retry_initial_backoff.as_millis() as u64;
+        PartialSuccessLayer::new(
+            mode,
+            self.retry_attempts,
+            ExponentialBackoff::from_millis(2)
+                .factor((initial_backoff_millis / 2).max(1))
+                .max_delay(self.retry_max_duration),
         )
     }
 
-    /// Note: This has been deprecated, please do not use when creating new Sinks.
+    /// Shuts down the services built from these settings from now on through the returned handle.
+    ///
+    /// Draining the handle stops them from admitting new requests, and waits for those they have in
+    /// flight to complete.
+    pub fn shutdown_handle(&mut self) -> ShutdownHandle {
+        self.shutdown
+            .get_or_insert_with(ShutdownHandle::default)
+            .clone()
+    }
+
+    /// Wraps `service` in the middleware configured by these settings: rate limiting, concurrency
+    /// limiting, timeouts and retries, as decided by `retry_logic`.
+    ///
+    /// This is shorthand for building `service` with [`ServiceBuilderExt::settings`]. Stream based
+    /// sinks, which send each request through the returned service themselves, such as with
+    /// `Driver`, should use it rather than the deprecated batch sinks.
+    pub fn service<Req, RL, S>(self, retry_logic: RL, service: S) -> Svc<S, RL>
+    where
+        Req: Clone + Send + 'static,
+        RL: RetryLogic<Response = S::Response>,
+        S: Service<Req> + Send + 'static,
+        S::Response: Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Future: Send + 'static,
+    {
+        ServiceBuilder::new()
+            .settings::<RL, Req>(self, retry_logic)
+            .service(service)
+    }
+
+    /// Adds the middleware configured by these settings to `builder`, on top of the layers it
+    /// already has.
+    ///
+    /// This is the same as calling [`ServiceBuilderExt::settings`] on `builder`, for callers
+    /// holding the settings rather than the builder.
+    pub fn apply_to_builder<L, RL, Req>(
+        &self,
+        retry_logic: RL,
+        builder: ServiceBuilder<L>,
+    ) -> ServiceBuilder<Stack<TowerRequestLayer<RL, Req>, L>> {
+        builder.settings(self.clone(), retry_logic)
+    }
+
+    /// Wraps `service` in the middleware configured by these settings, transforming each request
+    /// with `f` before it is sent.
+    ///
+    /// Requests are transformed on every attempt, so retries are made with the original request.
+    pub fn service_with_map<Req, Req2, RL, S, F>(
+        self,
+        retry_logic: RL,
+        service: S,
+        f: F,
+    ) -> Svc<Map<S, Req, Req2>, RL>
+    where
+        Req: Clone + Send + 'static,
+        Req2: 'static,
+        RL: RetryLogic<Response = S::Response>,
+        S: Service<Req2> + Send + 'static,
+        S::Response: Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Future: Send + 'static,
+        F: Fn(Req) -> Req2 + Send + Sync + 'static,
+    {
+        ServiceBuilder::new()
+            .settings::<RL, Req>(self, retry_logic)
+            .map(f)
+            .service(service)
+    }
+
+    /// Note: This has been deprecated, please do not use when creating new Sinks. Stream based sinks
+    /// should wrap their service with [`TowerRequestSettings::service`] instead.
     pub fn partition_sink<B, RL, S, K>(
         &self,
         retry_logic: RL,
@@ -279,10 +1267,15 @@ impl TowerRequestSettings {
         let service = ServiceBuilder::new()
             .settings(self.clone(), retry_logic)
             .service(service);
-        PartitionBatchSink::new(service, batch, batch_timeout)
+        let mut sink = PartitionBatchSink::new(service, batch, batch_timeout);
+        if let Some(limit) = self.partition_concurrency {
+            sink.limit_partition_concurrency(limit);
+        }
+        sink
     }
 
-    /// Note: This has been deprecated, please do not use when creating new Sinks.
+    /// Note: This has been deprecated, please do not use when creating new Sinks. Stream based sinks
+    /// should wrap their service with [`TowerRequestSettings::service`] instead.
     pub fn batch_sink<B, RL, S>(
         &self,
         retry_logic: RL,
@@ -307,8 +1300,10 @@ impl TowerRequestSettings {
 
     /// Distributes requests to services [(Endpoint, service, healthcheck)]
     ///
-    /// [BufferLayer] suggests that the `buffer_bound` should be at least equal to
-    /// the number of the callers of the service. For sinks, this should typically be 1.
+    /// [`BufferLayer`](tower::buffer::BufferLayer) suggests that the `buffer_bound` should be at
+    /// least equal to the number of the callers of the service. For sinks, this should typically
+    /// be 1.
+    /// It is overridden by `max_buffered_requests` or `buffer_mode`, if set.
     pub fn distributed_service<Req, RL, HL, S>(
         self,
         retry_logic: RL,
@@ -326,42 +1321,235 @@ impl TowerRequestSettings {
         S::Response: Send,
         S::Future: Send + 'static,
     {
-        let policy = self.retry_policy(retry_logic.clone());
+        self.drainable_distributed_service(
+            retry_logic,
+            services,
+            health_config,
+            health_logic,
+            buffer_bound,
+        )
+        .0
+    }
 
-        // Build services
+    /// Distributes requests to services like [`Self::distributed_service`], along with a handle to
+    /// drain individual endpoints from load balancing.
+    pub fn drainable_distributed_service<Req, RL, HL, S>(
+        self,
+        retry_logic: RL,
+        services: Vec<(String, S)>,
+        health_config: HealthConfig,
+        health_logic: HL,
+        buffer_bound: usize,
+    ) -> (DistributedService<S, RL, HL, usize, Req>, DrainHandle)
+    where
+        Req: Clone + Send + 'static,
+        RL: RetryLogic<Response = S::Response>,
+        HL: HealthLogic<Response = S::Response, Error = crate::Error>,
+        S: Service<Req> + Clone + Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Response: Send,
+        S::Future: Send + 'static,
+    {
+        let (service, drain, _) = self.build_distributed_service(
+            retry_logic,
+            services,
+            health_config,
+            health_logic,
+            buffer_bound,
+        );
+        (service, drain)
+    }
+
+    /// Distributes requests to services like [`Self::distributed_service`], along with a
+    /// controller to add and remove endpoints at runtime.
+    ///
+    /// Endpoints added through the controller are not covered by a [`DrainHandle`].
+    pub fn controllable_distributed_service<Req, RL, HL, S>(
+        self,
+        retry_logic: RL,
+        services: Vec<(String, S)>,
+        health_config: HealthConfig,
+        health_logic: HL,
+        buffer_bound: usize,
+    ) -> (
+        DistributedService<S, RL, HL, usize, Req>,
+        EndpointController<S>,
+    )
+    where
+        Req: Clone + Send + 'static,
+        RL: RetryLogic<Response = S::Response>,
+        HL: HealthLogic<Response = S::Response, Error = crate::Error>,
+        S: Service<Req> + Clone + Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Response: Send,
+        S::Future: Send + 'static,
+    {
+        let (service, _, controller) = self.build_distributed_service(
+            retry_logic,
+            services,
+            health_config,
+            health_logic,
+            buffer_bound,
+        );
+        (service, controller)
+    }
+
+    /// Distributes requests to the `primary` services like [`Self::distributed_service`], and to
+    /// the `fallback` ones instead while none of the `primary` endpoints are available.
+    ///
+    /// Both pools are built with these settings, so each has a rate limit of its own. See
+    /// [`Failover`] for when requests switch between them.
+    pub fn distributed_service_with_fallback<Req, RL, HL, S>(
+        self,
+        retry_logic: RL,
+        primary: Vec<(String, S)>,
+        fallback: Vec<(String, S)>,
+        health_config: HealthConfig,
+        health_logic: HL,
+        buffer_bound: usize,
+    ) -> Failover<
+        DistributedService<S, RL, HL, usize, Req>,
+        DistributedService<S, RL, HL, usize, Req>,
+    >
+    where
+        Req: Clone + Send + 'static,
+        RL: RetryLogic<Response = S::Response>,
+        HL: HealthLogic<Response = S::Response, Error = crate::Error>,
+        S: Service<Req> + Clone + Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Response: Send,
+        S::Future: Send + 'static,
+    {
+        let (primary, primary_endpoints) = self.clone().drainable_distributed_service(
+            retry_logic.clone(),
+            primary,
+            health_config.clone(),
+            health_logic.clone(),
+            buffer_bound,
+        );
+        let fallback = self.distributed_service(
+            retry_logic,
+            fallback,
+            health_config,
+            health_logic,
+            buffer_bound,
+        );
+        Failover::new(primary, fallback, primary_endpoints)
+    }
+
+    fn build_distributed_service<Req, RL, HL, S>(
+        self,
+        retry_logic: RL,
+        services: Vec<(String, S)>,
+        health_config: HealthConfig,
+        health_logic: HL,
+        buffer_bound: usize,
+    ) -> (
+        DistributedService<S, RL, HL, usize, Req>,
+        DrainHandle,
+        EndpointController<S>,
+    )
+    where
+        Req: Clone + Send + 'static,
+        RL: RetryLogic<Response = S::Response>,
+        HL: HealthLogic<Response = S::Response, Error = crate::Error>,
+        S: Service<Req> + Clone + Send + 'static,
+        S::Error: Into<crate::Error> + Send + Sync + 'static,
+        S::Response: Send,
+        S::Future: Send + 'static,
+    {
+        let policy = self.retry_policy(retry_logic.clone());
+        let mut discovery = HealthDrivenDiscovery::new(&health_config);
+        let mut drain = DrainHandle::default();
+        let mut keys = HashMap::new();
+        let mut next_key = services.len();
+
+        // Builds the services of an endpoint, each time it is (re)inserted into load balancing.
         let open = OpenGauge::new();
-        let services = services
-            .into_iter()
-            .map(|(endpoint, inner)| {
+        let make_endpoint = {
+            let (concurrency, adaptive_concurrency, timeout) = (
+                self.concurrency,
+                self.adaptive_concurrency_settings(),
+                self.timeout,
+            );
+            move |endpoint: String, inner: S, counters: Arc<HealthCounters>| {
+                let (retry_logic, health_config, health_logic) = (
+                    retry_logic.clone(),
+                    health_config.clone(),
+                    health_logic.clone(),
+                );
+                let open = open.clone();
                 // Build individual service
-                ServiceBuilder::new()
-                    .layer(AdaptiveConcurrencyLimitLayer::new(
-                        self.concurrency,
-                        self.adaptive_concurrency,
-                        retry_logic.clone(),
-                    ))
-                    .service(
-                        health_config.build(
-                            health_logic.clone(),
-                            ServiceBuilder::new().timeout(self.timeout).service(inner),
-                            open.clone(),
-                            endpoint,
-                        ), // NOTE: there is a version conflict for crate `tracing` between `tracing_tower` crate
-                           // and Vector. Once that is resolved, this can be used instead of passing endpoint everywhere.
-                           // .trace_service(|_| info_span!("endpoint", %endpoint)),
-                    )
+                move || {
+                    let service = ServiceBuilder::new()
+                        .layer(
+                            AdaptiveConcurrencyLimitLayer::new(
+                                concurrency,
+                                adaptive_concurrency,
+                                retry_logic.clone(),
+                            )
+                            .with_endpoint(endpoint.clone()),
+                        )
+                        .service(
+                            health_config.build_with_counters(
+                                health_logic.clone(),
+                                ServiceBuilder::new()
+                                    .timeout(timeout)
+                                    .service(inner.clone()),
+                                open.clone(),
+                                endpoint.clone(),
+                                Arc::clone(&counters),
+                            ), // NOTE: there is a version conflict for crate `tracing` between `tracing_tower` crate
+                               // and Vector. Once that is resolved, this can be used instead of passing endpoint everywhere.
+                               // .trace_service(|_| info_span!("endpoint", %endpoint)),
+                        );
+                    DegradedWeight::new(service, Arc::clone(&counters))
+                }
+            }
+        };
+
+        // Build services
+        for (i, (endpoint, inner)) in services.into_iter().enumerate() {
+            let counters = Arc::new(HealthCounters::new());
+            let make_service = make_endpoint(endpoint.clone(), inner, Arc::clone(&counters));
+            let signal = discovery.push(i, Arc::clone(&counters), make_service);
+            drain.push(endpoint.clone(), signal, counters);
+            keys.insert(endpoint, i);
+        }
+
+        // Endpoints changed at runtime are keyed after the ones the service was built with.
+        let (controller, changes) = EndpointController::new();
+        let updates = UnboundedReceiverStream::new(changes).filter_map(move |change| {
+            future::ready(match change {
+                Change::Insert(endpoint, inner) => {
+                    let key = *keys.entry(endpoint.clone()).or_insert_with(|| {
+                        next_key += 1;
+                        next_key - 1
+                    });
+                    let counters = Arc::new(HealthCounters::new());
+                    Some(EndpointUpdate::Insert {
+                        key,
+                        counters: Arc::clone(&counters),
+                        make_service: Box::new(make_endpoint(endpoint, inner, counters)),
+                    })
+                }
+                Change::Remove(endpoint) => keys.remove(&endpoint).map(EndpointUpdate::Remove),
             })
-            .enumerate()
-            .map(|(i, service)| Ok(Change::Insert(i, service)))
-            .collect::<Vec<_>>();
+        });
+        discovery.follow(updates.boxed());
 
         // Build sink service
-        ServiceBuilder::new()
-            .rate_limit(self.rate_limit_num, self.rate_limit_duration)
+        let service = ServiceBuilder::new()
+            .option_layer(self.rate_limit_layer())
             .retry(policy)
-            // [Balance] must be wrapped with a [BufferLayer] so that the overall service implements Clone.
-            .layer(BufferLayer::new(buffer_bound))
-            .service(Balance::new(Box::pin(stream::iter(services)) as Pin<Box<_>>))
+            // [LoadBalancer] must be wrapped with a buffer so that the overall service implements Clone.
+            .layer(self.buffer_mode(buffer_bound).layer())
+            .service(LoadBalancer::new(
+                self.load_balance_strategy,
+                Box::pin(discovery) as Pin<Box<_>>,
+            ));
+
+        (service, drain, controller)
     }
 }
 
@@ -385,35 +1573,120 @@ where
 
     fn layer(&self, inner: S) -> Self::Service {
         let policy = self.settings.retry_policy(self.retry_logic.clone());
+        let token_bucket = self.settings.token_bucket_rate_limit_layer();
+        // The token bucket takes over from the fixed window rate limit when bursts are allowed.
+        let fixed_window = match token_bucket {
+            Some(_) => None,
+            None => self.settings.rate_limit_layer(),
+        };
         ServiceBuilder::new()
-            .rate_limit(
-                self.settings.rate_limit_num,
-                self.settings.rate_limit_duration,
-            )
+            .option_layer(token_bucket)
+            .option_layer(fixed_window)
             .layer(AdaptiveConcurrencyLimitLayer::new(
-                self.settings.concurrency,
-                self.settings.adaptive_concurrency,
+                self.settings.burst_concurrency(),
+                self.settings.adaptive_concurrency_settings(),
                 self.retry_logic.clone(),
             ))
+            .option_layer(BurstLimitLayer::new(
+                self.settings.concurrency,
+                self.settings.concurrency_burst,
+                self.settings.concurrency_burst_refill,
+            ))
+            .option_layer(self.settings.max_in_flight_bytes.map(|max_bytes| {
+                InFlightBytesLayer::new(max_bytes, self.settings.default_request_size_bytes)
+            }))
+            .layer(InFlightLayer::new(self.settings.shutdown.clone()))
+            .option_layer(self.settings.startup_jitter_layer())
+            .option_layer(self.settings.request_deadline.map(DeadlineLayer::new))
+            .layer(TracingLayer)
+            .layer(RetryExhaustionLayer)
             .retry(policy)
-            .timeout(self.settings.timeout)
+            .layer(AttemptSpanLayer)
+            .layer(AttemptTimeoutLayer::new(self.settings.timeout))
             .service(inner)
     }
 }
 
+impl<RL, Request> TowerRequestLayer<RL, Request> {
+    /// Creates a layer wrapping services in the middleware configured by `settings`, retrying
+    /// requests as decided by `retry_logic`.
+    pub const fn new(settings: TowerRequestSettings, retry_logic: RL) -> Self {
+        Self {
+            settings,
+            retry_logic,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Wraps each arm of `inner` in the middleware configured by these settings, rather than the
+    /// `Either` as a whole.
+    ///
+    /// Each arm gets a stack of its own, so they don't share concurrency or rate limits. This
+    /// can't be an implementation of `Layer<Either<S1, S2>>`, as it would overlap with the
+    /// implementation for every service.
+    pub fn layer_either<S1, S2>(&self, inner: Either<S1, S2>) -> Either<Svc<S1, RL>, Svc<S2, RL>>
+    where
+        Self: Layer<S1, Service = Svc<S1, RL>> + Layer<S2, Service = Svc<S2, RL>>,
+    {
+        match inner {
+            Either::A(left) => Either::A(Layer::<S1>::layer(self, left)),
+            Either::B(right) => Either::B(Layer::<S2>::layer(self, right)),
+        }
+    }
+}
+
+/// Wraps each arm of an `Either` in the middleware configured by settings of its own, such as to
+/// give a fallback service other timeouts and retries than the primary one.
+#[derive(Debug, Clone)]
+pub struct TowerBiLayer<L, Request> {
+    left: TowerRequestLayer<L, Request>,
+    right: TowerRequestLayer<L, Request>,
+}
+
+impl<L: Clone, Request> TowerBiLayer<L, Request> {
+    /// Creates a layer wrapping the `Either::A` arm in the `left` settings, and the `Either::B`
+    /// arm in the `right` settings.
+    pub fn new(left: TowerRequestSettings, right: TowerRequestSettings, retry_logic: L) -> Self {
+        Self {
+            left: TowerRequestLayer::new(left, retry_logic.clone()),
+            right: TowerRequestLayer::new(right, retry_logic),
+        }
+    }
+}
+
+impl<S1, S2, RL, Request> Layer<Either<S1, S2>> for TowerBiLayer<RL, Request>
+where
+    TowerRequestLayer<RL, Request>:
+        Layer<S1, Service = Svc<S1, RL>> + Layer<S2, Service = Svc<S2, RL>>,
+{
+    type Service = Either<Svc<S1, RL>, Svc<S2, RL>>;
+
+    fn layer(&self, inner: Either<S1, S2>) -> Self::Service {
+        match inner {
+            Either::A(left) => Either::A(Layer::<S1>::layer(&self.left, left)),
+            Either::B(right) => Either::B(Layer::<S2>::layer(&self.right, right)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{
-        atomic::{AtomicBool, Ordering::AcqRel},
+        atomic::{
+            AtomicBool, AtomicUsize,
+            Ordering::{AcqRel, Acquire},
+        },
         Arc, Mutex,
     };
 
     use futures::{future, stream, FutureExt, SinkExt, StreamExt};
     use tokio::time::Duration;
+    use tower::ServiceExt;
     use vector_lib::json_size::JsonSize;
 
     use super::*;
     use crate::sinks::util::{
+        adaptive_concurrency::ConcurrencyController,
         retries::{RetryAction, RetryLogic},
         BatchSettings, EncodedEvent, PartitionBuffer, PartitionInnerBuffer, VecBuffer,
     };
@@ -427,25 +1700,29 @@ mod tests {
         toml::from_str::<TowerRequestConfig>(&toml).expect("Default config failed");
 
         let cfg = toml::from_str::<TowerRequestConfig>("").expect("Empty config failed");
-        assert_eq!(cfg.concurrency, Concurrency::Adaptive);
+        assert_eq!(cfg.concurrency, None);
 
         let cfg = toml::from_str::<TowerRequestConfig>("concurrency = 10")
             .expect("Fixed concurrency failed");
-        assert_eq!(cfg.concurrency, Concurrency::Fixed(10));
+        assert_eq!(cfg.concurrency, Some(Concurrency::Fixed(10)));
 
         let cfg = toml::from_str::<TowerRequestConfig>(r#"concurrency = "adaptive""#)
             .expect("Adaptive concurrency setting failed");
-        assert_eq!(cfg.concurrency, Concurrency::Adaptive);
+        assert_eq!(cfg.concurrency, Some(Concurrency::Adaptive));
 
         let cfg = toml::from_str::<TowerRequestConfig>(r#"concurrency = "none""#)
             .expect("None concurrency setting failed");
-        assert_eq!(cfg.concurrency, Concurrency::None);
+        assert_eq!(cfg.concurrency, Some(Concurrency::None));
 
         toml::from_str::<TowerRequestConfig>(r#"concurrency = "broken""#)
             .expect_err("Invalid concurrency setting didn't fail");
 
         toml::from_str::<TowerRequestConfig>(r#"concurrency = 0"#)
             .expect_err("Invalid concurrency setting didn't fail on zero");
+        assert_eq!(
+            Concurrency::Fixed(0).parse_concurrency(),
+            Err(ConcurrencyError::Zero)
+        );
 
         toml::from_str::<TowerRequestConfig>(r#"concurrency = -9"#)
             .expect_err("Invalid concurrency setting didn't fail on negative number");
@@ -456,13 +1733,26 @@ mod tests {
         let cfg = TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default();
         let settings = cfg.into_settings();
 
-        assert_eq!(settings.concurrency, None);
+        assert!(settings.is_adaptive_concurrency_enabled());
+        assert_eq!(settings.concurrency_burst, None);
+        assert_eq!(settings.max_in_flight_bytes, None);
+        assert_eq!(settings.default_request_size_bytes, 1024 * 1024);
         assert_eq!(settings.timeout, Duration::from_secs(60));
         assert_eq!(settings.rate_limit_duration, Duration::from_secs(1));
-        assert_eq!(settings.rate_limit_num, i64::max_value() as u64);
+        assert_eq!(settings.rate_limit_num, None);
+        assert!(!settings.rate_limit_jitter);
+        assert_eq!(settings.rate_limit_burst, None);
         assert_eq!(settings.retry_attempts, isize::max_value() as usize);
         assert_eq!(settings.retry_max_duration, Duration::from_secs(30));
         assert_eq!(settings.retry_initial_backoff, Duration::from_secs(1));
+        assert_eq!(settings.initial_batch_retry, None);
+        assert_eq!(settings.request_deadline, None);
+        assert_eq!(settings.startup_jitter, Duration::ZERO);
+        assert_eq!(settings.max_buffered_requests, None);
+        assert_eq!(settings.buffer_mode, None);
+        assert_eq!(settings.load_balance_strategy, LoadBalanceStrategy::P2C);
+        assert!(!settings.retry_on_any_error);
+        assert_eq!(settings.retryable_errors, RetryableErrorsConfig::default());
     }
 
     #[derive(Clone, Copy, Debug)]
@@ -472,26 +1762,168 @@ mod tests {
         const CONCURRENCY: Concurrency = Concurrency::None;
         const TIMEOUT_SECS: u64 = 1;
         const RATE_LIMIT_DURATION_SECS: u64 = 2;
-        const RATE_LIMIT_NUM: u64 = 3;
+        const RATE_LIMIT_NUM: RateLimitNum = RateLimitNum::Limited(3);
         const RETRY_ATTEMPTS: usize = 4;
         const RETRY_MAX_DURATION_SECS: u64 = 5;
         const RETRY_INITIAL_BACKOFF_SECS: u64 = 6;
     }
 
+    #[tokio::test]
+    async fn unlimited_rate_limit_skips_rate_limiting() {
+        let parse = |config: &str| {
+            toml::from_str::<TowerRequestConfig>(config)
+                .expect("Config failed to parse")
+                .into_settings()
+        };
+        let layer = |settings: TowerRequestSettings| {
+            TowerRequestLayer {
+                settings,
+                retry_logic: RetryAlways,
+                _pd: PhantomData,
+            }
+            .layer(tower::service_fn(|_: ()| future::ok::<_, crate::Error>(())))
+        };
+
+        let settings = parse(r#"rate_limit_num = "unlimited""#);
+        assert_eq!(settings.rate_limit_num, None);
+        assert!(matches!(layer(settings), Either::B(Either::B(_))));
+
+        let settings = parse("rate_limit_num = 100");
+        assert_eq!(settings.rate_limit_num, Some(100));
+        assert!(matches!(layer(settings), Either::B(Either::A(_))));
+
+        let settings = parse("rate_limit_num = 100\nrate_limit_burst = 50");
+        assert_eq!(settings.rate_limit_burst, Some(50));
+        assert!(matches!(layer(settings), Either::A(Either::B(_))));
+
+        // Bursts are only allowed on top of a rate limit.
+        let settings = parse("rate_limit_num = \"unlimited\"\nrate_limit_burst = 50");
+        assert!(matches!(layer(settings), Either::B(Either::B(_))));
+    }
+
+    #[test]
+    fn startup_jitter_is_skipped_without_a_delay() {
+        let parse = |config: &str| {
+            toml::from_str::<TowerRequestConfig>(config)
+                .expect("Config failed to parse")
+                .into_settings()
+        };
+
+        assert!(parse("").startup_jitter_layer().is_none());
+        assert!(parse("rate_limit_jitter = true")
+            .startup_jitter_layer()
+            .is_none());
+        assert!(parse("startup_jitter_secs = 5")
+            .startup_jitter_layer()
+            .is_some());
+        assert!(parse("rate_limit_num = 100\nrate_limit_jitter = true")
+            .startup_jitter_layer()
+            .is_some());
+    }
+
     #[test]
     fn into_settings_with_overridden_defaults() {
         let cfg = TowerRequestConfig::<TestTowerRequestConfigDefaults>::default();
         let settings = cfg.into_settings();
 
-        assert_eq!(settings.concurrency, Some(1));
+        assert_eq!(settings.fixed_concurrency_limit(), Some(1));
         assert_eq!(settings.timeout, Duration::from_secs(1));
         assert_eq!(settings.rate_limit_duration, Duration::from_secs(2));
-        assert_eq!(settings.rate_limit_num, 3);
+        assert_eq!(settings.rate_limit_num, Some(3));
         assert_eq!(settings.retry_attempts, 4);
         assert_eq!(settings.retry_max_duration, Duration::from_secs(5));
         assert_eq!(settings.retry_initial_backoff, Duration::from_secs(6));
     }
 
+    #[test]
+    fn or_keeps_options_set_to_their_default() {
+        let parse = |toml| toml::from_str::<TowerRequestConfig>(toml).unwrap();
+        let user = parse("timeout_secs = 60\nrate_limit_jitter = false");
+        let defaults = parse("timeout_secs = 10\nrate_limit_jitter = true\nretry_attempts = 5");
+
+        let settings = user | defaults;
+        assert_eq!(settings.timeout, Duration::from_secs(60));
+        assert!(!settings.rate_limit_jitter);
+        assert_eq!(settings.retry_attempts, 5);
+
+        // Configs built in code set only the options they hold a value for.
+        let user = TowerRequestConfig {
+            retry_attempts: Some(3),
+            ..Default::default()
+        };
+        let settings = user | defaults;
+        assert_eq!(settings.retry_attempts, 3);
+        assert_eq!(settings.timeout, Duration::from_secs(10));
+        assert!(settings.rate_limit_jitter);
+    }
+
+    #[test]
+    fn or_chains_prefer_the_most_specific_config() {
+        let parse = |toml| toml::from_str::<TowerRequestConfig>(toml).unwrap();
+        let sink = parse("retry_attempts = 1");
+        let pipeline = parse("retry_attempts = 2\ntimeout_secs = 20");
+        let global = parse("retry_attempts = 3\ntimeout_secs = 30\nrate_limit_num = 300");
+
+        let merged = sink.or(pipeline).or(global);
+        assert_eq!(merged.retry_attempts, Some(1));
+        assert_eq!(merged.timeout_secs, Some(20));
+        assert_eq!(merged.rate_limit_num, Some(RateLimitNum::Limited(300)));
+        // Options no config sets are left unset.
+        assert_eq!(merged.concurrency, None);
+    }
+
+    #[test]
+    fn buffer_settings_are_merged_together() {
+        let parse = |toml| toml::from_str::<TowerRequestConfig>(toml).unwrap();
+        let sink = parse("max_buffered_requests = 10");
+        let global = parse(r#"buffer_mode.type = "unbounded""#);
+
+        let merged = sink.or(global);
+        assert_eq!(merged.max_buffered_requests, Some(10));
+        assert_eq!(merged.buffer_mode, None);
+        let settings = merged.try_into_settings().unwrap();
+        assert_eq!(
+            settings.buffer_mode(1),
+            BufferMode::Bounded { capacity: 10 }
+        );
+
+        let merged = parse("").or(global);
+        assert_eq!(merged.into_settings().buffer_mode(1), BufferMode::Unbounded);
+        assert_eq!(
+            parse("").into_settings().buffer_mode(1),
+            BufferMode::Bounded { capacity: 1 }
+        );
+    }
+
+    #[test]
+    fn settings_round_trip_through_their_config() {
+        let cfg = toml::from_str::<TowerRequestConfig>(
+            r#" concurrency = 16
+            concurrency_burst = 32
+            timeout_secs = 1
+            rate_limit_num = 3
+            rate_limit_jitter = true
+            retry_attempts = 4
+            initial_batch_retry.retry_attempts = 8
+            propagate_deadline = true
+            buffer_mode.type = "unbounded"
+            retry_on_any_error = true
+            adaptive_concurrency.initial_concurrency = 2
+        "#,
+        )
+        .expect("Config failed to parse");
+        let settings = cfg.into_settings();
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let parsed: TowerRequestConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.into_settings(), settings);
+
+        let adaptive = TowerRequestConfig::default().into_settings();
+        let json = serde_json::to_value(&adaptive).unwrap();
+        let parsed: TowerRequestConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.into_settings(), adaptive);
+    }
+
     #[test]
     fn into_settings_with_populated_config() {
         // Populate with values not equal to the global defaults.
@@ -509,69 +1941,1138 @@ mod tests {
 
         // Merge with defaults
         let settings = cfg.into_settings();
-        assert_eq!(
-            settings.concurrency,
-            Concurrency::Fixed(16).parse_concurrency()
-        );
+        assert_eq!(settings.concurrency, Some(16));
         assert_eq!(settings.timeout, Duration::from_secs(1));
         assert_eq!(settings.rate_limit_duration, Duration::from_secs(2));
-        assert_eq!(settings.rate_limit_num, 3);
+        assert_eq!(settings.rate_limit_num, Some(3));
         assert_eq!(settings.retry_attempts, 4);
         assert_eq!(settings.retry_max_duration, Duration::from_secs(5));
         assert_eq!(settings.retry_initial_backoff, Duration::from_secs(6));
     }
 
-    #[tokio::test]
-    async fn partition_sink_retry_concurrency() {
-        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
-            concurrency: Concurrency::Fixed(1),
-            ..TowerRequestConfig::default()
-        };
+    #[test]
+    fn into_settings_with_distribution_options() {
+        let cfg = toml::from_str::<TowerRequestConfig>(
+            r#" max_buffered_requests = 7
+            load_balance_strategy = "round_robin"
+        "#,
+        )
+        .expect("Config failed to parse");
+
         let settings = cfg.into_settings();
+        assert_eq!(settings.max_buffered_requests, Some(7));
+        assert_eq!(
+            settings.load_balance_strategy,
+            LoadBalanceStrategy::RoundRobin
+        );
+    }
 
-        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+    #[test]
+    fn into_settings_with_retry_options() {
+        let cfg = toml::from_str::<TowerRequestConfig>(
+            r#" retry_on_any_error = true
+            retryable_errors.allow = ["timeout", "throttled"]
+        "#,
+        )
+        .expect("Config failed to parse");
 
-        let svc = {
-            let sent_requests = Arc::clone(&sent_requests);
-            let delay = Arc::new(AtomicBool::new(true));
-            tower::service_fn(move |req: PartitionInnerBuffer<_, _>| {
-                let (req, _) = req.into_parts();
-                if delay.swap(false, AcqRel) {
-                    // Error on first request
-                    future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, "")).boxed()
-                } else {
-                    sent_requests.lock().unwrap().push(req);
-                    future::ok::<_, std::io::Error>(()).boxed()
-                }
-            })
-        };
+        let settings = cfg.into_settings();
+        assert!(settings.retry_on_any_error);
+        assert_eq!(
+            settings.retryable_errors.allow,
+            Some(RetryErrorCategories::from_iter([
+                RetryErrorCategory::Timeout,
+                RetryErrorCategory::Throttled,
+            ]))
+        );
+    }
 
-        let mut batch_settings = BatchSettings::default();
-        batch_settings.size.bytes = 9999;
-        batch_settings.size.events = 10;
+    #[test]
+    fn adaptive_concurrency_settings_round_trip() {
+        let cfg = toml::from_str::<TowerRequestConfig>(
+            r#"adaptive_concurrency.initial_concurrency = 3
+            adaptive_concurrency.decrease_ratio = 0.5
+            adaptive_concurrency.ewma_alpha = 0.25
+            adaptive_concurrency.rtt_deviation_scale = 1.5
+            adaptive_concurrency.max_concurrency_limit = 50
+            adaptive_concurrency.probe_requests = 2
+            adaptive_concurrency.slow_start_secs = 30
+        "#,
+        )
+        .expect("Config failed to parse");
 
-        let mut sink = settings.partition_sink(
-            RetryAlways,
-            svc,
-            PartitionBuffer::new(VecBuffer::new(batch_settings.size)),
-            TIMEOUT,
+        let toml = toml::to_string(&cfg).unwrap();
+        let parsed = toml::from_str::<TowerRequestConfig>(&toml).expect("Round trip failed");
+        assert_eq!(parsed.adaptive_concurrency, cfg.adaptive_concurrency);
+    }
+
+    #[test]
+    fn config_round_trips_through_yaml() {
+        let cfg = serde_yaml::from_str::<TowerRequestConfig>(
+            r#"
+            concurrency: 10
+            concurrency_burst: 20
+            partition_concurrency: 5
+            concurrency_burst_refill_secs: 2
+            max_in_flight_bytes: 1000000
+            default_request_size_bytes: 1000
+            timeout_secs: 30
+            rate_limit_duration_secs: 2
+            rate_limit_num: 100
+            rate_limit_jitter: true
+            rate_limit_burst: 50
+            retry_attempts: 18446744073709551615
+            retry_max_duration_secs: 60
+            retry_initial_backoff_secs: 2
+            retry_jitter_mode: None
+            initial_batch_retry:
+              retry_attempts: 3
+              retry_max_duration_secs: 10
+              retry_initial_backoff_secs: 1
+            large_batch_retry:
+              min_bytes: 1000000
+              retry_attempts: 1
+            request_deadline_secs: 120
+            propagate_deadline: true
+            startup_jitter_secs: 5
+            buffer_mode:
+              type: bounded
+              capacity: 100
+            load_balance_strategy: least_loaded
+            retry_on_any_error: false
+            retryable_errors:
+              allow: [timeout, throttled]
+              deny: [throttled]
+            adaptive_concurrency:
+              initial_concurrency: 3
+              decrease_ratio: 0.5
+              ewma_alpha: 0.25
+              rtt_deviation_scale: 1.5
+              max_concurrency_limit: 50
+              probe_requests: 2
+              slow_start_secs: 30
+            "#,
+        )
+        .expect("Config failed to parse");
+        assert_eq!(cfg.concurrency, Some(Concurrency::Fixed(10)));
+        assert_eq!(cfg.retry_attempts, Some(usize::MAX));
+
+        let yaml = serde_yaml::to_string(&cfg).unwrap();
+        let parsed = serde_yaml::from_str::<TowerRequestConfig>(&yaml).expect("Round trip failed");
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            serde_json::to_value(cfg).unwrap()
         );
-        sink.ordered();
 
-        let input = (0..20).map(|i| PartitionInnerBuffer::new(i, 0));
-        sink.sink_map_err(drop)
-            .send_all(
-                &mut stream::iter(input)
-                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
-            )
-            .await
-            .unwrap();
+        let concurrency = |yaml: &str| {
+            serde_yaml::from_str::<TowerRequestConfig>(&format!("concurrency: {yaml}"))
+                .map(|cfg| cfg.concurrency)
+        };
+        for variant in [
+            Concurrency::None,
+            Concurrency::Adaptive,
+            Concurrency::Fixed(1),
+            Concurrency::Fixed(usize::MAX),
+        ] {
+            let yaml = serde_yaml::to_string(&variant).unwrap();
+            assert_eq!(concurrency(&yaml).unwrap(), Some(variant));
+        }
+        assert!(concurrency("0").is_err());
+        assert!(concurrency("-1").is_err());
+    }
+
+    #[test]
+    fn adaptive_concurrency_settings_from_individual_fields() {
+        let parse = |config: &str| {
+            toml::from_str::<TowerRequestConfig>(config)
+                .expect("Config failed to parse")
+                .adaptive_concurrency
+                .unwrap_or_default()
+        };
+        let default = AdaptiveConcurrencySettings::default();
 
-        let output = sent_requests.lock().unwrap();
         assert_eq!(
-            &*output,
-            &vec![(0..10).collect::<Vec<_>>(), (10..20).collect::<Vec<_>>(),]
+            parse("adaptive_concurrency.initial_concurrency = 3"),
+            AdaptiveConcurrencySettings {
+                initial_concurrency: 3,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse("adaptive_concurrency.decrease_ratio = 0.5"),
+            AdaptiveConcurrencySettings {
+                decrease_ratio: 0.5,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse("adaptive_concurrency.ewma_alpha = 0.25"),
+            AdaptiveConcurrencySettings {
+                ewma_alpha: 0.25,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse("adaptive_concurrency.rtt_deviation_scale = 1.5"),
+            AdaptiveConcurrencySettings {
+                rtt_deviation_scale: 1.5,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse("adaptive_concurrency.max_concurrency_limit = 50"),
+            AdaptiveConcurrencySettings {
+                max_concurrency_limit: 50,
+                ..default
+            }
         );
+        assert_eq!(
+            parse("adaptive_concurrency.probe_requests = 2"),
+            AdaptiveConcurrencySettings {
+                probe_requests: 2,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse("adaptive_concurrency.slow_start_secs = 30"),
+            AdaptiveConcurrencySettings {
+                slow_start_secs: 30,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse(r#"adaptive_concurrency.controller = "gradient""#),
+            AdaptiveConcurrencySettings {
+                controller: ConcurrencyController::Gradient,
+                ..default
+            }
+        );
+        assert_eq!(
+            parse(
+                r#"adaptive_concurrency.controller = "timeout_aimd"
+                adaptive_concurrency.timeout_increase_by = 0.5"#
+            ),
+            AdaptiveConcurrencySettings {
+                controller: ConcurrencyController::TimeoutAimd,
+                timeout_increase_by: 0.5,
+                ..default
+            }
+        );
+    }
+
+    #[test]
+    fn adaptive_concurrency_settings_builder() {
+        const TUNED: AdaptiveConcurrencySettings = AdaptiveConcurrencySettings::new()
+            .initial_concurrency(4)
+            .decrease_ratio(0.5)
+            .ewma_alpha(0.25)
+            .rtt_deviation_scale(1.5);
+
+        assert_eq!(
+            AdaptiveConcurrencySettings::new(),
+            AdaptiveConcurrencySettings::default()
+        );
+        assert_eq!(
+            TUNED,
+            AdaptiveConcurrencySettings {
+                initial_concurrency: 4,
+                decrease_ratio: 0.5,
+                ewma_alpha: 0.25,
+                rtt_deviation_scale: 1.5,
+                ..AdaptiveConcurrencySettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn adaptive_concurrency_settings_are_ignored_without_adaptive_concurrency() {
+        let parse = |config: &str| {
+            toml::from_str::<TowerRequestConfig>(config)
+                .unwrap()
+                .into_settings()
+        };
+
+        assert!(!parse("adaptive_concurrency.ewma_alpha = 0.2").ignores_adaptive_concurrency());
+        assert!(!parse("concurrency = 10").ignores_adaptive_concurrency());
+        assert!(
+            parse("concurrency = 10\nadaptive_concurrency.ewma_alpha = 0.2")
+                .ignores_adaptive_concurrency()
+        );
+        assert!(parse(
+            r#"concurrency = "none"
+            adaptive_concurrency.ewma_alpha = 0.2"#
+        )
+        .ignores_adaptive_concurrency());
+    }
+
+    #[test]
+    fn try_into_settings_rejects_invalid_config() {
+        let parse = |config: &str| toml::from_str::<TowerRequestConfig>(config).unwrap();
+
+        assert!(parse("").try_into_settings().is_ok());
+        assert_eq!(
+            parse("timeout_secs = 0").try_into_settings().unwrap_err(),
+            RequestConfigError::InvalidTimeout
+        );
+        assert_eq!(
+            parse("rate_limit_duration_secs = 0")
+                .try_into_settings()
+                .unwrap_err(),
+            RequestConfigError::InvalidRateLimitDuration
+        );
+        assert_eq!(
+            parse("buffer_size = 0").try_into_settings().unwrap_err(),
+            RequestConfigError::InvalidMaxBufferedRequests
+        );
+        assert_eq!(
+            parse("max_in_flight_bytes = 0")
+                .try_into_settings()
+                .unwrap_err(),
+            RequestConfigError::InvalidMaxInFlightBytes
+        );
+        assert_eq!(
+            parse(r#"buffer_mode = { type = "bounded", capacity = 0 }"#)
+                .try_into_settings()
+                .unwrap_err(),
+            RequestConfigError::InvalidBufferCapacity
+        );
+        assert_eq!(
+            parse("rate_limit_num = 0").try_into_settings().unwrap_err(),
+            RequestConfigError::InvalidRateLimitNum
+        );
+        // A concurrency of zero can't be parsed, but can still be set by a sink.
+        let config: TowerRequestConfig = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(0)),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.try_into_settings().unwrap_err(),
+            RequestConfigError::InvalidConcurrency
+        );
+        // Resolving it without validation raises it to a usable limit.
+        assert_eq!(config.into_settings().concurrency, Some(1));
+    }
+
+    #[test]
+    fn try_into_settings_accepts_configs_which_used_to_load() {
+        let parse = |config: &str| toml::from_str::<TowerRequestConfig>(config).unwrap();
+
+        // The request timeout is unrelated to the retry durations.
+        assert!(parse("retry_attempts = 1").try_into_settings().is_ok());
+        // A backoff above its maximum is clamped to it after the first retry.
+        assert!(
+            parse("retry_initial_backoff_secs = 10\nretry_max_duration_secs = 5")
+                .try_into_settings()
+                .is_ok()
+        );
+        assert!(parse(
+            r#"adaptive_concurrency.initial_concurrency = 10
+            adaptive_concurrency.max_concurrency_limit = 5"#
+        )
+        .try_into_settings()
+        .is_ok());
+        // `buffer_mode` takes precedence.
+        let settings = parse(
+            r#"max_buffered_requests = 10
+            buffer_mode.type = "unbounded""#,
+        )
+        .try_into_settings()
+        .unwrap();
+        assert_eq!(settings.buffer_mode(5), BufferMode::Unbounded);
+    }
+
+    #[test]
+    fn validate_reports_every_error() {
+        let config: TowerRequestConfig = TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(0)),
+            rate_limit_duration_secs: Some(0),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.into_settings().validate().unwrap_err(),
+            [
+                RequestConfigError::InvalidRateLimitDuration,
+                RequestConfigError::InvalidRateLimitNum,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn service_wraps_service_in_settings() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(1)),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+
+        let inner = MockService::with_responses(vec![Ok(()), Err(error()), Ok(())]);
+        let mut svc = cfg.into_settings().service(RetryAlways, inner.clone());
+
+        let start = tokio::time::Instant::now();
+        svc.ready().await.unwrap().call("first").await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The second request waits 1s for the next window of the rate limit, and is then retried
+        // once after a backoff of 1s.
+        svc.ready().await.unwrap().call("second").await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(inner.requests(), ["first", "second", "second"]);
+    }
+
+    #[tokio::test]
+    async fn service_retries_within_concurrency_limit() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(2)),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let mock = MockService::new(()).with_latency(Duration::from_secs(1));
+        mock.fail_times(2, || std::io::Error::new(std::io::ErrorKind::Other, ""));
+        let svc = settings.service(RetryAlways, mock.clone());
+
+        let calls = (0..4).map(|i| svc.clone().oneshot(i));
+        for result in future::join_all(calls).await {
+            result.unwrap();
+        }
+
+        assert_eq!(mock.calls(), 6);
+        assert_eq!(mock.retries(), 2);
+        assert_eq!(mock.max_in_flight(), 2);
+    }
+
+    #[tokio::test]
+    async fn count_attempts_reports_attempts_of_successful_requests() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let inner = MockService::with_responses(vec![Ok(()), Err(error()), Err(error()), Ok(())]);
+        let mut svc = ServiceBuilder::new()
+            .count_attempts()
+            .service(settings.service(RetryAlways, inner.clone()));
+
+        let first = svc.ready().await.unwrap().call("first").await.unwrap();
+        assert_eq!(first.attempts, 1);
+        let second = svc.ready().await.unwrap().call("second").await.unwrap();
+        assert_eq!(second.attempts, 3);
+        inner.assert_attempts(4);
+    }
+
+    #[tokio::test]
+    async fn apply_to_builder_builds_the_same_service_as_settings() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(1)),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        async fn run<S>(mut svc: S) -> (Vec<usize>, Duration)
+        where
+            S: Service<&'static str, Response = Attempted<()>>,
+            S::Error: std::fmt::Debug,
+        {
+            let start = tokio::time::Instant::now();
+            let mut attempts = Vec::new();
+            for request in ["first", "second"] {
+                let response = svc.ready().await.unwrap().call(request).await.unwrap();
+                attempts.push(response.attempts);
+            }
+            (attempts, start.elapsed())
+        }
+
+        let responses = || vec![Ok(()), Err(error()), Ok(())];
+        let applied_inner = MockService::with_responses(responses());
+        let applied = settings
+            .apply_to_builder(RetryAlways, ServiceBuilder::new().count_attempts())
+            .service(applied_inner.clone());
+        let built_inner = MockService::with_responses(responses());
+        let built = ServiceBuilder::new()
+            .count_attempts()
+            .settings(settings, RetryAlways)
+            .service(built_inner.clone());
+
+        let applied = run(applied).await;
+        assert_eq!(applied, run(built).await);
+        assert_eq!(applied.0, [1, 2]);
+        assert_eq!(applied_inner.requests(), built_inner.requests());
+    }
+
+    #[tokio::test]
+    async fn shutdown_handle_stops_services_built_from_the_settings() {
+        let cfg: TowerRequestConfig = TowerRequestConfig::default();
+        let mut settings = cfg.into_settings();
+        let shutdown = settings.shutdown_handle();
+
+        let inner = MockService::with_responses(vec![Ok(())]);
+        let mut svc = settings.service(RetryAlways, inner.clone());
+        svc.ready().await.unwrap().call("first").await.unwrap();
+
+        shutdown.drain(Duration::from_secs(1)).await.unwrap();
+        let error = svc.ready().await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SinkServiceError>(),
+            Some(SinkServiceError::ShuttingDown)
+        ));
+        assert_eq!(inner.requests(), ["first"]);
+    }
+
+    #[tokio::test]
+    async fn either_arms_are_wrapped_in_their_own_settings() {
+        tokio::time::pause();
+
+        let retrying: TowerRequestConfig = TowerRequestConfig {
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+        let not_retrying: TowerRequestConfig = TowerRequestConfig {
+            retry_attempts: Some(0),
+            ..retrying
+        };
+        let responses = || vec![Err(error()), Ok(())];
+
+        let layer = TowerBiLayer::new(
+            retrying.into_settings(),
+            not_retrying.into_settings(),
+            RetryAlways,
+        );
+        let left = MockService::with_responses(responses());
+        let mut svc = layer.layer(Either::<_, MockService<_, ()>>::A(left.clone()));
+        svc.ready().await.unwrap().call("left").await.unwrap();
+        assert_eq!(left.requests(), ["left", "left"]);
+
+        let right = MockService::with_responses(responses());
+        let mut svc = layer.layer(Either::<MockService<_, ()>, _>::B(right.clone()));
+        svc.ready().await.unwrap().call("right").await.unwrap_err();
+        assert_eq!(right.requests(), ["right"]);
+
+        // A single layer applies the same settings to both arms.
+        let layer = TowerRequestLayer::<_, &str>::new(not_retrying.into_settings(), RetryAlways);
+        let left = MockService::with_responses(responses());
+        let mut svc = layer.layer_either(Either::<_, MockService<_, ()>>::A(left.clone()));
+        svc.ready().await.unwrap().call("left").await.unwrap_err();
+        assert_eq!(left.requests(), ["left"]);
+    }
+
+    #[tokio::test]
+    async fn timeout_per_attempt_restarts_on_every_retry() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            retry_attempts: Some(2),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let mock = MockService::new(()).with_latency(Duration::from_secs(2));
+        mock.fail_times(2, error);
+        let svc = ServiceBuilder::new()
+            .retry(settings.retry_policy(RetryAlways))
+            .timeout_per_attempt(Duration::from_secs(3))
+            .service(mock.clone());
+
+        // The three attempts take 6s on top of two backoffs of 1s, which would not fit in a
+        // single timeout of 3s.
+        let start = tokio::time::Instant::now();
+        svc.oneshot(1).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(8));
+        assert_eq!(mock.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn distributed_service_can_be_debugged_and_cloned() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(1)),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let endpoints = [MockService::new(()), MockService::new(())];
+        let service = settings.clone().distributed_service(
+            RetryAlways,
+            vec![
+                ("http://a".to_owned(), endpoints[0].clone()),
+                ("http://b".to_owned(), endpoints[1].clone()),
+            ],
+            HealthConfig::default(),
+            AlwaysHealthy::new(),
+            2,
+        );
+        let service = DebugDistributedService::new(service, 2, &settings);
+
+        assert_eq!(
+            format!("{service:?}"),
+            "DistributedService { endpoints: 2, rate_limited: true }"
+        );
+
+        let clone = service.clone();
+        assert_eq!(clone.endpoints(), 2);
+        let start = tokio::time::Instant::now();
+        clone.oneshot("first").await.unwrap();
+        // Clones share the rate limit, so the second request waits for the next window.
+        service.oneshot("second").await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        assert_eq!(endpoints[0].calls() + endpoints[1].calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn distributed_service_tags_adaptive_concurrency_metrics_by_endpoint() {
+        crate::metrics::init_test();
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+
+        let service = settings.distributed_service(
+            RetryAlways,
+            vec![("http://tagged".to_owned(), MockService::<_, ()>::new(()))],
+            HealthConfig::default(),
+            AlwaysHealthy::new(),
+            1,
+        );
+        service.oneshot("request").await.unwrap();
+
+        let endpoints = crate::metrics::Controller::get()
+            .unwrap()
+            .capture_metrics()
+            .into_iter()
+            .filter(|metric| metric.name() == "adaptive_concurrency_in_flight")
+            .filter_map(|metric| metric.tag_value("endpoint"))
+            .collect::<Vec<_>>();
+        assert!(endpoints.iter().any(|endpoint| endpoint == "http://tagged"));
+    }
+
+    #[tokio::test]
+    async fn failover_sends_to_fallback_while_primary_is_unavailable() {
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+        let (primary, fallback) = (MockService::<_, ()>::new(()), MockService::new(()));
+        let pool =
+            |name: &str, endpoint: &MockService<_, ()>| vec![(name.to_owned(), endpoint.clone())];
+
+        let svc = settings.clone().distributed_service_with_fallback(
+            RetryAlways,
+            pool("http://primary", &primary),
+            pool("http://fallback", &fallback),
+            HealthConfig::default(),
+            AlwaysHealthy::new(),
+            1,
+        );
+        svc.oneshot("first").await.unwrap();
+
+        // Draining the only primary endpoint leaves none available.
+        let (primary_service, drain) = settings.clone().drainable_distributed_service(
+            RetryAlways,
+            pool("http://primary", &primary),
+            HealthConfig::default(),
+            AlwaysHealthy::new(),
+            1,
+        );
+        let fallback_service = settings.distributed_service(
+            RetryAlways,
+            pool("http://fallback", &fallback),
+            HealthConfig::default(),
+            AlwaysHealthy::new(),
+            1,
+        );
+        let mut svc = Failover::new(primary_service, fallback_service, drain.clone());
+
+        drain.drain("http://primary");
+        svc.ready().await.unwrap().call("second").await.unwrap();
+        assert_eq!(svc.pool(), FailoverPool::Fallback);
+
+        drain.undrain("http://primary");
+        svc.ready().await.unwrap().call("third").await.unwrap();
+        assert_eq!(svc.pool(), FailoverPool::Primary);
+
+        assert_eq!(primary.requests(), ["first", "third"]);
+        assert_eq!(fallback.requests(), ["second"]);
+    }
+
+    #[tokio::test]
+    async fn service_reports_exhausted_retries() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            retry_attempts: Some(2),
+            retry_jitter_mode: Some(JitterMode::None),
+            timeout_secs: Some(5),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let exhausted_after = |error: crate::Error| match error.downcast::<SinkServiceError>() {
+            Ok(error) => match *error {
+                SinkServiceError::RetryExhausted {
+                    attempts,
+                    last_error,
+                } => (attempts, last_error),
+                error => panic!("unexpected error {error:?}"),
+            },
+            Err(error) => panic!("unexpected error {error:?}"),
+        };
+
+        let failing = MockService::new(());
+        failing.fail_times(3, || {
+            std::io::Error::new(std::io::ErrorKind::Other, "refused")
+        });
+        let error = settings
+            .clone()
+            .service(RetryAlways, failing)
+            .oneshot(1)
+            .await
+            .unwrap_err();
+        let (attempts, last_error) = exhausted_after(error);
+        assert_eq!(attempts, 3);
+        assert_eq!(last_error.to_string(), "refused");
+
+        let slow = MockService::new(()).with_latency(Duration::from_secs(10));
+        let error = settings
+            .service(RetryAlways, slow)
+            .oneshot(1)
+            .await
+            .unwrap_err();
+        let (attempts, last_error) = exhausted_after(error);
+        assert_eq!(attempts, 3);
+        assert!(matches!(
+            last_error.downcast_ref::<SinkServiceError>(),
+            Some(SinkServiceError::Timeout { after }) if *after == Duration::from_secs(5)
+        ));
+    }
+
+    #[tokio::test]
+    async fn partition_sink_retry_concurrency() {
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = {
+            let sent_requests = Arc::clone(&sent_requests);
+            let delay = Arc::new(AtomicBool::new(true));
+            tower::service_fn(move |req: PartitionInnerBuffer<_, _>| {
+                let (req, _) = req.into_parts();
+                if delay.swap(false, AcqRel) {
+                    // Error on first request
+                    future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, "")).boxed()
+                } else {
+                    sent_requests.lock().unwrap().push(req);
+                    future::ok::<_, std::io::Error>(()).boxed()
+                }
+            })
+        };
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+
+        let mut sink = settings.partition_sink(
+            RetryAlways,
+            svc,
+            PartitionBuffer::new(VecBuffer::new(batch_settings.size)),
+            TIMEOUT,
+        );
+        sink.ordered();
+
+        let input = (0..20).map(|i| PartitionInnerBuffer::new(i, 0));
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(
+            &*output,
+            &vec![(0..10).collect::<Vec<_>>(), (10..20).collect::<Vec<_>>(),]
+        );
+    }
+
+    #[tokio::test]
+    async fn partition_sink_retries_initial_batch_separately() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            retry_attempts: Some(1),
+            retry_jitter_mode: Some(JitterMode::None),
+            initial_batch_retry: Some(InitialBatchRetryConfig {
+                retry_attempts: Some(3),
+                ..InitialBatchRetryConfig::default()
+            }),
+            ..TowerRequestConfig::default()
+        };
+        let settings = cfg.into_settings();
+
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let svc = {
+            let attempts = Arc::clone(&attempts);
+            tower::service_fn(move |req: PartitionInnerBuffer<Vec<usize>, usize>| {
+                let (req, _) = req.into_parts();
+                attempts.lock().unwrap().push(req[0]);
+                future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, ""))
+            })
+        };
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 1;
+
+        let mut sink = settings.partition_sink(
+            RetryAlways,
+            svc,
+            PartitionBuffer::new(VecBuffer::new(batch_settings.size)),
+            TIMEOUT,
+        );
+        sink.ordered();
+
+        let input = (0..3).map(|i| PartitionInnerBuffer::new(i, 0));
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        // The first batch of the partition is retried three times, the ones after it only once.
+        let attempts = attempts.lock().unwrap();
+        assert_eq!(&*attempts, &[0, 0, 0, 0, 1, 1, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn request_deadline_stops_retries() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            retry_jitter_mode: Some(JitterMode::None),
+            request_deadline_secs: Some(5),
+            ..TowerRequestConfig::default()
+        };
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = {
+            let attempts = Arc::clone(&attempts);
+            tower::service_fn(move |_: ()| {
+                attempts.fetch_add(1, AcqRel);
+                future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, ""))
+            })
+        };
+
+        let result = ServiceBuilder::new()
+            .settings(cfg.into_settings(), RetryAlways)
+            .service(svc)
+            .oneshot(())
+            .await;
+
+        // Attempts are made at 0s, 1s, 2s and 4s. The next backoff of 3s would run past the
+        // deadline, so the request is given up on instead.
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Acquire), 4);
+    }
+
+    #[tokio::test]
+    async fn retry_max_duration_caps_each_backoff_within_deadline() {
+        tokio::time::pause();
+
+        async fn attempts_within_deadline(retry_max_duration_secs: u64) -> usize {
+            let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+                concurrency: Some(Concurrency::Fixed(1)),
+                retry_jitter_mode: Some(JitterMode::None),
+                retry_max_duration_secs,
+                request_deadline_secs: Some(10),
+                ..TowerRequestConfig::default()
+            };
+
+            let attempts = Arc::new(AtomicUsize::new(0));
+            let svc = {
+                let attempts = Arc::clone(&attempts);
+                tower::service_fn(move |_: ()| {
+                    attempts.fetch_add(1, AcqRel);
+                    future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, ""))
+                })
+            };
+
+            let result = ServiceBuilder::new()
+                .settings(cfg.into_settings(), RetryAlways)
+                .service(svc)
+                .oneshot(())
+                .await;
+            assert!(result.is_err());
+            attempts.load(Acquire)
+        }
+
+        // Backoffs grow to 1s, 1s, 2s, 3s and then 5s, which would run past the deadline.
+        assert_eq!(attempts_within_deadline(30).await, 5);
+        // Capping each backoff at 1s makes an attempt every second until the deadline.
+        assert_eq!(attempts_within_deadline(1).await, 10);
+    }
+
+    #[tokio::test]
+    async fn service_with_map_transforms_each_attempt() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let svc = {
+            let requests = Arc::clone(&requests);
+            tower::service_fn(move |req: String| {
+                let mut requests = requests.lock().unwrap();
+                requests.push(req);
+                if requests.len() < 3 {
+                    future::err(std::io::Error::new(std::io::ErrorKind::Other, ""))
+                } else {
+                    future::ok(())
+                }
+            })
+        };
+
+        let result = cfg
+            .into_settings()
+            .service_with_map(RetryAlways, svc, |n: u32| n.to_string())
+            .oneshot(7)
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(&*requests.lock().unwrap(), &["7", "7", "7"]);
+    }
+
+    #[tokio::test]
+    async fn map_layer_transforms_requests_and_responses() {
+        let svc = tower::service_fn(|req: String| future::ok::<_, crate::Error>(req.len()));
+        let layer =
+            MapLayer::new(Arc::new(|n: u32| n.to_string())).with_response_map(|len: usize| len > 1);
+        let svc = ServiceBuilder::new().layer(layer).service(svc);
+
+        assert!(!svc.clone().oneshot(7).await.unwrap());
+        assert!(svc.oneshot(42).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn try_map_fails_requests_it_cannot_encode() {
+        #[derive(Debug, Snafu)]
+        #[snafu(display("cannot encode {value}"))]
+        struct EncodeError {
+            value: i32,
+        }
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            ..TowerRequestConfig::default()
+        };
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let svc = {
+            let requests = Arc::clone(&requests);
+            tower::service_fn(move |req: u32| {
+                requests.lock().unwrap().push(req);
+                future::ok::<_, std::io::Error>(())
+            })
+        };
+        let mut svc = ServiceBuilder::new()
+            .settings(cfg.into_settings(), RetryAlways)
+            .try_map(|value: i32| u32::try_from(value).map_err(|_| EncodeError { value }))
+            .service(svc);
+
+        svc.ready().await.unwrap().call(7).await.unwrap();
+        let error = svc.ready().await.unwrap().call(-1).await.unwrap_err();
+
+        // The encoding error is returned as is, without the request being sent or retried.
+        assert_eq!(error.to_string(), "cannot encode -1");
+        assert!(error.downcast_ref::<EncodeError>().is_some());
+        assert_eq!(&*requests.lock().unwrap(), &[7]);
+    }
+
+    #[tokio::test]
+    async fn map_error_transforms_service_errors() {
+        let svc = tower::service_fn(|fail: bool| {
+            if fail {
+                future::err(std::io::Error::new(std::io::ErrorKind::Other, "refused"))
+            } else {
+                future::ok(())
+            }
+        });
+        let mut svc = ServiceBuilder::new()
+            .map_error(|error: std::io::Error| format!("upstream failed: {error}").into())
+            .service(svc);
+
+        svc.ready().await.unwrap().call(false).await.unwrap();
+        let error = svc.ready().await.unwrap().call(true).await.unwrap_err();
+        assert_eq!(error.to_string(), "upstream failed: refused");
+    }
+
+    #[tokio::test]
+    async fn propagated_deadline_shrinks_retry_timeout() {
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            timeout_secs: Some(3),
+            retry_jitter_mode: Some(JitterMode::None),
+            propagate_deadline: Some(true),
+            ..TowerRequestConfig::default()
+        };
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = {
+            let attempts = Arc::clone(&attempts);
+            tower::service_fn(move |_: ()| {
+                if attempts.fetch_add(1, AcqRel) == 0 {
+                    future::err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, "")).boxed()
+                } else {
+                    future::pending().boxed()
+                }
+            })
+        };
+
+        let start = tokio::time::Instant::now();
+        let result = ServiceBuilder::new()
+            .settings(cfg.into_settings(), RetryAlways)
+            .service(svc)
+            .oneshot(())
+            .await;
+
+        // The retry is made after 1s and only gets the remaining 2s, rather than a full 3s which
+        // would have it time out at 4s.
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Acquire), 2);
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    type RecordedSpan = (Option<String>, Vec<(&'static str, String)>);
+
+    /// Sends a request which fails once before succeeding, and returns the parent and fields of
+    /// every `attempt` span opened for it.
+    async fn attempt_spans_of_a_retried_request() -> Vec<RecordedSpan> {
+        use tracing::{
+            field::{Field, Visit},
+            span::{Attributes, Id},
+            Instrument, Subscriber,
+        };
+        use tracing_subscriber::{
+            layer::{Context, SubscriberExt},
+            registry::LookupSpan,
+            Layer, Registry,
+        };
+
+        #[derive(Default)]
+        struct FieldVisitor(Vec<(&'static str, String)>);
+
+        impl Visit for FieldVisitor {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push((field.name(), format!("{:?}", value)));
+            }
+        }
+
+        struct RecordingLayer(Arc<Mutex<Vec<RecordedSpan>>>);
+
+        impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordingLayer {
+            fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+                if attrs.metadata().name() != "attempt" {
+                    return;
+                }
+                let mut visitor = FieldVisitor::default();
+                attrs.record(&mut visitor);
+                let parent = ctx
+                    .span(id)
+                    .and_then(|span| span.parent())
+                    .map(|parent| parent.name().to_owned());
+                self.0.lock().unwrap().push((parent, visitor.0));
+            }
+        }
+
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        let _guard = tracing::subscriber::set_default(
+            Registry::default().with(RecordingLayer(Arc::clone(&spans))),
+        );
+
+        tokio::time::pause();
+
+        let cfg: TowerRequestConfig<GlobalTowerRequestConfigDefaults> = TowerRequestConfig {
+            concurrency: Some(Concurrency::Fixed(1)),
+            retry_jitter_mode: Some(JitterMode::None),
+            ..TowerRequestConfig::default()
+        };
+
+        let svc = {
+            let fail = Arc::new(AtomicBool::new(true));
+            tower::service_fn(move |_: ()| {
+                if fail.swap(false, AcqRel) {
+                    future::err(std::io::Error::new(std::io::ErrorKind::Other, ""))
+                } else {
+                    future::ok(())
+                }
+            })
+        };
+
+        ServiceBuilder::new()
+            .settings(cfg.into_settings(), RetryAlways)
+            .service(svc)
+            .oneshot(())
+            .instrument(info_span!("request"))
+            .await
+            .expect("request should succeed after a retry");
+
+        let spans = spans.lock().unwrap();
+        spans.clone()
+    }
+
+    #[cfg(feature = "tracing-instrumentation")]
+    #[tokio::test]
+    async fn retry_attempts_are_traced() {
+        assert_eq!(
+            attempt_spans_of_a_retried_request().await,
+            vec![
+                (
+                    Some("request".to_owned()),
+                    vec![("attempt_number", "1".to_owned())]
+                ),
+                (
+                    Some("request".to_owned()),
+                    vec![
+                        ("attempt_number", "2".to_owned()),
+                        ("delay_ms", "1000".to_owned()),
+                        ("error_kind", "\"error\"".to_owned()),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[cfg(not(feature = "tracing-instrumentation"))]
+    #[tokio::test]
+    async fn retry_attempts_are_not_traced_without_the_feature() {
+        assert!(attempt_spans_of_a_retried_request().await.is_empty());
+    }
+
+    fn error() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Other, "")
     }
 
     #[derive(Clone, Debug, Copy)]
@@ -0,0 +1,81 @@
+//! Reports the number of attempts requests took to succeed.
+//!
+//! [`CountAttempts`] sits above the request settings and counts the attempts of each request in a
+//! task-local, which the retry policy updates whenever it schedules a retry. Services which don't
+//! count attempts keep their response type, and the retry policy finds no task-local to update.
+
+use std::{
+    cell::Cell,
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tower::{Layer, Service};
+
+tokio::task_local! {
+    static ATTEMPTS: Cell<usize>;
+}
+
+/// Records that the request currently being processed is about to be retried.
+pub(crate) fn count_retry() {
+    _ = ATTEMPTS.try_with(|attempts| attempts.set(attempts.get() + 1));
+}
+
+/// A response along with the number of attempts made for its request, including the first one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attempted<Res> {
+    pub response: Res,
+    pub attempts: usize,
+}
+
+/// Counts the attempts made for each request passing through the wrapped service.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CountAttemptsLayer;
+
+impl<S> Layer<S> for CountAttemptsLayer {
+    type Service = CountAttempts<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountAttempts { inner }
+    }
+}
+
+/// A service which responds with the number of attempts made for each request, along with the
+/// response of the last one.
+///
+/// This is meant to wrap the tower request settings of a sink, so that it can report how many
+/// attempts it took to deliver a request.
+#[derive(Clone, Debug)]
+pub struct CountAttempts<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for CountAttempts<S>
+where
+    S: Service<Req>,
+    S::Future: Send + 'static,
+{
+    type Response = Attempted<S::Response>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        // Retries are only scheduled once the response future is polled, but the first attempt is
+        // made right away.
+        let inner = &mut self.inner;
+        let future = ATTEMPTS.sync_scope(Cell::new(1), || inner.call(req));
+        ATTEMPTS
+            .scope(Cell::new(1), async move {
+                let response = future.await?;
+                Ok(Attempted {
+                    response,
+                    attempts: ATTEMPTS.with(Cell::get),
+                })
+            })
+            .boxed()
+    }
+}
@@ -0,0 +1,470 @@
+//! Load balancing strategies for distributing requests across endpoints.
+//!
+//! Besides the power of two choices implemented by [`Balance`], requests can be sent to the
+//! endpoints in turn with [`RoundRobinBalance`], to a random one with [`RandomBalance`], or to the
+//! least loaded one with [`LeastLoadedBalance`]. All of them follow the endpoints added and removed
+//! by the same discovery stream.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::Hash,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, FutureExt, TryFutureExt};
+use indexmap::IndexMap;
+use rand::Rng;
+use tower::{
+    balance::p2c::Balance,
+    discover::{Change, Discover},
+    load::Load,
+    Service,
+};
+use vector_lib::configurable::configurable_component;
+
+/// The strategy used to pick the endpoint each request is sent to.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalanceStrategy {
+    /// Picks the less loaded of two random endpoints.
+    #[default]
+    #[serde(rename = "p2c", alias = "power_of_two_choices")]
+    P2C,
+
+    /// Sends requests to the endpoints in turn.
+    RoundRobin,
+
+    /// Sends each request to a random endpoint.
+    Random,
+
+    /// Sends each request to the endpoint with the fewest requests in flight.
+    LeastLoaded,
+}
+
+/// The endpoints known to a load balancer, in the order they were discovered.
+struct Endpoints<D: Discover> {
+    discover: D,
+    services: IndexMap<D::Key, D::Service>,
+    ready: Option<usize>,
+}
+
+impl<D> Endpoints<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+{
+    fn new(discover: D) -> Self {
+        Self {
+            discover,
+            services: IndexMap::new(),
+            ready: None,
+        }
+    }
+
+    fn poll_discover(&mut self, cx: &mut Context<'_>) -> Result<(), crate::Error> {
+        loop {
+            match Pin::new(&mut self.discover).poll_discover(cx) {
+                Poll::Pending | Poll::Ready(None) => return Ok(()),
+                Poll::Ready(Some(Err(error))) => return Err(error.into()),
+                Poll::Ready(Some(Ok(Change::Insert(key, service)))) => {
+                    if self.services.insert(key, service).is_some() {
+                        self.ready = None;
+                    }
+                }
+                Poll::Ready(Some(Ok(Change::Remove(key)))) => {
+                    self.ready = None;
+                    self.services.shift_remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Polls the endpoints in the order given by `order`, until one of them is ready.
+    ///
+    /// Endpoints which fail to become ready are removed.
+    fn poll_ready<Req>(
+        &mut self,
+        cx: &mut Context<'_>,
+        order: impl FnOnce(&IndexMap<D::Key, D::Service>) -> Vec<usize>,
+    ) -> Poll<Result<(), crate::Error>>
+    where
+        D::Service: Service<Req>,
+        <D::Service as Service<Req>>::Error: Into<crate::Error>,
+    {
+        self.poll_discover(cx)?;
+        if self.ready.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut failed = Vec::new();
+        for index in order(&self.services) {
+            let (_, service) = self
+                .services
+                .get_index_mut(index)
+                .expect("index is in bounds");
+            match service.poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    self.ready = Some(index);
+                    break;
+                }
+                Poll::Ready(Err(error)) => {
+                    let error: crate::Error = error.into();
+                    warn!(message = "Removing failed endpoint from load balancing.", %error);
+                    failed.push(index);
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        failed.sort_unstable();
+        self.ready = self
+            .ready
+            .map(|ready| ready - failed.iter().filter(|&&index| index < ready).count());
+        for index in failed.into_iter().rev() {
+            self.services.shift_remove_index(index);
+        }
+
+        if self.ready.is_some() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Sends a request to the endpoint found ready, returning its index along with the response.
+    fn call<Req>(
+        &mut self,
+        req: Req,
+    ) -> (
+        usize,
+        futures::future::ErrInto<<D::Service as Service<Req>>::Future, crate::Error>,
+    )
+    where
+        D::Service: Service<Req>,
+        <D::Service as Service<Req>>::Error: Into<crate::Error>,
+    {
+        let index = self
+            .ready
+            .take()
+            .expect("called before the load balancer was ready");
+        let (_, service) = self
+            .services
+            .get_index_mut(index)
+            .expect("ready endpoint is still present");
+        (index, service.call(req).err_into())
+    }
+}
+
+/// Every index out of `len`, starting from `start` and wrapping around.
+fn cyclic(start: usize, len: usize) -> Vec<usize> {
+    (0..len).map(|offset| (start + offset) % len).collect()
+}
+
+/// A load balancer which sends requests to its endpoints in turn, skipping the ones which are not
+/// ready.
+pub struct RoundRobinBalance<D: Discover> {
+    endpoints: Endpoints<D>,
+    next: usize,
+}
+
+impl<D> RoundRobinBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+{
+    pub fn new(discover: D) -> Self {
+        Self {
+            endpoints: Endpoints::new(discover),
+            next: 0,
+        }
+    }
+}
+
+impl<D, Req> Service<Req> for RoundRobinBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+    D::Service: Service<Req>,
+    <D::Service as Service<Req>>::Error: Into<crate::Error>,
+{
+    type Response = <D::Service as Service<Req>>::Response;
+    type Error = crate::Error;
+    type Future = futures::future::ErrInto<<D::Service as Service<Req>>::Future, crate::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let next = self.next;
+        self.endpoints
+            .poll_ready(cx, |services| cyclic(next, services.len()))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let (index, future) = self.endpoints.call(req);
+        self.next = index + 1;
+        future
+    }
+}
+
+impl<D: Discover> fmt::Debug for RoundRobinBalance<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoundRobinBalance")
+            .field("endpoints", &self.endpoints.services.len())
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+/// A load balancer which sends each request to a random endpoint among the ones which are ready.
+pub struct RandomBalance<D: Discover> {
+    endpoints: Endpoints<D>,
+}
+
+impl<D> RandomBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+{
+    pub fn new(discover: D) -> Self {
+        Self {
+            endpoints: Endpoints::new(discover),
+        }
+    }
+}
+
+impl<D, Req> Service<Req> for RandomBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+    D::Service: Service<Req>,
+    <D::Service as Service<Req>>::Error: Into<crate::Error>,
+{
+    type Response = <D::Service as Service<Req>>::Response;
+    type Error = crate::Error;
+    type Future = futures::future::ErrInto<<D::Service as Service<Req>>::Future, crate::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.endpoints.poll_ready(cx, |services| {
+            let start = match services.len() {
+                0 => 0,
+                len => rand::thread_rng().gen_range(0..len),
+            };
+            cyclic(start, services.len())
+        })
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.endpoints.call(req).1
+    }
+}
+
+impl<D: Discover> fmt::Debug for RandomBalance<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomBalance")
+            .field("endpoints", &self.endpoints.services.len())
+            .finish()
+    }
+}
+
+/// A load balancer which sends each request to the least loaded endpoint among the ones which are
+/// ready.
+pub struct LeastLoadedBalance<D: Discover> {
+    endpoints: Endpoints<D>,
+}
+
+impl<D> LeastLoadedBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+{
+    pub fn new(discover: D) -> Self {
+        Self {
+            endpoints: Endpoints::new(discover),
+        }
+    }
+}
+
+impl<D, Req> Service<Req> for LeastLoadedBalance<D>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq,
+    D::Error: Into<crate::Error>,
+    D::Service: Service<Req> + Load,
+    <D::Service as Load>::Metric: PartialOrd,
+    <D::Service as Service<Req>>::Error: Into<crate::Error>,
+{
+    type Response = <D::Service as Service<Req>>::Response;
+    type Error = crate::Error;
+    type Future = futures::future::ErrInto<<D::Service as Service<Req>>::Future, crate::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.endpoints.poll_ready(cx, |services| {
+            let loads: Vec<_> = services.values().map(Load::load).collect();
+            let mut order: Vec<_> = (0..services.len()).collect();
+            order.sort_by(|&a, &b| loads[a].partial_cmp(&loads[b]).unwrap_or(Ordering::Equal));
+            order
+        })
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.endpoints.call(req).1
+    }
+}
+
+impl<D: Discover> fmt::Debug for LeastLoadedBalance<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeastLoadedBalance")
+            .field("endpoints", &self.endpoints.services.len())
+            .finish()
+    }
+}
+
+/// A load balancer using any of the [`LoadBalanceStrategy`] strategies.
+pub enum LoadBalancer<D, Req>
+where
+    D: Discover,
+    D::Key: Hash,
+{
+    P2C(Balance<D, Req>),
+    RoundRobin(RoundRobinBalance<D>),
+    Random(RandomBalance<D>),
+    LeastLoaded(LeastLoadedBalance<D>),
+}
+
+impl<D, Req> LoadBalancer<D, Req>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq + Clone,
+    D::Error: Into<crate::Error>,
+    D::Service: Service<Req> + Load,
+    <D::Service as Load>::Metric: fmt::Debug + PartialOrd,
+    <D::Service as Service<Req>>::Error: Into<crate::Error>,
+{
+    pub fn new(strategy: LoadBalanceStrategy, discover: D) -> Self {
+        match strategy {
+            LoadBalanceStrategy::P2C => Self::P2C(Balance::new(discover)),
+            LoadBalanceStrategy::RoundRobin => Self::RoundRobin(RoundRobinBalance::new(discover)),
+            LoadBalanceStrategy::Random => Self::Random(RandomBalance::new(discover)),
+            LoadBalanceStrategy::LeastLoaded => {
+                Self::LeastLoaded(LeastLoadedBalance::new(discover))
+            }
+        }
+    }
+}
+
+impl<D, Req> Service<Req> for LoadBalancer<D, Req>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Eq + Clone,
+    D::Error: Into<crate::Error>,
+    D::Service: Service<Req> + Load,
+    <D::Service as Load>::Metric: fmt::Debug + PartialOrd,
+    <D::Service as Service<Req>>::Error: Into<crate::Error> + 'static,
+    <D::Service as Service<Req>>::Future: Send + 'static,
+{
+    type Response = <D::Service as Service<Req>>::Response;
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self {
+            Self::P2C(balance) => balance.poll_ready(cx),
+            Self::RoundRobin(balance) => balance.poll_ready(cx),
+            Self::Random(balance) => balance.poll_ready(cx),
+            Self::LeastLoaded(balance) => balance.poll_ready(cx),
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        match self {
+            Self::P2C(balance) => balance.call(req).boxed(),
+            Self::RoundRobin(balance) => balance.call(req).boxed(),
+            Self::Random(balance) => balance.call(req).boxed(),
+            Self::LeastLoaded(balance) => balance.call(req).boxed(),
+        }
+    }
+}
+
+impl<D, Req> fmt::Debug for LoadBalancer<D, Req>
+where
+    D: Discover,
+    D::Key: Hash,
+    Balance<D, Req>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::P2C(balance) => balance.fmt(f),
+            Self::RoundRobin(balance) => balance.fmt(f),
+            Self::Random(balance) => balance.fmt(f),
+            Self::LeastLoaded(balance) => balance.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::future;
+    use tokio_test::assert_ready_ok;
+    use tower::{discover::ServiceList, load::Constant, ServiceExt};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_robin_distributes_requests_evenly() {
+        let counts: Vec<_> = (0..4).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let services = counts
+            .iter()
+            .map(|count| {
+                let count = Arc::clone(count);
+                tower::service_fn(move |_: ()| {
+                    count.fetch_add(1, Ordering::Relaxed);
+                    future::ok::<_, crate::Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut balance = RoundRobinBalance::new(ServiceList::new(services));
+        for _ in 0..100 {
+            balance.ready().await.unwrap().call(()).await.unwrap();
+        }
+
+        for count in counts {
+            assert_eq!(count.load(Ordering::Relaxed), 25);
+        }
+    }
+
+    #[tokio::test]
+    async fn least_loaded_prefers_ready_endpoints_with_the_lowest_load() {
+        let (services, mut handles): (Vec<_>, Vec<_>) = [3, 1, 2]
+            .into_iter()
+            .map(|load| {
+                let (service, handle) = mock::pair::<&str, &str>();
+                (Constant::new(service, load), handle)
+            })
+            .unzip();
+        let mut balance = mock::Spawn::new(LeastLoadedBalance::new(ServiceList::new(services)));
+
+        assert_ready_ok!(balance.poll_ready());
+        let _first = balance.call("first");
+        assert_request_eq!(handles[1], "first").send_response("ok");
+
+        // The next least loaded endpoint is used while the least loaded one is not ready.
+        handles[1].allow(0);
+        assert_ready_ok!(balance.poll_ready());
+        let _second = balance.call("second");
+        assert_request_eq!(handles[2], "second").send_response("ok");
+    }
+}
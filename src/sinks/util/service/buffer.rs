@@ -0,0 +1,87 @@
+//! How requests are queued in front of the endpoints of distributed services.
+
+use tokio::sync::Semaphore;
+use tower::buffer::BufferLayer;
+use vector_lib::configurable::configurable_component;
+
+/// How requests are queued in front of the endpoints of sinks which distribute requests across
+/// several of them.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[configurable(metadata(
+    docs::enum_tag_description = "How requests are queued in front of the endpoints."
+))]
+pub enum BufferMode {
+    /// Queues up to `capacity` requests, after which the sink applies backpressure.
+    Bounded {
+        /// The maximum number of requests to queue up. It must be at least `1`.
+        #[configurable(metadata(docs::type_unit = "requests"))]
+        #[configurable(validation(range(min = 1)))]
+        capacity: usize,
+    },
+
+    /// Queues requests without limit, so the sink never applies backpressure.
+    ///
+    /// This is not recommended for production, as memory usage grows without bound while the
+    /// endpoints can't keep up.
+    Unbounded,
+}
+
+impl BufferMode {
+    /// The number of requests queued before the sink applies backpressure.
+    ///
+    /// Tower's buffer bounds its queue with a semaphore, so an unbounded buffer is one with as
+    /// many permits as a semaphore can hold, which no sink gets close to queueing.
+    pub const fn capacity(self) -> usize {
+        match self {
+            Self::Bounded { capacity } => capacity,
+            Self::Unbounded => Semaphore::MAX_PERMITS,
+        }
+    }
+
+    /// Builds the layer queueing requests as configured.
+    pub fn layer<Req>(self) -> BufferLayer<Req> {
+        if self == Self::Unbounded {
+            warn!(
+                message = "Requests are buffered without bound, so memory usage grows without limit while the endpoints can't keep up."
+            );
+        }
+        BufferLayer::new(self.capacity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::mock;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn bounded_buffer_applies_backpressure_once_full() {
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<&str, (), _>(BufferMode::Bounded { capacity: 2 }.layer());
+        // The endpoints never become ready, so every request stays queued.
+        handle.allow(0);
+
+        let mut queued = Vec::new();
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            queued.push(svc.call("request"));
+        }
+        assert_pending!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn unbounded_buffer_never_applies_backpressure() {
+        let (mut svc, mut handle) = mock::spawn_layer::<&str, (), _>(BufferMode::Unbounded.layer());
+        handle.allow(0);
+
+        let mut queued = Vec::new();
+        for _ in 0..1000 {
+            assert_ready_ok!(svc.poll_ready());
+            queued.push(svc.call("request"));
+        }
+    }
+}
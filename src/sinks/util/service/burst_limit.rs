@@ -0,0 +1,293 @@
+//! Lets a fixed concurrency limit be exceeded for short bursts.
+//!
+//! Up to the steady concurrency, requests are always admitted. Every request admitted beyond it, up
+//! to the burst concurrency, spends a credit. Credits are only earned back while fewer requests
+//! than the steady concurrency are in flight, one per refill interval, up to the difference between
+//! the burst and steady concurrency. Sustained load therefore settles at the steady concurrency,
+//! while an idle period builds up capacity which is spent on the next spike.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll, Waker},
+    time::Duration,
+};
+
+use pin_project::pin_project;
+use tokio::time::Instant;
+use tower::{Layer, Service};
+
+#[derive(Clone, Copy, Debug)]
+struct Burst {
+    steady: usize,
+    max_credits: usize,
+    refill: Duration,
+}
+
+/// Allows the wrapped service to exceed a steady concurrency for short bursts.
+#[derive(Clone, Copy, Debug)]
+pub struct BurstLimitLayer {
+    burst: Burst,
+}
+
+impl BurstLimitLayer {
+    /// Creates a new burst limit layer.
+    ///
+    /// Returns `None` unless both `steady` and `burst` are given and `burst` is above `steady`, as
+    /// there is nothing to limit then. The concurrency limit above this layer is expected to be
+    /// set to `burst`.
+    pub const fn new(
+        steady: Option<usize>,
+        burst: Option<usize>,
+        refill: Duration,
+    ) -> Option<Self> {
+        match (steady, burst) {
+            (Some(steady), Some(burst)) if burst > steady => Some(Self {
+                burst: Burst {
+                    steady,
+                    max_credits: burst - steady,
+                    refill,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<S> Layer<S> for BurstLimitLayer {
+    type Service = BurstLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BurstLimit {
+            inner,
+            state: Arc::new(Mutex::new(BurstState::new(self.burst))),
+            permit: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BurstState {
+    burst: Burst,
+    in_flight: usize,
+    credits: usize,
+    refill_progress: Duration,
+    last_update: Instant,
+    waiters: Vec<Waker>,
+}
+
+impl BurstState {
+    fn new(burst: Burst) -> Self {
+        Self {
+            burst,
+            in_flight: 0,
+            credits: 0,
+            refill_progress: Duration::ZERO,
+            last_update: Instant::now(),
+            waiters: Vec::new(),
+        }
+    }
+
+    /// Earns credits for the time passed since the last update, if it was spent below the steady
+    /// concurrency.
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.last_update;
+        self.last_update = now;
+
+        if self.in_flight >= self.burst.steady {
+            return;
+        }
+
+        if self.burst.refill.is_zero() {
+            self.credits = self.burst.max_credits;
+            return;
+        }
+
+        self.refill_progress += elapsed;
+        let earned = (self.refill_progress.as_nanos() / self.burst.refill.as_nanos())
+            .min(self.burst.max_credits as u128) as usize;
+        self.credits = (self.credits + earned).min(self.burst.max_credits);
+        self.refill_progress = if self.credits == self.burst.max_credits {
+            Duration::ZERO
+        } else {
+            self.refill_progress - self.burst.refill * earned as u32
+        };
+    }
+
+    fn poll_acquire(state: &Arc<Mutex<Self>>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let mut this = state.lock().expect("burst limit state poisoned");
+        this.refill();
+
+        if this.in_flight >= this.burst.steady {
+            if this.credits == 0 {
+                if !this.waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    this.waiters.push(cx.waker().clone());
+                }
+                return Poll::Pending;
+            }
+            this.credits -= 1;
+        }
+
+        this.in_flight += 1;
+        Poll::Ready(Permit {
+            state: Arc::clone(state),
+        })
+    }
+}
+
+/// A request admitted by [`BurstLimit`], which is released when dropped.
+#[derive(Debug)]
+struct Permit {
+    state: Arc<Mutex<BurstState>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().expect("burst limit state poisoned");
+        state.refill();
+        state.in_flight -= 1;
+        for waker in state.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// A service which admits requests beyond its steady concurrency as long as it has burst credits
+/// left to spend.
+#[derive(Debug)]
+pub struct BurstLimit<S> {
+    inner: S,
+    state: Arc<Mutex<BurstState>>,
+    permit: Option<Permit>,
+}
+
+impl<S, Req> Service<Req> for BurstLimit<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BurstLimitFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.permit.is_none() {
+            self.permit = Some(ready!(BurstState::poll_acquire(&self.state, cx)));
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let permit = self
+            .permit
+            .take()
+            .expect("burst limit exceeded; poll_ready must be called first");
+
+        BurstLimitFuture {
+            inner: self.inner.call(req),
+            _permit: permit,
+        }
+    }
+}
+
+impl<S: Clone> Clone for BurstLimit<S> {
+    fn clone(&self) -> Self {
+        // Permits are reserved by each clone for itself.
+        Self {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+            permit: None,
+        }
+    }
+}
+
+/// Future for [`BurstLimit`], which holds on to the request's permit until it completes.
+#[pin_project]
+#[derive(Debug)]
+pub struct BurstLimitFuture<F> {
+    #[pin]
+    inner: F,
+    _permit: Permit,
+}
+
+impl<F: Future> Future for BurstLimitFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::mock;
+
+    use super::*;
+
+    fn layer() -> BurstLimitLayer {
+        BurstLimitLayer::new(Some(2), Some(4), Duration::from_secs(1))
+            .expect("burst is above steady")
+    }
+
+    #[test]
+    fn nothing_to_limit_without_a_burst_above_steady() {
+        let refill = Duration::from_secs(1);
+        assert!(BurstLimitLayer::new(None, Some(4), refill).is_none());
+        assert!(BurstLimitLayer::new(Some(2), None, refill).is_none());
+        assert!(BurstLimitLayer::new(Some(4), Some(4), refill).is_none());
+    }
+
+    #[tokio::test]
+    async fn sustained_load_settles_at_steady_concurrency() {
+        pause();
+
+        let (mut svc, _handle) = mock::spawn_layer::<&str, (), _>(layer());
+
+        let mut in_flight = Vec::new();
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            in_flight.push(svc.call("request"));
+        }
+        assert_pending!(svc.poll_ready());
+
+        // No burst credits are earned while the steady concurrency is in use.
+        advance(Duration::from_secs(10)).await;
+        assert_pending!(svc.poll_ready());
+
+        // Completing a request makes room for exactly one more.
+        in_flight.pop();
+        assert!(svc.is_woken());
+        assert_ready_ok!(svc.poll_ready());
+        in_flight.push(svc.call("request"));
+        assert_pending!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn idle_periods_accumulate_burst_capacity() {
+        pause();
+
+        let (mut svc, _handle) = mock::spawn_layer::<&str, (), _>(layer());
+
+        // One credit is earned per second spent below the steady concurrency.
+        advance(Duration::from_secs(1)).await;
+        let mut in_flight = Vec::new();
+        for _ in 0..3 {
+            assert_ready_ok!(svc.poll_ready());
+            in_flight.push(svc.call("request"));
+        }
+        assert_pending!(svc.poll_ready());
+
+        // Credits are capped at the difference between the burst and steady concurrency.
+        in_flight.clear();
+        advance(Duration::from_secs(5)).await;
+        for _ in 0..4 {
+            assert_ready_ok!(svc.poll_ready());
+            in_flight.push(svc.call("request"));
+        }
+        assert_pending!(svc.poll_ready());
+    }
+}
@@ -0,0 +1,170 @@
+//! The source of time of the middleware which waits, such as the retry policy and the rate limit.
+//!
+//! Middleware defaults to [`TokioClock`], and tests can swap in a [`ManualClock`] to step through
+//! backoffs and rate limits deterministically, without sleeping or pausing the tokio runtime.
+
+use std::{fmt, future::Future, pin::Pin, time::Duration};
+#[cfg(any(test, feature = "test-utils"))]
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+use tokio::time::Instant;
+
+/// A future completing once a [`Clock`] reaches the instant it was created for.
+pub type ClockSleep = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// A source of time.
+pub trait Clock: fmt::Debug + Send + Sync + 'static {
+    /// The current instant.
+    fn now(&self) -> Instant;
+
+    /// Waits until `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep;
+
+    /// Waits for `duration`.
+    fn sleep(&self, duration: Duration) -> ClockSleep {
+        self.sleep_until(self.now() + duration)
+    }
+}
+
+/// The clock of the tokio runtime, which the middleware uses unless told otherwise.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep {
+        Box::pin(tokio::time::sleep_until(deadline))
+    }
+}
+
+/// A clock which only moves forward when told to, and records how long it was asked to sleep for.
+///
+/// Clones share the same time, so a test can keep one clone to advance time while another is
+/// injected into the middleware under test.
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualState>>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug)]
+struct ManualState {
+    now: Instant,
+    sleeps: Vec<Duration>,
+    waiters: Vec<Waker>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl ManualClock {
+    /// Creates a clock starting at the current instant of the tokio clock.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ManualState {
+                now: Instant::now(),
+                sleeps: Vec::new(),
+                waiters: Vec::new(),
+            })),
+        }
+    }
+
+    /// Moves the clock forward by `duration`, completing the sleeps it reaches the end of.
+    pub fn advance(&self, duration: Duration) {
+        let waiters = {
+            let mut state = self.state();
+            state.now += duration;
+            std::mem::take(&mut state.waiters)
+        };
+        waiters.into_iter().for_each(Waker::wake);
+    }
+
+    /// How long the clock was asked to sleep for each time, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.state().sleeps.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, ManualState> {
+        self.state.lock().expect("manual clock state poisoned")
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.state().now
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep {
+        let mut state = self.state();
+        let duration = deadline.saturating_duration_since(state.now);
+        state.sleeps.push(duration);
+        Box::pin(ManualSleep {
+            clock: self.clone(),
+            deadline,
+        })
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+struct ManualSleep {
+    clock: ManualClock,
+    deadline: Instant,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.clock.state();
+        if state.now >= self.deadline {
+            return Poll::Ready(());
+        }
+        if !state
+            .waiters
+            .iter()
+            .any(|waker| waker.will_wake(cx.waker()))
+        {
+            state.waiters.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready, task};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn manual_clock_sleeps_until_advanced_past_the_deadline() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+
+        let mut sleep = task::spawn(clock.sleep(Duration::from_secs(2)));
+        assert_pending!(sleep.poll());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(sleep.is_woken());
+        assert_pending!(sleep.poll());
+
+        clock.advance(Duration::from_secs(1));
+        assert_ready!(sleep.poll());
+        assert_eq!(clock.now() - start, Duration::from_secs(2));
+        assert_eq!(clock.sleeps(), vec![Duration::from_secs(2)]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Coalesces identical requests made while one of them is in flight.
+//!
+//! Some sinks make the same request several times within a short window, such as refreshing
+//! metadata. Rather than sending each of them, the coalescing service sends the first one and
+//! shares its result with the identical requests made before it completes.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt, TryFutureExt,
+};
+use tower::{Layer, Service};
+
+/// Requests which can share the response of an identical request.
+pub trait Coalescible {
+    /// Identifies requests which are identical.
+    type Key: Clone + Eq + Hash + Send + 'static;
+
+    /// Returns the key of this request. Requests with the same key are sent once while in flight.
+    fn coalesce_key(&self) -> Self::Key;
+}
+
+/// The error of a request whose result was shared with identical requests.
+#[derive(Clone, Debug)]
+pub struct CoalescedError(Arc<crate::Error>);
+
+impl fmt::Display for CoalescedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for CoalescedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+type SharedResponse<Res> = Shared<BoxFuture<'static, Result<Res, CoalescedError>>>;
+type InFlight<K, Res> = Arc<Mutex<HashMap<K, SharedResponse<Res>>>>;
+
+/// Coalesces identical requests made through the wrapped service.
+pub struct CoalesceLayer<Req> {
+    _request: PhantomData<fn(Req)>,
+}
+
+impl<Req> CoalesceLayer<Req> {
+    pub const fn new() -> Self {
+        Self {
+            _request: PhantomData,
+        }
+    }
+}
+
+impl<Req> Default for CoalesceLayer<Req> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Req> Clone for CoalesceLayer<Req> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<S, Req> Layer<S> for CoalesceLayer<Req>
+where
+    S: Service<Req>,
+    Req: Coalescible,
+{
+    type Service = Coalesce<S, Req>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Coalesce {
+            inner,
+            in_flight: Arc::default(),
+        }
+    }
+}
+
+/// A service which sends identical requests once while one of them is in flight, and returns its
+/// result for each of them.
+///
+/// This is meant to wrap the tower request settings of a sink, so that coalesced requests neither
+/// take up concurrency nor get retried more than once. Which requests are coalesced is only known
+/// once they are made, so the wrapped service is polled ready for each request, and the capacity
+/// it reserved is released again for those which are coalesced, as they are not sent.
+pub struct Coalesce<S, Req>
+where
+    S: Service<Req>,
+    Req: Coalescible,
+{
+    inner: S,
+    in_flight: InFlight<Req::Key, S::Response>,
+}
+
+impl<S, Req> Clone for Coalesce<S, Req>
+where
+    S: Service<Req> + Clone,
+    Req: Coalescible,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            in_flight: Arc::clone(&self.in_flight),
+        }
+    }
+}
+
+impl<S, Req> Service<Req> for Coalesce<S, Req>
+where
+    S: Service<Req> + Clone,
+    S::Response: Clone + Send + Sync + 'static,
+    S::Error: Into<crate::Error>,
+    S::Future: Send + 'static,
+    Req: Coalescible,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let key = req.coalesce_key();
+        let response = {
+            let mut in_flight = self.in_flight.lock().expect("in-flight requests poisoned");
+            match in_flight.get(&key) {
+                Some(response) => {
+                    // Clones of a service don't share its readiness, so replacing it with one
+                    // releases whatever it reserved for this request, such as a concurrency permit.
+                    let inner = self.inner.clone();
+                    drop(std::mem::replace(&mut self.inner, inner));
+                    response.clone()
+                }
+                None => {
+                    let response = self
+                        .inner
+                        .call(req)
+                        .map_err(|error| CoalescedError(Arc::new(error.into())))
+                        .boxed()
+                        .shared();
+                    in_flight.insert(key.clone(), response.clone());
+                    response
+                }
+            }
+        };
+
+        let in_flight = Arc::clone(&self.in_flight);
+        async move {
+            let result = response.clone().await;
+
+            // The first identical request to complete removes the shared response, so that
+            // requests made from now on are sent again.
+            let mut in_flight = in_flight.lock().expect("in-flight requests poisoned");
+            if in_flight
+                .get(&key)
+                .is_some_and(|shared| shared.ptr_eq(&response))
+            {
+                in_flight.remove(&key);
+            }
+
+            result.map_err(Into::into)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future::join_all;
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct Refresh(&'static str);
+
+    impl Coalescible for Refresh {
+        type Key = &'static str;
+
+        fn coalesce_key(&self) -> Self::Key {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_concurrent_requests_are_sent_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Notify::new());
+        let svc = {
+            let calls = Arc::clone(&calls);
+            let release = Arc::clone(&release);
+            tower::service_fn(move |req: Refresh| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let release = Arc::clone(&release);
+                async move {
+                    release.notified().await;
+                    Ok::<_, crate::Error>(req.0)
+                }
+            })
+        };
+        let svc = CoalesceLayer::new().layer(svc);
+
+        let responses = (0..5)
+            .map(|_| svc.clone().oneshot(Refresh("metadata")))
+            .collect::<Vec<_>>();
+        let responses = tokio::spawn(join_all(responses));
+        tokio::task::yield_now().await;
+        release.notify_one();
+
+        for response in responses.await.unwrap() {
+            assert_eq!(response.unwrap(), "metadata");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Once completed, the request is sent again.
+        let response = svc.oneshot(Refresh("metadata"));
+        release.notify_one();
+        assert_eq!(response.await.unwrap(), "metadata");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn coalesced_requests_release_the_capacity_they_reserved() {
+        let release = Arc::new(Notify::new());
+        let svc = {
+            let release = Arc::clone(&release);
+            tower::service_fn(move |req: Refresh| {
+                let release = Arc::clone(&release);
+                async move {
+                    release.notified().await;
+                    Ok::<_, crate::Error>(req.0)
+                }
+            })
+        };
+        let svc = CoalesceLayer::new().layer(tower::limit::ConcurrencyLimit::new(svc, 2));
+
+        let mut first = svc.clone();
+        let first = first.ready().await.unwrap().call(Refresh("metadata"));
+        let mut second = svc.clone();
+        let second = second.ready().await.unwrap().call(Refresh("metadata"));
+
+        // The second request shares the response of the first, leaving room for another request.
+        let mut other = svc.clone();
+        assert!(other.ready().now_or_never().is_some());
+
+        release.notify_one();
+        assert_eq!(first.await.unwrap(), "metadata");
+        assert_eq!(second.await.unwrap(), "metadata");
+    }
+}
@@ -15,6 +15,7 @@ use serde::{
     de::{self, Unexpected, Visitor},
     Deserialize, Deserializer, Serialize,
 };
+use snafu::Snafu;
 
 /// Configuration for outbound request concurrency.
 ///
@@ -55,18 +56,28 @@ impl Default for Concurrency {
     }
 }
 
+/// Why a concurrency setting cannot be used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Snafu)]
+pub enum ConcurrencyError {
+    #[snafu(display("concurrency must be greater than zero"))]
+    Zero,
+}
+
 impl Concurrency {
-    pub const fn parse_concurrency(&self) -> Option<usize> {
+    /// Resolves the fixed concurrency limit, or `None` if concurrency is adaptive.
+    pub const fn parse_concurrency(&self) -> Result<Option<usize>, ConcurrencyError> {
         match self {
-            Concurrency::None => Some(1),
-            Concurrency::Adaptive => None,
-            Concurrency::Fixed(limit) => Some(*limit),
+            Concurrency::None => Ok(Some(1)),
+            Concurrency::Adaptive => Ok(None),
+            Concurrency::Fixed(0) => Err(ConcurrencyError::Zero),
+            Concurrency::Fixed(limit) => Ok(Some(*limit)),
         }
     }
 }
 
 impl<'de> Deserialize<'de> for Concurrency {
-    // Deserialize either a positive integer or the string "adaptive"
+    // Deserialize either a positive integer or the string "adaptive" or "none". Integers are
+    // signed in TOML, but unsigned in formats such as YAML and JSON, so both are accepted.
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
@@ -91,24 +102,24 @@ impl<'de> Deserialize<'de> for Concurrency {
             }
 
             fn visit_i64<E: de::Error>(self, value: i64) -> Result<Concurrency, E> {
-                if value > 0 {
-                    Ok(Concurrency::Fixed(value as usize))
-                } else {
-                    Err(de::Error::invalid_value(
+                match u64::try_from(value) {
+                    Ok(value) => self.visit_u64(value),
+                    Err(_) => Err(de::Error::invalid_value(
                         Unexpected::Signed(value),
                         &"positive integer",
-                    ))
+                    )),
                 }
             }
 
             fn visit_u64<E: de::Error>(self, value: u64) -> Result<Concurrency, E> {
-                if value > 0 {
-                    Ok(Concurrency::Fixed(value as usize))
-                } else {
-                    Err(de::Error::invalid_value(
+                // Limits which don't fit in a `usize` are rejected rather than truncated.
+                let concurrency = usize::try_from(value).map(Concurrency::Fixed);
+                match concurrency {
+                    Ok(concurrency) if concurrency.parse_concurrency().is_ok() => Ok(concurrency),
+                    _ => Err(de::Error::invalid_value(
                         Unexpected::Unsigned(value),
                         &"positive integer",
-                    ))
+                    )),
                 }
             }
         }
@@ -0,0 +1,495 @@
+//! Bounds the total time a request can take, including all of its retries.
+//!
+//! The deadline is established when the request enters the [`Deadline`] service and is made
+//! available to the layers below it (the retry policy and [`AttemptTimeout`], in particular)
+//! through a task-local, so that the request type flowing through the stack stays unchanged.
+//!
+//! Requests implementing [`HasTimeout`] can likewise override the timeout [`AttemptTimeout`]
+//! applies to each of their attempts, once they have passed through a [`RequestTimeout`] service.
+//! Other requests can be wrapped in a [`WithTimeout`] and sent through a [`DynamicTimeout`]
+//! service to the same effect.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use pin_project::pin_project;
+use tokio::{
+    task::futures::TaskLocalFuture,
+    time::{error::Elapsed, timeout_at, Instant, Timeout},
+};
+use tower::{Layer, Service};
+
+use super::SinkServiceError;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+    static REQUEST_TIMEOUT: Duration;
+}
+
+/// Returns the deadline of the request currently being processed, if there is one.
+pub(crate) fn current_deadline() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Applies an overall deadline to each request passing through the wrapped service.
+#[derive(Clone, Copy, Debug)]
+pub struct DeadlineLayer {
+    budget: Duration,
+}
+
+impl DeadlineLayer {
+    /// Creates a new deadline layer, giving each request `budget` to complete.
+    pub const fn new(budget: Duration) -> Self {
+        Self { budget }
+    }
+}
+
+impl<S> Layer<S> for DeadlineLayer {
+    type Service = Deadline<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Deadline {
+            inner,
+            budget: self.budget,
+        }
+    }
+}
+
+/// A service which aborts requests, and any pending retries, once their deadline has passed.
+#[derive(Clone, Debug)]
+pub struct Deadline<S> {
+    inner: S,
+    budget: Duration,
+}
+
+impl<S, Req> Service<Req> for Deadline<S>
+where
+    S: Service<Req>,
+    S::Error: Into<crate::Error>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = DeadlineFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let deadline = Instant::now() + self.budget;
+        let inner = &mut self.inner;
+        let future = DEADLINE.sync_scope(deadline, || inner.call(req));
+
+        DeadlineFuture {
+            inner: DEADLINE.scope(deadline, timeout_at(deadline, future)),
+            budget: self.budget,
+        }
+    }
+}
+
+/// Future for [`Deadline`], which fails once the deadline of its request has passed.
+#[pin_project]
+pub struct DeadlineFuture<F> {
+    #[pin]
+    inner: TaskLocalFuture<Instant, Timeout<F>>,
+    budget: Duration,
+}
+
+impl<F, T, E> Future for DeadlineFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<crate::Error>,
+{
+    type Output = Result<T, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        Poll::Ready(fail_on_timeout(ready!(this.inner.poll(cx)), *this.budget))
+    }
+}
+
+/// Fails with [`SinkServiceError::Timeout`] if `result` timed out `after` the given duration.
+fn fail_on_timeout<T, E>(
+    result: Result<Result<T, E>, Elapsed>,
+    after: Duration,
+) -> Result<T, crate::Error>
+where
+    E: Into<crate::Error>,
+{
+    match result {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(SinkServiceError::Timeout { after }.into()),
+    }
+}
+
+/// Requests which can override the timeout of their attempts.
+pub trait HasTimeout {
+    /// Returns the timeout to apply to each attempt of this request.
+    ///
+    /// Returns `None` to apply the timeout configured for the sink.
+    fn timeout(&self) -> Option<Duration>;
+}
+
+/// Applies the timeout of each request passing through the wrapped service to its attempts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestTimeoutLayer;
+
+impl<S> Layer<S> for RequestTimeoutLayer {
+    type Service = RequestTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTimeout { inner }
+    }
+}
+
+/// A service which makes the timeout returned by [`HasTimeout::timeout`] the one applied by
+/// [`AttemptTimeout`] to each attempt of the request.
+///
+/// This is meant to wrap the tower request settings of a sink.
+#[derive(Clone, Debug)]
+pub struct RequestTimeout<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for RequestTimeout<S>
+where
+    S: Service<Req>,
+    S::Future: Send + 'static,
+    Req: HasTimeout,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let timeout = req.timeout();
+        call_with_timeout(&mut self.inner, req, timeout)
+    }
+}
+
+/// Calls `inner` with `req`, overriding the timeout of its attempts with `timeout` if set.
+fn call_with_timeout<S, Req>(
+    inner: &mut S,
+    req: Req,
+    timeout: Option<Duration>,
+) -> BoxFuture<'static, Result<S::Response, S::Error>>
+where
+    S: Service<Req>,
+    S::Future: Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return inner.call(req).boxed();
+    };
+
+    let future = REQUEST_TIMEOUT.sync_scope(timeout, || inner.call(req));
+    REQUEST_TIMEOUT.scope(timeout, future).boxed()
+}
+
+/// A request along with the timeout to apply to each of its attempts, if it overrides the one
+/// configured for the sink.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithTimeout<Req> {
+    pub request: Req,
+    pub timeout: Option<Duration>,
+}
+
+impl<Req> WithTimeout<Req> {
+    pub const fn new(request: Req, timeout: Option<Duration>) -> Self {
+        Self { request, timeout }
+    }
+}
+
+/// Wraps a request which keeps the timeout configured for the sink.
+impl<Req> From<Req> for WithTimeout<Req> {
+    fn from(request: Req) -> Self {
+        Self::new(request, None)
+    }
+}
+
+impl<Req> HasTimeout for WithTimeout<Req> {
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+/// Unwraps [`WithTimeout`] requests, applying their timeout to their attempts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DynamicTimeoutLayer;
+
+impl<S> Layer<S> for DynamicTimeoutLayer {
+    type Service = DynamicTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DynamicTimeout { inner }
+    }
+}
+
+/// A service which sends the request wrapped in each [`WithTimeout`], making its timeout the one
+/// applied by [`AttemptTimeout`] to each of its attempts.
+///
+/// Requests without a timeout are sent with the timeout configured for the sink. This is meant to
+/// wrap the tower request settings of a sink whose requests don't implement [`HasTimeout`].
+#[derive(Clone, Debug)]
+pub struct DynamicTimeout<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<WithTimeout<Req>> for DynamicTimeout<S>
+where
+    S: Service<Req>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: WithTimeout<Req>) -> Self::Future {
+        call_with_timeout(&mut self.inner, req.request, req.timeout)
+    }
+}
+
+/// Applies a timeout to each attempt made through the wrapped service, shrunk to fit within the
+/// deadline of the request.
+#[derive(Clone, Copy, Debug)]
+pub struct AttemptTimeoutLayer {
+    timeout: Duration,
+}
+
+impl AttemptTimeoutLayer {
+    /// Creates a new attempt timeout layer.
+    pub const fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for AttemptTimeoutLayer {
+    type Service = AttemptTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AttemptTimeout {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// A service which times out each attempt after `timeout`, or once the deadline of the request has
+/// passed, whichever comes first.
+///
+/// The timeout of requests which passed through a [`RequestTimeout`] service is used in place of
+/// `timeout`, if they have one.
+///
+/// Attempts made with no time left before the deadline fail right away, without calling the inner
+/// service.
+#[derive(Clone, Debug)]
+pub struct AttemptTimeout<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S, Req> Service<Req> for AttemptTimeout<S>
+where
+    S: Service<Req>,
+    S::Error: Into<crate::Error>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = AttemptTimeoutFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let now = Instant::now();
+        let timeout = REQUEST_TIMEOUT
+            .try_with(|timeout| *timeout)
+            .unwrap_or(self.timeout);
+        let timeout = now + timeout;
+        let timeout = current_deadline().map_or(timeout, |deadline| timeout.min(deadline));
+        let after = timeout.saturating_duration_since(now);
+        let inner = (!after.is_zero()).then(|| timeout_at(timeout, self.inner.call(req)));
+
+        AttemptTimeoutFuture { inner, after }
+    }
+}
+
+/// Future for [`AttemptTimeout`], which fails once the attempt times out.
+///
+/// Attempts made with no time left are never started, and fail on their first poll.
+#[pin_project]
+pub struct AttemptTimeoutFuture<F> {
+    #[pin]
+    inner: Option<Timeout<F>>,
+    after: Duration,
+}
+
+impl<F, T, E> Future for AttemptTimeoutFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<crate::Error>,
+{
+    type Output = Result<T, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let Some(inner) = this.inner.as_pin_mut() else {
+            return Poll::Ready(Err(SinkServiceError::Timeout { after: *this.after }.into()));
+        };
+        Poll::Ready(fail_on_timeout(ready!(inner.poll(cx)), *this.after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Request(Option<Duration>);
+
+    impl HasTimeout for Request {
+        fn timeout(&self) -> Option<Duration> {
+            self.0
+        }
+    }
+
+    fn timed_out_after(error: &crate::Error) -> Option<Duration> {
+        match error.downcast_ref::<SinkServiceError>() {
+            Some(SinkServiceError::Timeout { after }) => Some(*after),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn passes_through_before_deadline() {
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(DeadlineLayer::new(Duration::from_secs(5)));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_response(());
+        assert_ready_ok!(fut.poll());
+    }
+
+    #[tokio::test]
+    async fn aborts_request_after_deadline() {
+        pause();
+
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(DeadlineLayer::new(Duration::from_secs(5)));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut fut = task::spawn(svc.call("hello"));
+        let _response = assert_request_eq!(handle, "hello");
+        assert_pending!(fut.poll());
+
+        advance(Duration::from_secs(6)).await;
+        let error = assert_ready_err!(fut.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn attempt_timeout_shrinks_to_deadline() {
+        pause();
+
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(AttemptTimeoutLayer::new(Duration::from_secs(10)));
+
+        assert_ready_ok!(svc.poll_ready());
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut fut = task::spawn(DEADLINE.sync_scope(deadline, || svc.call("hello")));
+        let _response = assert_request_eq!(handle, "hello");
+        assert_pending!(fut.poll());
+
+        advance(Duration::from_secs(3)).await;
+        let error = assert_ready_err!(fut.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(2)));
+    }
+
+    #[tokio::test]
+    async fn attempt_fails_immediately_past_deadline() {
+        pause();
+
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(AttemptTimeoutLayer::new(Duration::from_secs(10)));
+
+        assert_ready_ok!(svc.poll_ready());
+        let deadline = Instant::now();
+        let mut fut = task::spawn(DEADLINE.sync_scope(deadline, || svc.call("hello")));
+        let error = assert_ready_err!(fut.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(0)));
+
+        drop(svc);
+        assert!(handle.next_request().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn request_timeout_overrides_attempt_timeout() {
+        pause();
+
+        let layer = tower::ServiceBuilder::new()
+            .layer(RequestTimeoutLayer)
+            .layer(AttemptTimeoutLayer::new(Duration::from_secs(10)));
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(layer);
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut large = task::spawn(svc.call(Request(Some(Duration::from_secs(30)))));
+        let _response = assert_request_eq!(handle, Request(Some(Duration::from_secs(30))));
+        assert_ready_ok!(svc.poll_ready());
+        let mut small = task::spawn(svc.call(Request(None)));
+        let _response = assert_request_eq!(handle, Request(None));
+
+        advance(Duration::from_secs(20)).await;
+        let error = assert_ready_err!(small.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(10)));
+        assert_pending!(large.poll());
+
+        advance(Duration::from_secs(20)).await;
+        let error = assert_ready_err!(large.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(30)));
+    }
+
+    #[tokio::test]
+    async fn dynamic_timeout_unwraps_requests() {
+        pause();
+
+        let layer = tower::ServiceBuilder::new()
+            .layer(DynamicTimeoutLayer)
+            .layer(AttemptTimeoutLayer::new(Duration::from_secs(10)));
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(layer);
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut large =
+            task::spawn(svc.call(WithTimeout::new("large", Some(Duration::from_secs(30)))));
+        let _response = assert_request_eq!(handle, "large");
+        assert_ready_ok!(svc.poll_ready());
+        let mut small = task::spawn(svc.call(WithTimeout::from("small")));
+        let _response = assert_request_eq!(handle, "small");
+
+        advance(Duration::from_secs(20)).await;
+        let error = assert_ready_err!(small.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(10)));
+        assert_pending!(large.poll());
+
+        advance(Duration::from_secs(20)).await;
+        let error = assert_ready_err!(large.poll());
+        assert_eq!(timed_out_after(&error), Some(Duration::from_secs(30)));
+    }
+}
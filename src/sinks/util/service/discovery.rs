@@ -0,0 +1,706 @@
+//! Adds and removes endpoints from load balancing based on their health.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{stream::BoxStream, task::AtomicWaker, Stream, StreamExt};
+use tokio::{
+    sync::mpsc,
+    time::{sleep, Sleep},
+};
+use tower::discover::Change;
+
+use super::health::{HealthConfig, HealthCounters};
+use crate::sinks::util::retries::ExponentialBackoff;
+
+/// A discovery stream which removes endpoints from load balancing once they are observed to be
+/// unhealthy, and reinserts them after a backoff.
+///
+/// A reinserted endpoint is on probation until it has served enough healthy responses in a row,
+/// and is removed again on its first unhealthy response until then.
+///
+/// Endpoints can also be added and removed at runtime by following a stream of updates.
+pub struct HealthDrivenDiscovery<K, S> {
+    endpoints: Vec<DiscoveredEndpoint<K, S>>,
+    updates: Option<BoxStream<'static, EndpointUpdate<K, S>>>,
+    removal_threshold: usize,
+    reinsertion_threshold: usize,
+    config: HealthConfig,
+}
+
+/// An endpoint added to, or removed from, a [`HealthDrivenDiscovery`] at runtime.
+pub(super) enum EndpointUpdate<K, S> {
+    /// Adds an endpoint, replacing any endpoint with the same key.
+    Insert {
+        key: K,
+        counters: Arc<HealthCounters>,
+        make_service: Box<dyn FnMut() -> S + Send>,
+    },
+
+    /// Removes an endpoint, leaving requests already sent to it to complete.
+    Remove(K),
+}
+
+/// Adds and removes endpoints of a distributed service at runtime, such as from an admin API.
+///
+/// Endpoints are identified by name, including those the service was built with. Clones of the
+/// controller manage the same service.
+pub struct EndpointController<S> {
+    sender: mpsc::UnboundedSender<Change<String, S>>,
+}
+
+impl<S> EndpointController<S> {
+    pub(super) fn new() -> (Self, mpsc::UnboundedReceiver<Change<String, S>>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    /// Adds `endpoint` to load balancing, served by `service`. An endpoint with the same name is
+    /// replaced.
+    ///
+    /// Returns `false` if the distributed service has been dropped.
+    pub fn insert(&self, endpoint: impl Into<String>, service: S) -> bool {
+        self.sender
+            .send(Change::Insert(endpoint.into(), service))
+            .is_ok()
+    }
+
+    /// Removes `endpoint` from load balancing. Requests already sent to it are left to complete.
+    ///
+    /// Returns `false` if the distributed service has been dropped.
+    pub fn remove(&self, endpoint: impl Into<String>) -> bool {
+        self.sender.send(Change::Remove(endpoint.into())).is_ok()
+    }
+}
+
+// Derived impls would needlessly require `S: Clone` and `S: Debug`.
+impl<S> Clone for EndpointController<S> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for EndpointController<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EndpointController").finish_non_exhaustive()
+    }
+}
+
+/// Drains endpoints of a distributed service from load balancing, and restores them, while
+/// keeping them registered.
+///
+/// Requests already sent to an endpoint when it is drained are left to complete. The handle also
+/// reports whether the service is ready to accept work, based on the state of its endpoints.
+#[derive(Clone, Debug, Default)]
+pub struct DrainHandle {
+    endpoints: Vec<HandleEndpoint>,
+}
+
+#[derive(Clone, Debug)]
+struct HandleEndpoint {
+    name: String,
+    drain: Arc<DrainSignal>,
+    counters: Arc<HealthCounters>,
+}
+
+impl HandleEndpoint {
+    fn unavailable(&self) -> Option<Unavailable> {
+        if self.drain.is_set() {
+            Some(Unavailable::Drained)
+        } else if self.counters.is_removed() {
+            Some(Unavailable::Removed)
+        } else if self.counters.is_circuit_open() {
+            Some(Unavailable::CircuitOpen)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why an endpoint of a distributed service is not receiving requests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Unavailable {
+    /// The endpoint was drained through a [`DrainHandle`].
+    Drained,
+
+    /// The endpoint was removed from load balancing after too many unhealthy responses.
+    Removed,
+
+    /// The circuit breaker of the endpoint is open.
+    CircuitOpen,
+}
+
+/// The readiness of a distributed service to accept work.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Readiness {
+    /// The endpoints which are receiving requests.
+    pub available: Vec<String>,
+
+    /// The endpoints which are not receiving requests, along with the reason why.
+    pub unavailable: Vec<(String, Unavailable)>,
+}
+
+impl Readiness {
+    /// Whether at least one endpoint is receiving requests.
+    pub fn is_ready(&self) -> bool {
+        !self.available.is_empty()
+    }
+}
+
+impl DrainHandle {
+    pub(super) fn push(
+        &mut self,
+        endpoint: String,
+        drain: Arc<DrainSignal>,
+        counters: Arc<HealthCounters>,
+    ) {
+        self.endpoints.push(HandleEndpoint {
+            name: endpoint,
+            drain,
+            counters,
+        });
+    }
+
+    /// Reports which endpoints are currently receiving requests.
+    pub fn readiness(&self) -> Readiness {
+        let mut readiness = Readiness::default();
+        for endpoint in &self.endpoints {
+            match endpoint.unavailable() {
+                Some(reason) => readiness.unavailable.push((endpoint.name.clone(), reason)),
+                None => readiness.available.push(endpoint.name.clone()),
+            }
+        }
+        readiness
+    }
+
+    /// Whether at least one endpoint is receiving requests, without listing them like
+    /// [`Self::readiness`] does.
+    pub fn any_available(&self) -> bool {
+        self.endpoints
+            .iter()
+            .any(|endpoint| endpoint.unavailable().is_none())
+    }
+
+    /// Stops sending new requests to `endpoint` until it is undrained.
+    ///
+    /// Returns `false` if there is no such endpoint.
+    pub fn drain(&self, endpoint: &str) -> bool {
+        self.set_draining(endpoint, true)
+    }
+
+    /// Resumes sending requests to `endpoint` after it was drained.
+    ///
+    /// Returns `false` if there is no such endpoint.
+    pub fn undrain(&self, endpoint: &str) -> bool {
+        self.set_draining(endpoint, false)
+    }
+
+    fn set_draining(&self, endpoint: &str, draining: bool) -> bool {
+        self.endpoints
+            .iter()
+            .filter(|handle| handle.name == endpoint)
+            .map(|handle| handle.drain.set(draining))
+            .count()
+            > 0
+    }
+}
+
+/// Whether an endpoint is drained, shared between a [`DrainHandle`] and the discovery stream.
+#[derive(Debug, Default)]
+pub(super) struct DrainSignal {
+    draining: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl DrainSignal {
+    fn set(&self, draining: bool) {
+        self.draining.store(draining, Ordering::Release);
+        self.waker.wake();
+    }
+
+    fn is_draining(&self, cx: &mut Context<'_>) -> bool {
+        self.waker.register(cx.waker());
+        self.is_set()
+    }
+
+    fn is_set(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+}
+
+struct DiscoveredEndpoint<K, S> {
+    key: K,
+    counters: Arc<HealthCounters>,
+    drain: Arc<DrainSignal>,
+    make_service: Box<dyn FnMut() -> S + Send>,
+    backoff: ExponentialBackoff,
+    state: EndpointState,
+}
+
+enum EndpointState {
+    /// Not inserted into load balancing yet.
+    New,
+
+    /// Part of load balancing.
+    Inserted { on_probation: bool },
+
+    /// Removed from load balancing until the timer fires.
+    Removed(Pin<Box<Sleep>>),
+
+    /// Removed from load balancing until it is undrained.
+    Drained { on_probation: bool },
+}
+
+impl<K, S> HealthDrivenDiscovery<K, S> {
+    pub(super) fn new(config: &HealthConfig) -> Self {
+        Self {
+            endpoints: Vec::new(),
+            updates: None,
+            removal_threshold: config.removal_threshold as usize,
+            reinsertion_threshold: config.reinsertion_threshold as usize,
+            config: config.clone(),
+        }
+    }
+
+    /// Adds an endpoint, whose services record their responses in `counters`.
+    ///
+    /// `make_service` is called every time the endpoint is (re)inserted into load balancing. The
+    /// returned signal drains the endpoint.
+    pub(super) fn push<F>(
+        &mut self,
+        key: K,
+        counters: Arc<HealthCounters>,
+        make_service: F,
+    ) -> Arc<DrainSignal>
+    where
+        F: FnMut() -> S + Send + 'static,
+    {
+        let endpoint = self.endpoint(key, counters, Box::new(make_service));
+        let drain = Arc::clone(&endpoint.drain);
+        self.endpoints.push(endpoint);
+        drain
+    }
+
+    /// Adds and removes endpoints as `updates` are received, until the stream ends.
+    pub(super) fn follow(&mut self, updates: BoxStream<'static, EndpointUpdate<K, S>>) {
+        self.updates = Some(updates);
+    }
+
+    fn endpoint(
+        &self,
+        key: K,
+        counters: Arc<HealthCounters>,
+        make_service: Box<dyn FnMut() -> S + Send>,
+    ) -> DiscoveredEndpoint<K, S> {
+        DiscoveredEndpoint {
+            key,
+            counters,
+            drain: Arc::new(DrainSignal::default()),
+            make_service,
+            backoff: self.config.backoff(),
+            state: EndpointState::New,
+        }
+    }
+}
+
+impl<K: Clone + PartialEq, S> HealthDrivenDiscovery<K, S> {
+    /// Applies an update, returning the change to load balancing it causes right away, if any.
+    ///
+    /// Inserted endpoints are picked up when the endpoints are next polled.
+    fn apply(&mut self, update: EndpointUpdate<K, S>) -> Option<Change<K, S>> {
+        match update {
+            EndpointUpdate::Insert {
+                key,
+                counters,
+                make_service,
+            } => {
+                let endpoint = self.endpoint(key, counters, make_service);
+                // Inserting an endpoint with the key of one which is already part of load
+                // balancing replaces its service there.
+                match self.endpoints.iter_mut().find(|e| e.key == endpoint.key) {
+                    Some(existing) => *existing = endpoint,
+                    None => self.endpoints.push(endpoint),
+                }
+                None
+            }
+            EndpointUpdate::Remove(key) => {
+                let index = self.endpoints.iter().position(|e| e.key == key)?;
+                let endpoint = self.endpoints.remove(index);
+                debug!(message = "Removing endpoint from load balancing on request.");
+                // Endpoints which are not part of load balancing have nothing to remove.
+                matches!(endpoint.state, EndpointState::Inserted { .. })
+                    .then(|| Change::Remove(endpoint.key))
+            }
+        }
+    }
+}
+
+impl<K: Clone, S> DiscoveredEndpoint<K, S> {
+    fn poll_change(
+        &mut self,
+        cx: &mut Context<'_>,
+        removal_threshold: usize,
+        reinsertion_threshold: usize,
+    ) -> Option<Change<K, S>> {
+        if self.drain.is_draining(cx) {
+            return match self.state {
+                EndpointState::New => {
+                    self.state = EndpointState::Drained {
+                        on_probation: false,
+                    };
+                    None
+                }
+                EndpointState::Inserted { on_probation } => {
+                    debug!(message = "Draining endpoint from load balancing.");
+                    self.state = EndpointState::Drained { on_probation };
+                    Some(Change::Remove(self.key.clone()))
+                }
+                // Removed endpoints are reinserted on probation once undrained and their backoff
+                // has passed.
+                EndpointState::Removed(_) | EndpointState::Drained { .. } => None,
+            };
+        }
+
+        match self.state {
+            EndpointState::New => {
+                self.state = EndpointState::Inserted {
+                    on_probation: false,
+                };
+                Some(Change::Insert(self.key.clone(), (self.make_service)()))
+            }
+            EndpointState::Inserted { on_probation } => {
+                self.counters.register(cx.waker());
+
+                let unhealthy = self.counters.consecutive_unhealthy();
+                if on_probation && unhealthy > 0 {
+                    debug!(message = "Endpoint failed probation; removing it from load balancing.");
+                    Some(self.remove())
+                } else if on_probation {
+                    if self.counters.consecutive_healthy() >= reinsertion_threshold {
+                        debug!(message = "Endpoint passed probation.");
+                        self.backoff.reset();
+                        self.state = EndpointState::Inserted {
+                            on_probation: false,
+                        };
+                    }
+                    None
+                } else if removal_threshold > 0 && unhealthy >= removal_threshold {
+                    warn!(message = "Endpoint is unhealthy; removing it from load balancing.");
+                    Some(self.remove())
+                } else {
+                    None
+                }
+            }
+            EndpointState::Removed(ref mut timer) => {
+                if timer.as_mut().poll(cx).is_pending() {
+                    return None;
+                }
+
+                debug!(message = "Reinserting endpoint into load balancing on probation.");
+                self.counters.reset_consecutive();
+                self.counters.set_removed(false);
+                self.state = EndpointState::Inserted { on_probation: true };
+                Some(Change::Insert(self.key.clone(), (self.make_service)()))
+            }
+            EndpointState::Drained { on_probation } => {
+                debug!(message = "Restoring drained endpoint into load balancing.");
+                self.counters.reset_consecutive();
+                self.state = EndpointState::Inserted { on_probation };
+                Some(Change::Insert(self.key.clone(), (self.make_service)()))
+            }
+        }
+    }
+
+    fn remove(&mut self) -> Change<K, S> {
+        let delay = self.backoff.next().expect("Should never end");
+        self.counters.set_removed(true);
+        self.state = EndpointState::Removed(Box::pin(sleep(delay)));
+        Change::Remove(self.key.clone())
+    }
+}
+
+// Nothing is structurally pinned, the endpoints' timers are pinned on the heap.
+impl<K, S> Unpin for HealthDrivenDiscovery<K, S> {}
+
+impl<K: Clone + PartialEq, S> Stream for HealthDrivenDiscovery<K, S> {
+    type Item = Result<Change<K, S>, crate::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while let Some(updates) = this.updates.as_mut() {
+            match updates.poll_next_unpin(cx) {
+                Poll::Ready(Some(update)) => {
+                    if let Some(change) = this.apply(update) {
+                        return Poll::Ready(Some(Ok(change)));
+                    }
+                }
+                // The remaining endpoints are kept once nobody can update them anymore.
+                Poll::Ready(None) => this.updates = None,
+                Poll::Pending => break,
+            }
+        }
+
+        for endpoint in &mut this.endpoints {
+            if let Some(change) =
+                endpoint.poll_change(cx, this.removal_threshold, this.reinsertion_threshold)
+            {
+                return Poll::Ready(Some(Ok(change)));
+            }
+        }
+
+        // Endpoints may always become available again, so this never ends.
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready, assert_ready_err, assert_ready_ok, task};
+    use tower::{balance::p2c::Balance, load::Constant};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+    use crate::{internal_events::OpenGauge, sinks::util::service::HealthLogic};
+
+    #[derive(Clone)]
+    struct ErrorIsUnhealthy;
+
+    impl HealthLogic for ErrorIsUnhealthy {
+        type Error = crate::Error;
+        type Response = ();
+
+        fn is_healthy(&self, response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+            Some(response.is_ok())
+        }
+    }
+
+    fn next_change(
+        discovery: &mut task::Spawn<HealthDrivenDiscovery<usize, ()>>,
+    ) -> Change<usize, ()> {
+        assert_ready!(discovery.poll_next()).unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn removes_and_reinserts_endpoints() {
+        pause();
+
+        let config = HealthConfig {
+            retry_initial_backoff_secs: 1,
+            retry_max_duration_secs: Duration::from_secs(10),
+            removal_threshold: 3,
+            reinsertion_threshold: 2,
+            probe_interval_secs: None,
+            ..Default::default()
+        };
+        let counters = Arc::new(HealthCounters::new());
+        let mut discovery = HealthDrivenDiscovery::new(&config);
+        discovery.push(0, Arc::clone(&counters), || ());
+        let mut discovery = task::spawn(discovery);
+
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        assert_pending!(discovery.poll_next());
+
+        // Removed once enough unhealthy responses are observed in a row.
+        counters.inc_unhealthy();
+        counters.inc_unhealthy();
+        assert!(discovery.is_woken());
+        assert_pending!(discovery.poll_next());
+        counters.inc_unhealthy();
+        assert!(matches!(next_change(&mut discovery), Change::Remove(0)));
+        assert_pending!(discovery.poll_next());
+
+        // Reinserted on probation after the backoff, and removed again on failing it.
+        advance(Duration::from_millis(1001)).await;
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        counters.inc_healthy();
+        counters.inc_unhealthy();
+        assert!(matches!(next_change(&mut discovery), Change::Remove(0)));
+
+        // The backoff grows, and passing probation makes the endpoint subject to the removal
+        // threshold again.
+        advance(Duration::from_millis(1001)).await;
+        assert_pending!(discovery.poll_next());
+        advance(Duration::from_millis(1000)).await;
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        counters.inc_healthy();
+        counters.inc_healthy();
+        assert_pending!(discovery.poll_next());
+        counters.inc_unhealthy();
+        assert_pending!(discovery.poll_next());
+    }
+
+    #[tokio::test]
+    async fn keeps_endpoints_without_removal_threshold() {
+        let counters = Arc::new(HealthCounters::new());
+        let mut discovery = HealthDrivenDiscovery::new(&HealthConfig::default());
+        discovery.push(0, Arc::clone(&counters), || ());
+        let mut discovery = task::spawn(discovery);
+
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        for _ in 0..10 {
+            counters.inc_unhealthy();
+        }
+        assert_pending!(discovery.poll_next());
+    }
+
+    #[tokio::test]
+    async fn follows_endpoint_updates() {
+        let (updates, receiver) = futures::channel::mpsc::unbounded();
+        let insert = |key| EndpointUpdate::Insert {
+            key,
+            counters: Arc::new(HealthCounters::new()),
+            make_service: Box::new(|| ()),
+        };
+        let mut discovery = HealthDrivenDiscovery::new(&HealthConfig::default());
+        discovery.push(0, Arc::new(HealthCounters::new()), || ());
+        discovery.follow(receiver.boxed());
+        let mut discovery = task::spawn(discovery);
+
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        assert_pending!(discovery.poll_next());
+
+        updates.unbounded_send(insert(1)).unwrap();
+        assert!(discovery.is_woken());
+        assert!(matches!(next_change(&mut discovery), Change::Insert(1, ())));
+
+        // Inserting an existing endpoint replaces its service.
+        updates.unbounded_send(insert(0)).unwrap();
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+
+        updates.unbounded_send(EndpointUpdate::Remove(1)).unwrap();
+        assert!(matches!(next_change(&mut discovery), Change::Remove(1)));
+        updates.unbounded_send(EndpointUpdate::Remove(1)).unwrap();
+        assert_pending!(discovery.poll_next());
+
+        // The endpoints are kept once the updates end.
+        drop(updates);
+        assert_pending!(discovery.poll_next());
+    }
+
+    #[tokio::test]
+    async fn drained_endpoints_finish_in_flight_requests() {
+        let (first, mut first_handle) = mock::pair::<&str, &str>();
+        let (second, mut second_handle) = mock::pair::<&str, &str>();
+
+        let mut discovery = HealthDrivenDiscovery::new(&HealthConfig::default());
+        let first_drain = discovery.push(0, Arc::new(HealthCounters::new()), move || {
+            Constant::new(first.clone(), 0)
+        });
+        let second_drain = discovery.push(1, Arc::new(HealthCounters::new()), move || {
+            Constant::new(second.clone(), 0)
+        });
+        let mut balance = mock::Spawn::new(Balance::new(discovery));
+
+        // With the second endpoint drained, requests only go to the first one.
+        second_drain.set(true);
+        assert_ready_ok!(balance.poll_ready());
+        let mut in_flight = task::spawn(balance.call("in flight"));
+        let response = assert_request_eq!(first_handle, "in flight");
+
+        // Draining the first endpoint stops new requests from reaching it, but lets its in-flight
+        // request complete.
+        first_drain.set(true);
+        second_drain.set(false);
+        assert_ready_ok!(balance.poll_ready());
+        let _second = balance.call("second");
+        assert_request_eq!(second_handle, "second").send_response("ok");
+
+        response.send_response("done");
+        assert_eq!(assert_ready_ok!(in_flight.poll()), "done");
+
+        // Undrained endpoints receive requests again.
+        first_drain.set(false);
+        second_drain.set(true);
+        assert_ready_ok!(balance.poll_ready());
+        let _third = balance.call("third");
+        assert_request_eq!(first_handle, "third").send_response("ok");
+    }
+
+    #[tokio::test]
+    async fn readiness_follows_endpoint_health() {
+        pause();
+
+        let config = HealthConfig {
+            retry_initial_backoff_secs: 1,
+            retry_max_duration_secs: Duration::from_secs(10),
+            removal_threshold: 1,
+            reinsertion_threshold: 1,
+            probe_interval_secs: None,
+            ..Default::default()
+        };
+        let mut handle = DrainHandle::default();
+
+        // The first endpoint is removed from load balancing by discovery.
+        let removed_counters = Arc::new(HealthCounters::new());
+        let mut discovery = HealthDrivenDiscovery::new(&config);
+        let signal = discovery.push(0, Arc::clone(&removed_counters), || ());
+        handle.push("removed".to_owned(), signal, Arc::clone(&removed_counters));
+        let mut discovery = task::spawn(discovery);
+
+        // The circuit breaker of the second endpoint opens.
+        let circuit_counters = Arc::new(HealthCounters::new());
+        let (inner, mut inner_handle) = mock::pair::<(), ()>();
+        let mut health = mock::Spawn::new(config.build_with_counters(
+            ErrorIsUnhealthy,
+            inner,
+            OpenGauge::new(),
+            "circuit".to_owned(),
+            Arc::clone(&circuit_counters),
+        ));
+        handle.push(
+            "circuit".to_owned(),
+            Arc::new(DrainSignal::default()),
+            Arc::clone(&circuit_counters),
+        );
+
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        assert!(handle.readiness().is_ready());
+
+        removed_counters.inc_unhealthy();
+        assert!(matches!(next_change(&mut discovery), Change::Remove(0)));
+        assert_eq!(handle.readiness().available, ["circuit"]);
+
+        for _ in 0..5 {
+            assert_ready_ok!(health.poll_ready());
+            let mut response = task::spawn(health.call(()));
+            assert_request_eq!(inner_handle, ()).send_error("unhealthy");
+            assert_ready_err!(response.poll());
+        }
+        assert_pending!(health.poll_ready());
+        assert_eq!(
+            handle.readiness(),
+            Readiness {
+                available: vec![],
+                unavailable: vec![
+                    ("removed".to_owned(), Unavailable::Removed),
+                    ("circuit".to_owned(), Unavailable::CircuitOpen),
+                ],
+            }
+        );
+
+        // Both endpoints become available again once they recover.
+        advance(Duration::from_millis(1001)).await;
+        assert!(matches!(next_change(&mut discovery), Change::Insert(0, ())));
+        assert_eq!(handle.readiness().available, ["removed"]);
+
+        assert_ready_ok!(health.poll_ready());
+        let mut response = task::spawn(health.call(()));
+        assert_request_eq!(inner_handle, ()).send_response(());
+        assert_ready_ok!(response.poll());
+        assert_ready_ok!(health.poll_ready());
+        assert_eq!(handle.readiness().available, ["removed", "circuit"]);
+    }
+}
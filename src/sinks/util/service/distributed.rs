@@ -0,0 +1,153 @@
+//! Wrappers making distributed services and their discovery easier to inspect and share, such as
+//! in test assertions.
+
+use std::{
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{Stream, StreamExt};
+use tower::{
+    buffer::{future::ResponseFuture, Buffer},
+    Service,
+};
+
+use super::{MaybeRateLimit, TowerRequestSettings};
+
+/// A [`DistributedService`](super::DistributedService) which implements `Debug`, by formatting the
+/// number of its endpoints, and `Clone`.
+///
+/// The endpoints are counted when the service is wrapped, so endpoints added or removed at runtime
+/// are not accounted for. The service is moved behind a buffer, sized like the one in front of the
+/// endpoints, so that clones share its rate limit rather than each getting one of their own. As
+/// such, it must be wrapped from within a Tokio runtime.
+pub struct DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req>,
+{
+    service: Buffer<MaybeRateLimit<S>, Req>,
+    endpoints: usize,
+    rate_limited: bool,
+}
+
+impl<S, Req> DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req> + Send + 'static,
+    <MaybeRateLimit<S> as Service<Req>>::Error: Into<crate::Error> + Send + Sync,
+    <MaybeRateLimit<S> as Service<Req>>::Future: Send,
+    Req: Send + 'static,
+{
+    /// Wraps `service`, built with `settings` to distribute requests across `endpoints` endpoints.
+    pub fn new(
+        service: MaybeRateLimit<S>,
+        endpoints: usize,
+        settings: &TowerRequestSettings,
+    ) -> Self {
+        Self {
+            service: Buffer::new(service, settings.buffer_mode(endpoints).capacity()),
+            endpoints,
+            rate_limited: settings.rate_limit_num.is_some(),
+        }
+    }
+}
+
+impl<S, Req> DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req>,
+{
+    /// The number of endpoints the service was built with.
+    pub const fn endpoints(&self) -> usize {
+        self.endpoints
+    }
+}
+
+impl<S, Req> Service<Req> for DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req>,
+    <MaybeRateLimit<S> as Service<Req>>::Error: Into<crate::Error>,
+{
+    type Response = <MaybeRateLimit<S> as Service<Req>>::Response;
+    type Error = crate::Error;
+    type Future = ResponseFuture<<MaybeRateLimit<S> as Service<Req>>::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+// Derived impls would needlessly require `S: Clone` and `S: Debug`.
+impl<S, Req> Clone for DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service.clone(),
+            endpoints: self.endpoints,
+            rate_limited: self.rate_limited,
+        }
+    }
+}
+
+impl<S, Req> fmt::Debug for DebugDistributedService<S, Req>
+where
+    MaybeRateLimit<S>: Service<Req>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DistributedService")
+            .field("endpoints", &self.endpoints)
+            .field("rate_limited", &self.rate_limited)
+            .finish()
+    }
+}
+
+/// A [`DiscoveryService`](super::DiscoveryService), or any other stream, shared between its clones.
+///
+/// Clones poll the same stream, so each change is only seen by the clone which polled it, rather
+/// than by every clone. Only the task which polled the stream last is woken up when it has a new
+/// change, so it should only be polled from one task at a time.
+pub struct CloneableDiscoveryService<St> {
+    stream: Arc<Mutex<St>>,
+}
+
+impl<St> CloneableDiscoveryService<St> {
+    pub fn new(stream: St) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(stream)),
+        }
+    }
+}
+
+// Derived impls would needlessly require `St: Clone` and `St: Debug`.
+impl<St> Clone for CloneableDiscoveryService<St> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: Arc::clone(&self.stream),
+        }
+    }
+}
+
+impl<St> fmt::Debug for CloneableDiscoveryService<St> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloneableDiscoveryService")
+            .field("clones", &Arc::strong_count(&self.stream))
+            .finish()
+    }
+}
+
+impl<St: Stream + Unpin> Stream for CloneableDiscoveryService<St> {
+    type Item = St::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream
+            .lock()
+            .expect("discovery stream poisoned")
+            .poll_next_unpin(cx)
+    }
+}
@@ -0,0 +1,166 @@
+//! Reads tower request settings from environment variables, for deployments configured through
+//! their environment, such as containers.
+
+use std::env::{self, VarError};
+
+use snafu::{ResultExt, Snafu};
+
+use super::{TowerRequestConfig, TowerRequestConfigDefaults};
+
+/// The settings which can be read from environment variables, which are those holding a single
+/// value.
+const ENV_FIELDS: &[&str] = &[
+    "concurrency",
+    "concurrency_burst",
+    "partition_concurrency",
+    "concurrency_burst_refill_secs",
+    "max_in_flight_bytes",
+    "default_request_size_bytes",
+    "timeout_secs",
+    "rate_limit_duration_secs",
+    "rate_limit_num",
+    "rate_limit_jitter",
+    "rate_limit_burst",
+    "retry_attempts",
+    "retry_max_duration_secs",
+    "retry_initial_backoff_secs",
+    "retry_jitter_mode",
+    "request_deadline_secs",
+    "propagate_deadline",
+    "startup_jitter_secs",
+    "max_buffered_requests",
+    "load_balance_strategy",
+    "retry_on_any_error",
+];
+
+/// An environment variable which could not be read as a tower request setting.
+#[derive(Debug, Snafu)]
+pub enum EnvConfigError {
+    #[snafu(display("environment variable `{variable}` is not valid unicode"))]
+    NotUnicode { variable: String },
+    #[snafu(display("environment variable `{variable}` is invalid: {source}"))]
+    InvalidValue {
+        variable: String,
+        source: toml::de::Error,
+    },
+    #[snafu(display(
+        "environment variables prefixed with `{prefix}` are invalid together: {source}"
+    ))]
+    InvalidCombination {
+        prefix: String,
+        source: toml::de::Error,
+    },
+}
+
+impl<D: TowerRequestConfigDefaults> TowerRequestConfig<D> {
+    /// Reads the configuration from the environment variables named after its settings, in upper
+    /// case and prefixed with `prefix` and an underscore, such as `{prefix}_TIMEOUT_SECS`.
+    ///
+    /// Each variable is parsed the way the value of the setting is in TOML, with values which are
+    /// not valid TOML, such as `adaptive`, read as strings. Settings without a variable keep their
+    /// default, and settings made of several values, such as `adaptive_concurrency`, can't be set
+    /// this way.
+    pub fn from_env(prefix: &str) -> Result<Self, EnvConfigError> {
+        Self::from_lookup(prefix, |variable| env::var(variable))
+    }
+
+    /// Reads the configuration the way [`Self::from_env`] does, from the variables returned by
+    /// `lookup` rather than from the environment of the process.
+    pub fn from_lookup(
+        prefix: &str,
+        lookup: impl Fn(&str) -> Result<String, VarError>,
+    ) -> Result<Self, EnvConfigError> {
+        let mut table = toml::Table::new();
+        for field in ENV_FIELDS {
+            let variable = format!("{prefix}_{}", field.to_uppercase());
+            let raw = match lookup(&variable) {
+                Ok(raw) => raw,
+                Err(VarError::NotPresent) => continue,
+                Err(VarError::NotUnicode(_)) => return NotUnicodeSnafu { variable }.fail(),
+            };
+            let value = parse_value(&raw);
+
+            // Each variable is checked on its own, so that errors point to the variable at fault.
+            toml::Value::Table(toml::Table::from_iter([(field.to_string(), value.clone())]))
+                .try_into::<Self>()
+                .context(InvalidValueSnafu { variable })?;
+            table.insert(field.to_string(), value);
+        }
+
+        toml::Value::Table(table)
+            .try_into()
+            .context(InvalidCombinationSnafu { prefix })
+    }
+}
+
+/// Parses `raw` as a TOML value, or as a string if it is not one.
+fn parse_value(raw: &str) -> toml::Value {
+    format!("value = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::sinks::util::service::{
+        Concurrency, GlobalTowerRequestConfigDefaults, RateLimitNum,
+    };
+
+    // The process environment is shared by tests running in parallel, so variables are looked up
+    // in a map instead.
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Result<String, VarError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned().ok_or(VarError::NotPresent)
+    }
+
+    #[test]
+    fn from_env_reads_prefixed_variables() {
+        let vars = lookup(&[
+            ("PREFIX_TIMEOUT_SECS", "15"),
+            ("PREFIX_CONCURRENCY", "adaptive"),
+            ("PREFIX_RATE_LIMIT_NUM", "100"),
+            ("PREFIX_RETRY_ON_ANY_ERROR", "true"),
+            ("PREFIX_RETRY_JITTER_MODE", "None"),
+            ("OTHER_TIMEOUT_SECS", "30"),
+        ]);
+
+        let config: TowerRequestConfig = TowerRequestConfig::from_lookup("PREFIX", vars).unwrap();
+        let defaults: TowerRequestConfig = Default::default();
+        assert_eq!(config.timeout_secs, Some(15));
+        assert_eq!(config.concurrency, Some(Concurrency::Adaptive));
+        assert_eq!(config.rate_limit_num, Some(RateLimitNum::Limited(100)));
+        assert_eq!(config.retry_on_any_error, Some(true));
+        assert_eq!(config.max_buffered_requests, None);
+        assert_eq!(config.retry_attempts, defaults.retry_attempts);
+    }
+
+    #[test]
+    fn from_env_reports_the_invalid_variable() {
+        let vars = lookup(&[("PREFIX_TIMEOUT_SECS", "15"), ("PREFIX_CONCURRENCY", "-3")]);
+
+        let error =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::from_lookup("PREFIX", vars)
+                .unwrap_err();
+        assert!(
+            matches!(&error, EnvConfigError::InvalidValue { variable, .. } if variable == "PREFIX_CONCURRENCY"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn from_env_keeps_defaults_without_variables() {
+        let config: TowerRequestConfig =
+            TowerRequestConfig::from_env("FROM_ENV_WITHOUT_VARIABLES").unwrap();
+        let defaults: TowerRequestConfig = Default::default();
+        assert_eq!(config.timeout_secs, defaults.timeout_secs);
+        assert_eq!(config.concurrency, defaults.concurrency);
+    }
+}
@@ -0,0 +1,112 @@
+//! Errors raised by the middleware built from the tower request settings, rather than by the
+//! service it wraps.
+
+use std::{
+    cell::Cell,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::future::BoxFuture;
+use snafu::Snafu;
+use tower::{timeout::error::Elapsed, Layer, Service};
+
+tokio::task_local! {
+    static EXHAUSTED_AFTER: Cell<Option<usize>>;
+}
+
+/// A failure of the middleware itself.
+///
+/// Services still fail with a `crate::Error`, which these can be downcast from.
+#[derive(Debug, Snafu)]
+pub enum SinkServiceError {
+    /// The request failed on every attempt it was allowed.
+    #[snafu(display("retries exhausted after {attempts} attempts: {last_error}"))]
+    RetryExhausted {
+        attempts: usize,
+        last_error: crate::Error,
+    },
+
+    /// The request, or one of its attempts, took too long.
+    #[snafu(display("request timed out after {after:?}"))]
+    Timeout { after: Duration },
+
+    /// The request was rejected as the circuit breaker of its endpoint is open.
+    #[snafu(display("circuit breaker is open"))]
+    CircuitOpen,
+
+    /// The request was rejected by a rate limit.
+    #[snafu(display("request was rate limited"))]
+    RateLimited,
+
+    /// The request was rejected as the sink is shutting down.
+    #[snafu(display("sink is shutting down"))]
+    ShuttingDown,
+}
+
+/// Whether `error` is a timeout, raised either by this middleware or by tower's.
+pub(crate) fn is_timeout(error: &crate::Error) -> bool {
+    error.downcast_ref::<Elapsed>().is_some()
+        || matches!(
+            error.downcast_ref::<SinkServiceError>(),
+            Some(SinkServiceError::Timeout { .. })
+        )
+}
+
+/// Records that the request currently being processed failed for the last time after `attempts`
+/// attempts, as it can't be retried anymore.
+pub(crate) fn record_retries_exhausted(attempts: usize) {
+    _ = EXHAUSTED_AFTER.try_with(|exhausted| exhausted.set(Some(attempts)));
+}
+
+/// Reports the requests which ran out of retries through the wrapped retry service.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetryExhaustionLayer;
+
+impl<S> Layer<S> for RetryExhaustionLayer {
+    type Service = RetryExhaustion<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetryExhaustion { inner }
+    }
+}
+
+/// A service which fails with [`SinkServiceError::RetryExhausted`] when the retry policy it wraps
+/// gives up on a request for lack of retries left.
+///
+/// Requests the retry policy gives up on for any other reason fail with their own error.
+#[derive(Clone, Debug)]
+pub struct RetryExhaustion<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for RetryExhaustion<S>
+where
+    S: Service<Req>,
+    S::Error: Into<crate::Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let future = self.inner.call(req);
+        Box::pin(EXHAUSTED_AFTER.scope(Cell::new(None), async move {
+            future
+                .await
+                .map_err(|error| match EXHAUSTED_AFTER.with(Cell::get) {
+                    Some(attempts) => SinkServiceError::RetryExhausted {
+                        attempts,
+                        last_error: error.into(),
+                    }
+                    .into(),
+                    None => error.into(),
+                })
+        }))
+    }
+}
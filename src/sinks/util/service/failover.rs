@@ -0,0 +1,102 @@
+//! Sends requests to a fallback service while none of the endpoints of a primary distributed
+//! service are available.
+
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+
+use tower::{util::Either, Service};
+
+use super::discovery::DrainHandle;
+
+/// Which of the services of a [`Failover`] requests are sent to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FailoverPool {
+    Primary,
+    Fallback,
+}
+
+/// A service which sends requests to `primary` as long as at least one of its endpoints is
+/// receiving requests, and to `fallback` otherwise.
+///
+/// The endpoints of `primary` are checked each time the service is polled for readiness, so
+/// requests go back to `primary` as soon as one of its endpoints is available again.
+#[derive(Clone)]
+pub struct Failover<P, F> {
+    primary: P,
+    fallback: F,
+    primary_endpoints: DrainHandle,
+    pool: FailoverPool,
+}
+
+impl<P, F> Failover<P, F> {
+    /// Wraps `primary`, whose endpoints are reported by `primary_endpoints`, and `fallback`.
+    pub const fn new(primary: P, fallback: F, primary_endpoints: DrainHandle) -> Self {
+        Self {
+            primary,
+            fallback,
+            primary_endpoints,
+            pool: FailoverPool::Primary,
+        }
+    }
+
+    /// The service the last request was sent to, or the next one will be.
+    pub const fn pool(&self) -> FailoverPool {
+        self.pool
+    }
+
+    fn select_pool(&mut self) {
+        let pool = if self.primary_endpoints.any_available() {
+            FailoverPool::Primary
+        } else {
+            FailoverPool::Fallback
+        };
+
+        if pool != self.pool {
+            match pool {
+                FailoverPool::Primary => info!(message = "Failing back to primary endpoints."),
+                FailoverPool::Fallback => info!(message = "Failing over to fallback endpoints."),
+            }
+            self.pool = pool;
+        }
+    }
+}
+
+impl<P, F, Req> Service<Req> for Failover<P, F>
+where
+    P: Service<Req>,
+    P::Error: Into<crate::Error>,
+    F: Service<Req, Response = P::Response>,
+    F::Error: Into<crate::Error>,
+{
+    type Response = P::Response;
+    type Error = crate::Error;
+    type Future = Either<P::Future, F::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.select_pool();
+        match self.pool {
+            FailoverPool::Primary => self.primary.poll_ready(cx).map_err(Into::into),
+            FailoverPool::Fallback => self.fallback.poll_ready(cx).map_err(Into::into),
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        match self.pool {
+            FailoverPool::Primary => Either::A(self.primary.call(req)),
+            FailoverPool::Fallback => Either::B(self.fallback.call(req)),
+        }
+    }
+}
+
+// A derived impl would needlessly require `P: Debug` and `F: Debug`, which distributed services
+// don't implement.
+impl<P, F> fmt::Debug for Failover<P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Failover")
+            .field("pool", &self.pool)
+            .field("primary_endpoints", &self.primary_endpoints.readiness())
+            .finish()
+    }
+}
@@ -1,30 +1,53 @@
 use std::{
+    collections::VecDeque,
+    fmt,
     future::Future,
+    marker::PhantomData,
+    ops::RangeInclusive,
     pin::Pin,
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
     },
-    task::{ready, Context, Poll},
+    task::{ready, Context, Poll, Waker},
 };
 
-use futures::FutureExt;
+use futures::{task::AtomicWaker, FutureExt};
 use futures_util::{future::BoxFuture, TryFuture};
+use http::Request;
+use hyper::Body;
 use pin_project::pin_project;
 use serde_with::serde_as;
 use stream_cancel::{Trigger, Tripwire};
-use tokio::time::{sleep, Duration};
-use tower::Service;
-use vector_lib::{configurable::configurable_component, emit};
+use tokio::{
+    sync::broadcast,
+    task::JoinHandle,
+    time::{interval_at, sleep, Duration, Instant, MissedTickBehavior},
+};
+use tower::{load::Load, Service};
+use vector_lib::{
+    config::proxy::ProxyConfig,
+    configurable::configurable_component,
+    emit,
+    internal_event::{InternalEventHandle as _, Registered},
+};
 
 use crate::{
-    internal_events::{EndpointsActive, OpenGauge, OpenToken},
-    sinks::util::retries::ExponentialBackoff,
+    http::{Auth, HttpClient},
+    internal_events::{
+        EndpointHealthState, EndpointHealthStateDuration, EndpointHealthStateDwell,
+        EndpointsActive, OpenGauge, OpenToken,
+    },
+    sinks::util::retries::{ExponentialBackoff, HasStatusCode},
 };
 
 const RETRY_MAX_DURATION_SECONDS_DEFAULT: u64 = 3_600;
 const RETRY_INITIAL_BACKOFF_SECONDS_DEFAULT: u64 = 1;
 const UNHEALTHY_AMOUNT_OF_ERRORS: usize = 5;
+/// Weight of a degraded endpoint relative to a healthy one.
+const DEGRADED_WEIGHT: f64 = 0.5;
+/// Number of health transitions a subscriber can fall behind before missing the oldest ones.
+const HEALTH_WATCH_CAPACITY: usize = 256;
 
 /// Options for determining the health of an endpoint.
 #[serde_as]
@@ -44,6 +67,77 @@ pub struct HealthConfig {
     #[serde(default = "default_retry_max_duration_secs")]
     #[configurable(metadata(docs::human_name = "Max Retry Duration"))]
     pub retry_max_duration_secs: Duration,
+
+    /// Number of consecutive unhealthy responses after which an endpoint is removed from load balancing.
+    ///
+    /// Removed endpoints are reinserted after the same backoff used to reactivate unhealthy
+    /// endpoints. If set to `0`, endpoints are never removed.
+    #[serde(default)]
+    #[configurable(metadata(docs::human_name = "Removal Threshold"))]
+    pub removal_threshold: u32,
+
+    /// Number of consecutive healthy responses a reinserted endpoint needs to be considered healthy again.
+    ///
+    /// Until then, a single unhealthy response removes it from load balancing again.
+    #[serde(default)]
+    #[configurable(metadata(docs::human_name = "Reinsertion Threshold"))]
+    pub reinsertion_threshold: u32,
+
+    /// Interval between active probes of the health of each endpoint.
+    ///
+    /// Probes are made in the background regardless of request traffic, so that an unhealthy
+    /// endpoint can recover without requests being sent to it, and count the same as responses to
+    /// requests. If not set, endpoints are not probed. This has no effect on sinks which don't
+    /// support probing their endpoints.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Probe Interval"))]
+    pub probe_interval_secs: Option<u64>,
+
+    /// Path to probe the health of each endpoint with an HTTP `GET` request, such as `/health`.
+    ///
+    /// The path is appended to the URL of the endpoint, and the probe succeeds if the response has
+    /// a `2xx` status code. If set, endpoints are probed this way rather than in a way specific to
+    /// the sink, which makes probing available to every sink distributing requests across HTTP
+    /// endpoints. This has no effect unless `probe_interval_secs` is set.
+    #[configurable(metadata(docs::examples = "/health"))]
+    pub probe_path: Option<String>,
+
+    /// Time window over which responses are observed to determine the health of an endpoint.
+    ///
+    /// If set, an endpoint is found unhealthy once the unhealthy responses observed within the
+    /// window outnumber the healthy ones, rather than after a few unhealthy responses in a row, so
+    /// that transient errors don't make it flap between healthy and unhealthy. If set to `0`, the
+    /// window is not used.
+    #[serde(default)]
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    #[configurable(metadata(docs::human_name = "Observation Window"))]
+    pub observation_window_secs: u64,
+
+    /// Number of responses which must be observed within `observation_window_secs` to determine
+    /// the health of an endpoint.
+    ///
+    /// Until then, its health is unknown, and it keeps receiving requests as if it was healthy.
+    /// This can't be less than `1`, but a low value lets a few transient errors find the endpoint
+    /// unhealthy.
+    #[serde(default = "default_min_sample_size")]
+    #[configurable(metadata(docs::human_name = "Minimum Sample Size"))]
+    pub min_sample_size: u32,
+
+    /// Where the health transitions of endpoints are published, if they are watched.
+    #[serde(skip)]
+    pub(crate) watch: Option<HealthWatch>,
+
+    /// The client endpoints are probed with over HTTP, if set up by the sink.
+    #[serde(skip)]
+    pub(crate) probe_client: Option<HttpProbeClient>,
+}
+
+/// The client endpoints are probed with over HTTP, along with the credentials probes are sent
+/// with.
+#[derive(Clone, Debug)]
+pub(crate) struct HttpProbeClient {
+    client: HttpClient,
+    auth: Option<Auth>,
 }
 
 const fn default_retry_initial_backoff_secs() -> u64 {
@@ -54,30 +148,185 @@ const fn default_retry_max_duration_secs() -> std::time::Duration {
     Duration::from_secs(RETRY_MAX_DURATION_SECONDS_DEFAULT)
 }
 
+const fn default_min_sample_size() -> u32 {
+    UNHEALTHY_AMOUNT_OF_ERRORS as u32
+}
+
 impl HealthConfig {
-    pub fn build<S, L>(
+    /// Publishes the health transitions of the endpoints built from this config from now on to
+    /// the returned watch.
+    ///
+    /// Endpoints only publish their transitions once watched, so that services nobody watches
+    /// don't pay for it.
+    pub fn watch_health(&mut self) -> HealthWatch {
+        self.watch.get_or_insert_with(HealthWatch::new).clone()
+    }
+
+    /// Probes endpoints over HTTP with `client`, sending probes with `auth`.
+    ///
+    /// Sinks should pass the client and credentials they send requests with, so that probes go
+    /// through the same TLS settings and proxy. Otherwise, probes are sent with a default client,
+    /// and without credentials.
+    pub fn probe_with(&mut self, client: HttpClient, auth: Option<Auth>) {
+        self.probe_client = Some(HttpProbeClient { client, auth });
+    }
+
+    pub fn build<S, L: HealthLogic>(
         &self,
         logic: L,
         inner: S,
         open: OpenGauge,
         endpoint: String,
     ) -> HealthService<S, L> {
-        let counters = Arc::new(HealthCounters::new());
+        self.build_with_counters(
+            logic,
+            inner,
+            open,
+            endpoint,
+            Arc::new(HealthCounters::new()),
+        )
+    }
+
+    /// Builds a health service which records the responses it observes into `counters`, so that
+    /// they can be shared with the services built for the same endpoint later on.
+    pub(super) fn build_with_counters<S, L>(
+        &self,
+        logic: L,
+        inner: S,
+        open: OpenGauge,
+        endpoint: String,
+        counters: Arc<HealthCounters>,
+    ) -> HealthService<S, L>
+    where
+        L: HealthLogic,
+    {
         let snapshot = counters.snapshot();
+        counters.set_circuit_open(false);
+        let probe = self.probe_interval_secs.map(|secs| {
+            let (endpoint, interval, counters) = (
+                endpoint.clone(),
+                Duration::from_secs(secs),
+                Arc::clone(&counters),
+            );
+            match &self.probe_path {
+                Some(path) => tokio::spawn(probe(
+                    http_probe(path.clone(), self.probe_client.clone()),
+                    endpoint,
+                    interval,
+                    counters,
+                )),
+                None => {
+                    let logic = logic.clone();
+                    tokio::spawn(probe(
+                        move |endpoint: &str| logic.probe(endpoint),
+                        endpoint,
+                        interval,
+                        counters,
+                    ))
+                }
+            }
+        });
+
+        if let Some(watch) = &self.watch {
+            watch.publish(&endpoint, EndpointHealthState::Healthy);
+        }
 
         HealthService {
             inner,
             logic,
             counters,
             snapshot,
+            state_duration: register!(EndpointHealthStateDuration {
+                endpoint: endpoint.clone(),
+            }),
+            state_since: Instant::now(),
             endpoint,
             state: CircuitState::Closed(open.clone().open(emit_active_endpoints)),
             open,
-            // An exponential backoff starting from retry_initial_backoff_sec and doubling every time
-            // up to retry_max_duration_secs.
-            backoff: ExponentialBackoff::from_millis(2)
-                .factor((self.retry_initial_backoff_secs.saturating_mul(1000) / 2).max(1))
-                .max_delay(self.retry_max_duration_secs),
+            backoff: self.backoff(),
+            probe,
+            window: (self.observation_window_secs > 0).then(|| {
+                counters.record_observations();
+                ObservationWindow {
+                    duration: Duration::from_secs(self.observation_window_secs),
+                    min_samples: (self.min_sample_size as usize).max(1),
+                    observations: VecDeque::new(),
+                }
+            }),
+            watch: self.watch.clone(),
+        }
+    }
+
+    /// An exponential backoff starting from retry_initial_backoff_sec and doubling every time up
+    /// to retry_max_duration_secs.
+    pub(super) fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff::from_millis(2)
+            .factor((self.retry_initial_backoff_secs.saturating_mul(1000) / 2).max(1))
+            .max_delay(self.retry_max_duration_secs)
+    }
+}
+
+/// A change in the health of an endpoint, as tracked by its circuit breaker.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HealthTransition {
+    pub endpoint: String,
+    pub state: EndpointHealthState,
+}
+
+/// Publishes the health transitions of endpoints to any number of subscribers, such as to show
+/// which endpoints are in rotation.
+///
+/// Endpoints publish their state as they are built, which is healthy, and whenever it changes.
+/// Subscribers only receive the transitions published after they subscribed, and lagging more
+/// than 256 transitions behind makes them miss the oldest ones.
+#[derive(Clone, Debug)]
+pub struct HealthWatch {
+    sender: broadcast::Sender<HealthTransition>,
+}
+
+impl HealthWatch {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(HEALTH_WATCH_CAPACITY);
+        Self { sender }
+    }
+
+    /// Subscribes to the transitions published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthTransition> {
+        self.sender.subscribe()
+    }
+
+    fn publish(&self, endpoint: &str, state: EndpointHealthState) {
+        // This only fails when there are no subscribers, which have nothing to miss.
+        _ = self.sender.send(HealthTransition {
+            endpoint: endpoint.to_owned(),
+            state,
+        });
+    }
+}
+
+impl Default for HealthWatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The health of an endpoint, as classified from a response.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HealthStatus {
+    Healthy,
+
+    /// The endpoint still works, but should receive fewer requests until it is healthy again.
+    Degraded,
+
+    Unhealthy,
+}
+
+impl From<bool> for HealthStatus {
+    fn from(healthy: bool) -> Self {
+        if healthy {
+            Self::Healthy
+        } else {
+            Self::Unhealthy
         }
     }
 }
@@ -89,6 +338,285 @@ pub trait HealthLogic: Clone + Send + Sync + 'static {
     /// Returns health of the endpoint based on the response/error.
     /// None if there is not enough information to determine it.
     fn is_healthy(&self, response: &Result<Self::Response, Self::Error>) -> Option<bool>;
+
+    /// Returns health of the endpoint based on the response/error, telling degraded endpoints
+    /// apart from healthy and unhealthy ones.
+    /// None if there is not enough information to determine it.
+    ///
+    /// Defaults to the health returned by [`Self::is_healthy`], which never deems an endpoint
+    /// degraded.
+    fn health(&self, response: &Result<Self::Response, Self::Error>) -> Option<HealthStatus> {
+        self.is_healthy(response).map(HealthStatus::from)
+    }
+
+    /// Returns a lightweight check of the health of `endpoint`, made independently of requests.
+    ///
+    /// This is only used if probing is enabled in the health config. Returns `None` if the
+    /// endpoint can't be probed.
+    fn probe(&self, _endpoint: &str) -> Option<BoxFuture<'static, bool>> {
+        None
+    }
+}
+
+/// A health logic which probes endpoints with a closure, on top of another health logic.
+#[derive(Clone)]
+pub struct ProbingHealthLogic<L, F> {
+    logic: L,
+    probe: F,
+}
+
+impl<L, F> ProbingHealthLogic<L, F> {
+    /// Probes endpoints with `probe`, which is given the name of the endpoint and resolves to
+    /// whether it is healthy.
+    pub const fn new(logic: L, probe: F) -> Self {
+        Self { logic, probe }
+    }
+}
+
+impl<L, F> HealthLogic for ProbingHealthLogic<L, F>
+where
+    L: HealthLogic,
+    F: Fn(&str) -> BoxFuture<'static, bool> + Clone + Send + Sync + 'static,
+{
+    type Error = L::Error;
+    type Response = L::Response;
+
+    fn is_healthy(&self, response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+        self.logic.is_healthy(response)
+    }
+
+    fn health(&self, response: &Result<Self::Response, Self::Error>) -> Option<HealthStatus> {
+        self.logic.health(response)
+    }
+
+    fn probe(&self, endpoint: &str) -> Option<BoxFuture<'static, bool>> {
+        Some((self.probe)(endpoint))
+    }
+}
+
+/// A health logic which classifies responses by their HTTP status code.
+///
+/// Responses whose status code is in `healthy_range` are healthy, and responses whose status code
+/// is in `degraded_range` are degraded. Any other response is unhealthy, as is any error.
+pub struct HttpHealthLogic<Res> {
+    pub healthy_range: RangeInclusive<u16>,
+    pub degraded_range: Option<RangeInclusive<u16>>,
+    _response: PhantomData<fn() -> Res>,
+}
+
+impl<Res> HttpHealthLogic<Res> {
+    pub const fn new(
+        healthy_range: RangeInclusive<u16>,
+        degraded_range: Option<RangeInclusive<u16>>,
+    ) -> Self {
+        Self {
+            healthy_range,
+            degraded_range,
+            _response: PhantomData,
+        }
+    }
+}
+
+impl<Res> Default for HttpHealthLogic<Res> {
+    /// Only `2xx` responses are healthy, and none are degraded.
+    fn default() -> Self {
+        Self::new(200..=299, None)
+    }
+}
+
+// Derived impls would needlessly require `Res: Clone` and `Res: Debug`.
+impl<Res> Clone for HttpHealthLogic<Res> {
+    fn clone(&self) -> Self {
+        Self::new(self.healthy_range.clone(), self.degraded_range.clone())
+    }
+}
+
+impl<Res> fmt::Debug for HttpHealthLogic<Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpHealthLogic")
+            .field("healthy_range", &self.healthy_range)
+            .field("degraded_range", &self.degraded_range)
+            .finish()
+    }
+}
+
+impl<Res: HasStatusCode + Send + 'static> HealthLogic for HttpHealthLogic<Res> {
+    type Error = crate::Error;
+    type Response = Res;
+
+    /// Degraded responses count as neither healthy nor unhealthy.
+    fn is_healthy(&self, response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+        match self.health(response)? {
+            HealthStatus::Healthy => Some(true),
+            HealthStatus::Degraded => None,
+            HealthStatus::Unhealthy => Some(false),
+        }
+    }
+
+    fn health(&self, response: &Result<Self::Response, Self::Error>) -> Option<HealthStatus> {
+        let Ok(response) = response else {
+            return Some(HealthStatus::Unhealthy);
+        };
+
+        let status = response.status_code();
+        Some(if self.healthy_range.contains(&status) {
+            HealthStatus::Healthy
+        } else if self
+            .degraded_range
+            .as_ref()
+            .is_some_and(|degraded| degraded.contains(&status))
+        {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Unhealthy
+        })
+    }
+}
+
+/// A health logic which deems every response and error healthy.
+#[cfg(any(test, feature = "test-utils"))]
+pub struct AlwaysHealthy<Res> {
+    _response: PhantomData<fn() -> Res>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> AlwaysHealthy<Res> {
+    pub const fn new() -> Self {
+        Self {
+            _response: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> Default for AlwaysHealthy<Res> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> Clone for AlwaysHealthy<Res> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res: Send + 'static> HealthLogic for AlwaysHealthy<Res> {
+    type Error = crate::Error;
+    type Response = Res;
+
+    fn is_healthy(&self, _response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+        Some(true)
+    }
+}
+
+/// A health logic which deems every response and error unhealthy.
+#[cfg(any(test, feature = "test-utils"))]
+pub struct AlwaysUnhealthy<Res> {
+    _response: PhantomData<fn() -> Res>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> AlwaysUnhealthy<Res> {
+    pub const fn new() -> Self {
+        Self {
+            _response: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> Default for AlwaysUnhealthy<Res> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res> Clone for AlwaysUnhealthy<Res> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<Res: Send + 'static> HealthLogic for AlwaysUnhealthy<Res> {
+    type Error = crate::Error;
+    type Response = Res;
+
+    fn is_healthy(&self, _response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+        Some(false)
+    }
+}
+
+/// Returns a check of the health of an endpoint which sends an HTTP `GET` request to `path` on it
+/// with `client`, and succeeds on a `2xx` response.
+///
+/// Without a client, probes are sent with a default one.
+fn http_probe(
+    path: String,
+    client: Option<HttpProbeClient>,
+) -> impl Fn(&str) -> Option<BoxFuture<'static, bool>> + Send + 'static {
+    let client = client.or_else(|| match HttpClient::new(None, &ProxyConfig::default()) {
+        Ok(client) => Some(HttpProbeClient { client, auth: None }),
+        Err(error) => {
+            error!(message = "Failed to build the HTTP client for health probes.", %error);
+            None
+        }
+    });
+
+    move |endpoint: &str| {
+        let HttpProbeClient { client, auth } = client.clone()?;
+        let uri = format!("{}{}", endpoint.trim_end_matches('/'), path);
+        let mut request = match Request::get(&uri).body(Body::empty()) {
+            Ok(request) => request,
+            Err(error) => {
+                error!(message = "Invalid health probe request.", %uri, %error);
+                return None;
+            }
+        };
+        if let Some(auth) = &auth {
+            auth.apply(&mut request);
+        }
+
+        Some(
+            client
+                .send(request)
+                .map(|response| response.map_or(false, |response| response.status().is_success()))
+                .boxed(),
+        )
+    }
+}
+
+/// Probes the health of `endpoint` every `interval` with `check`, recording the outcome into
+/// `counters`.
+///
+/// A probe which doesn't complete within `interval` fails, so that a hanging endpoint is found
+/// unhealthy rather than holding up the next probes. Probing stops once `check` returns `None`,
+/// as the endpoint can't be probed.
+async fn probe<F>(check: F, endpoint: String, interval: Duration, counters: Arc<HealthCounters>)
+where
+    F: Fn(&str) -> Option<BoxFuture<'static, bool>>,
+{
+    let timeout = interval;
+    let mut interval = interval_at(Instant::now() + interval, interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        let Some(probe) = check(&endpoint) else {
+            return;
+        };
+
+        if tokio::time::timeout(timeout, probe).await.unwrap_or(false) {
+            counters.inc_probe_healthy();
+        } else {
+            debug!(message = "Endpoint failed health probe.", endpoint = %endpoint);
+            counters.inc_unhealthy();
+        }
+    }
 }
 
 enum CircuitState {
@@ -106,6 +634,65 @@ enum CircuitState {
     Closed(OpenToken<fn(usize)>),
 }
 
+impl CircuitState {
+    const fn health(&self) -> EndpointHealthState {
+        match self {
+            CircuitState::Open(_) => EndpointHealthState::Unhealthy,
+            CircuitState::HalfOpen { .. } => EndpointHealthState::Probation,
+            CircuitState::Closed(_) => EndpointHealthState::Healthy,
+        }
+    }
+}
+
+/// The health of an endpoint according to the responses observed within its observation window.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum WindowHealth {
+    /// Too few responses were observed to tell, so the endpoint is treated as healthy.
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+/// The responses observed for an endpoint over a rolling time window.
+struct ObservationWindow {
+    duration: Duration,
+    min_samples: usize,
+    /// When each response was observed, and whether it was healthy.
+    observations: VecDeque<(Instant, bool)>,
+}
+
+impl ObservationWindow {
+    /// Records the responses observed since the last call, and forgets the ones which fell out of
+    /// the window as of `now`.
+    fn observe(&mut self, now: Instant, observed: Vec<(Instant, bool)>) {
+        self.observations.extend(observed);
+        while let Some((observed_at, _)) = self.observations.front() {
+            if now.duration_since(*observed_at) < self.duration {
+                break;
+            }
+            self.observations.pop_front();
+        }
+    }
+
+    fn health(&self) -> WindowHealth {
+        let observed = self.observations.len();
+        if observed < self.min_samples {
+            return WindowHealth::Unknown;
+        }
+
+        let unhealthy = self
+            .observations
+            .iter()
+            .filter(|(_, healthy)| !healthy)
+            .count();
+        if unhealthy * 2 > observed {
+            WindowHealth::Unhealthy
+        } else {
+            WindowHealth::Healthy
+        }
+    }
+}
+
 /// A service which monitors the health of a service.
 /// Behaves like a circuit breaker.
 pub struct HealthService<S, L> {
@@ -115,8 +702,42 @@ pub struct HealthService<S, L> {
     snapshot: HealthSnapshot,
     backoff: ExponentialBackoff,
     state: CircuitState,
+    /// When time spent in the current state was last recorded.
+    state_since: Instant,
+    state_duration: Registered<EndpointHealthStateDuration>,
     open: OpenGauge,
     endpoint: String,
+    probe: Option<JoinHandle<()>>,
+    /// The responses observed recently, if health is determined over an observation window.
+    window: Option<ObservationWindow>,
+    /// Where state transitions are published, if they are watched.
+    watch: Option<HealthWatch>,
+}
+
+impl<S, L> HealthService<S, L> {
+    /// Records the time spent in the current state since it was last recorded.
+    fn record_state_duration(&mut self) {
+        // Only whole milliseconds are recorded, the remainder is carried over to the next recording.
+        let duration = Duration::from_millis(self.state_since.elapsed().as_millis() as u64);
+        if duration.is_zero() {
+            return;
+        }
+
+        self.state_duration.emit(EndpointHealthStateDwell {
+            state: self.state.health(),
+            duration,
+        });
+        self.state_since += duration;
+    }
+}
+
+impl<S, L> Drop for HealthService<S, L> {
+    fn drop(&mut self) {
+        self.record_state_duration();
+        if let Some(probe) = &self.probe {
+            probe.abort();
+        }
+    }
 }
 
 impl<S, L, Req> Service<Req> for HealthService<S, L>
@@ -130,8 +751,26 @@ where
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         loop {
+            // State only ever changes here, so recording before each transition attributes all of
+            // the time spent to the right state.
+            self.record_state_duration();
+
+            let previous = self.state.health();
             self.state = match self.state {
+                CircuitState::Open(_)
+                    if self.probe.is_some() && self.counters.probe_healthy_since(self.snapshot) =>
+                {
+                    // A probe found the endpoint healthy in the meantime. Responses to requests
+                    // sent before the circuit opened don't count, as they say little about its
+                    // health now.
+                    info!(message = "Endpoint is healthy.", endpoint = %&self.endpoint);
+
+                    self.backoff.reset();
+                    self.counters.set_circuit_open(false);
+                    CircuitState::Closed(self.open.clone().open(emit_active_endpoints))
+                }
                 CircuitState::Open(ref mut timer) => {
+                    self.counters.register_service(cx.waker());
                     ready!(timer.as_mut().poll(cx));
 
                     debug!(message = "Endpoint is on probation.", endpoint = %&self.endpoint);
@@ -163,6 +802,7 @@ where
                         info!(message = "Endpoint is healthy.", endpoint = %&self.endpoint);
 
                         self.backoff.reset();
+                        self.counters.set_circuit_open(false);
                         CircuitState::Closed(self.open.clone().open(emit_active_endpoints))
                     } else {
                         debug!(message = "Endpoint failed probation.", endpoint = %&self.endpoint);
@@ -172,6 +812,24 @@ where
                         )
                     }
                 }
+                CircuitState::Closed(_) if self.window.is_some() => {
+                    let window = self.window.as_mut().expect("checked above");
+                    window.observe(Instant::now(), self.counters.take_observations());
+                    self.snapshot = self.counters.snapshot();
+
+                    if window.health() != WindowHealth::Unhealthy {
+                        return self.inner.poll_ready(cx).map_err(Into::into);
+                    }
+
+                    // The endpoint starts over with an empty window once it is healthy again.
+                    window.observations.clear();
+                    self.counters.take_observations();
+                    warn!(message = "Endpoint is unhealthy.", endpoint = %&self.endpoint);
+                    self.counters.set_circuit_open(true);
+                    CircuitState::Open(
+                        sleep(self.backoff.next().expect("Should never end")).boxed(),
+                    )
+                }
                 CircuitState::Closed(_) => {
                     // Check for errors
                     match self.counters.healthy(self.snapshot) {
@@ -183,6 +841,7 @@ where
                         Err(errors) if errors >= UNHEALTHY_AMOUNT_OF_ERRORS => {
                             // Unhealthy
                             warn!(message = "Endpoint is unhealthy.", endpoint = %&self.endpoint);
+                            self.counters.set_circuit_open(true);
                             CircuitState::Open(
                                 sleep(self.backoff.next().expect("Should never end")).boxed(),
                             )
@@ -193,6 +852,13 @@ where
                         }
                     }
                 }
+            };
+
+            if let Some(watch) = &self.watch {
+                let state = self.state.health();
+                if state != previous {
+                    watch.publish(&self.endpoint, state);
+                }
             }
         }
     }
@@ -235,10 +901,11 @@ where
         let this = self.project();
         let output = ready!(this.inner.poll(cx)).map_err(Into::into);
 
-        match this.logic.is_healthy(&output) {
+        match this.logic.health(&output) {
             None => (),
-            Some(true) => this.counters.inc_healthy(),
-            Some(false) => this.counters.inc_unhealthy(),
+            Some(HealthStatus::Healthy) => this.counters.inc_healthy(),
+            Some(HealthStatus::Degraded) => this.counters.set_degraded(),
+            Some(HealthStatus::Unhealthy) => this.counters.inc_unhealthy(),
         }
 
         // Request is done so we can now drop the permit.
@@ -248,26 +915,196 @@ where
     }
 }
 
-/// Tracker of response health, incremented by HealthFuture and used by HealthService.
-struct HealthCounters {
+/// A service whose load is weighted down while its endpoint is degraded, so that load balancers
+/// comparing the load of endpoints send it fewer requests.
+#[derive(Clone, Debug)]
+pub struct DegradedWeight<S> {
+    inner: S,
+    counters: Arc<HealthCounters>,
+}
+
+impl<S> DegradedWeight<S> {
+    pub(super) const fn new(inner: S, counters: Arc<HealthCounters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl<S, Req> Service<Req> for DegradedWeight<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+impl<S: Load<Metric = f64>> Load for DegradedWeight<S> {
+    type Metric = f64;
+
+    fn load(&self) -> Self::Metric {
+        let load = self.inner.load();
+        if self.counters.is_degraded() {
+            load / DEGRADED_WEIGHT
+        } else {
+            load
+        }
+    }
+}
+
+/// Tracker of response health, incremented by HealthFuture and used by HealthService and
+/// HealthDrivenDiscovery.
+///
+/// It also records whether the endpoint is currently kept from receiving requests by either of
+/// them, so that its readiness can be reported.
+#[derive(Debug)]
+pub(super) struct HealthCounters {
     healthy: AtomicUsize,
+    /// Number of healthy responses to probes, which are also counted in `healthy`.
+    probe_healthy: AtomicUsize,
     unhealthy: AtomicUsize,
+    consecutive_healthy: AtomicUsize,
+    consecutive_unhealthy: AtomicUsize,
+    circuit_open: AtomicBool,
+    removed: AtomicBool,
+    degraded: AtomicBool,
+    waker: AtomicWaker,
+    service_waker: AtomicWaker,
+    /// When each response was observed since the health service last took them, and whether it
+    /// was healthy, if they are recorded for an observation window.
+    observations: Mutex<Option<Vec<(Instant, bool)>>>,
 }
 
 impl HealthCounters {
-    const fn new() -> Self {
+    pub(super) const fn new() -> Self {
         HealthCounters {
             healthy: AtomicUsize::new(0),
+            probe_healthy: AtomicUsize::new(0),
             unhealthy: AtomicUsize::new(0),
+            consecutive_healthy: AtomicUsize::new(0),
+            consecutive_unhealthy: AtomicUsize::new(0),
+            circuit_open: AtomicBool::new(false),
+            removed: AtomicBool::new(false),
+            degraded: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+            service_waker: AtomicWaker::new(),
+            observations: Mutex::new(None),
         }
     }
 
-    fn inc_healthy(&self) {
+    pub(super) fn inc_healthy(&self) {
+        self.observe(true);
         self.healthy.fetch_add(1, Ordering::Release);
+        self.degraded.store(false, Ordering::Release);
+        self.consecutive_unhealthy.store(0, Ordering::Release);
+        self.consecutive_healthy.fetch_add(1, Ordering::Release);
+        self.waker.wake();
+        self.service_waker.wake();
+    }
+
+    fn inc_probe_healthy(&self) {
+        self.probe_healthy.fetch_add(1, Ordering::Release);
+        self.inc_healthy();
     }
 
-    fn inc_unhealthy(&self) {
+    pub(super) fn inc_unhealthy(&self) {
+        self.observe(false);
         self.unhealthy.fetch_add(1, Ordering::Release);
+        self.consecutive_healthy.store(0, Ordering::Release);
+        self.consecutive_unhealthy.fetch_add(1, Ordering::Release);
+        self.waker.wake();
+        self.service_waker.wake();
+    }
+
+    /// Records when responses are observed from now on, for an observation window to be fed with
+    /// them as of when they were observed rather than as of when it is updated.
+    fn record_observations(&self) {
+        self.observations
+            .lock()
+            .expect("lock poisoned")
+            .get_or_insert_with(Vec::new);
+    }
+
+    fn observe(&self, healthy: bool) {
+        if let Some(observations) = self.observations.lock().expect("lock poisoned").as_mut() {
+            observations.push((Instant::now(), healthy));
+        }
+    }
+
+    /// Takes the responses recorded since the last call.
+    fn take_observations(&self) -> Vec<(Instant, bool)> {
+        self.observations
+            .lock()
+            .expect("lock poisoned")
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Number of healthy responses observed since the last unhealthy one.
+    pub(super) fn consecutive_healthy(&self) -> usize {
+        self.consecutive_healthy.load(Ordering::Acquire)
+    }
+
+    /// Number of unhealthy responses observed since the last healthy one.
+    pub(super) fn consecutive_unhealthy(&self) -> usize {
+        self.consecutive_unhealthy.load(Ordering::Acquire)
+    }
+
+    pub(super) fn reset_consecutive(&self) {
+        self.consecutive_healthy.store(0, Ordering::Release);
+        self.consecutive_unhealthy.store(0, Ordering::Release);
+    }
+
+    /// Whether the circuit breaker of the endpoint is open, or half open.
+    pub(super) fn is_circuit_open(&self) -> bool {
+        self.circuit_open.load(Ordering::Acquire)
+    }
+
+    fn set_circuit_open(&self, open: bool) {
+        self.circuit_open.store(open, Ordering::Release);
+    }
+
+    /// Whether the endpoint is removed from load balancing for being unhealthy.
+    pub(super) fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Acquire)
+    }
+
+    pub(super) fn set_removed(&self, removed: bool) {
+        self.removed.store(removed, Ordering::Release);
+    }
+
+    /// Whether the endpoint was found degraded, and has not been found healthy since.
+    pub(super) fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire)
+    }
+
+    fn set_degraded(&self) {
+        self.degraded.store(true, Ordering::Release);
+    }
+
+    /// Registers a task to be woken up on the next observed response.
+    pub(super) fn register(&self, waker: &Waker) {
+        self.waker.register(waker);
+    }
+
+    /// Registers the health service of the endpoint to be woken up on the next observed response.
+    ///
+    /// This is kept apart from [`Self::register`], which is used by the discovery of the endpoint.
+    fn register_service(&self, waker: &Waker) {
+        self.service_waker.register(waker);
+    }
+
+    /// Whether a probe found the endpoint healthy since `snapshot` was taken.
+    fn probe_healthy_since(&self, snapshot: HealthSnapshot) -> bool {
+        self.probe_healthy.load(Ordering::Acquire) > snapshot.probe_healthy
     }
 
     /// Checks if healthy.
@@ -293,6 +1130,7 @@ impl HealthCounters {
     fn snapshot(&self) -> HealthSnapshot {
         HealthSnapshot {
             healthy: self.healthy.load(Ordering::Acquire),
+            probe_healthy: self.probe_healthy.load(Ordering::Acquire),
             unhealthy: self.unhealthy.load(Ordering::Acquire),
         }
     }
@@ -301,6 +1139,7 @@ impl HealthCounters {
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 struct HealthSnapshot {
     healthy: usize,
+    probe_healthy: usize,
     unhealthy: usize,
 }
 
@@ -310,7 +1149,462 @@ fn emit_active_endpoints(count: usize) {
 
 #[cfg(test)]
 mod tests {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::mock;
+    use vector_lib::event::MetricValue;
+
     use super::*;
+    use crate::metrics::{self, Controller};
+
+    struct Status(u16);
+
+    impl HasStatusCode for Status {
+        fn status_code(&self) -> u16 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn http_health_logic_classifies_status_codes() {
+        let plain = HttpHealthLogic::default();
+        let degraded = HttpHealthLogic::new(200..=299, Some(429..=429));
+
+        for (status, plain_health, degraded_health) in [
+            (200, Some(true), Some(true)),
+            (204, Some(true), Some(true)),
+            (429, Some(false), None),
+            (500, Some(false), Some(false)),
+            (503, Some(false), Some(false)),
+        ] {
+            assert_eq!(
+                plain.is_healthy(&Ok(Status(status))),
+                plain_health,
+                "status {status} without a degraded range"
+            );
+            assert_eq!(
+                degraded.is_healthy(&Ok(Status(status))),
+                degraded_health,
+                "status {status} with a degraded range"
+            );
+        }
+        assert_eq!(
+            plain.is_healthy(&Err("connection refused".into())),
+            Some(false)
+        );
+
+        assert_eq!(
+            degraded.health(&Ok(Status(429))),
+            Some(HealthStatus::Degraded)
+        );
+        assert_eq!(
+            degraded.health(&Ok(Status(200))),
+            Some(HealthStatus::Healthy)
+        );
+        assert_eq!(
+            degraded.health(&Err("connection refused".into())),
+            Some(HealthStatus::Unhealthy)
+        );
+        // Logics which only classify responses as healthy or not never find them degraded.
+        assert_eq!(
+            AlwaysHealthy::<Status>::new().health(&Ok(Status(429))),
+            Some(HealthStatus::Healthy)
+        );
+    }
+
+    #[tokio::test]
+    async fn degraded_endpoints_are_weighted_down_until_healthy() {
+        let counters = Arc::new(HealthCounters::new());
+        let (mut svc, mut handle) = mock::spawn_with::<(), Status, _, _>(|inner| {
+            HealthConfig::default().build_with_counters(
+                HttpHealthLogic::new(200..=299, Some(429..=429)),
+                inner,
+                OpenGauge::new(),
+                "degraded".to_owned(),
+                Arc::clone(&counters),
+            )
+        });
+        let weighted = DegradedWeight::new(tower::load::Constant::new((), 0.5), counters);
+        assert_eq!(weighted.load(), 0.5);
+
+        for (status, load) in [(429, 1.0), (500, 1.0), (200, 0.5)] {
+            assert_ready_ok!(svc.poll_ready());
+            let response = svc.call(());
+            let (_, send_response) = handle.next_request().await.unwrap();
+            send_response.send_response(Status(status));
+            response.await.unwrap();
+            assert_eq!(weighted.load(), load, "after status {status}");
+        }
+    }
+
+    #[test]
+    fn always_healthy_and_unhealthy_ignore_responses() {
+        let response = Ok(Status(500));
+        let error = Err("connection refused".into());
+
+        assert_eq!(AlwaysHealthy::new().is_healthy(&response), Some(true));
+        assert_eq!(
+            AlwaysHealthy::<Status>::new().is_healthy(&error),
+            Some(true)
+        );
+        assert_eq!(
+            AlwaysUnhealthy::new().is_healthy(&Ok(Status(200))),
+            Some(false)
+        );
+        assert_eq!(
+            AlwaysUnhealthy::<Status>::new().is_healthy(&error),
+            Some(false)
+        );
+    }
+
+    #[derive(Clone)]
+    struct ErrorIsUnhealthy;
+
+    impl HealthLogic for ErrorIsUnhealthy {
+        type Error = crate::Error;
+        type Response = ();
+
+        fn is_healthy(&self, response: &Result<Self::Response, Self::Error>) -> Option<bool> {
+            Some(response.is_ok())
+        }
+    }
+
+    fn state_duration(endpoint: &str, state: &str) -> f64 {
+        Controller::get()
+            .expect("There must be a controller")
+            .capture_metrics()
+            .into_iter()
+            .find(|metric| {
+                metric.name() == "endpoint_health_state_duration_milliseconds_total"
+                    && metric.tag_value("endpoint").as_deref() == Some(endpoint)
+                    && metric.tag_value("state").as_deref() == Some(state)
+            })
+            .map_or(0.0, |metric| match metric.value() {
+                MetricValue::Counter { value } => *value,
+                value => panic!("unexpected metric value {:?}", value),
+            })
+    }
+
+    #[tokio::test]
+    async fn records_time_spent_in_each_state() {
+        metrics::init_test();
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            HealthConfig::default().build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "state-duration".to_owned(),
+            )
+        });
+
+        // Healthy for 10s, until enough errors are observed to trip the circuit.
+        assert_ready_ok!(svc.poll_ready());
+        advance(Duration::from_secs(10)).await;
+        for _ in 0..UNHEALTHY_AMOUNT_OF_ERRORS {
+            assert_ready_ok!(svc.poll_ready());
+            let response = svc.call(());
+            let (_, send_response) = handle.next_request().await.unwrap();
+            send_response.send_error("unavailable");
+            assert!(response.await.is_err());
+        }
+        assert_pending!(svc.poll_ready());
+
+        // Unhealthy for the initial backoff of 1s, then on probation for 2s while a request succeeds.
+        advance(Duration::from_secs(1)).await;
+        assert_ready_ok!(svc.poll_ready());
+        let response = svc.call(());
+        let (_, send_response) = handle.next_request().await.unwrap();
+        advance(Duration::from_secs(2)).await;
+        send_response.send_response(());
+        response.await.unwrap();
+
+        // Healthy again for 3s.
+        assert_ready_ok!(svc.poll_ready());
+        advance(Duration::from_secs(3)).await;
+        drop(svc);
+
+        assert_eq!(state_duration("state-duration", "healthy"), 13_000.0);
+        assert_eq!(state_duration("state-duration", "unhealthy"), 1_000.0);
+        assert_eq!(state_duration("state-duration", "probation"), 2_000.0);
+    }
+
+    async fn send(
+        svc: &mut tokio_test::task::Spawn<HealthService<mock::Mock<(), ()>, ErrorIsUnhealthy>>,
+        handle: &mut mock::Handle<(), ()>,
+        healthy: bool,
+    ) {
+        assert_ready_ok!(svc.poll_ready());
+        let response = svc.call(());
+        let (_, send_response) = handle.next_request().await.unwrap();
+        if healthy {
+            send_response.send_response(());
+        } else {
+            send_response.send_error("unavailable");
+        }
+        _ = response.await;
+    }
+
+    fn windowed(min_sample_size: u32) -> HealthConfig {
+        HealthConfig {
+            observation_window_secs: 10,
+            min_sample_size,
+            ..HealthConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn observation_window_treats_too_few_samples_as_unknown() {
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            windowed(8).build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "unknown".to_owned(),
+            )
+        });
+
+        // More errors than would otherwise trip the circuit, but too few to tell.
+        for _ in 0..7 {
+            send(&mut svc, &mut handle, false).await;
+        }
+        assert_ready_ok!(svc.poll_ready());
+
+        send(&mut svc, &mut handle, false).await;
+        assert_pending!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn observation_window_forgets_old_failures() {
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            windowed(3).build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "rolling".to_owned(),
+            )
+        });
+
+        for _ in 0..2 {
+            send(&mut svc, &mut handle, false).await;
+        }
+        assert_ready_ok!(svc.poll_ready());
+
+        // Had the first two errors stayed in the window, this one would make three out of three.
+        advance(Duration::from_secs(11)).await;
+        send(&mut svc, &mut handle, false).await;
+        assert_ready_ok!(svc.poll_ready());
+        for _ in 0..2 {
+            send(&mut svc, &mut handle, true).await;
+        }
+        assert_ready_ok!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn observation_window_dates_responses_from_when_they_arrive() {
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            windowed(1).build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "dated".to_owned(),
+            )
+        });
+
+        // The error is already out of the window by the time the service is polled again.
+        send(&mut svc, &mut handle, false).await;
+        advance(Duration::from_secs(11)).await;
+        assert_ready_ok!(svc.poll_ready());
+    }
+
+    #[test]
+    fn min_sample_size_defaults_to_several_responses() {
+        let config: HealthConfig = toml::from_str("observation_window_secs = 10").unwrap();
+        assert_eq!(config.min_sample_size, UNHEALTHY_AMOUNT_OF_ERRORS as u32);
+    }
+
+    #[tokio::test]
+    async fn health_transitions_are_published_to_every_subscriber() {
+        pause();
+
+        let mut config = HealthConfig::default();
+        let watch = config.watch_health();
+        let mut subscribers = [watch.subscribe(), watch.subscribe()];
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            config.build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "watched".to_owned(),
+            )
+        });
+
+        for _ in 0..UNHEALTHY_AMOUNT_OF_ERRORS {
+            send(&mut svc, &mut handle, false).await;
+        }
+        assert_pending!(svc.poll_ready());
+        advance(Duration::from_secs(1)).await;
+        send(&mut svc, &mut handle, true).await;
+        assert_ready_ok!(svc.poll_ready());
+
+        let transition = |state| HealthTransition {
+            endpoint: "watched".to_owned(),
+            state,
+        };
+        for subscriber in &mut subscribers {
+            for state in [
+                EndpointHealthState::Healthy,
+                EndpointHealthState::Unhealthy,
+                EndpointHealthState::Probation,
+                EndpointHealthState::Healthy,
+            ] {
+                assert_eq!(subscriber.try_recv(), Ok(transition(state)));
+            }
+            assert!(subscriber.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn probes_recover_idle_endpoints() {
+        pause();
+
+        let config = HealthConfig {
+            retry_initial_backoff_secs: 60,
+            retry_max_duration_secs: Duration::from_secs(60),
+            probe_interval_secs: Some(1),
+            ..Default::default()
+        };
+        let counters = Arc::new(HealthCounters::new());
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            config.build_with_counters(
+                ProbingHealthLogic::new(ErrorIsUnhealthy, |_: &str| {
+                    futures::future::ready(true).boxed()
+                }),
+                inner,
+                OpenGauge::new(),
+                "probed".to_owned(),
+                Arc::clone(&counters),
+            )
+        });
+
+        for _ in 0..UNHEALTHY_AMOUNT_OF_ERRORS {
+            assert_ready_ok!(svc.poll_ready());
+            let response = svc.call(());
+            let (_, send_response) = handle.next_request().await.unwrap();
+            send_response.send_error("unavailable");
+            assert!(response.await.is_err());
+        }
+        assert_pending!(svc.poll_ready());
+        assert!(counters.is_circuit_open());
+
+        // A successful probe closes the circuit long before the backoff is over, without any
+        // request being sent.
+        advance(Duration::from_secs(1)).await;
+        tokio::task::yield_now().await;
+        assert!(svc.is_woken());
+        assert_ready_ok!(svc.poll_ready());
+        assert!(!counters.is_circuit_open());
+    }
+
+    #[tokio::test]
+    async fn late_responses_do_not_close_the_circuit() {
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<(), (), _, _>(|inner| {
+            HealthConfig::default().build(
+                ErrorIsUnhealthy,
+                inner,
+                OpenGauge::new(),
+                "late".to_owned(),
+            )
+        });
+
+        // A request which is still in flight once the circuit opens.
+        assert_ready_ok!(svc.poll_ready());
+        let late = svc.call(());
+        let (_, late_response) = handle.next_request().await.unwrap();
+
+        for _ in 0..UNHEALTHY_AMOUNT_OF_ERRORS {
+            send(&mut svc, &mut handle, false).await;
+        }
+        assert_pending!(svc.poll_ready());
+
+        late_response.send_response(());
+        late.await.unwrap();
+        assert_pending!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn probes_endpoints_over_http() {
+        use std::convert::Infallible;
+
+        use hyper::{
+            service::{make_service_fn, service_fn},
+            Response, Server, StatusCode,
+        };
+
+        let addr = crate::test_util::next_addr();
+        let server = Server::bind(&addr).serve(make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|request: Request<Body>| async move {
+                let authorized = request.headers().contains_key(http::header::AUTHORIZATION);
+                let status = match request.uri().path() {
+                    "/health" if authorized => StatusCode::OK,
+                    "/health" => StatusCode::UNAUTHORIZED,
+                    _ => StatusCode::SERVICE_UNAVAILABLE,
+                };
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }))
+        }));
+        tokio::spawn(server);
+
+        let endpoint = format!("http://{addr}/");
+        let client = HttpProbeClient {
+            client: HttpClient::new(None, &ProxyConfig::default()).unwrap(),
+            auth: Some(Auth::Bearer {
+                token: "token".to_owned().into(),
+            }),
+        };
+        let check = http_probe("/health".to_owned(), Some(client.clone()));
+        assert!(check(&endpoint).unwrap().await);
+        let check = http_probe("/missing".to_owned(), Some(client));
+        assert!(!check(&endpoint).unwrap().await);
+        // Probes are only sent with the credentials of the sink's client.
+        let check = http_probe("/health".to_owned(), None);
+        assert!(!check(&endpoint).unwrap().await);
+    }
+
+    #[tokio::test]
+    async fn probes_fail_once_the_interval_is_over() {
+        pause();
+
+        let counters = Arc::new(HealthCounters::new());
+        let probing = tokio::spawn(probe(
+            |_: &str| Some(futures::future::pending().boxed()),
+            "hanging".to_owned(),
+            Duration::from_secs(1),
+            Arc::clone(&counters),
+        ));
+
+        // The first probe starts after 1s, and is given up on 1s later.
+        for _ in 0..2 {
+            advance(Duration::from_secs(1)).await;
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(counters.consecutive_unhealthy(), 1);
+        probing.abort();
+    }
 
     #[test]
     fn test_health_counters() {
@@ -0,0 +1,298 @@
+//! Reports the number of requests in flight through a service, and waits for them to complete on
+//! shutdown.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use pin_project::pin_project;
+use tokio::{sync::Notify, time::error::Elapsed};
+use tower::{Layer, Service};
+
+use super::SinkServiceError;
+use crate::internal_events::{OpenGauge, OpenToken, RequestsInFlight};
+
+/// Reports the requests in flight through the wrapped service.
+#[derive(Clone, Debug, Default)]
+pub struct InFlightLayer {
+    shutdown: Option<ShutdownHandle>,
+}
+
+impl InFlightLayer {
+    /// Creates a new in flight layer, whose services are shut down through `shutdown`, if any.
+    pub const fn new(shutdown: Option<ShutdownHandle>) -> Self {
+        Self { shutdown }
+    }
+}
+
+impl<S> Layer<S> for InFlightLayer {
+    type Service = InFlight<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlight {
+            inner,
+            open: self
+                .shutdown
+                .as_ref()
+                .map_or_else(OpenGauge::new, |shutdown| shutdown.state.open.clone()),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+/// A service which counts each request as in flight from the time it is called until its
+/// response, or error, is returned.
+///
+/// Once its [`ShutdownHandle`] is drained, the service fails with
+/// [`SinkServiceError::ShuttingDown`] instead of admitting new requests. Clones of the service
+/// share the same count.
+#[derive(Clone)]
+pub struct InFlight<S> {
+    inner: S,
+    open: OpenGauge,
+    shutdown: Option<ShutdownHandle>,
+}
+
+impl<S, Req> Service<Req> for InFlight<S>
+where
+    S: Service<Req>,
+    S::Error: Into<crate::Error>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = InFlightFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self
+            .shutdown
+            .as_ref()
+            .map_or(false, ShutdownHandle::is_draining)
+        {
+            return Poll::Ready(Err(SinkServiceError::ShuttingDown.into()));
+        }
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let guard = InFlightGuard {
+            token: Some(self.open.clone().open(emit_in_flight as fn(usize))),
+            shutdown: self.shutdown.clone(),
+        };
+        InFlightFuture {
+            inner: self.inner.call(req),
+            _guard: guard,
+        }
+    }
+}
+
+/// Future for [`InFlight`], which counts the request as in flight until it completes.
+#[pin_project]
+pub struct InFlightFuture<F> {
+    #[pin]
+    inner: F,
+    _guard: InFlightGuard,
+}
+
+impl<F, T, E> Future for InFlightFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<crate::Error>,
+{
+    type Output = Result<T, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(ready!(self.project().inner.poll(cx)).map_err(Into::into))
+    }
+}
+
+/// Counts a request as in flight until its response is returned or it is cancelled.
+struct InFlightGuard {
+    token: Option<OpenToken<fn(usize)>>,
+    shutdown: Option<ShutdownHandle>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        // The request must no longer be counted once draining is notified of its completion.
+        drop(self.token.take());
+        if let Some(shutdown) = self.shutdown.as_ref() {
+            shutdown.completed();
+        }
+    }
+}
+
+/// Shuts down every service built from the request settings it was taken from, letting the
+/// requests they have in flight complete.
+///
+/// Clones of the handle shut down the same services.
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+    state: Arc<ShutdownState>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    draining: AtomicBool,
+    open: OpenGauge,
+    drained: Notify,
+}
+
+impl ShutdownHandle {
+    /// Whether the services were asked to stop admitting new requests.
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::Acquire)
+    }
+
+    /// Stops the services from admitting new requests, and waits up to `timeout` for the requests
+    /// in flight to complete, including those waiting to be retried.
+    ///
+    /// Fails if requests are still in flight once `timeout` elapses.
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Elapsed> {
+        self.state.draining.store(true, Ordering::Release);
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Waiters are notified of the requests completing after they are created, so the
+                // last request can't complete unnoticed between the check and the wait.
+                let drained = self.state.drained.notified();
+                if !self.state.open.any_open() {
+                    return;
+                }
+                drained.await;
+            }
+        })
+        .await
+    }
+
+    fn completed(&self) {
+        if self.is_draining() && !self.state.open.any_open() {
+            self.state.drained.notify_waiters();
+        }
+    }
+}
+
+// Services built from the same settings share the same handle.
+impl PartialEq for ShutdownHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+// A derived impl would needlessly require `OpenGauge: Debug`.
+impl fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownHandle")
+            .field("draining", &self.is_draining())
+            .field("in_flight", &self.state.open.any_open())
+            .finish()
+    }
+}
+
+fn emit_in_flight(count: usize) {
+    emit!(RequestsInFlight { count });
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
+    use tower_test::{assert_request_eq, mock};
+    use vector_lib::event::MetricValue;
+
+    use super::*;
+    use crate::metrics::{self, Controller};
+
+    fn in_flight() -> Option<f64> {
+        Controller::get()
+            .expect("There must be a controller")
+            .capture_metrics()
+            .into_iter()
+            .find(|metric| metric.name() == "in_flight_requests")
+            .map(|metric| match metric.value() {
+                MetricValue::Gauge { value } => *value,
+                value => panic!("unexpected metric value {:?}", value),
+            })
+    }
+
+    #[tokio::test]
+    async fn counts_requests_until_they_complete() {
+        metrics::init_test();
+
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(InFlightLayer::default());
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut first = task::spawn(svc.call("first"));
+        assert_ready_ok!(svc.poll_ready());
+        let mut second = task::spawn(svc.call("second"));
+        assert_eq!(in_flight(), Some(2.0));
+
+        assert_request_eq!(handle, "first").send_response(());
+        assert_ready_ok!(first.poll());
+        assert_eq!(in_flight(), Some(1.0));
+
+        assert_request_eq!(handle, "second").send_error("unavailable");
+        assert!(second.poll().is_ready());
+        assert_eq!(in_flight(), Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_requests_in_flight() {
+        let shutdown = ShutdownHandle::default();
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(InFlightLayer::new(Some(shutdown.clone())));
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut request = task::spawn(svc.call("request"));
+
+        let mut drain = task::spawn(shutdown.drain(Duration::from_secs(10)));
+        assert_pending!(drain.poll());
+        let error = assert_ready_err!(svc.poll_ready());
+        assert!(matches!(
+            error.downcast_ref::<SinkServiceError>(),
+            Some(SinkServiceError::ShuttingDown)
+        ));
+
+        assert_request_eq!(handle, "request").send_response(());
+        assert_ready_ok!(request.poll());
+        assert!(drain.is_woken());
+        assert_ready_ok!(drain.poll());
+    }
+
+    #[tokio::test]
+    async fn drain_gives_up_after_its_timeout() {
+        tokio::time::pause();
+
+        let shutdown = ShutdownHandle::default();
+        let (mut svc, _handle) =
+            mock::spawn_layer::<_, (), _>(InFlightLayer::new(Some(shutdown.clone())));
+
+        assert_ready_ok!(svc.poll_ready());
+        let _request = svc.call("request");
+
+        let result = shutdown.drain(Duration::from_secs(10)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn drain_completes_once_requests_in_flight_are_cancelled() {
+        let shutdown = ShutdownHandle::default();
+        let (mut svc, _handle) =
+            mock::spawn_layer::<_, (), _>(InFlightLayer::new(Some(shutdown.clone())));
+
+        assert_ready_ok!(svc.poll_ready());
+        let request = svc.call("request");
+
+        let mut drain = task::spawn(shutdown.drain(Duration::from_secs(10)));
+        assert_pending!(drain.poll());
+
+        drop(request);
+        assert!(drain.is_woken());
+        assert_ready_ok!(drain.poll());
+    }
+}
@@ -0,0 +1,287 @@
+//! Limits the total encoded size of the requests in flight through a service.
+//!
+//! Requests are sized by the [`RequestSize`] service wrapping the request settings, and their size
+//! is made available to the [`InFlightBytes`] service within them through a task-local, so that the
+//! request type flowing through the stack stays unchanged. Requests which were not sized count as
+//! the default size the limit was configured with.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+use pin_project::pin_project;
+use tower::{Layer, Service};
+use vector_lib::request_metadata::MetaDescriptive;
+
+tokio::task_local! {
+    static REQUEST_SIZE: usize;
+}
+
+/// Makes the encoded size of each request passing through the wrapped service the size counted
+/// by [`InFlightBytes`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestSizeLayer;
+
+impl<S> Layer<S> for RequestSizeLayer {
+    type Service = RequestSize<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestSize { inner }
+    }
+}
+
+/// A service which makes the encoded size of the request, as reported by its metadata, the size
+/// [`InFlightBytes`] counts it as.
+///
+/// This is meant to wrap the tower request settings of a sink.
+#[derive(Clone, Debug)]
+pub struct RequestSize<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for RequestSize<S>
+where
+    S: Service<Req>,
+    Req: MetaDescriptive,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let size = req.get_metadata().request_encoded_size();
+        let inner = &mut self.inner;
+        REQUEST_SIZE.sync_scope(size, || inner.call(req))
+    }
+}
+
+/// Limits the total size of the requests in flight through the wrapped service.
+#[derive(Clone, Copy, Debug)]
+pub struct InFlightBytesLayer {
+    max_bytes: usize,
+    default_size: usize,
+}
+
+impl InFlightBytesLayer {
+    /// Creates a new in flight bytes layer, which counts requests of unknown size as
+    /// `default_size` bytes.
+    pub const fn new(max_bytes: usize, default_size: usize) -> Self {
+        Self {
+            max_bytes,
+            default_size,
+        }
+    }
+}
+
+impl<S> Layer<S> for InFlightBytesLayer {
+    type Service = InFlightBytes<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlightBytes {
+            inner,
+            limit: Arc::new(ByteLimit {
+                max_bytes: self.max_bytes,
+                state: Mutex::default(),
+            }),
+            default_size: self.default_size,
+        }
+    }
+}
+
+/// A service which stops admitting requests while the requests in flight through it add up to
+/// `max_bytes` or more.
+///
+/// The size of a request is only known once it is called, so a request is admitted as long as the
+/// limit is not reached yet, and can take the requests in flight beyond it. This way, requests
+/// larger than the limit are still sent, once on their own. Clones of the service share the same
+/// limit.
+#[derive(Clone)]
+pub struct InFlightBytes<S> {
+    inner: S,
+    limit: Arc<ByteLimit>,
+    default_size: usize,
+}
+
+impl<S> InFlightBytes<S> {
+    /// The total size of the requests in flight.
+    pub fn in_flight_bytes(&self) -> usize {
+        self.limit.state().in_flight
+    }
+}
+
+struct ByteLimit {
+    max_bytes: usize,
+    state: Mutex<LimitState>,
+}
+
+#[derive(Default)]
+struct LimitState {
+    in_flight: usize,
+    waiters: Vec<Waker>,
+}
+
+impl ByteLimit {
+    fn state(&self) -> MutexGuard<'_, LimitState> {
+        self.state.lock().expect("in flight bytes state poisoned")
+    }
+}
+
+impl<S, Req> Service<Req> for InFlightBytes<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = InFlightBytesFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        {
+            let mut state = self.limit.state();
+            if state.in_flight >= self.limit.max_bytes {
+                if !state
+                    .waiters
+                    .iter()
+                    .any(|waker| waker.will_wake(cx.waker()))
+                {
+                    state.waiters.push(cx.waker().clone());
+                }
+                return Poll::Pending;
+            }
+        }
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let size = REQUEST_SIZE
+            .try_with(|size| *size)
+            .unwrap_or(self.default_size);
+        self.limit.state().in_flight += size;
+        let guard = InFlightGuard {
+            limit: Arc::clone(&self.limit),
+            size,
+        };
+
+        InFlightBytesFuture {
+            inner: self.inner.call(req),
+            _guard: guard,
+        }
+    }
+}
+
+// A derived impl would needlessly require `S: Debug`.
+impl<S> fmt::Debug for InFlightBytes<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InFlightBytes")
+            .field("max_bytes", &self.limit.max_bytes)
+            .field("in_flight_bytes", &self.in_flight_bytes())
+            .field("default_size", &self.default_size)
+            .finish()
+    }
+}
+
+/// Future for [`InFlightBytes`], which counts the bytes of the request as in flight until it
+/// completes.
+#[pin_project]
+pub struct InFlightBytesFuture<F> {
+    #[pin]
+    inner: F,
+    _guard: InFlightGuard,
+}
+
+impl<F: Future> Future for InFlightBytesFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+/// Releases the bytes of a request once its response is returned or it is cancelled.
+struct InFlightGuard {
+    limit: Arc<ByteLimit>,
+    size: usize,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self.limit.state();
+            state.in_flight -= self.size;
+            std::mem::take(&mut state.waiters)
+        };
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready_ok, task};
+    use tower_test::mock;
+    use vector_lib::request_metadata::{GroupedCountByteSize, RequestMetadata};
+
+    use super::*;
+
+    struct Batch(RequestMetadata);
+
+    impl Batch {
+        fn new(size: usize) -> Self {
+            Self(RequestMetadata::new(
+                1,
+                size,
+                size,
+                size,
+                GroupedCountByteSize::default(),
+            ))
+        }
+    }
+
+    impl MetaDescriptive for Batch {
+        fn get_metadata(&self) -> &RequestMetadata {
+            &self.0
+        }
+
+        fn metadata_mut(&mut self) -> &mut RequestMetadata {
+            &mut self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn admission_stops_once_the_limit_is_reached() {
+        let (mut svc, mut handle) = mock::spawn_with::<Batch, (), _, _>(|mock| {
+            RequestSizeLayer.layer(InFlightBytesLayer::new(100, 10).layer(mock))
+        });
+
+        assert_ready_ok!(svc.poll_ready());
+        let mut small = task::spawn(svc.call(Batch::new(40)));
+        assert_ready_ok!(svc.poll_ready());
+        let _large = task::spawn(svc.call(Batch::new(70)));
+        assert_pending!(svc.poll_ready());
+
+        // Completing the small request brings the bytes in flight back below the limit.
+        let (_, response) = handle.next_request().await.unwrap();
+        response.send_response(());
+        assert_ready_ok!(small.poll());
+        assert!(svc.is_woken());
+        assert_ready_ok!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn unsized_requests_count_as_the_default_size() {
+        let (mut svc, _handle) = mock::spawn_layer::<&str, (), _>(InFlightBytesLayer::new(100, 50));
+
+        let mut in_flight = Vec::new();
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            in_flight.push(svc.call("request"));
+        }
+        assert_pending!(svc.poll_ready());
+    }
+}
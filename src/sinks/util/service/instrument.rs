@@ -0,0 +1,166 @@
+//! Structured tracing of individual request attempts.
+//!
+//! [`Tracing`] sits above the retry layer and tracks the attempts of each request in a task-local,
+//! which the retry policy updates whenever it schedules a retry. [`AttemptSpan`] sits below the
+//! retry layer and opens an `attempt` span for every call to the inner service, carrying the
+//! attempt number along with the backoff delay and kind of failure that led to it.
+//!
+//! All of this is only active when the `tracing-instrumentation` feature is enabled. Otherwise,
+//! both services pass requests straight through to the inner service.
+
+use std::task::{Context, Poll};
+#[cfg(feature = "tracing-instrumentation")]
+use std::{cell::RefCell, time::Duration};
+
+#[cfg(feature = "tracing-instrumentation")]
+use tokio::task::futures::TaskLocalFuture;
+use tower::{Layer, Service};
+#[cfg(feature = "tracing-instrumentation")]
+use tracing::{instrument::Instrumented, Instrument, Span};
+
+#[cfg(feature = "tracing-instrumentation")]
+tokio::task_local! {
+    static ATTEMPT: RefCell<AttemptState>;
+}
+
+/// The attempts made so far for a single request.
+#[cfg(feature = "tracing-instrumentation")]
+pub struct AttemptState {
+    parent: Span,
+    attempt_number: usize,
+    delay: Option<Duration>,
+    error_kind: Option<&'static str>,
+}
+
+#[cfg(feature = "tracing-instrumentation")]
+impl AttemptState {
+    fn new(parent: Span) -> RefCell<Self> {
+        RefCell::new(Self {
+            parent,
+            attempt_number: 1,
+            delay: None,
+            error_kind: None,
+        })
+    }
+
+    fn span(&self) -> Span {
+        info_span!(
+            parent: &self.parent,
+            "attempt",
+            attempt_number = self.attempt_number,
+            delay_ms = self.delay.map(|delay| delay.as_millis() as u64),
+            error_kind = self.error_kind,
+        )
+    }
+}
+
+/// Records that the request currently being processed is about to be retried.
+#[cfg(feature = "tracing-instrumentation")]
+pub(crate) fn record_retry(delay: Duration, error_kind: &'static str) {
+    _ = ATTEMPT.try_with(|state| {
+        let mut state = state.borrow_mut();
+        state.attempt_number += 1;
+        state.delay = Some(delay);
+        state.error_kind = Some(error_kind);
+    });
+}
+
+/// Records that the request currently being processed is about to be retried.
+#[cfg(not(feature = "tracing-instrumentation"))]
+pub(crate) fn record_retry(_delay: std::time::Duration, _error_kind: &'static str) {}
+
+/// Tracks the attempts made for each request passing through the wrapped service.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingLayer;
+
+impl<S> Layer<S> for TracingLayer {
+    type Service = Tracing<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Tracing { inner }
+    }
+}
+
+/// A service which tracks the attempts made for each request, to be used by [`AttemptSpan`].
+#[derive(Clone, Debug)]
+pub struct Tracing<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for Tracing<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    #[cfg(feature = "tracing-instrumentation")]
+    type Future = TaskLocalFuture<RefCell<AttemptState>, S::Future>;
+    #[cfg(not(feature = "tracing-instrumentation"))]
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[cfg(feature = "tracing-instrumentation")]
+    fn call(&mut self, req: Req) -> Self::Future {
+        // The first attempt is made right away, so it needs to see the state too.
+        let parent = Span::current();
+        let inner = &mut self.inner;
+        let future = ATTEMPT.sync_scope(AttemptState::new(parent.clone()), || inner.call(req));
+        ATTEMPT.scope(AttemptState::new(parent), future)
+    }
+
+    #[cfg(not(feature = "tracing-instrumentation"))]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+/// Opens a span for every attempt made through the wrapped service.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AttemptSpanLayer;
+
+impl<S> Layer<S> for AttemptSpanLayer {
+    type Service = AttemptSpan<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AttemptSpan { inner }
+    }
+}
+
+/// A service which instruments every attempt with an `attempt` span.
+#[derive(Clone, Debug)]
+pub struct AttemptSpan<S> {
+    inner: S,
+}
+
+impl<S, Req> Service<Req> for AttemptSpan<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    #[cfg(feature = "tracing-instrumentation")]
+    type Future = Instrumented<S::Future>;
+    #[cfg(not(feature = "tracing-instrumentation"))]
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[cfg(feature = "tracing-instrumentation")]
+    fn call(&mut self, req: Req) -> Self::Future {
+        let span = ATTEMPT
+            .try_with(|state| state.borrow().span())
+            .unwrap_or_else(|_| Span::none());
+        let _enter = span.enter();
+        self.inner.call(req).in_current_span()
+    }
+
+    #[cfg(not(feature = "tracing-instrumentation"))]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
@@ -0,0 +1,232 @@
+//! Request settings which can be changed at runtime, without rebuilding the sink.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    sync::watch,
+    time::{sleep_until, Instant, Sleep},
+};
+use tower::{limit::rate::Rate, Layer, Service};
+
+use super::{TowerRequestConfig, TowerRequestSettings};
+
+/// Request settings resolved from a shared configuration whenever it changes, so that changes
+/// made to the configuration are picked up by the services built from them.
+#[derive(Clone, Debug)]
+pub struct LiveTowerRequestSettings {
+    config: watch::Receiver<TowerRequestConfig>,
+    defaults: TowerRequestConfig,
+}
+
+impl LiveTowerRequestSettings {
+    pub(super) const fn new(
+        config: watch::Receiver<TowerRequestConfig>,
+        defaults: TowerRequestConfig,
+    ) -> Self {
+        Self { config, defaults }
+    }
+
+    /// Resolves the settings from the current configuration.
+    pub fn current(&self) -> TowerRequestSettings {
+        let config = *self.config.borrow();
+        config.or(self.defaults).into_settings()
+    }
+
+    /// Rate limits requests according to the current configuration.
+    pub fn rate_limit_layer(&self) -> LiveRateLimitLayer {
+        LiveRateLimitLayer {
+            settings: self.clone(),
+        }
+    }
+
+    /// Resolves the rate limit if the configuration changed since it was last resolved.
+    fn changed_rate_limit(&mut self) -> Option<Option<Rate>> {
+        // A dropped sender leaves the configuration as it last was.
+        if !self.config.has_changed().unwrap_or(false) {
+            return None;
+        }
+        self.config.borrow_and_update();
+        Some(self.rate_limit())
+    }
+
+    fn rate_limit(&self) -> Option<Rate> {
+        let settings = self.current();
+        settings
+            .rate_limit_num
+            .map(|num| Rate::new(num, settings.rate_limit_duration))
+    }
+}
+
+/// Rate limits requests through the wrapped service according to live settings.
+#[derive(Clone, Debug)]
+pub struct LiveRateLimitLayer {
+    settings: LiveTowerRequestSettings,
+}
+
+impl<S> Layer<S> for LiveRateLimitLayer {
+    type Service = LiveRateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let mut settings = self.settings.clone();
+        settings.config.borrow_and_update();
+        let now = Instant::now();
+        LiveRateLimit {
+            inner,
+            rate: settings.rate_limit(),
+            settings,
+            window: Window {
+                until: now,
+                sent: 0,
+            },
+            sleep: Box::pin(sleep_until(now)),
+        }
+    }
+}
+
+/// The time window requests are currently being counted in.
+#[derive(Debug)]
+struct Window {
+    until: Instant,
+    sent: u64,
+}
+
+/// A service which is rate limited according to the settings current when it is polled for
+/// readiness.
+///
+/// The rate limit is only resolved again when the configuration changes. Requests already sent
+/// in the current time window count against the new rate limit, rather than it restarting from a
+/// full window.
+#[derive(Debug)]
+pub struct LiveRateLimit<S> {
+    inner: S,
+    rate: Option<Rate>,
+    settings: LiveTowerRequestSettings,
+    window: Window,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl<S> LiveRateLimit<S> {
+    fn update_rate(&mut self, rate: Option<Rate>) {
+        if let Some(rate) = rate {
+            // A shorter time window cuts the current one short.
+            self.window.until = self.window.until.min(Instant::now() + rate.per());
+        }
+        self.rate = rate;
+    }
+}
+
+impl<S, Req> Service<Req> for LiveRateLimit<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Some(rate) = self.settings.changed_rate_limit() {
+            self.update_rate(rate);
+        }
+
+        if let Some(rate) = self.rate {
+            if self.window.sent >= rate.num() && Instant::now() < self.window.until {
+                self.sleep.as_mut().reset(self.window.until);
+                if self.sleep.as_mut().poll(cx).is_pending() {
+                    trace!("Rate limit exceeded; sleeping.");
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        if let Some(rate) = self.rate {
+            let now = Instant::now();
+            if now >= self.window.until {
+                self.window = Window {
+                    until: now + rate.per(),
+                    sent: 0,
+                };
+            }
+            self.window.sent += 1;
+        }
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+    use crate::sinks::util::service::RateLimitNum;
+
+    #[tokio::test]
+    async fn rate_limit_follows_config_changes() {
+        pause();
+
+        let (config, receiver) = watch::channel(TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(1)),
+            ..Default::default()
+        });
+        let settings = TowerRequestSettings::live_reload(receiver, Default::default());
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(settings.rate_limit_layer());
+
+        assert_ready_ok!(svc.poll_ready());
+        let _response = svc.call("first");
+        assert_request_eq!(handle, "first").send_response(());
+        assert_pending!(svc.poll_ready());
+
+        // The new rate limit applies to the next request.
+        config.send_modify(|config| config.rate_limit_num = Some(RateLimitNum::Limited(2)));
+        assert_ready_ok!(svc.poll_ready());
+        let _response = svc.call("second");
+        assert_request_eq!(handle, "second").send_response(());
+
+        config.send_modify(|config| config.rate_limit_num = Some(RateLimitNum::Unlimited));
+        for _ in 0..5 {
+            assert_ready_ok!(svc.poll_ready());
+            let _response = svc.call("unlimited");
+            assert_request_eq!(handle, "unlimited").send_response(());
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_changes_keep_the_requests_sent_in_the_window() {
+        pause();
+
+        let (config, receiver) = watch::channel(TowerRequestConfig {
+            rate_limit_num: Some(RateLimitNum::Limited(2)),
+            ..Default::default()
+        });
+        let settings = TowerRequestSettings::live_reload(receiver, Default::default());
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(settings.rate_limit_layer());
+
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            let _response = svc.call("sent");
+            assert_request_eq!(handle, "sent").send_response(());
+        }
+        assert_pending!(svc.poll_ready());
+
+        // Both requests already sent count against the raised limit.
+        config.send_modify(|config| config.rate_limit_num = Some(RateLimitNum::Limited(3)));
+        assert_ready_ok!(svc.poll_ready());
+        let _response = svc.call("third");
+        assert_request_eq!(handle, "third").send_response(());
+        assert_pending!(svc.poll_ready());
+
+        advance(Duration::from_secs(1)).await;
+        assert_ready_ok!(svc.poll_ready());
+    }
+}
@@ -0,0 +1,349 @@
+//! Sheds load by dropping requests rather than applying backpressure.
+//!
+//! This is meant for sinks sending high volume, low value data, such as verbose telemetry, where
+//! losing some of it is preferable to slowing down the rest of the pipeline. A dropped request is
+//! answered with a synthetic response, as if it was delivered, so the events it holds are
+//! acknowledged and lost for good. As such, shedding is never enabled by default, and is only
+//! enabled once `allow_data_loss` is set on top of the policy.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::future::{self, Either, Ready};
+use snafu::Snafu;
+use tokio::time::{sleep, Sleep};
+use tower::{Layer, Service};
+use vector_lib::configurable::configurable_component;
+
+use crate::internal_events::RequestShed;
+
+/// Configuration for dropping requests to shed load.
+///
+/// Dropped requests are reported as delivered, and the events they hold are lost. This is only
+/// meant for data which is acceptable to lose, and is not recommended otherwise.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    /// Acknowledges that requests dropped to shed load are lost.
+    ///
+    /// This must be set to `true` for requests to be dropped, so that data isn't lost by mistake.
+    #[serde(default)]
+    pub allow_data_loss: bool,
+
+    #[configurable(derived)]
+    pub policy: LoadSheddingPolicy,
+}
+
+/// Which requests are dropped to shed load.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[configurable(metadata(docs::enum_tag_description = "Which requests are dropped."))]
+pub enum LoadSheddingPolicy {
+    /// Drops a random fraction of the requests, whether or not the sink is keeping up.
+    Sample {
+        /// The fraction of requests to drop, between `0` and `1`.
+        #[configurable(validation(range(min = 0.0, max = 1.0)))]
+        drop_rate: f64,
+    },
+
+    /// Drops requests while the sink has not been able to send any for `max_pending_ms`, rather
+    /// than waiting any longer.
+    Pending {
+        /// The time to wait for the sink to be able to send a request before dropping it.
+        #[configurable(metadata(docs::type_unit = "milliseconds"))]
+        max_pending_ms: u64,
+    },
+}
+
+impl LoadSheddingPolicy {
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Sample { .. } => "sample",
+            Self::Pending { .. } => "pending",
+        }
+    }
+}
+
+/// A load shedding configuration which can't be applied.
+#[derive(Debug, PartialEq, Snafu)]
+pub enum LoadSheddingError {
+    #[snafu(display(
+        "`load_shedding.allow_data_loss` must be set to `true`, as requests dropped to shed load are lost"
+    ))]
+    DataLossNotAllowed,
+    #[snafu(display("`load_shedding.policy.drop_rate` must be between 0 and 1"))]
+    InvalidDropRate,
+}
+
+impl LoadSheddingConfig {
+    /// Builds the layer shedding load according to this configuration, answering each dropped
+    /// request with the response returned by `shed_response`.
+    ///
+    /// The layer should wrap the request settings, so that dropped requests don't take up
+    /// concurrency or retries, and so that requests are dropped while the settings hold them back.
+    pub fn layer<Res, F>(&self, shed_response: F) -> Result<LoadShedLayer<Res>, LoadSheddingError>
+    where
+        F: Fn() -> Res + Send + Sync + 'static,
+    {
+        if !self.allow_data_loss {
+            return Err(LoadSheddingError::DataLossNotAllowed);
+        }
+        if let LoadSheddingPolicy::Sample { drop_rate } = self.policy {
+            if !(0.0..=1.0).contains(&drop_rate) {
+                return Err(LoadSheddingError::InvalidDropRate);
+            }
+        }
+        Ok(LoadShedLayer {
+            policy: self.policy,
+            shed_response: Arc::new(shed_response),
+        })
+    }
+}
+
+/// Drops requests to the wrapped service according to a [`LoadSheddingPolicy`].
+pub struct LoadShedLayer<Res> {
+    policy: LoadSheddingPolicy,
+    shed_response: Arc<dyn Fn() -> Res + Send + Sync + 'static>,
+}
+
+// Derived impls would needlessly require `Res: Clone` and `Res: Debug`.
+impl<Res> Clone for LoadShedLayer<Res> {
+    fn clone(&self) -> Self {
+        Self {
+            policy: self.policy,
+            shed_response: Arc::clone(&self.shed_response),
+        }
+    }
+}
+
+impl<Res> fmt::Debug for LoadShedLayer<Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShedLayer")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, Res> Layer<S> for LoadShedLayer<Res> {
+    type Service = LoadShed<S, Res>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadShed {
+            inner,
+            policy: self.policy,
+            shed_response: Arc::clone(&self.shed_response),
+            pending: None,
+            shed_next: None,
+        }
+    }
+}
+
+/// A service which answers the requests it drops with a synthetic response, without sending them
+/// to the wrapped service.
+pub struct LoadShed<S, Res> {
+    inner: S,
+    policy: LoadSheddingPolicy,
+    shed_response: Arc<dyn Fn() -> Res + Send + Sync + 'static>,
+    /// Expires once the wrapped service has not been ready for `max_pending_ms`.
+    pending: Option<Pin<Box<Sleep>>>,
+    /// Whether the next request is dropped, once decided while polling the service ready. It is
+    /// only decided once per request, however many times the wrapped service is polled.
+    shed_next: Option<bool>,
+}
+
+impl<S, Res, Req> Service<Req> for LoadShed<S, Res>
+where
+    S: Service<Req, Response = Res>,
+{
+    type Response = Res;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Res, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.shed_next == Some(true) {
+            return Poll::Ready(Ok(()));
+        }
+
+        match self.policy {
+            LoadSheddingPolicy::Sample { drop_rate } => {
+                let shed = *self
+                    .shed_next
+                    .get_or_insert_with(|| rand::random::<f64>() < drop_rate);
+                if shed {
+                    return Poll::Ready(Ok(()));
+                }
+                self.inner.poll_ready(cx)
+            }
+            LoadSheddingPolicy::Pending { max_pending_ms } => match self.inner.poll_ready(cx) {
+                Poll::Ready(result) => {
+                    self.pending = None;
+                    Poll::Ready(result)
+                }
+                Poll::Pending => {
+                    // Once expired, the timer is kept, so that every request is dropped until the
+                    // wrapped service is ready again.
+                    let pending = self.pending.get_or_insert_with(|| {
+                        Box::pin(sleep(Duration::from_millis(max_pending_ms)))
+                    });
+                    if pending.as_mut().poll(cx).is_ready() {
+                        self.shed_next = Some(true);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Poll::Pending
+                }
+            },
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        if self.shed_next.take() == Some(true) {
+            emit!(RequestShed {
+                policy: self.policy.name()
+            });
+            return Either::Left(future::ok((self.shed_response)()));
+        }
+        Either::Right(self.inner.call(req))
+    }
+}
+
+// Derived impls would needlessly require `Res: Clone` and `Res: Debug`. Clones start out without
+// the readiness of the service they are cloned from, as tower services do.
+impl<S: Clone, Res> Clone for LoadShed<S, Res> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: self.policy,
+            shed_response: Arc::clone(&self.shed_response),
+            pending: None,
+            shed_next: None,
+        }
+    }
+}
+
+impl<S: fmt::Debug, Res> fmt::Debug for LoadShed<S, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShed")
+            .field("inner", &self.inner)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+
+    fn layer(policy: LoadSheddingPolicy) -> LoadShedLayer<&'static str> {
+        LoadSheddingConfig {
+            allow_data_loss: true,
+            policy,
+        }
+        .layer(|| "shed")
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sample_policy_drops_the_configured_fraction() {
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, &str, _>(layer(LoadSheddingPolicy::Sample { drop_rate: 1.0 }));
+        for _ in 0..3 {
+            assert_ready_ok!(svc.poll_ready());
+            assert_eq!(svc.call("request").await.unwrap(), "shed");
+        }
+        assert!(handle.poll_request().is_pending());
+
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, &str, _>(layer(LoadSheddingPolicy::Sample { drop_rate: 0.0 }));
+        assert_ready_ok!(svc.poll_ready());
+        let response = svc.call("request");
+        assert_request_eq!(handle, "request").send_response("sent");
+        assert_eq!(response.await.unwrap(), "sent");
+    }
+
+    #[tokio::test]
+    async fn sample_policy_decides_once_per_request() {
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, &str, _>(layer(LoadSheddingPolicy::Sample { drop_rate: 0.5 }));
+        handle.allow(0);
+
+        // A request kept while the service is not ready stays kept, rather than being sampled
+        // again each time the service is polled.
+        let mut kept = 0;
+        for _ in 0..50 {
+            if svc.poll_ready().is_pending() {
+                kept += 1;
+                for _ in 0..50 {
+                    assert_pending!(svc.poll_ready());
+                }
+                break;
+            }
+            assert_eq!(svc.call("request").await.unwrap(), "shed");
+        }
+        assert_eq!(kept, 1);
+
+        handle.allow(1);
+        assert_ready_ok!(svc.poll_ready());
+        let response = svc.call("request");
+        assert_request_eq!(handle, "request").send_response("sent");
+        assert_eq!(response.await.unwrap(), "sent");
+    }
+
+    #[tokio::test]
+    async fn pending_policy_drops_while_the_service_is_not_ready() {
+        pause();
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, &str, _>(layer(LoadSheddingPolicy::Pending {
+                max_pending_ms: 100,
+            }));
+
+        handle.allow(0);
+        assert_pending!(svc.poll_ready());
+        advance(Duration::from_millis(100)).await;
+        assert_ready_ok!(svc.poll_ready());
+        assert_eq!(svc.call("first").await.unwrap(), "shed");
+
+        // Requests keep being dropped until the service is ready again.
+        assert_ready_ok!(svc.poll_ready());
+        assert_eq!(svc.call("second").await.unwrap(), "shed");
+
+        handle.allow(1);
+        assert_ready_ok!(svc.poll_ready());
+        let response = svc.call("third");
+        assert_request_eq!(handle, "third").send_response("sent");
+        assert_eq!(response.await.unwrap(), "sent");
+    }
+
+    #[test]
+    fn shedding_requires_allowing_data_loss() {
+        let config: LoadSheddingConfig = toml::from_str(
+            r#"policy.type = "sample"
+            policy.drop_rate = 0.5"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.layer(|| ()).unwrap_err(),
+            LoadSheddingError::DataLossNotAllowed
+        );
+
+        let config = LoadSheddingConfig {
+            allow_data_loss: true,
+            policy: LoadSheddingPolicy::Sample { drop_rate: 1.5 },
+        };
+        assert_eq!(
+            config.layer(|| ()).unwrap_err(),
+            LoadSheddingError::InvalidDropRate
+        );
+    }
+}
@@ -1,47 +1,121 @@
 use std::{
     fmt,
+    future::Future,
+    pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
 
+use futures::{
+    future::{self, Either, ErrInto, Ready},
+    TryFutureExt,
+};
+use pin_project::pin_project;
 use tower::{Layer, Service};
 
-pub struct MapLayer<R1, R2> {
+pub struct MapLayer<R1, R2, M = KeepResponse> {
     f: Arc<dyn Fn(R1) -> R2 + Send + Sync + 'static>,
+    response: M,
 }
 
 impl<R1, R2> MapLayer<R1, R2> {
     pub(crate) fn new(f: Arc<dyn Fn(R1) -> R2 + Send + Sync + 'static>) -> Self {
-        Self { f }
+        Self {
+            f,
+            response: KeepResponse,
+        }
+    }
+
+    /// Also transforms each response of the service with `f` before returning it.
+    pub fn with_response_map<Res1, Res2, F>(self, f: F) -> MapLayer<R1, R2, ResponseMap<Res1, Res2>>
+    where
+        F: Fn(Res1) -> Res2 + Send + Sync + 'static,
+    {
+        MapLayer {
+            f: self.f,
+            response: ResponseMap { f: Arc::new(f) },
+        }
     }
 }
 
-impl<S, R1, R2> Layer<S> for MapLayer<R1, R2>
+impl<S, R1, R2, M> Layer<S> for MapLayer<R1, R2, M>
 where
     S: Service<R2>,
+    M: Clone,
 {
-    type Service = Map<S, R1, R2>;
+    type Service = Map<S, R1, R2, M>;
 
     fn layer(&self, inner: S) -> Self::Service {
         Map {
             f: Arc::clone(&self.f),
+            response: self.response.clone(),
             inner,
         }
     }
 }
 
-pub struct Map<S, R1, R2> {
+/// Transforms the responses of a [`Map`] service.
+pub trait MapResponse<Res>: Clone {
+    type Response;
+
+    fn map_response(&self, response: Res) -> Self::Response;
+}
+
+/// Leaves the responses of a [`Map`] service as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeepResponse;
+
+impl<Res> MapResponse<Res> for KeepResponse {
+    type Response = Res;
+
+    fn map_response(&self, response: Res) -> Self::Response {
+        response
+    }
+}
+
+/// Transforms the responses of a [`Map`] service with a function.
+pub struct ResponseMap<Res1, Res2> {
+    f: Arc<dyn Fn(Res1) -> Res2 + Send + Sync + 'static>,
+}
+
+impl<Res1, Res2> Clone for ResponseMap<Res1, Res2> {
+    fn clone(&self) -> Self {
+        Self {
+            f: Arc::clone(&self.f),
+        }
+    }
+}
+
+impl<Res1, Res2> fmt::Debug for ResponseMap<Res1, Res2> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseMap").finish_non_exhaustive()
+    }
+}
+
+impl<Res1, Res2> MapResponse<Res1> for ResponseMap<Res1, Res2> {
+    type Response = Res2;
+
+    fn map_response(&self, response: Res1) -> Self::Response {
+        (self.f)(response)
+    }
+}
+
+/// A service which transforms each request before sending it, and optionally each response
+/// before returning it.
+pub struct Map<S, R1, R2, M = KeepResponse> {
     f: Arc<dyn Fn(R1) -> R2 + Send + Sync + 'static>,
+    response: M,
     pub(crate) inner: S,
 }
 
-impl<S, R1, R2> Service<R1> for Map<S, R1, R2>
+impl<S, R1, R2, M> Service<R1> for Map<S, R1, R2, M>
 where
     S: Service<R2>,
+    M: MapResponse<S::Response>,
 {
-    type Response = S::Response;
+    type Response = M::Response;
     type Error = S::Error;
-    type Future = S::Future;
+    type Future = MapFuture<S::Future, M>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.inner.poll_ready(cx)
@@ -49,23 +123,28 @@ where
 
     fn call(&mut self, req: R1) -> Self::Future {
         let req = (self.f)(req);
-        self.inner.call(req)
+        MapFuture {
+            inner: self.inner.call(req),
+            response: self.response.clone(),
+        }
     }
 }
 
-impl<S, R1, R2> Clone for Map<S, R1, R2>
+impl<S, R1, R2, M> Clone for Map<S, R1, R2, M>
 where
     S: Clone,
+    M: Clone,
 {
     fn clone(&self) -> Self {
         Self {
             f: Arc::clone(&self.f),
+            response: self.response.clone(),
             inner: self.inner.clone(),
         }
     }
 }
 
-impl<S, R1, R2> fmt::Debug for Map<S, R1, R2>
+impl<S, R1, R2, M> fmt::Debug for Map<S, R1, R2, M>
 where
     S: fmt::Debug,
 {
@@ -73,3 +152,194 @@ where
         f.debug_struct("Map").field("inner", &self.inner).finish()
     }
 }
+
+/// Future for the `Map` service.
+#[pin_project]
+pub struct MapFuture<F, M> {
+    #[pin]
+    inner: F,
+    response: M,
+}
+
+impl<F, M, T, E> Future for MapFuture<F, M>
+where
+    F: Future<Output = Result<T, E>>,
+    M: MapResponse<T>,
+{
+    type Output = Result<M::Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.inner
+            .poll(cx)
+            .map_ok(|response| this.response.map_response(response))
+    }
+}
+
+pub struct TryMapLayer<R1, R2, E> {
+    f: Arc<dyn Fn(R1) -> Result<R2, E> + Send + Sync + 'static>,
+}
+
+impl<R1, R2, E> TryMapLayer<R1, R2, E> {
+    pub(crate) fn new(f: Arc<dyn Fn(R1) -> Result<R2, E> + Send + Sync + 'static>) -> Self {
+        Self { f }
+    }
+}
+
+impl<S, R1, R2, E> Layer<S> for TryMapLayer<R1, R2, E>
+where
+    S: Service<R2>,
+{
+    type Service = TryMap<S, R1, R2, E>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TryMap {
+            f: Arc::clone(&self.f),
+            inner,
+        }
+    }
+}
+
+/// A service which transforms each request with a fallible function before sending it.
+///
+/// Requests which fail to be transformed are not sent, and fail with the error of the function.
+pub struct TryMap<S, R1, R2, E> {
+    f: Arc<dyn Fn(R1) -> Result<R2, E> + Send + Sync + 'static>,
+    pub(crate) inner: S,
+}
+
+impl<S, R1, R2, E> Service<R1> for TryMap<S, R1, R2, E>
+where
+    S: Service<R2>,
+    S::Error: Into<crate::Error>,
+    E: Into<crate::Error>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future =
+        Either<Ready<Result<S::Response, crate::Error>>, ErrInto<S::Future, crate::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: R1) -> Self::Future {
+        match (self.f)(req) {
+            Ok(req) => Either::Right(self.inner.call(req).err_into()),
+            Err(error) => Either::Left(future::err(error.into())),
+        }
+    }
+}
+
+impl<S, R1, R2, E> Clone for TryMap<S, R1, R2, E>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            f: Arc::clone(&self.f),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, R1, R2, E> fmt::Debug for TryMap<S, R1, R2, E>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+pub struct MapErrLayer<E> {
+    f: Arc<dyn Fn(E) -> crate::Error + Send + Sync + 'static>,
+}
+
+impl<E> MapErrLayer<E> {
+    pub(crate) fn new(f: Arc<dyn Fn(E) -> crate::Error + Send + Sync + 'static>) -> Self {
+        Self { f }
+    }
+}
+
+impl<S, E> Layer<S> for MapErrLayer<E> {
+    type Service = MapErr<S, E>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MapErr {
+            f: Arc::clone(&self.f),
+            inner,
+        }
+    }
+}
+
+/// A service which transforms the errors of the wrapped service into a `crate::Error`.
+pub struct MapErr<S, E> {
+    f: Arc<dyn Fn(E) -> crate::Error + Send + Sync + 'static>,
+    pub(crate) inner: S,
+}
+
+impl<S, E, Req> Service<Req> for MapErr<S, E>
+where
+    S: Service<Req, Error = E>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = MapErrFuture<S::Future, E>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(|error| (self.f)(error))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        MapErrFuture {
+            inner: self.inner.call(req),
+            f: Arc::clone(&self.f),
+        }
+    }
+}
+
+impl<S, E> Clone for MapErr<S, E>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            f: Arc::clone(&self.f),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, E> fmt::Debug for MapErr<S, E>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapErr")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// Future for the `MapErr` service.
+#[pin_project]
+pub struct MapErrFuture<F, E> {
+    #[pin]
+    inner: F,
+    f: Arc<dyn Fn(E) -> crate::Error + Send + Sync + 'static>,
+}
+
+impl<F, T, E> Future for MapErrFuture<F, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.inner.poll(cx).map_err(|error| (this.f)(error))
+    }
+}
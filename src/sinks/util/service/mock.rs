@@ -0,0 +1,287 @@
+//! An in-memory service for testing the behaviour of the middleware stack.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+};
+
+use futures_util::future::BoxFuture;
+use snafu::Snafu;
+use tokio::time::{sleep, Duration};
+use tower::Service;
+
+/// An error for scripted responses to fail with, when the test has no use for a specific one.
+///
+/// Like any other error, it converts into a `crate::Error`, which it can be downcast back from.
+#[derive(Clone, Debug, Eq, PartialEq, Snafu)]
+#[snafu(display("{message}"))]
+pub struct TestError {
+    message: String,
+}
+
+impl TestError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// A service which records the requests it receives and answers them with scripted responses.
+///
+/// Clones share their state, so a test can keep one clone to inspect what the stack sent while
+/// another is wrapped in the middleware under test.
+pub struct MockService<Req, Res, E = std::io::Error> {
+    state: Arc<Mutex<MockState<Req, Res, E>>>,
+    latency: Option<Duration>,
+}
+
+struct MockState<Req, Res, E> {
+    requests: Vec<Req>,
+    responses: VecDeque<Result<Res, E>>,
+    /// Answers calls without a scripted response, which panic if there is none.
+    default_response: Option<Res>,
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+impl<Req, Res, E> MockService<Req, Res, E> {
+    /// Creates a service which answers every request with `default_response`, unless another
+    /// response has been scripted.
+    pub fn new(default_response: Res) -> Self {
+        Self::scripted(VecDeque::new(), Some(default_response))
+    }
+
+    /// Creates a service which answers the requests it receives with `responses`, in order.
+    ///
+    /// Receiving more requests than there are responses is a bug in the test, so it panics.
+    pub fn with_responses(responses: Vec<Result<Res, E>>) -> Self {
+        Self::scripted(responses.into(), None)
+    }
+
+    fn scripted(responses: VecDeque<Result<Res, E>>, default_response: Option<Res>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                requests: Vec::new(),
+                responses,
+                default_response,
+                in_flight: 0,
+                max_in_flight: 0,
+            })),
+            latency: None,
+        }
+    }
+
+    /// Delays every response by `latency`, so that concurrent requests overlap.
+    pub const fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Scripts the result of the next call which does not already have one scripted.
+    pub fn respond_with(&self, result: Result<Res, E>) -> &Self {
+        self.state().responses.push_back(result);
+        self
+    }
+
+    /// Scripts the next `times` calls to fail with the errors made by `error`.
+    pub fn fail_times(&self, times: usize, error: impl Fn() -> E) -> &Self {
+        self.state()
+            .responses
+            .extend((0..times).map(|_| Err(error())));
+        self
+    }
+
+    /// The requests received so far, in the order they were received.
+    pub fn requests(&self) -> Vec<Req>
+    where
+        Req: Clone,
+    {
+        self.state().requests.clone()
+    }
+
+    /// The number of calls made so far.
+    pub fn calls(&self) -> usize {
+        self.state().requests.len()
+    }
+
+    /// Asserts that the service was called exactly `attempts` times, counting retries.
+    #[track_caller]
+    pub fn assert_attempts(&self, attempts: usize) {
+        let calls = self.calls();
+        assert_eq!(
+            calls, attempts,
+            "expected {attempts} attempts, but {calls} requests were received"
+        );
+    }
+
+    /// The number of calls made with a request equal to one received earlier.
+    pub fn retries(&self) -> usize
+    where
+        Req: PartialEq,
+    {
+        let state = self.state();
+        state
+            .requests
+            .iter()
+            .enumerate()
+            .filter(|(i, request)| state.requests[..*i].contains(request))
+            .count()
+    }
+
+    /// The number of calls whose response has not been returned yet.
+    pub fn in_flight(&self) -> usize {
+        self.state().in_flight
+    }
+
+    /// The largest number of calls that were in flight at the same time.
+    pub fn max_in_flight(&self) -> usize {
+        self.state().max_in_flight
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState<Req, Res, E>> {
+        self.state.lock().expect("mock service state poisoned")
+    }
+}
+
+// Derived impls would needlessly require `Req`, `Res` and `E` to implement `Clone` and `Debug`.
+impl<Req, Res, E> Clone for MockService<Req, Res, E> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            latency: self.latency,
+        }
+    }
+}
+
+impl<Req, Res, E> fmt::Debug for MockService<Req, Res, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("MockService")
+            .field("calls", &state.requests.len())
+            .field("scripted_responses", &state.responses.len())
+            .field("in_flight", &state.in_flight)
+            .field("latency", &self.latency)
+            .finish()
+    }
+}
+
+impl<Req, Res, E> Service<Req> for MockService<Req, Res, E>
+where
+    Req: Send + 'static,
+    Res: Clone + Send + 'static,
+    E: Send + 'static,
+{
+    type Response = Res;
+    type Error = E;
+    type Future = BoxFuture<'static, Result<Res, E>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let result = {
+            let mut state = self.state();
+            state.requests.push(request);
+            let result = match (state.responses.pop_front(), &state.default_response) {
+                (Some(result), _) => result,
+                (None, Some(response)) => Ok(response.clone()),
+                (None, None) => panic!(
+                    "mock service received request number {} but has no response left for it",
+                    state.requests.len()
+                ),
+            };
+            state.in_flight += 1;
+            state.max_in_flight = state.max_in_flight.max(state.in_flight);
+            result
+        };
+
+        let guard = InFlightGuard {
+            state: Arc::clone(&self.state),
+        };
+        let latency = self.latency;
+        Box::pin(async move {
+            let _guard = guard;
+            if let Some(latency) = latency {
+                sleep(latency).await;
+            }
+            result
+        })
+    }
+}
+
+/// Marks a call as finished once its response is returned or it is cancelled.
+struct InFlightGuard<Req, Res, E> {
+    state: Arc<Mutex<MockState<Req, Res, E>>>,
+}
+
+impl<Req, Res, E> Drop for InFlightGuard<Req, Res, E> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.in_flight -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn error() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Other, "scripted")
+    }
+
+    #[tokio::test]
+    async fn mock_service_scripts_responses() {
+        let svc = MockService::<&str, u32>::new(0);
+        svc.respond_with(Ok(1)).fail_times(1, error);
+
+        assert_eq!(svc.clone().oneshot("first").await.unwrap(), 1);
+        assert!(svc.clone().oneshot("second").await.is_err());
+        assert_eq!(svc.clone().oneshot("second").await.unwrap(), 0);
+
+        assert_eq!(svc.requests(), vec!["first", "second", "second"]);
+        assert_eq!(svc.calls(), 3);
+        assert_eq!(svc.retries(), 1);
+    }
+
+    #[tokio::test]
+    async fn mock_service_answers_with_the_given_responses() {
+        let svc = MockService::with_responses(vec![Ok(1), Err(TestError::new("refused"))]);
+
+        assert_eq!(svc.clone().oneshot("first").await, Ok(1));
+        let error: crate::Error = svc.clone().oneshot("second").await.unwrap_err().into();
+        assert_eq!(error.to_string(), "refused");
+
+        assert_eq!(svc.requests(), vec!["first", "second"]);
+        svc.assert_attempts(2);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "no response left")]
+    async fn mock_service_panics_without_responses() {
+        let svc = MockService::<_, (), TestError>::with_responses(Vec::new());
+        _ = svc.oneshot("unexpected").await;
+    }
+
+    #[tokio::test]
+    async fn mock_service_tracks_concurrency() {
+        tokio::time::pause();
+
+        let svc = MockService::<usize, ()>::new(()).with_latency(Duration::from_secs(1));
+        let limited = tower::limit::ConcurrencyLimit::new(svc.clone(), 2);
+
+        let calls = (0..5).map(|i| limited.clone().oneshot(i));
+        join_all(calls).await;
+
+        assert_eq!(svc.calls(), 5);
+        assert_eq!(svc.max_in_flight(), 2);
+        assert_eq!(svc.in_flight(), 0);
+    }
+}
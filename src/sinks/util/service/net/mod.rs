@@ -24,7 +24,7 @@ use crate::{
 #[cfg(unix)]
 use crate::internal_events::{UnixSendIncompleteError, UnixSocketConnectionEstablished};
 
-pub use self::tcp::TcpConnectorConfig;
+pub use self::tcp::{TcpConnectorConfig, TcpService, TcpServiceConfig};
 pub use self::udp::UdpConnectorConfig;
 
 #[cfg(unix)]
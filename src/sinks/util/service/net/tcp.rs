@@ -1,7 +1,20 @@
-use std::net::SocketAddr;
+use std::{
+    marker::PhantomData,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
 
+use futures_util::{future::BoxFuture, task::noop_waker_ref, FutureExt};
 use snafu::ResultExt;
-use tokio::net::TcpStream;
+use tokio::{
+    io::{AsyncRead, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+    time::timeout,
+};
+use tower::Service;
 
 use vector_lib::configurable::configurable_component;
 use vector_lib::{
@@ -9,7 +22,11 @@ use vector_lib::{
     tls::{MaybeTlsSettings, MaybeTlsStream, TlsEnableableConfig},
 };
 
-use crate::dns;
+use crate::{
+    dns,
+    internal_events::{SocketOutgoingConnectionError, TcpSocketConnectionEstablished},
+    sinks::util::service::TowerRequestSettings,
+};
 
 use super::{net_error::*, ConnectorType, HostAndPort, NetError, NetworkConnector};
 
@@ -99,3 +116,250 @@ impl TcpConnector {
         Ok((addr, stream))
     }
 }
+
+/// Configuration for sending requests over a pool of TCP connections.
+#[configurable_component]
+#[derive(Clone, Debug)]
+pub struct TcpServiceConfig {
+    #[configurable(derived)]
+    address: HostAndPort,
+
+    /// The time to wait before starting to send TCP keepalive probes on an idle connection.
+    #[configurable(metadata(docs::type_unit = "seconds"))]
+    keepalive_secs: Option<u64>,
+
+    /// The time to wait for a connection to be established.
+    ///
+    /// Defaults to the request timeout.
+    #[configurable(metadata(docs::type_unit = "milliseconds"))]
+    #[configurable(metadata(docs::examples = 500))]
+    connect_timeout_ms: Option<u64>,
+}
+
+impl TcpServiceConfig {
+    pub const fn from_address(host: String, port: u16) -> Self {
+        Self {
+            address: HostAndPort { host, port },
+            keepalive_secs: None,
+            connect_timeout_ms: None,
+        }
+    }
+
+    /// Creates a [`TcpService`] which times out connecting and writing after the request timeout
+    /// of `settings`, unless a connect timeout is configured.
+    pub fn build<Req>(&self, settings: &TowerRequestSettings) -> TcpService<Req> {
+        TcpService {
+            connector: TcpConnector {
+                address: self.address.clone(),
+                keepalive: self.keepalive_secs.map(|time_secs| TcpKeepaliveConfig {
+                    time_secs: Some(time_secs),
+                }),
+                send_buffer_size: None,
+                tls: None,
+            },
+            connect_timeout: self
+                .connect_timeout_ms
+                .map_or(settings.timeout, Duration::from_millis),
+            write_timeout: settings.timeout,
+            idle: Arc::default(),
+            _request: PhantomData,
+        }
+    }
+}
+
+/// A `Service` which writes each request to one of a pool of TCP connections.
+///
+/// Connections are opened as needed, so that concurrent requests are written to separate
+/// connections, and are reused once idle. A connection whose write fails, or which the peer closed
+/// while idle, is dropped, and a new one is opened for the next request. Clones of the service
+/// share their pool.
+pub struct TcpService<Req> {
+    connector: TcpConnector,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    idle: Arc<Mutex<Vec<MaybeTlsStream<TcpStream>>>>,
+    _request: PhantomData<fn(Req)>,
+}
+
+impl<Req> Clone for TcpService<Req> {
+    fn clone(&self) -> Self {
+        Self {
+            connector: self.connector.clone(),
+            connect_timeout: self.connect_timeout,
+            write_timeout: self.write_timeout,
+            idle: Arc::clone(&self.idle),
+            _request: PhantomData,
+        }
+    }
+}
+
+impl<Req> TcpService<Req> {
+    async fn connect(
+        connector: TcpConnector,
+        connect_timeout: Duration,
+    ) -> crate::Result<MaybeTlsStream<TcpStream>> {
+        let result = match timeout(connect_timeout, connector.connect()).await {
+            Ok(result) => result.map_err(Into::into),
+            Err(elapsed) => Err(elapsed.into()),
+        };
+
+        match result {
+            Ok((peer_addr, stream)) => {
+                emit!(TcpSocketConnectionEstablished {
+                    peer_addr: Some(peer_addr)
+                });
+                Ok(stream)
+            }
+            Err(error) => {
+                emit!(SocketOutgoingConnectionError { error: &*error });
+                Err(error)
+            }
+        }
+    }
+
+    /// Takes an idle connection from the pool, dropping those the peer closed in the meantime.
+    fn take_idle(&self) -> Option<MaybeTlsStream<TcpStream>> {
+        let mut idle = self.idle.lock().expect("idle connections poisoned");
+        while let Some(mut stream) = idle.pop() {
+            if !is_closed(&mut stream) {
+                return Some(stream);
+            }
+        }
+        None
+    }
+}
+
+/// Whether the peer closed `stream`, which reading from it without waiting tells, as a closed
+/// connection reads zero bytes or fails rather than having nothing to read yet.
+fn is_closed(stream: &mut MaybeTlsStream<TcpStream>) -> bool {
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut buf = [0u8; 1];
+    let mut buf = ReadBuf::new(&mut buf);
+    match Pin::new(stream).poll_read(&mut cx, &mut buf) {
+        Poll::Ready(Err(_)) => true,
+        Poll::Ready(Ok(())) => buf.filled().is_empty(),
+        Poll::Pending => false,
+    }
+}
+
+impl<Req> Service<Req> for TcpService<Req>
+where
+    Req: AsRef<[u8]> + Send + 'static,
+{
+    type Response = ();
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The number of connections is bounded by the concurrency of the requests.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let idle = self.take_idle();
+        let connector = self.connector.clone();
+        let connect_timeout = self.connect_timeout;
+        let write_timeout = self.write_timeout;
+        let pool = Arc::clone(&self.idle);
+
+        async move {
+            let mut stream = match idle {
+                Some(stream) => stream,
+                None => Self::connect(connector, connect_timeout).await?,
+            };
+
+            // The connection is only returned to the pool once written to successfully.
+            timeout(write_timeout, stream.write_all(req.as_ref()))
+                .await?
+                .context(FailedToSend)?;
+            pool.lock().expect("idle connections poisoned").push(stream);
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::AsyncReadExt, net::TcpListener};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        sinks::util::{service::GlobalTowerRequestConfigDefaults, TowerRequestConfig},
+        test_util::next_addr,
+    };
+
+    #[tokio::test]
+    async fn reuses_idle_connections() {
+        let addr = next_addr();
+        let listener = TcpListener::bind(addr).await.unwrap();
+
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+        let service = TcpServiceConfig::from_address(addr.ip().to_string(), addr.port())
+            .build::<&'static str>(&settings);
+
+        service.clone().oneshot("first\n").await.unwrap();
+        service.clone().oneshot("second\n").await.unwrap();
+        drop(service);
+
+        // Both requests were written to the same connection, which is closed with the service.
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        socket.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"first\nsecond\n");
+    }
+
+    #[tokio::test]
+    async fn reconnects_instead_of_reusing_closed_connections() {
+        let addr = next_addr();
+        let listener = TcpListener::bind(addr).await.unwrap();
+
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+        let service = TcpServiceConfig::from_address(addr.ip().to_string(), addr.port())
+            .build::<&'static str>(&settings);
+
+        service.clone().oneshot("first\n").await.unwrap();
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut received = [0; 6];
+        socket.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, b"first\n");
+        drop(socket);
+        // Lets the idle connection see that it was closed.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        service.clone().oneshot("second\n").await.unwrap();
+        drop(service);
+
+        let (mut socket, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("a new connection should be opened")
+            .unwrap();
+        let mut received = Vec::new();
+        socket.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"second\n");
+    }
+
+    #[test]
+    fn connect_timeout_defaults_to_the_request_timeout() {
+        let config = TcpServiceConfig::from_address("localhost".to_owned(), 9000);
+        let settings =
+            TowerRequestConfig::<GlobalTowerRequestConfigDefaults>::default().into_settings();
+        assert_eq!(
+            config.build::<&str>(&settings).connect_timeout,
+            settings.timeout
+        );
+
+        let config = TcpServiceConfig {
+            connect_timeout_ms: Some(100),
+            ..config
+        };
+        assert_eq!(
+            config.build::<&str>(&settings).connect_timeout,
+            Duration::from_millis(100)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Retries requests which the downstream service only partially delivered.
+//!
+//! Some services accept or reject the items of a request individually, so a request can succeed
+//! for some of its items and fail for the rest. Retrying the whole request keeps the items of an
+//! ordered partition in order, but sends the delivered items again. Retrying only the items that
+//! failed avoids that, at the cost of them being delivered after items that followed them in the
+//! request.
+
+use std::task::{Context, Poll};
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::time::sleep;
+use tower::{Layer, Service, ServiceExt};
+use vector_lib::configurable::configurable_component;
+
+use crate::sinks::util::retries::ExponentialBackoff;
+
+/// How to retry requests which were only partially delivered.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartialSuccessMode {
+    /// Retries the whole request, as if none of it was delivered.
+    ///
+    /// This preserves the order of the items, but sends the items which were already delivered
+    /// again.
+    #[default]
+    RetryAll,
+
+    /// Retries only the items which were not delivered.
+    ///
+    /// Those items may be delivered after items which followed them in the request.
+    RetryFailed,
+}
+
+/// How much of a request its response reports was delivered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Delivery<Req> {
+    /// The whole request was delivered.
+    Full,
+
+    /// Only part of the request was delivered, the rest of it being the given request.
+    Partial(Req),
+
+    /// None of the request was delivered.
+    ///
+    /// Whether the request is retried is then up to the retry logic of the sink.
+    Failed,
+}
+
+/// Requests made up of items which the downstream service can accept or reject individually.
+pub trait PartialDelivery<Res>: Sized {
+    /// Returns how much of this request `response` reports was delivered.
+    fn delivery(&self, response: &Res) -> Delivery<Self>;
+}
+
+/// Retries partially delivered requests made through the wrapped service.
+#[derive(Clone, Debug)]
+pub struct PartialSuccessLayer {
+    mode: PartialSuccessMode,
+    retry_attempts: usize,
+    backoff: ExponentialBackoff,
+}
+
+impl PartialSuccessLayer {
+    /// Creates a new partial success layer, which retries a partially delivered request up to
+    /// `retry_attempts` times, waiting for `backoff` before each retry.
+    pub const fn new(
+        mode: PartialSuccessMode,
+        retry_attempts: usize,
+        backoff: ExponentialBackoff,
+    ) -> Self {
+        Self {
+            mode,
+            retry_attempts,
+            backoff,
+        }
+    }
+}
+
+impl<S> Layer<S> for PartialSuccessLayer {
+    type Service = PartialSuccess<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PartialSuccess {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// A service which retries requests as long as their response reports that they were only
+/// partially delivered.
+///
+/// This is meant to be wrapped by the tower request settings of a sink, so that each attempt made
+/// by the retry policy includes the partial retries. The retry logic of the sink should therefore
+/// treat partially delivered responses as successful.
+#[derive(Clone, Debug)]
+pub struct PartialSuccess<S> {
+    inner: S,
+    layer: PartialSuccessLayer,
+}
+
+impl<S, Req> Service<Req> for PartialSuccess<S>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Response: Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+    Req: PartialDelivery<S::Response> + Clone + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let future = self.inner.call(req.clone());
+        let mut inner = self.inner.clone();
+        let PartialSuccessLayer {
+            mode,
+            mut retry_attempts,
+            mut backoff,
+        } = self.layer.clone();
+
+        async move {
+            let mut request = req;
+            let mut response = future.await?;
+            while let Delivery::Partial(undelivered) = request.delivery(&response) {
+                if retry_attempts == 0 {
+                    error!(
+                        message = "Request was only partially delivered and retries are exhausted.",
+                        internal_log_rate_limit = true,
+                    );
+                    break;
+                }
+                retry_attempts -= 1;
+
+                if mode == PartialSuccessMode::RetryFailed {
+                    request = undelivered;
+                }
+                warn!(
+                    message = "Request was only partially delivered; retrying.",
+                    ?mode,
+                    internal_log_rate_limit = true,
+                );
+                sleep(backoff.next().expect("Should never end")).await;
+                response = inner.ready().await?.call(request.clone()).await?;
+            }
+
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures::future;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Items(Vec<u32>);
+
+    #[derive(Debug)]
+    struct Rejected(Vec<u32>);
+
+    impl PartialDelivery<Rejected> for Items {
+        fn delivery(&self, response: &Rejected) -> Delivery<Self> {
+            let rejected = &response.0;
+            if rejected.is_empty() {
+                Delivery::Full
+            } else if rejected.len() < self.0.len() {
+                Delivery::Partial(Self(rejected.clone()))
+            } else {
+                Delivery::Failed
+            }
+        }
+    }
+
+    /// Sends a request of three items, the second of which is rejected once.
+    async fn send(mode: PartialSuccessMode) -> Vec<Items> {
+        tokio::time::pause();
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let svc = {
+            let sent = Arc::clone(&sent);
+            tower::service_fn(move |req: Items| {
+                let mut sent = sent.lock().unwrap();
+                let rejected = if sent.is_empty() { vec![2] } else { vec![] };
+                sent.push(req);
+                future::ok::<_, crate::Error>(Rejected(rejected))
+            })
+        };
+        let layer = PartialSuccessLayer::new(
+            mode,
+            3,
+            ExponentialBackoff::from_millis(2).max_delay(Duration::from_secs(1)),
+        );
+
+        layer
+            .layer(svc)
+            .oneshot(Items(vec![1, 2, 3]))
+            .await
+            .unwrap();
+        Arc::try_unwrap(sent).unwrap().into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn retry_all_resends_the_whole_request() {
+        assert_eq!(
+            send(PartialSuccessMode::RetryAll).await,
+            [Items(vec![1, 2, 3]), Items(vec![1, 2, 3])]
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_failed_resends_only_undelivered_items() {
+        assert_eq!(
+            send(PartialSuccessMode::RetryFailed).await,
+            [Items(vec![1, 2, 3]), Items(vec![2])]
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_failed_requests_to_the_retry_logic() {
+        let sent = Arc::new(Mutex::new(0));
+        let svc = {
+            let sent = Arc::clone(&sent);
+            tower::service_fn(move |req: Items| {
+                *sent.lock().unwrap() += 1;
+                future::ok::<_, crate::Error>(Rejected(req.0))
+            })
+        };
+        let layer = PartialSuccessLayer::new(
+            PartialSuccessMode::RetryFailed,
+            3,
+            ExponentialBackoff::from_millis(2),
+        );
+
+        let response = layer.layer(svc).oneshot(Items(vec![1, 2])).await.unwrap();
+        assert_eq!(response.0, [1, 2]);
+        assert_eq!(*sent.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use serde_json::Value;
+use vector_lib::configurable::attributes::CustomAttribute;
+use vector_lib::configurable::{
+    schema::{
+        apply_base_metadata, generate_const_string_schema, generate_number_schema,
+        generate_one_of_schema, SchemaGenerator, SchemaObject,
+    },
+    Configurable, GenerateError, Metadata, ToValue,
+};
+
+use serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use tokio::time::Instant;
+use tower::{Layer, Service};
+
+use super::{Clock, ClockSleep, TokioClock};
+
+/// The maximum number of requests allowed within the rate limit time window.
+///
+/// This can be set either to `unlimited` or to a number of requests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateLimitNum {
+    /// Requests are not rate limited.
+    Unlimited,
+
+    /// Up to this many requests are allowed within the time window.
+    Limited(u64),
+}
+
+impl RateLimitNum {
+    /// The number of requests allowed within the time window, if they are rate limited.
+    pub const fn limit(&self) -> Option<u64> {
+        match self {
+            RateLimitNum::Unlimited => None,
+            RateLimitNum::Limited(num) => Some(*num),
+        }
+    }
+}
+
+impl Serialize for RateLimitNum {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self {
+            RateLimitNum::Unlimited => serializer.serialize_str("unlimited"),
+            RateLimitNum::Limited(num) => serializer.serialize_u64(*num),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RateLimitNum {
+    // Deserialize either a non-negative integer or the string "unlimited"
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct U64OrUnlimited;
+
+        impl<'de> Visitor<'de> for U64OrUnlimited {
+            type Value = RateLimitNum;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(r#"non-negative integer or "unlimited""#)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<RateLimitNum, E> {
+                if value == "unlimited" {
+                    Ok(RateLimitNum::Unlimited)
+                } else {
+                    Err(de::Error::unknown_variant(value, &["unlimited"]))
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<RateLimitNum, E> {
+                u64::try_from(value)
+                    .map(RateLimitNum::Limited)
+                    .map_err(|_| {
+                        de::Error::invalid_value(Unexpected::Signed(value), &"non-negative integer")
+                    })
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<RateLimitNum, E> {
+                Ok(RateLimitNum::Limited(value))
+            }
+        }
+
+        deserializer.deserialize_any(U64OrUnlimited)
+    }
+}
+
+impl Configurable for RateLimitNum {
+    fn referenceable_name() -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    fn metadata() -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.set_description(
+            r#"The maximum number of requests allowed within the rate limit time window.
+
+This can be set either to `unlimited` or to a number of requests."#,
+        );
+        metadata.add_custom_attribute(CustomAttribute::kv("docs::enum_tagging", "external"));
+        metadata
+    }
+
+    fn generate_schema(_: &RefCell<SchemaGenerator>) -> Result<SchemaObject, GenerateError> {
+        let mut unlimited_schema = generate_const_string_schema("unlimited".to_string());
+        let mut unlimited_metadata = Metadata::with_title("Requests are not rate limited.");
+        unlimited_metadata.add_custom_attribute(CustomAttribute::kv("logical_name", "Unlimited"));
+        apply_base_metadata(&mut unlimited_schema, unlimited_metadata);
+
+        let mut limited_schema = generate_number_schema::<u64>();
+        let mut limited_metadata = Metadata::with_description(
+            "Up to this many requests are allowed within the time window.",
+        );
+        limited_metadata.set_transparent();
+        limited_metadata.add_custom_attribute(CustomAttribute::kv("docs::numeric_type", "uint"));
+        limited_metadata.add_custom_attribute(CustomAttribute::kv("logical_name", "Limited"));
+        apply_base_metadata(&mut limited_schema, limited_metadata);
+
+        Ok(generate_one_of_schema(&[unlimited_schema, limited_schema]))
+    }
+}
+
+impl ToValue for RateLimitNum {
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("Could not convert rate limit to JSON")
+    }
+}
+
+/// Rate limits requests with a token bucket, which allows short bursts of requests above the
+/// sustained rate limit.
+///
+/// The bucket holds up to `num + burst` tokens and starts out full. Each request takes a token,
+/// and tokens are put back one at a time at the sustained rate of `num` every `per`.
+#[derive(Clone, Debug)]
+pub struct TokenBucketRateLimitLayer {
+    num: u64,
+    per: Duration,
+    burst: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl TokenBucketRateLimitLayer {
+    /// Creates a new token bucket rate limit layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num` or `per` is zero.
+    pub fn new(num: u64, per: Duration, burst: u64) -> Self {
+        assert!(num > 0, "rate limit must allow at least one request");
+        assert!(!per.is_zero(), "rate limit time window must not be zero");
+        Self {
+            num,
+            per,
+            burst,
+            clock: Arc::new(TokioClock),
+        }
+    }
+
+    /// Refills the bucket and waits for tokens according to `clock` rather than the tokio clock.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl<S> Layer<S> for TokenBucketRateLimitLayer {
+    type Service = TokenBucketRateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let now = self.clock.now();
+        let refill_every = self.per.as_nanos() / u128::from(self.num);
+        let capacity = self.num.saturating_add(self.burst);
+
+        TokenBucketRateLimit {
+            inner,
+            bucket: TokenBucket {
+                capacity,
+                tokens: capacity,
+                refill_every: Duration::from_nanos(refill_every.max(1) as u64),
+                refilled_at: now,
+            },
+            clock: Arc::clone(&self.clock),
+            sleep: None,
+        }
+    }
+}
+
+struct TokenBucket {
+    capacity: u64,
+    tokens: u64,
+    refill_every: Duration,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Puts back the tokens refilled since the last refill.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let refilled = elapsed.as_nanos() / self.refill_every.as_nanos();
+        match u64::try_from(refilled) {
+            Ok(refilled) if refilled < self.capacity - self.tokens => {
+                self.tokens += refilled;
+                // Time short of a whole token is kept for the next refill. This can't overflow, as
+                // it is no more than the time elapsed.
+                self.refilled_at += Duration::from_nanos(
+                    (self.refill_every.as_nanos() * u128::from(refilled)) as u64,
+                );
+            }
+            // A full bucket doesn't accumulate tokens, so it starts refilling from now on.
+            _ => {
+                self.tokens = self.capacity;
+                self.refilled_at = now;
+            }
+        }
+    }
+
+    fn next_token_at(&self) -> Instant {
+        self.refilled_at + self.refill_every
+    }
+}
+
+/// A service which is rate limited by a token bucket.
+pub struct TokenBucketRateLimit<S> {
+    inner: S,
+    bucket: TokenBucket,
+    clock: Arc<dyn Clock>,
+    // The wait for the next token, along with the instant it is due, while the bucket is empty.
+    sleep: Option<(Instant, ClockSleep)>,
+}
+
+impl<S> TokenBucketRateLimit<S> {
+    pub const fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, Req> Service<Req> for TokenBucketRateLimit<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            self.bucket.refill(self.clock.now());
+            if self.bucket.tokens > 0 {
+                self.sleep = None;
+                return self.inner.poll_ready(cx);
+            }
+
+            let next_token_at = self.bucket.next_token_at();
+            let (_, sleep) = match &mut self.sleep {
+                Some(sleep) if sleep.0 == next_token_at => sleep,
+                sleep => sleep.insert((next_token_at, self.clock.sleep_until(next_token_at))),
+            };
+            ready!(sleep.as_mut().poll(cx));
+            self.sleep = None;
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.bucket.tokens = self
+            .bucket
+            .tokens
+            .checked_sub(1)
+            .expect("service not ready; poll_ready must be called first");
+        self.inner.call(req)
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for TokenBucketRateLimit<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBucketRateLimit")
+            .field("inner", &self.inner)
+            .field("tokens", &self.bucket.tokens)
+            .field("capacity", &self.bucket.capacity)
+            .finish()
+    }
+}
+
+#[test]
+fn is_serialization_reversible() {
+    let variants = [RateLimitNum::Unlimited, RateLimitNum::Limited(100)];
+
+    for v in variants {
+        let value = serde_json::to_value(v).unwrap();
+        let deserialized = serde_json::from_value::<RateLimitNum>(value)
+            .expect("Failed to deserialize a previously serialized RateLimitNum value");
+
+        assert_eq!(v, deserialized)
+    }
+}
+
+#[tokio::test]
+async fn token_bucket_allows_bursts_at_the_sustained_rate() {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::{assert_request_eq, mock};
+
+    pause();
+
+    let layer = TokenBucketRateLimitLayer::new(10, Duration::from_secs(1), 10);
+    let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(layer);
+
+    // Twice the rate limit is sent at once, using up the burst.
+    for i in 0..20 {
+        assert_ready_ok!(svc.poll_ready());
+        let _response = svc.call(i);
+        assert_request_eq!(handle, i).send_response(());
+    }
+    assert_pending!(svc.poll_ready());
+
+    // Requests are then only sent at the sustained rate, of one every 100ms.
+    let mut sent = 0;
+    for _ in 0..500 {
+        advance(Duration::from_millis(10)).await;
+        while svc.poll_ready().is_ready() {
+            let _response = svc.call(sent);
+            assert_request_eq!(handle, sent).send_response(());
+            sent += 1;
+        }
+    }
+    assert_eq!(sent, 50);
+}
+
+#[tokio::test]
+async fn token_bucket_waits_for_tokens_on_its_clock() {
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::ManualClock;
+
+    let clock = ManualClock::new();
+    let layer =
+        TokenBucketRateLimitLayer::new(1, Duration::from_secs(1), 0).with_clock(clock.clone());
+    let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(layer);
+
+    assert_ready_ok!(svc.poll_ready());
+    let _response = svc.call(0);
+    assert_request_eq!(handle, 0).send_response(());
+    assert_pending!(svc.poll_ready());
+    assert_eq!(clock.sleeps(), vec![Duration::from_secs(1)]);
+
+    clock.advance(Duration::from_secs(1));
+    assert!(svc.is_woken());
+    assert_ready_ok!(svc.poll_ready());
+}
@@ -0,0 +1,199 @@
+//! Delays the first request sent through a service by a random amount of time.
+//!
+//! When many identical sinks start at the same time they would otherwise all send their first
+//! request at once, so each one waits out its own random delay before admitting its first request.
+//!
+//! The same delay can shift the phase of a rate limit wrapped around the service: its windows start
+//! from the first request it admits, so identical sinks rate limited with the same window would
+//! otherwise roll over their windows, and send their next burst of requests, at the same time.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::time::{sleep, sleep_until, Sleep};
+use tower::{Layer, Service};
+
+/// Delays the first request admitted by the wrapped service by up to `max_jitter`.
+#[derive(Clone, Copy, Debug)]
+pub struct StartupJitterLayer {
+    max_jitter: Duration,
+    rate_limit_window: Duration,
+}
+
+impl StartupJitterLayer {
+    /// Creates a new startup jitter layer.
+    ///
+    /// A `max_jitter` of zero disables the delay.
+    pub const fn new(max_jitter: Duration) -> Self {
+        Self {
+            max_jitter,
+            rate_limit_window: Duration::ZERO,
+        }
+    }
+
+    /// Further delays the first request by a random fraction of `window`, the time window of the
+    /// rate limit wrapped around this layer, so as to offset the phase of that rate limit.
+    ///
+    /// A `window` of zero leaves the phase of the rate limit alone.
+    pub const fn with_rate_limit_phase(mut self, window: Duration) -> Self {
+        self.rate_limit_window = window;
+        self
+    }
+
+    fn random_delay(&self) -> Duration {
+        let max_millis = self.max_jitter.as_millis() as u64;
+        let jitter = rand::random::<u64>() % (max_millis + 1);
+        let window_millis = self.rate_limit_window.as_millis() as u64;
+        let phase = match window_millis {
+            0 => 0,
+            _ => rand::random::<u64>() % window_millis,
+        };
+        Duration::from_millis(jitter + phase)
+    }
+}
+
+impl<S> Layer<S> for StartupJitterLayer {
+    type Service = StartupJitter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let gate = if self.max_jitter.is_zero() && self.rate_limit_window.is_zero() {
+            Gate::Open
+        } else {
+            Gate::Closed(self.random_delay())
+        };
+
+        StartupJitter { inner, gate }
+    }
+}
+
+enum Gate {
+    /// No request has been admitted yet, and the first one has to wait for the given delay.
+    Closed(Duration),
+
+    /// Waiting for the delay to pass before admitting the first request.
+    Opening(Pin<Box<Sleep>>),
+
+    /// Requests are passed straight through.
+    Open,
+}
+
+/// A service which holds back the first request until its startup delay has passed.
+pub struct StartupJitter<S> {
+    inner: S,
+    gate: Gate,
+}
+
+impl<S, Req> Service<Req> for StartupJitter<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            self.gate = match self.gate {
+                Gate::Closed(delay) => Gate::Opening(Box::pin(sleep(delay))),
+                Gate::Opening(ref mut delay) => {
+                    ready!(delay.as_mut().poll(cx));
+                    Gate::Open
+                }
+                Gate::Open => return self.inner.poll_ready(cx),
+            }
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+impl<S: Clone> Clone for StartupJitter<S> {
+    fn clone(&self) -> Self {
+        let gate = match &self.gate {
+            Gate::Closed(delay) => Gate::Closed(*delay),
+            Gate::Opening(delay) => Gate::Opening(Box::pin(sleep_until(delay.deadline()))),
+            Gate::Open => Gate::Open,
+        };
+
+        Self {
+            inner: self.inner.clone(),
+            gate,
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for StartupJitter<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StartupJitter")
+            .field("inner", &self.inner)
+            .field("open", &matches!(self.gate, Gate::Open))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{advance, pause};
+    use tokio_test::{assert_pending, assert_ready_ok};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn passes_through_without_jitter() {
+        let (mut svc, mut handle) =
+            mock::spawn_layer::<_, (), _>(StartupJitterLayer::new(Duration::ZERO));
+
+        assert_ready_ok!(svc.poll_ready());
+        let _fut = svc.call("hello");
+        let _response = assert_request_eq!(handle, "hello");
+    }
+
+    #[tokio::test]
+    async fn rate_limit_phase_is_within_one_window() {
+        pause();
+
+        let layer =
+            StartupJitterLayer::new(Duration::ZERO).with_rate_limit_phase(Duration::from_secs(1));
+        for _ in 0..10 {
+            assert!(layer.random_delay() < Duration::from_secs(1));
+        }
+
+        let (mut svc, mut handle) = mock::spawn_layer::<_, (), _>(layer);
+        _ = svc.poll_ready();
+        advance(Duration::from_secs(1)).await;
+        assert_ready_ok!(svc.poll_ready());
+        let _fut = svc.call("first");
+        let _response = assert_request_eq!(handle, "first");
+    }
+
+    #[tokio::test]
+    async fn delays_only_the_first_request() {
+        pause();
+
+        let (mut svc, mut handle) = mock::spawn_with::<_, (), _, _>(|inner| StartupJitter {
+            inner,
+            gate: Gate::Closed(Duration::from_secs(5)),
+        });
+
+        assert_pending!(svc.poll_ready());
+        advance(Duration::from_secs(4)).await;
+        assert_pending!(svc.poll_ready());
+        advance(Duration::from_secs(2)).await;
+        assert_ready_ok!(svc.poll_ready());
+
+        let _fut = svc.call("first");
+        let _response = assert_request_eq!(handle, "first");
+
+        assert_ready_ok!(svc.poll_ready());
+        let _fut = svc.call("second");
+        let _response = assert_request_eq!(handle, "second");
+    }
+}
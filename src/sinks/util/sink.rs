@@ -32,21 +32,35 @@
 //! it to notify the consumer that the request has succeeded.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::Infallible,
     fmt,
+    future::Future,
     hash::Hash,
     marker::PhantomData,
     pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{ready, Context, Poll},
 };
 
-use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, Sink, Stream, TryFutureExt};
+use futures::{
+    future::{self, BoxFuture},
+    stream::FuturesUnordered,
+    FutureExt, Sink, Stream, StreamExt, TryFutureExt,
+};
+use indexmap::IndexMap;
 use pin_project::pin_project;
 use tokio::{
     sync::oneshot,
-    time::{sleep, Duration, Sleep},
+    time::{sleep, sleep_until, Duration, Instant, Sleep},
+};
+use tower::{
+    limit::{rate::Rate, RateLimit},
+    Service, ServiceBuilder,
 };
-use tower::{Service, ServiceBuilder};
 use tracing::Instrument;
 use vector_lib::internal_event::{
     CallError, CountByteSize, EventsSent, InternalEventHandle as _, Output,
@@ -57,10 +71,11 @@ pub use vector_lib::sink::StreamSink;
 use super::{
     batch::{Batch, EncodedBatch, FinalizersBatch, PushResult, StatefulBatch},
     buffer::{Partition, PartitionBuffer, PartitionInnerBuffer},
+    retries::{send_batch, send_initial_batch},
     service::{Map, ServiceBuilderExt},
     EncodedEvent,
 };
-use crate::event::EventStatus;
+use crate::{event::EventStatus, stats::EwmaDefault};
 
 // === BatchSink ===
 
@@ -111,6 +126,31 @@ where
         let inner = PartitionBatchSink::new(service, batch, timeout);
         Self { inner }
     }
+
+    /// Stops accepting events while `capacity` requests or more are in flight.
+    ///
+    /// Returns the sink along with a signal reporting whether that is currently the case.
+    pub fn with_backpressure(mut self, capacity: usize) -> (Self, BackpressureSignal) {
+        let signal = self.inner.with_backpressure(capacity);
+        (self, signal)
+    }
+
+    /// Sends the batch once no event was received for `idle`, rather than waiting for the batch
+    /// timeout, so that sinks receiving events slowly don't hold on to them for the whole timeout.
+    ///
+    /// This is only useful with an idle timeout shorter than the batch timeout.
+    pub fn flush_on_idle(mut self, idle: Duration) -> Self {
+        self.inner.flush_on_idle(idle);
+        self
+    }
+
+    /// Adapts the batch timeout to how quickly batches fill up, between `min` and `max`.
+    ///
+    /// See [`PartitionBatchSink::adaptive_timeout`].
+    pub fn adaptive_timeout(mut self, min: Duration, max: Duration) -> Self {
+        self.inner.adaptive_timeout(min, max);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -189,13 +229,351 @@ where
     service: ServiceSink<S, B::Output>,
     buffer: Option<(K, EncodedEvent<B::Input>)>,
     batch: StatefulBatch<FinalizersBatch<B>>,
-    partitions: HashMap<K, StatefulBatch<FinalizersBatch<B>>>,
+    partitions: IndexMap<K, StatefulBatch<FinalizersBatch<B>>>,
     timeout: Duration,
     lingers: HashMap<K, Pin<Box<Sleep>>>,
     in_flight: Option<HashMap<K, BoxFuture<'static, ()>>>,
+    sent_partitions: Option<SentPartitions<K>>,
+    partition_concurrency: Option<PartitionConcurrency<K>>,
+    backpressure: Option<BackpressureSignal>,
+    max_partitions: Option<usize>,
+    partition_order: bool,
+    partition_priority: Option<PartitionPriority<K>>,
+    partition_weights: Option<PartitionWeights<K>>,
+    partition_capacity: Option<PartitionCapacity<K>>,
+    partition_rate_limits: Option<PartitionRateLimits<K>>,
+    idle: Option<PartitionIdle<K>>,
+    adaptive_timeout: Option<AdaptiveTimeout<K>>,
     closing: bool,
 }
 
+/// The priority of each partition, used to pick which of the batches ready at the same time are
+/// sent first.
+struct PartitionPriority<K> {
+    priority: Box<dyn Fn(&K) -> u8 + Send + Sync>,
+    /// The number of times each partition had a batch ready which was not sent, as batches of
+    /// higher priority were sent instead.
+    passed_over: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq> PartitionPriority<K> {
+    /// The priority of `partition`, raised by one each time it is passed over so that it can't be
+    /// starved by partitions of higher priority.
+    fn effective(&self, partition: &K) -> usize {
+        (self.priority)(partition) as usize + self.passed_over.get(partition).copied().unwrap_or(0)
+    }
+
+    /// Sorts `partitions` from the highest to the lowest effective priority, keeping the order of
+    /// partitions of the same priority.
+    fn sort(&self, partitions: &mut [K]) {
+        partitions.sort_by_cached_key(|partition| std::cmp::Reverse(self.effective(partition)));
+    }
+
+    fn sent(&mut self, partition: &K) {
+        self.passed_over.remove(partition);
+    }
+}
+
+impl<K: Hash + Eq + Clone> PartitionPriority<K> {
+    fn passed_over(&mut self, partitions: &[K]) {
+        for partition in partitions {
+            *self.passed_over.entry(partition.clone()).or_default() += 1;
+        }
+    }
+}
+
+/// The weight of each partition, used to share the batches sent between the partitions whose
+/// batches are ready in proportion to their weights, by deficit round robin.
+struct PartitionWeights<K> {
+    weight: Box<dyn Fn(&K) -> u32 + Send + Sync>,
+    /// The number of batches each partition may still send in the current round.
+    deficits: HashMap<K, u64>,
+}
+
+impl<K: Hash + Eq + Clone> PartitionWeights<K> {
+    fn deficit(&self, partition: &K) -> u64 {
+        self.deficits.get(partition).copied().unwrap_or(0)
+    }
+
+    /// Keeps the partitions of `partitions` which may still send a batch in the current round,
+    /// from the one with the most batches left to the one with the fewest.
+    ///
+    /// Once none of them may, a new round starts, in which each partition may send as many batches
+    /// as its weight. Partitions without a batch ready don't carry what they had left over.
+    fn schedule(&mut self, partitions: &mut Vec<K>) {
+        if !partitions.is_empty()
+            && partitions
+                .iter()
+                .all(|partition| self.deficit(partition) == 0)
+        {
+            self.deficits
+                .retain(|partition, _| partitions.contains(partition));
+            for partition in partitions.iter() {
+                let weight = u64::from((self.weight)(partition).max(1));
+                *self.deficits.entry(partition.clone()).or_default() += weight;
+            }
+        }
+        partitions.retain(|partition| self.deficit(partition) > 0);
+        partitions.sort_by_cached_key(|partition| std::cmp::Reverse(self.deficit(partition)));
+    }
+
+    fn sent(&mut self, partition: &K) {
+        if let Some(deficit) = self.deficits.get_mut(partition) {
+            *deficit = deficit.saturating_sub(1);
+        }
+    }
+}
+
+/// The bytes batched for each partition, when they are capped.
+struct PartitionCapacity<K> {
+    limit: u64,
+    bytes: HashMap<K, u64>,
+}
+
+impl<K: Hash + Eq> PartitionCapacity<K> {
+    /// Whether the batch of `partition` holds as many bytes as allowed.
+    fn is_full(&self, partition: &K) -> bool {
+        self.bytes
+            .get(partition)
+            .map_or(false, |bytes| *bytes >= self.limit)
+    }
+
+    /// Whether the batch of any partition holds as many bytes as allowed.
+    fn any_full(&self) -> bool {
+        self.bytes.values().any(|bytes| *bytes >= self.limit)
+    }
+}
+
+/// The number of partitions remembered as having sent a batch.
+const MAX_SENT_PARTITIONS: usize = 10_000;
+
+/// The partitions which sent a batch, so that the first batch of each partition can be told apart.
+///
+/// Only the partitions which sent their first batch most recently are remembered, so that sinks
+/// whose partitions keep changing don't remember them without bound. The next batch of a forgotten
+/// partition is sent as if it was its first.
+struct SentPartitions<K> {
+    capacity: usize,
+    sent: HashSet<K>,
+    /// The partitions remembered, from the one which sent its first batch the earliest.
+    order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone> SentPartitions<K> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            sent: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Records a batch sent for `partition`, returning whether it is the first one.
+    fn insert(&mut self, partition: &K) -> bool {
+        if self.sent.contains(partition) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sent.remove(&oldest);
+            }
+        }
+        self.sent.insert(partition.clone());
+        self.order.push_back(partition.clone());
+        true
+    }
+}
+
+/// The time since each partition last received an event, when their batches are sent once idle.
+struct PartitionIdle<K> {
+    timeout: Duration,
+    timers: HashMap<K, Pin<Box<Sleep>>>,
+}
+
+impl<K: Hash + Eq + Clone> PartitionIdle<K> {
+    /// Restarts the idle timeout of `partition`, which just received an event.
+    fn received(&mut self, partition: &K) {
+        let deadline = Instant::now() + self.timeout;
+        match self.timers.get_mut(partition) {
+            Some(timer) => timer.as_mut().reset(deadline),
+            None => {
+                self.timers
+                    .insert(partition.clone(), Box::pin(sleep_until(deadline)));
+            }
+        }
+    }
+
+    /// Whether `partition` received no event for the idle timeout.
+    fn poll_is_idle(&mut self, partition: &K, cx: &mut Context<'_>) -> bool {
+        self.timers
+            .get_mut(partition)
+            .map_or(false, |timer| timer.poll_unpin(cx).is_ready())
+    }
+}
+
+/// The smoothing factor of the average time batches take to fill up.
+const BATCH_FILL_TIME_EWMA_ALPHA: f64 = 0.3;
+
+/// The batch timeout, when it follows the average time batches take to fill up.
+struct AdaptiveTimeout<K> {
+    min: Duration,
+    max: Duration,
+    /// The average time batches took to fill up, in seconds.
+    fill_time: EwmaDefault,
+    /// When the batch of each partition was started.
+    started: HashMap<K, Instant>,
+}
+
+impl<K: Hash + Eq> AdaptiveTimeout<K> {
+    fn new(min: Duration, max: Duration, initial: Duration) -> Self {
+        Self {
+            min,
+            max,
+            fill_time: EwmaDefault::new(BATCH_FILL_TIME_EWMA_ALPHA, initial.as_secs_f64()),
+            started: HashMap::new(),
+        }
+    }
+
+    /// The timeout of the batches started now.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.fill_time.average()).clamp(self.min, self.max)
+    }
+
+    fn started(&mut self, partition: K) {
+        self.started.insert(partition, Instant::now());
+    }
+
+    /// Forgets the batch of `partition`, which was sent before it could fill up or time out.
+    fn discard(&mut self, partition: &K) {
+        self.started.remove(partition);
+    }
+
+    /// Records how long the batch of `partition` took to fill up, if it did.
+    ///
+    /// Batches sent before filling up would have taken longer than they waited, so they count as
+    /// having taken twice as long, which keeps lengthening the timeout while batches don't fill.
+    fn sent(&mut self, partition: &K, was_full: bool) {
+        if let Some(started) = self.started.remove(partition) {
+            let waited = started.elapsed();
+            let fill_time = if was_full { waited } else { waited * 2 };
+            self.fill_time.update(fill_time.as_secs_f64());
+        }
+    }
+}
+
+/// The requests in flight for each partition, when their number is limited.
+struct PartitionConcurrency<K> {
+    limit: usize,
+    in_flight: HashMap<K, FuturesUnordered<BoxFuture<'static, ()>>>,
+}
+
+impl<K: Hash + Eq> PartitionConcurrency<K> {
+    /// Whether another request can be sent for `partition`.
+    fn poll_has_capacity(&mut self, partition: &K, cx: &mut Context<'_>) -> bool {
+        self.in_flight.get_mut(partition).map_or(true, |requests| {
+            while let Poll::Ready(Some(())) = requests.poll_next_unpin(cx) {}
+            requests.len() < self.limit
+        })
+    }
+}
+
+/// The number of partition rate limits kept before the expired ones are evicted.
+const MIN_PARTITION_RATE_LIMITS_EVICTED: usize = 64;
+
+/// The rate at which the batches of a partition are sent, as at most `num` batches every
+/// `duration`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PartitionRate {
+    pub num: u64,
+    pub duration: Duration,
+}
+
+/// The rate limits of partitions, when their batches are sent at a rate of their own.
+struct PartitionRateLimits<K> {
+    rates: HashMap<K, PartitionRate>,
+    default: Option<PartitionRate>,
+    /// The rate limit of each partition sent recently, which acquires a slot of the limit for each
+    /// batch it lets through, along with when its current window ends at the latest.
+    limiters: HashMap<K, (RateLimit<Permit>, Instant)>,
+    /// The number of rate limits from which the expired ones are evicted.
+    evict_at: usize,
+}
+
+impl<K: Hash + Eq + Clone> PartitionRateLimits<K> {
+    fn new(rates: HashMap<K, PartitionRate>, default: Option<PartitionRate>) -> Self {
+        Self {
+            rates,
+            default,
+            limiters: HashMap::new(),
+            evict_at: MIN_PARTITION_RATE_LIMITS_EVICTED,
+        }
+    }
+
+    fn rate(&self, partition: &K) -> Option<PartitionRate> {
+        self.rates.get(partition).copied().or(self.default)
+    }
+
+    /// Whether a batch can be sent for `partition` within its rate limit, if it has one.
+    fn poll_has_capacity(&mut self, partition: &K, cx: &mut Context<'_>) -> bool {
+        let Some(rate) = self.rate(partition) else {
+            return true;
+        };
+        self.limiters
+            .entry(partition.clone())
+            .or_insert_with(|| {
+                let limiter = RateLimit::new(Permit, Rate::new(rate.num, rate.duration));
+                (limiter, Instant::now())
+            })
+            .0
+            .poll_ready(cx)
+            .is_ready()
+    }
+
+    /// Counts a batch sent for `partition` towards its rate limit.
+    ///
+    /// This must follow a call to [`Self::poll_has_capacity`] for the partition which returned
+    /// `true`.
+    fn sent(&mut self, partition: &K) {
+        let Some(rate) = self.rate(partition) else {
+            return;
+        };
+        if let Some((limiter, window_end)) = self.limiters.get_mut(partition) {
+            _ = limiter.call(());
+            *window_end = Instant::now() + rate.duration;
+        }
+        self.evict_expired();
+    }
+
+    /// Evicts the rate limits whose window has ended, as they would let as many batches through as
+    /// new ones, once there are enough of them for it to be worth it.
+    fn evict_expired(&mut self) {
+        if self.limiters.len() < self.evict_at {
+            return;
+        }
+        let now = Instant::now();
+        self.limiters.retain(|_, (_, window_end)| *window_end > now);
+        self.evict_at = (self.limiters.len() * 2).max(MIN_PARTITION_RATE_LIMITS_EVICTED);
+    }
+}
+
+/// A service which is always ready, wrapped in the rate limit of a partition so that its slots can
+/// be acquired without sending anything through it.
+struct Permit;
+
+impl Service<()> for Permit {
+    type Response = ();
+    type Error = Infallible;
+    type Future = future::Ready<Result<(), Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        future::ok(())
+    }
+}
+
 impl<S, B, K> PartitionBatchSink<S, B, K>
 where
     B: Batch,
@@ -211,17 +589,165 @@ where
             service: ServiceSink::new(service),
             buffer: None,
             batch: StatefulBatch::from(FinalizersBatch::from(batch)),
-            partitions: HashMap::new(),
+            partitions: IndexMap::new(),
             timeout,
             lingers: HashMap::new(),
             in_flight: None,
+            sent_partitions: None,
+            partition_concurrency: None,
+            backpressure: None,
+            max_partitions: None,
+            partition_order: false,
+            partition_priority: None,
+            partition_weights: None,
+            partition_capacity: None,
+            partition_rate_limits: None,
+            idle: None,
+            adaptive_timeout: None,
             closing: false,
         }
     }
 
+    /// Stops accepting events while `capacity` requests or more are in flight, across all
+    /// partitions.
+    ///
+    /// The returned signal reports whether that is currently the case. At least one request is
+    /// always let through.
+    pub fn with_backpressure(&mut self, capacity: usize) -> BackpressureSignal {
+        let signal = BackpressureSignal::new(capacity);
+        self.backpressure = Some(signal.clone());
+        signal
+    }
+
+    /// Limits the number of requests in flight for each partition, on top of the limit applied by
+    /// the service to all of them.
+    ///
+    /// This has no effect on ordered sinks, which already send one request per partition at a time.
+    pub fn limit_partition_concurrency(&mut self, limit: usize) {
+        self.partition_concurrency = Some(PartitionConcurrency {
+            limit,
+            in_flight: HashMap::new(),
+        });
+    }
+
+    /// Limits the number of partitions batched at once.
+    ///
+    /// Once the limit is reached, events for a new partition wait for the batch of the least
+    /// recently used partition to be sent, regardless of its size or linger timeout.
+    pub fn limit_partitions(&mut self, max_partitions: usize) {
+        self.max_partitions = Some(max_partitions.max(1));
+    }
+
+    /// Whether as many partitions are batched as allowed.
+    fn at_max_partitions(&self) -> bool {
+        self.max_partitions
+            .map_or(false, |max| self.partitions.len() >= max)
+    }
+
     /// Enforces per partition ordering of request.
+    ///
+    /// This also tracks the partitions that have been sent so far, so that the first batch of each
+    /// partition is retried with the initial batch retry settings, if any.
     pub fn ordered(&mut self) {
         self.in_flight = Some(HashMap::new());
+        self.sent_partitions = Some(SentPartitions::new(MAX_SENT_PARTITIONS));
+    }
+
+    /// Enforces per partition ordering of requests like [`Self::ordered`], and also sends the
+    /// batches of partitions in the order their first event arrived.
+    ///
+    /// A batch ready to be sent waits for the batches of the partitions whose first event arrived
+    /// before its own to be sent. With [`Self::limit_partitions`], the partition whose first event
+    /// arrived first is the one sent to make room for a new partition.
+    pub fn ordered_by_partition(&mut self) {
+        self.ordered();
+        self.partition_order = true;
+    }
+
+    /// Sends the batches ready at the same time from the partition of highest `priority` to the
+    /// one of lowest priority, rather than in the order their partitions were first batched.
+    ///
+    /// Each time a ready batch is left waiting while batches of higher priority are sent, the
+    /// priority of its partition is raised by one until that batch is sent, so that busy partitions
+    /// of higher priority can't starve it. This has no effect on sinks ordered with
+    /// [`Self::ordered_by_partition`].
+    pub fn with_partition_priority(&mut self, priority: impl Fn(&K) -> u8 + Send + Sync + 'static) {
+        self.partition_priority = Some(PartitionPriority {
+            priority: Box::new(priority),
+            passed_over: HashMap::new(),
+        });
+    }
+
+    /// Shares the batches sent between the partitions with a batch ready in proportion to their
+    /// `weight`, so that under contention for the service, a partition of weight 3 sends about
+    /// three batches for each one sent by a partition of weight 1.
+    ///
+    /// Partitions are scheduled by deficit round robin: each round, every partition with a batch
+    /// ready may send as many batches as its weight, at least one. This only balances the batches
+    /// sent while several partitions have one ready, and takes precedence over
+    /// [`Self::with_partition_priority`]. It has no effect on sinks ordered with
+    /// [`Self::ordered_by_partition`].
+    pub fn with_partition_weights(&mut self, weight: impl Fn(&K) -> u32 + Send + Sync + 'static) {
+        self.partition_weights = Some(PartitionWeights {
+            weight: Box::new(weight),
+            deficits: HashMap::new(),
+        });
+    }
+
+    /// Sends the batch of a partition as soon as its events add up to `capacity_bytes`, rather than
+    /// waiting for its linger timeout, so that a partition receiving large events can't hold on to
+    /// more than its share of memory.
+    ///
+    /// Events are counted by their byte size, which can differ from the encoded size the batch
+    /// itself is limited by.
+    pub fn with_partition_capacity(&mut self, capacity_bytes: u64) {
+        self.partition_capacity = Some(PartitionCapacity {
+            limit: capacity_bytes,
+            bytes: HashMap::new(),
+        });
+    }
+
+    /// Limits the rate at which the batches of each partition are sent, on top of the rate limit
+    /// applied by the service to all of them.
+    ///
+    /// Partitions are limited to their rate in `rates`, or to `default` if they have none, and
+    /// are not limited if there is no default either. A batch waiting for the rate limit of its
+    /// partition does not hold back the batches of other partitions, unless the sink is ordered
+    /// with [`Self::ordered_by_partition`].
+    pub fn with_partition_rate_limits(
+        &mut self,
+        rates: HashMap<K, PartitionRate>,
+        default: Option<PartitionRate>,
+    ) {
+        self.partition_rate_limits = Some(PartitionRateLimits::new(rates, default));
+    }
+
+    /// Sends the batch of a partition once it received no event for `idle`, rather than waiting
+    /// for its linger timeout.
+    pub fn flush_on_idle(&mut self, idle: Duration) {
+        self.idle = Some(PartitionIdle {
+            timeout: idle,
+            timers: HashMap::new(),
+        });
+    }
+
+    /// Adapts the batch timeout to how quickly batches fill up, between `min` and `max`, rather
+    /// than keeping it fixed.
+    ///
+    /// The timeout of each new batch is the moving average of the time batches took to fill up,
+    /// starting from the fixed timeout, so that it shortens while batches fill up quickly and
+    /// lengthens while they don't. Batches sent early, as the sink closes or once their
+    /// partition is idle, are not accounted for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn adaptive_timeout(&mut self, min: Duration, max: Duration) {
+        assert!(
+            min <= max,
+            "the minimum batch timeout must not exceed the maximum"
+        );
+        self.adaptive_timeout = Some(AdaptiveTimeout::new(min, max, self.timeout));
     }
 }
 
@@ -238,7 +764,20 @@ where
     type Error = crate::Error;
 
     fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        if self.buffer.is_some() {
+        let this = self.as_mut().project();
+        if let Some(backpressure) = this.backpressure.as_ref() {
+            backpressure.set_in_flight(this.service.poll_in_flight(cx));
+            if backpressure.is_full() {
+                return Poll::Pending;
+            }
+        }
+
+        // Partitions at capacity are sent right away, rather than once the events stop coming.
+        let at_capacity = self
+            .partition_capacity
+            .as_ref()
+            .map_or(false, PartitionCapacity::any_full);
+        if self.buffer.is_some() || at_capacity {
             match self.as_mut().poll_flush(cx) {
                 Poll::Ready(Ok(())) => {}
                 Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
@@ -259,6 +798,18 @@ where
     ) -> Result<(), Self::Error> {
         let partition = item.item.partition();
 
+        match self.partitions.get_index_of(&partition) {
+            Some(index) if self.max_partitions.is_some() && !self.partition_order => {
+                let last = self.partitions.len() - 1;
+                self.partitions.move_index(index, last);
+            }
+            None if self.at_max_partitions() => {
+                self.buffer = Some((partition, item));
+                return Ok(());
+            }
+            _ => {}
+        }
+
         let batch = loop {
             if let Some(batch) = self.partitions.get_mut(&partition) {
                 break batch;
@@ -267,12 +818,28 @@ where
             let batch = self.batch.fresh();
             self.partitions.insert(partition.clone(), batch);
 
-            let delay = sleep(self.timeout);
+            let timeout = match self.adaptive_timeout.as_mut() {
+                Some(adaptive) => {
+                    adaptive.started(partition.clone());
+                    adaptive.timeout()
+                }
+                None => self.timeout,
+            };
+            let delay = sleep(timeout);
             self.lingers.insert(partition.clone(), Box::pin(delay));
         };
 
-        if let PushResult::Overflow(item) = batch.push(item) {
-            self.buffer = Some((partition, item));
+        let byte_size = item.byte_size as u64;
+        match batch.push(item) {
+            PushResult::Overflow(item) => self.buffer = Some((partition, item)),
+            PushResult::Ok(_) => {
+                if let Some(idle) = self.idle.as_mut() {
+                    idle.received(&partition);
+                }
+                if let Some(capacity) = self.partition_capacity.as_mut() {
+                    *capacity.bytes.entry(partition).or_default() += byte_size;
+                }
+            }
         }
 
         Ok(())
@@ -287,29 +854,80 @@ where
             }
 
             // Try send batches.
+            // An event for a new partition waits for the least recently used one to be sent.
+            let evict = self.at_max_partitions()
+                && self.buffer.as_ref().map_or(false, |(partition, _)| {
+                    !self.partitions.contains_key(partition)
+                });
             let this = self.as_mut().project();
             let mut partitions_ready = vec![];
-            for (partition, batch) in this.partitions.iter() {
-                if ((*this.closing && !batch.is_empty())
-                    || batch.was_full()
+            // The partitions sent before their batch filled up or timed out, which say nothing of
+            // how quickly batches fill up.
+            let mut flushed_early = vec![];
+            for (index, (partition, batch)) in this.partitions.iter().enumerate() {
+                let filled_or_timed_out = batch.was_full()
+                    || this
+                        .partition_capacity
+                        .as_ref()
+                        .map_or(false, |capacity| capacity.is_full(partition))
                     || matches!(
                         this.lingers
                             .get_mut(partition)
                             .expect("linger should exists for poll_flush")
                             .poll_unpin(cx),
                         Poll::Ready(())
-                    ))
+                    );
+                let flushed = !filled_or_timed_out
+                    && ((*this.closing && !batch.is_empty())
+                        || (evict && index == 0)
+                        || this
+                            .idle
+                            .as_mut()
+                            .map_or(false, |idle| idle.poll_is_idle(partition, cx)));
+                let ready = (filled_or_timed_out || flushed)
                     && this
                         .in_flight
                         .as_mut()
                         .and_then(|map| map.get_mut(partition))
                         .map(|req| matches!(req.poll_unpin(cx), Poll::Ready(())))
                         .unwrap_or(true)
-                {
+                    && this
+                        .partition_concurrency
+                        .as_mut()
+                        .map_or(true, |concurrency| {
+                            concurrency.poll_has_capacity(partition, cx)
+                        })
+                    && this
+                        .partition_rate_limits
+                        .as_mut()
+                        .map_or(true, |rate_limits| {
+                            rate_limits.poll_has_capacity(partition, cx)
+                        });
+                if ready {
+                    if flushed && this.adaptive_timeout.is_some() {
+                        flushed_early.push(partition.clone());
+                    }
                     partitions_ready.push(partition.clone());
+                } else if *this.partition_order {
+                    // Later partitions wait for this one to be sent first.
+                    break;
                 }
             }
+            let mut weights = this
+                .partition_weights
+                .as_mut()
+                .filter(|_| !*this.partition_order);
+            let priority = this
+                .partition_priority
+                .as_mut()
+                .filter(|_| !*this.partition_order && weights.is_none());
+            if let Some(weights) = weights.as_mut() {
+                weights.schedule(&mut partitions_ready);
+            } else if let Some(priority) = priority.as_ref() {
+                priority.sort(&mut partitions_ready);
+            }
             let mut batch_consumed = false;
+            let mut sent = 0;
             for partition in partitions_ready.iter() {
                 let service_ready = match this.service.poll_ready(cx) {
                     Poll::Ready(Ok(())) => true,
@@ -319,21 +937,72 @@ where
                 if service_ready {
                     trace!("Service ready; Sending batch.");
 
-                    let batch = this.partitions.remove(partition).unwrap();
+                    // The order of partitions only matters to find the least recently used one,
+                    // or the one whose first event arrived first.
+                    let batch = if this.max_partitions.is_some() || *this.partition_order {
+                        this.partitions.shift_remove(partition)
+                    } else {
+                        this.partitions.swap_remove(partition)
+                    }
+                    .unwrap();
                     this.lingers.remove(partition);
+                    if let Some(adaptive) = this.adaptive_timeout.as_mut() {
+                        if flushed_early.contains(partition) {
+                            adaptive.discard(partition);
+                        } else {
+                            adaptive.sent(partition, batch.was_full());
+                        }
+                    }
+                    if let Some(idle) = this.idle.as_mut() {
+                        idle.timers.remove(partition);
+                    }
+                    if let Some(capacity) = this.partition_capacity.as_mut() {
+                        capacity.bytes.remove(partition);
+                    }
+                    if let Some(rate_limits) = this.partition_rate_limits.as_mut() {
+                        rate_limits.sent(partition);
+                    }
 
                     let batch = batch.finish();
-                    let future = tokio::spawn(this.service.call(batch));
+                    let is_initial_batch = this
+                        .sent_partitions
+                        .as_mut()
+                        .map_or(false, |sent| sent.insert(partition));
+                    let future = if is_initial_batch {
+                        tokio::spawn(send_initial_batch(|| this.service.call(batch)))
+                    } else {
+                        tokio::spawn(this.service.call(batch))
+                    };
 
                     if let Some(map) = this.in_flight.as_mut() {
                         map.insert(partition.clone(), future.map(|_| ()).fuse().boxed());
+                    } else if let Some(concurrency) = this.partition_concurrency.as_mut() {
+                        concurrency
+                            .in_flight
+                            .entry(partition.clone())
+                            .or_default()
+                            .push(future.map(|_| ()).boxed());
                     }
 
                     batch_consumed = true;
+                    sent += 1;
                 } else {
                     break;
                 }
             }
+            if let Some(weights) = weights {
+                for partition in &partitions_ready[..sent] {
+                    weights.sent(partition);
+                }
+            }
+            if let Some(priority) = priority {
+                for partition in &partitions_ready[..sent] {
+                    priority.sent(partition);
+                }
+                if sent > 0 {
+                    priority.passed_over(&partitions_ready[sent..]);
+                }
+            }
             if batch_consumed {
                 continue;
             }
@@ -349,10 +1018,16 @@ where
                     });
                 }
             }
+            if let Some(concurrency) = this.partition_concurrency.as_mut() {
+                concurrency.in_flight.retain(|_, requests| {
+                    while let Poll::Ready(Some(())) = requests.poll_next_unpin(cx) {}
+                    !requests.is_empty()
+                });
+            }
 
             // Try move item from buffer to batch.
             if let Some((partition, item)) = self.buffer.take() {
-                if self.partitions.contains_key(&partition) {
+                if self.partitions.contains_key(&partition) || self.at_max_partitions() {
                     self.buffer = Some((partition, item));
                 } else {
                     self.as_mut().start_send(item)?;
@@ -378,6 +1053,29 @@ where
     }
 }
 
+impl<S, B, K> PartitionBatchSink<S, B, K>
+where
+    B: Batch,
+    B::Input: Partition<K>,
+    K: Hash + Eq + Clone + Send + 'static,
+    S: Service<B::Output>,
+    S::Future: Send + 'static,
+    S::Error: Into<crate::Error> + Send + 'static,
+    S::Response: Response + Send + 'static,
+{
+    /// Consumes the sink, sending the batches of every partition regardless of their linger
+    /// timeout, and completes once all requests in flight have completed.
+    ///
+    /// This is what closing the sink through [`futures::SinkExt::close`] does, without leaving a
+    /// closed sink behind, so that batches which were not sent yet can't be lost by dropping the
+    /// sink before it is done closing.
+    pub fn drain(self) -> DrainFuture<S, B, K> {
+        DrainFuture {
+            sink: Box::pin(self),
+        }
+    }
+}
+
 impl<S, B, K> fmt::Debug for PartitionBatchSink<S, B, K>
 where
     S: Service<B::Output> + fmt::Debug,
@@ -392,6 +1090,66 @@ where
     }
 }
 
+// === DrainFuture ===
+
+/// Drives a [`PartitionBatchSink`] being closed until all of its batches are sent and their
+/// requests have completed.
+///
+/// Returned by [`PartitionBatchSink::drain`].
+#[must_use = "futures do nothing unless polled"]
+pub struct DrainFuture<S, B, K>
+where
+    B: Batch,
+    S: Service<B::Output>,
+{
+    sink: Pin<Box<PartitionBatchSink<S, B, K>>>,
+}
+
+impl<S, B, K> Future for DrainFuture<S, B, K>
+where
+    B: Batch,
+    B::Input: Partition<K>,
+    K: Hash + Eq + Clone + Send + 'static,
+    S: Service<B::Output>,
+    S::Future: Send + 'static,
+    S::Error: Into<crate::Error> + Send + 'static,
+    S::Response: Response + Send + 'static,
+{
+    type Output = crate::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.sink.as_mut().poll_close(cx)
+    }
+}
+
+// === Backpressure ===
+
+/// Reports whether a batch sink has stopped accepting events because too many of its requests are
+/// in flight.
+#[derive(Clone, Debug)]
+pub struct BackpressureSignal {
+    capacity: usize,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl BackpressureSignal {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            in_flight: Arc::default(),
+        }
+    }
+
+    fn set_in_flight(&self, in_flight: usize) {
+        self.in_flight.store(in_flight, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the sink is not accepting events until some of its requests complete.
+    pub fn is_full(&self) -> bool {
+        self.in_flight.load(Ordering::Relaxed) >= self.capacity
+    }
+}
+
 // === ServiceSink ===
 
 struct ServiceSink<S, Request> {
@@ -426,8 +1184,8 @@ where
             items,
             finalizers,
             count,
+            byte_size,
             json_byte_size,
-            ..
         } = batch;
 
         let (tx, rx) = oneshot::channel();
@@ -442,8 +1200,7 @@ where
             in_flight_requests = self.in_flight.len()
         );
         let events_sent = register!(EventsSent::from(Output(None)));
-        self.service
-            .call(items)
+        send_batch(byte_size, || self.service.call(items))
             .err_into()
             .map(move |result| {
                 let status = result_status(&result);
@@ -475,6 +1232,16 @@ where
             .boxed()
     }
 
+    /// Forgets the requests which completed, and returns the number of requests still in flight.
+    ///
+    /// The waker is notified when another request completes.
+    fn poll_in_flight(&mut self, cx: &mut Context<'_>) -> usize {
+        while let Poll::Ready(Some(result)) = Pin::new(&mut self.in_flight).poll_next(cx) {
+            result.expect("ServiceSink service sender dropped.");
+        }
+        self.in_flight.len()
+    }
+
     fn poll_complete(&mut self, cx: &mut Context<'_>) -> Poll<()> {
         while !self.in_flight.is_empty() {
             match ready!(Pin::new(&mut self.in_flight).poll_next(cx)) {
@@ -575,7 +1342,7 @@ mod tests {
 
     use bytes::Bytes;
     use futures::{future, stream, task::noop_waker_ref, SinkExt, StreamExt};
-    use tokio::{task::yield_now, time::Instant};
+    use tokio::{sync::Semaphore, task::yield_now, time::Instant};
     use vector_lib::{
         finalization::{BatchNotifier, BatchStatus, EventFinalizer, EventFinalizers},
         json_size::JsonSize,
@@ -754,6 +1521,54 @@ mod tests {
         assert_eq!(ack_counter.load(Relaxed), 21);
     }
 
+    #[tokio::test]
+    async fn batch_sink_applies_backpressure_at_capacity() {
+        let permits = Arc::new(Semaphore::new(0));
+        let svc = {
+            let permits = Arc::clone(&permits);
+            tower::service_fn(move |_: Vec<usize>| {
+                let permits = Arc::clone(&permits);
+                async move {
+                    permits.acquire().await.unwrap().forget();
+                    Ok::<(), Infallible>(())
+                }
+            })
+        };
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 1;
+
+        let (mut sink, backpressure) =
+            BatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT).with_backpressure(2);
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        for item in 0..2 {
+            assert!(matches!(
+                sink.poll_ready_unpin(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            assert!(matches!(
+                sink.start_send_unpin(EncodedEvent::new(item, 0, JsonSize::zero())),
+                Ok(())
+            ));
+            assert!(matches!(sink.poll_flush_unpin(&mut cx), Poll::Pending));
+        }
+
+        // Both requests are in flight, so the source has to wait.
+        assert!(sink.poll_ready_unpin(&mut cx).is_pending());
+        assert!(backpressure.is_full());
+
+        permits.add_permits(1);
+        yield_now().await;
+
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert!(!backpressure.is_full());
+    }
+
     #[tokio::test]
     async fn batch_sink_buffers_messages_until_limit() {
         let sent_requests = Arc::new(Mutex::new(Vec::new()));
@@ -876,6 +1691,143 @@ mod tests {
         assert_eq!(&*output, &vec![vec![0, 1]]);
     }
 
+    #[tokio::test]
+    async fn batch_sink_flushes_on_idle() {
+        tokio::time::pause();
+
+        let start = Instant::now();
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req| {
+            sent_requests
+                .lock()
+                .unwrap()
+                .push((req, start.elapsed().as_millis()));
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+        let buffered = BatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT)
+            .flush_on_idle(Duration::from_secs(1));
+
+        // Events arrive every 500ms, with a pause of 3s after the first three.
+        let input = stream::iter(0..6).then(|item| async move {
+            let delay = if item == 3 { 3000 } else { 500 };
+            sleep(Duration::from_millis(delay)).await;
+            Ok(EncodedEvent::new(item, 0, JsonSize::zero()))
+        });
+        buffered
+            .sink_map_err(drop)
+            .send_all(&mut Box::pin(input))
+            .await
+            .unwrap();
+
+        // Each batch is sent 1s after its last event, well before the batch timeout.
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(
+            &*output,
+            &vec![(vec![0, 1, 2], 2500), (vec![3, 4, 5], 6500)]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_sink_shortens_adaptive_timeout_while_batches_fill_quickly() {
+        tokio::time::pause();
+
+        let start = Instant::now();
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req| {
+            sent_requests
+                .lock()
+                .unwrap()
+                .push((req, start.elapsed().as_millis()));
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 2;
+        let buffered = BatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT)
+            .adaptive_timeout(Duration::from_secs(1), Duration::from_secs(20));
+
+        buffered
+            .sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(0..41)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        // The batches before it filled up right away, so the last one waits for the minimum
+        // timeout rather than the fixed one.
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(output.len(), 21);
+        assert_eq!(output[20], (vec![40], 1000));
+    }
+
+    #[tokio::test]
+    async fn adaptive_timeout_lengthens_while_batches_do_not_fill() {
+        tokio::time::pause();
+
+        let mut adaptive = AdaptiveTimeout::new(
+            Duration::from_secs(1),
+            Duration::from_secs(20),
+            Duration::from_secs(5),
+        );
+        for _ in 0..10 {
+            let timeout = adaptive.timeout();
+            adaptive.started(());
+            tokio::time::advance(timeout).await;
+            adaptive.sent(&(), false);
+        }
+        assert_eq!(adaptive.timeout(), Duration::from_secs(20));
+
+        // Batches filling up right away bring it back down.
+        for _ in 0..5 {
+            adaptive.started(());
+            adaptive.sent(&(), true);
+        }
+        assert!(adaptive.timeout() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn adaptive_timeout_ignores_batches_flushed_early() {
+        tokio::time::pause();
+
+        let svc = tower::service_fn(|_req: Vec<usize>| future::ok::<_, std::io::Error>(()));
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+        let mut buffered = BatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT)
+            .flush_on_idle(Duration::from_millis(100))
+            .adaptive_timeout(Duration::from_secs(1), Duration::from_secs(20));
+
+        for item in 0..3 {
+            buffered
+                .send(EncodedEvent::new(item, 0, JsonSize::zero()))
+                .await
+                .unwrap();
+        }
+
+        // The batches were sent once idle, long before they would have filled up or timed out.
+        let adaptive = buffered.inner.adaptive_timeout.as_ref().unwrap();
+        assert_eq!(adaptive.timeout(), TIMEOUT);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not exceed the maximum")]
+    fn adaptive_timeout_rejects_a_minimum_above_the_maximum() {
+        let svc = tower::service_fn(|_req: Vec<usize>| future::ok::<_, std::io::Error>(()));
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.events = 10;
+        _ = BatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT)
+            .adaptive_timeout(Duration::from_secs(20), Duration::from_secs(1));
+    }
+
     #[tokio::test]
     async fn partition_batch_sink_buffers_messages_until_limit() {
         let sent_requests = Arc::new(Mutex::new(Vec::new()));
@@ -1123,6 +2075,419 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn partition_batch_sink_ordering_by_partition() {
+        tokio::time::pause();
+
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            sent_requests.lock().unwrap().push(req);
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 3;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.ordered_by_partition();
+
+        // The batches of partitions 2 and then 1 fill up first, but wait for the batch of
+        // partition 0 to be sent after its linger timeout.
+        let input = [(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (2, 2), (1, 2)];
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(
+            &*output,
+            &vec![
+                vec![(0, 0)],
+                vec![(1, 0), (1, 1), (1, 2)],
+                vec![(2, 0), (2, 1), (2, 2)],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_sends_higher_priority_partitions_first() {
+        tokio::time::pause();
+
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            sent_requests.lock().unwrap().push(req);
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 3;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.with_partition_priority(|&partition| partition as u8);
+
+        // Every batch is ready once the sink is closed.
+        let input = [(0, 0), (1, 0), (2, 0)];
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(&*output, &vec![vec![(2, 0)], vec![(1, 0)], vec![(0, 0)]]);
+    }
+
+    #[test]
+    fn partition_priority_raises_passed_over_partitions() {
+        let mut priority = PartitionPriority {
+            priority: Box::new(|&partition: &usize| if partition == 0 { 0 } else { 2 }),
+            passed_over: HashMap::new(),
+        };
+
+        for _ in 0..2 {
+            let mut ready = [0, 1];
+            priority.sort(&mut ready);
+            assert_eq!(ready, [1, 0]);
+            priority.sent(&1);
+            priority.passed_over(&[0]);
+        }
+
+        // Partition 0 has caught up with partition 1, and was batched first.
+        let mut ready = [0, 1];
+        priority.sort(&mut ready);
+        assert_eq!(ready, [0, 1]);
+
+        // Its priority goes back down once its batch is sent.
+        priority.sent(&0);
+        priority.sort(&mut ready);
+        assert_eq!(ready, [1, 0]);
+    }
+
+    #[test]
+    fn sent_partitions_forget_the_earliest_partitions_past_capacity() {
+        let mut sent = SentPartitions::new(2);
+        assert!(sent.insert(&0));
+        assert!(sent.insert(&1));
+        assert!(!sent.insert(&0));
+
+        // Partition 0 is forgotten to make room for partition 2.
+        assert!(sent.insert(&2));
+        assert_eq!(sent.sent.len(), 2);
+        assert!(!sent.insert(&1));
+        assert!(sent.insert(&0));
+    }
+
+    #[test]
+    fn partition_weights_share_sends_in_proportion_under_saturation() {
+        let mut weights = PartitionWeights {
+            weight: Box::new(|&partition: &usize| if partition == 0 { 3 } else { 1 }),
+            deficits: HashMap::new(),
+        };
+
+        // Both partitions always have a batch ready, but only one can be sent at a time.
+        let mut sent = [0; 2];
+        for _ in 0..400 {
+            let mut ready = vec![0, 1];
+            weights.schedule(&mut ready);
+            weights.sent(&ready[0]);
+            sent[ready[0]] += 1;
+        }
+        assert_eq!(sent, [300, 100]);
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_shares_a_saturated_service_by_weight() {
+        // The service only accepts a request once allowed to, one at a time.
+        let (svc, mut handle) = tower_test::mock::pair::<Vec<(usize, usize)>, ()>();
+        handle.allow(0);
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 1;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.with_partition_weights(|&partition| if partition == 0 { 3 } else { 1 });
+        // Weights take precedence over priority, which would favor partition 1.
+        sink.with_partition_priority(|&partition| partition as u8);
+        let mut sink = Box::pin(sink);
+
+        // Both partitions always have a full batch ready, but only one can be sent at a time.
+        let event = |partition| EncodedEvent::new((partition, 0), 0, JsonSize::zero());
+        for partition in [0, 1] {
+            sink.as_mut().start_send(event(partition)).unwrap();
+        }
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut sent = [0; 2];
+        for _ in 0..400 {
+            handle.allow(1);
+            assert!(sink.as_mut().poll_flush(&mut cx).is_pending());
+
+            let (request, response) = handle.next_request().await.unwrap();
+            response.send_response(());
+            let partition = request[0].0;
+            sent[partition] += 1;
+            sink.as_mut().start_send(event(partition)).unwrap();
+        }
+        assert_eq!(sent, [300, 100]);
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_limits_concurrency_per_partition() {
+        tokio::time::pause();
+
+        // The current and maximum number of requests in flight, per partition and in total.
+        let in_flight = Arc::new(Mutex::new([0; 3]));
+        let max_in_flight = Arc::new(Mutex::new([0; 3]));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            let partition = req[0].0;
+            {
+                let mut in_flight = in_flight.lock().unwrap();
+                let mut max_in_flight = max_in_flight.lock().unwrap();
+                for i in [partition, 2] {
+                    in_flight[i] += 1;
+                    max_in_flight[i] = max_in_flight[i].max(in_flight[i]);
+                }
+            }
+
+            let in_flight = Arc::clone(&in_flight);
+            sleep(Duration::from_secs(1))
+                .map(move |_| {
+                    let mut in_flight = in_flight.lock().unwrap();
+                    in_flight[partition] -= 1;
+                    in_flight[2] -= 1;
+                    Result::<_, std::io::Error>::Ok(())
+                })
+                .boxed()
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.limit_partition_concurrency(2);
+
+        let input = (0..40).map(|i| (0, i)).chain((0..10).map(|i| (1, i)));
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        // The busy partition never has more than two requests in flight, and does not hold back
+        // the other one.
+        assert_eq!(*max_in_flight.lock().unwrap(), [2, 1, 3]);
+    }
+
+    const fn rate(num: u64) -> PartitionRate {
+        PartitionRate {
+            num,
+            duration: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_limits_rate_per_partition() {
+        tokio::time::pause();
+
+        let start = Instant::now();
+        let sent_at = Arc::new(Mutex::new([Vec::new(), Vec::new()]));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            sent_at.lock().unwrap()[req[0].0].push(start.elapsed().as_secs());
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 1;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.with_partition_rate_limits(HashMap::from([(1, rate(3))]), Some(rate(1)));
+
+        let input = (0..6).map(|i| (1, i)).chain((0..3).map(|i| (0, i)));
+        sink.sink_map_err(drop)
+            .send_all(
+                &mut stream::iter(input)
+                    .map(|item| Ok(EncodedEvent::new(item, 0, JsonSize::zero()))),
+            )
+            .await
+            .unwrap();
+
+        // Partition 1 sends three batches a second, while partition 0 falls back to the default
+        // of one batch a second.
+        let sent_at = sent_at.lock().unwrap();
+        assert_eq!(sent_at[1].len(), 6);
+        assert_eq!(sent_at[1][..3], [0, 0, 0]);
+        assert_eq!(sent_at[0].len(), 3);
+        assert_eq!(sent_at[0][..2], [1, 2]);
+        for (partition, rate) in [(0, 1), (1, 3)] {
+            for second in sent_at[partition].iter() {
+                let sent = sent_at[partition].iter().filter(|s| *s == second).count();
+                assert!(
+                    sent <= rate,
+                    "partition {partition} sent {sent} batches in a second"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn partition_rate_limits_evict_expired_windows() {
+        tokio::time::pause();
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut rate_limits = PartitionRateLimits::new(HashMap::new(), Some(rate(1)));
+        let mut send = |partitions: std::ops::Range<usize>| {
+            for partition in partitions {
+                assert!(rate_limits.poll_has_capacity(&partition, &mut cx));
+                rate_limits.sent(&partition);
+            }
+        };
+
+        send(0..100);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        send(100..200);
+
+        // The rate limits of the first partitions were evicted once their window ended.
+        assert!(rate_limits.limiters.len() <= 100);
+        assert!(rate_limits.limiters.contains_key(&199));
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_limits_partitions() {
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            sent_requests.lock().unwrap().push(req);
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.limit_partitions(10);
+
+        let mut max_partitions = 0;
+        for partition in 0..1000 {
+            future::poll_fn(|cx| sink.poll_ready_unpin(cx))
+                .await
+                .unwrap();
+            sink.start_send_unpin(EncodedEvent::new((partition, 0), 0, JsonSize::zero()))
+                .unwrap();
+            max_partitions = max_partitions.max(sink.partitions.len());
+        }
+        sink.close().await.unwrap();
+
+        assert_eq!(max_partitions, 10);
+        let output = sent_requests.lock().unwrap();
+        assert_eq!(output.len(), 1000);
+        assert!(output.iter().all(|req| req.len() == 1));
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_drain_delivers_every_partition() {
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            let sent_requests = Arc::clone(&sent_requests);
+            async move {
+                // Requests complete after the sink is asked to close.
+                yield_now().await;
+                sent_requests.lock().unwrap().push(req);
+                Ok::<_, std::io::Error>(())
+            }
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        for partition in 0..3 {
+            for item in 0..2 {
+                future::poll_fn(|cx| sink.poll_ready_unpin(cx))
+                    .await
+                    .unwrap();
+                sink.start_send_unpin(EncodedEvent::new((partition, item), 0, JsonSize::zero()))
+                    .unwrap();
+            }
+        }
+        assert!(sent_requests.lock().unwrap().is_empty());
+
+        sink.drain().await.unwrap();
+
+        let mut output = sent_requests.lock().unwrap().clone();
+        output.sort();
+        assert_eq!(
+            output,
+            vec![
+                vec![(0, 0), (0, 1)],
+                vec![(1, 0), (1, 1)],
+                vec![(2, 0), (2, 1)]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn partition_batch_sink_sends_partitions_at_capacity() {
+        let sent_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let svc = tower::service_fn(|req: Vec<(usize, usize)>| {
+            sent_requests.lock().unwrap().push(req);
+            future::ok::<_, std::io::Error>(())
+        });
+
+        let mut batch_settings = BatchSettings::default();
+        batch_settings.size.bytes = 9999;
+        batch_settings.size.events = 10;
+
+        let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch_settings.size), TIMEOUT);
+        sink.with_partition_capacity(100);
+
+        for (partition, byte_size) in [(0, 60), (1, 1), (0, 60), (1, 1)] {
+            future::poll_fn(|cx| sink.poll_ready_unpin(cx))
+                .await
+                .unwrap();
+            sink.start_send_unpin(EncodedEvent::new(
+                (partition, byte_size),
+                byte_size,
+                JsonSize::zero(),
+            ))
+            .unwrap();
+        }
+
+        // The first partition is sent as soon as it reaches its capacity, while the second one
+        // waits for its linger timeout.
+        future::poll_fn(|cx| sink.poll_ready_unpin(cx))
+            .await
+            .unwrap();
+        assert_eq!(*sent_requests.lock().unwrap(), vec![vec![(0, 60), (0, 60)]]);
+
+        advance_time(TIMEOUT + Duration::from_secs(1)).await;
+        sink.flush().await.unwrap();
+        assert_eq!(
+            *sent_requests.lock().unwrap(),
+            vec![vec![(0, 60), (0, 60)], vec![(1, 1), (1, 1)]]
+        );
+    }
+
     #[derive(Debug, PartialEq, Eq, Ord, PartialOrd)]
     enum Partitions {
         A,
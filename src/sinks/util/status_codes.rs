@@ -0,0 +1,250 @@
+//! Ranges of HTTP status codes, as configured by operators, such as `429` or `500-599`.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use snafu::Snafu;
+use vector_lib::configurable::configurable_component;
+
+/// The HTTP status codes which exist, as ranges are rejected beyond them.
+const STATUS_CODES: std::ops::RangeInclusive<u16> = 100..=599;
+
+/// An HTTP status code, such as `429`, or an inclusive range of them, such as `"500-599"`.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(try_from = "StatusCodeSpec", into = "StatusCodeSpec")]
+pub struct StatusCodeRange {
+    /// The first status code of the range.
+    start: u16,
+
+    /// The last status code of the range, included.
+    end: u16,
+}
+
+/// An HTTP status code or range of them, as written in the configuration.
+#[configurable_component]
+#[derive(Clone, Debug)]
+#[serde(untagged)]
+pub enum StatusCodeSpec {
+    /// A single status code, such as `429`.
+    Code(u16),
+
+    /// A single status code, or an inclusive range of them, such as `"500-599"`.
+    Range(String),
+}
+
+/// A range of HTTP status codes which could not be parsed or combined with others.
+#[derive(Debug, Eq, PartialEq, Snafu)]
+pub enum StatusCodeRangeError {
+    #[snafu(display(
+        "`{spec}` is not a status code or a range of status codes such as `500-599`"
+    ))]
+    InvalidSpec { spec: String },
+    #[snafu(display("`{code}` is not an HTTP status code, which range from 100 to 599"))]
+    InvalidStatusCode { code: u16 },
+    #[snafu(display("range `{range}` ends before it starts"))]
+    ReversedRange { range: StatusCodeRange },
+    #[snafu(display("ranges `{first}` and `{second}` overlap"))]
+    OverlappingRanges {
+        first: StatusCodeRange,
+        second: StatusCodeRange,
+    },
+}
+
+impl StatusCodeRange {
+    /// The range made of the single status code `code`.
+    pub const fn single(code: u16) -> Self {
+        Self {
+            start: code,
+            end: code,
+        }
+    }
+
+    /// Whether `status` is in the range.
+    pub const fn contains(&self, status: u16) -> bool {
+        self.start <= status && status <= self.end
+    }
+}
+
+impl FromStr for StatusCodeRange {
+    type Err = StatusCodeRangeError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parse = |code: &str| {
+            code.trim().parse::<u16>().map_err(|_| {
+                InvalidSpecSnafu {
+                    spec: spec.to_owned(),
+                }
+                .build()
+            })
+        };
+        let range = match spec.split_once('-') {
+            Some((start, end)) => Self {
+                start: parse(start)?,
+                end: parse(end)?,
+            },
+            None => Self::single(parse(spec)?),
+        };
+
+        for code in [range.start, range.end] {
+            if !STATUS_CODES.contains(&code) {
+                return InvalidStatusCodeSnafu { code }.fail();
+            }
+        }
+        if range.start > range.end {
+            return ReversedRangeSnafu { range }.fail();
+        }
+        Ok(range)
+    }
+}
+
+impl TryFrom<StatusCodeSpec> for StatusCodeRange {
+    type Error = StatusCodeRangeError;
+
+    fn try_from(spec: StatusCodeSpec) -> Result<Self, Self::Error> {
+        match spec {
+            StatusCodeSpec::Code(code) if STATUS_CODES.contains(&code) => Ok(Self::single(code)),
+            StatusCodeSpec::Code(code) => InvalidStatusCodeSnafu { code }.fail(),
+            StatusCodeSpec::Range(spec) => spec.parse(),
+        }
+    }
+}
+
+impl From<StatusCodeRange> for StatusCodeSpec {
+    fn from(range: StatusCodeRange) -> Self {
+        if range.start == range.end {
+            Self::Code(range.start)
+        } else {
+            Self::Range(range.to_string())
+        }
+    }
+}
+
+impl fmt::Display for StatusCodeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// Matches HTTP status codes against a set of ranges.
+///
+/// Clones share the same ranges.
+#[derive(Clone, Debug, Default)]
+pub struct StatusCodeRanges {
+    ranges: Arc<[StatusCodeRange]>,
+}
+
+impl StatusCodeRanges {
+    /// Builds a matcher from `ranges`, rejecting ranges which overlap, as they are more likely a
+    /// mistake than intended.
+    pub fn new(ranges: &[StatusCodeRange]) -> Result<Self, StatusCodeRangeError> {
+        let mut sorted = ranges.to_vec();
+        sorted.sort_by_key(|range| range.start);
+        if let Some(pair) = sorted.windows(2).find(|pair| pair[0].end >= pair[1].start) {
+            return OverlappingRangesSnafu {
+                first: pair[0],
+                second: pair[1],
+            }
+            .fail();
+        }
+        Ok(Self {
+            ranges: sorted.into(),
+        })
+    }
+
+    /// Adds the single status codes `codes` to the ranges matched, whether or not they overlap with
+    /// them.
+    pub fn with_codes(self, codes: &[u16]) -> Self {
+        let ranges = self
+            .ranges
+            .iter()
+            .copied()
+            .chain(codes.iter().copied().map(StatusCodeRange::single))
+            .collect();
+        Self { ranges }
+    }
+
+    /// Whether `status` is in any of the ranges.
+    pub fn contains(&self, status: u16) -> bool {
+        self.ranges.iter().any(|range| range.contains(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(specs: &[&str]) -> Result<StatusCodeRanges, StatusCodeRangeError> {
+        let ranges = specs
+            .iter()
+            .map(|spec| spec.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        StatusCodeRanges::new(&ranges)
+    }
+
+    #[test]
+    fn ranges_match_the_codes_they_span() {
+        let ranges = ranges(&["429", "500-599"]).unwrap();
+
+        for status in [429, 500, 503, 599] {
+            assert!(ranges.contains(status), "{status}");
+        }
+        for status in [200, 428, 430, 499] {
+            assert!(!ranges.contains(status), "{status}");
+        }
+        assert!(ranges.with_codes(&[404]).contains(404));
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        assert!(matches!(
+            ranges(&["5xx"]),
+            Err(StatusCodeRangeError::InvalidSpec { .. })
+        ));
+        assert!(matches!(
+            ranges(&["500-"]),
+            Err(StatusCodeRangeError::InvalidSpec { .. })
+        ));
+        assert!(matches!(
+            ranges(&["99"]),
+            Err(StatusCodeRangeError::InvalidStatusCode { code: 99 })
+        ));
+        assert!(matches!(
+            ranges(&["599-500"]),
+            Err(StatusCodeRangeError::ReversedRange { .. })
+        ));
+        assert_eq!(
+            ranges(&["500-599", "429", "503"]).unwrap_err().to_string(),
+            "ranges `500-599` and `503` overlap"
+        );
+    }
+
+    #[test]
+    fn ranges_are_deserialized_from_codes_and_strings() {
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct Config {
+            codes: Vec<StatusCodeRange>,
+        }
+
+        let config: Config = toml::from_str(r#"codes = [429, "503", "500-599"]"#).unwrap();
+        assert_eq!(
+            config.codes,
+            [
+                StatusCodeRange::single(429),
+                StatusCodeRange::single(503),
+                "500-599".parse().unwrap()
+            ]
+        );
+        assert!(toml::from_str::<Config>("codes = [600]").is_err());
+        assert!(toml::from_str::<Config>(r#"codes = ["600"]"#).is_err());
+
+        // Single status codes are written back as numbers.
+        assert_eq!(
+            toml::to_string(&config).unwrap().trim(),
+            r#"codes = [429, 503, "500-599"]"#
+        );
+    }
+}
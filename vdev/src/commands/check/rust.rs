@@ -17,7 +17,7 @@ impl Cli {
     pub fn exec(self) -> Result<()> {
         let features = self.features.join(",");
         let features = if self.features.is_empty() {
-            "default,all-integration-tests"
+            "default,all-integration-tests,hot-reload"
         } else {
             &features
         };
@@ -677,5 +677,13 @@ components: sinks: [Name=string]: {
 		component_sent_events_total:          components.sources.internal_metrics.output.metrics.component_sent_events_total
 		component_sent_event_bytes_total:     components.sources.internal_metrics.output.metrics.component_sent_event_bytes_total
 		utilization:                          components.sources.internal_metrics.output.metrics.utilization
+
+		if features.send != _|_ {
+			if features.send.request.enabled {
+				in_flight_requests:    components.sources.internal_metrics.output.metrics.in_flight_requests
+				request_retries_total: components.sources.internal_metrics.output.metrics.request_retries_total
+				retry_exhausted_total: components.sources.internal_metrics.output.metrics.retry_exhausted_total
+			}
+		}
 	}
 }
@@ -607,6 +607,18 @@ base: components: sinks: aws_cloudwatch_logs: configuration: {
 					}
 				}
 			}
+			propagate_deadline: {
+				description: """
+					Whether `timeout_secs` bounds the request as a whole, including all of its retries.
+
+					When enabled, each retry attempt is only given the time remaining out of `timeout_secs`
+					since the request was first attempted, rather than a full `timeout_secs` of its own, and no
+					further attempts are made once that time has run out. This has no effect if
+					`request_deadline_secs` is set, as that already bounds the request as a whole.
+					"""
+				required: false
+				type: bool: {}
+			}
 			rate_limit_duration_secs: {
 				description: "The time window used for the `rate_limit_num` option."
 				required:    false
@@ -623,6 +635,17 @@ base: components: sinks: aws_cloudwatch_logs: configuration: {
 					unit:    "requests"
 				}
 			}
+			request_deadline_secs: {
+				description: """
+					The maximum amount of time a request can take in total, including all of its retries.
+
+					Once the deadline is reached, the request is aborted and no further retries are attempted.
+					The `timeout_secs` option still bounds each individual attempt. If not set, there is no
+					overall deadline.
+					"""
+				required: false
+				type: uint: unit: "seconds"
+			}
 			retry_attempts: {
 				description: "The maximum number of retries to make for failed requests."
 				required:    false
@@ -427,6 +427,55 @@ base: components: sinks: http: configuration: {
 			type: string: {}
 		}
 	}
+	load_shedding: {
+		description: """
+			Configuration for dropping requests to shed load.
+
+			Dropped requests are reported as delivered, and the events they hold are lost. This is only
+			meant for data which is acceptable to lose, and is not recommended otherwise.
+			"""
+		required: false
+		type: object: options: {
+			allow_data_loss: {
+				description: """
+					Acknowledges that requests dropped to shed load are lost.
+
+					This must be set to `true` for requests to be dropped, so that data isn't lost by mistake.
+					"""
+				required: false
+				type: bool: default: false
+			}
+			policy: {
+				description: "Which requests are dropped to shed load."
+				required:    true
+				type: object: options: {
+					drop_rate: {
+						description:   "The fraction of requests to drop, between `0` and `1`."
+						relevant_when: "type = \"sample\""
+						required:      true
+						type: float: {}
+					}
+					max_pending_ms: {
+						description:   "The time to wait for the sink to be able to send a request before dropping it."
+						relevant_when: "type = \"pending\""
+						required:      true
+						type: uint: unit: "milliseconds"
+					}
+					type: {
+						description: "Which requests are dropped."
+						required:    true
+						type: string: enum: {
+							pending: """
+								Drops requests while the sink has not been able to send any for `max_pending_ms`, rather
+								than waiting any longer.
+								"""
+							sample: "Drops a random fraction of the requests, whether or not the sink is keeping up."
+						}
+					}
+				}
+			}
+		}
+	}
 	method: {
 		description: """
 			HTTP method.
@@ -594,6 +643,28 @@ base: components: sinks: http: configuration: {
 					}
 				}
 			}
+			max_response_body_bytes: {
+				description: """
+					The maximum size of a response body to accept, in bytes.
+
+					Requests whose response body is larger than this fail without being retried, instead of
+					buffering the whole response body in memory. If not set, response bodies are not limited.
+					"""
+				required: false
+				type: uint: unit: "bytes"
+			}
+			propagate_deadline: {
+				description: """
+					Whether `timeout_secs` bounds the request as a whole, including all of its retries.
+
+					When enabled, each retry attempt is only given the time remaining out of `timeout_secs`
+					since the request was first attempted, rather than a full `timeout_secs` of its own, and no
+					further attempts are made once that time has run out. This has no effect if
+					`request_deadline_secs` is set, as that already bounds the request as a whole.
+					"""
+				required: false
+				type: bool: {}
+			}
 			rate_limit_duration_secs: {
 				description: "The time window used for the `rate_limit_num` option."
 				required:    false
@@ -610,6 +681,17 @@ base: components: sinks: http: configuration: {
 					unit:    "requests"
 				}
 			}
+			request_deadline_secs: {
+				description: """
+					The maximum amount of time a request can take in total, including all of its retries.
+
+					Once the deadline is reached, the request is aborted and no further retries are attempted.
+					The `timeout_secs` option still bounds each individual attempt. If not set, there is no
+					overall deadline.
+					"""
+				required: false
+				type: uint: unit: "seconds"
+			}
 			retry_attempts: {
 				description: "The maximum number of retries to make for failed requests."
 				required:    false
@@ -658,6 +740,21 @@ base: components: sinks: http: configuration: {
 					unit:    "seconds"
 				}
 			}
+			retry_on_response_codes: {
+				description: """
+					The HTTP status codes of responses to retry, as single status codes such as `429`, or as
+					ranges of them such as `"500-599"`.
+
+					If set, responses are retried if, and only if, their status code is in this list. Otherwise,
+					responses with a `429` or `5xx` status code are retried, except for `501`. Ranges must not
+					overlap.
+					"""
+				required: false
+				type: array: items: type: {
+					string: examples: ["500-599"]
+					uint: examples: [429]
+				}
+			}
 			timeout_secs: {
 				description: """
 					The time a request can take before being aborted.
@@ -91,4 +91,8 @@ components: sinks: http: {
 		}
 		traces: true
 	}
+
+	telemetry: metrics: {
+		requests_shed_total: components.sources.internal_metrics.output.metrics.requests_shed_total
+	}
 }
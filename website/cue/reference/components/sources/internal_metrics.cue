@@ -711,6 +711,12 @@ components: sources: internal_metrics: {
 				status: _status
 			}
 		}
+		in_flight_requests: {
+			description:       "The number of requests currently in flight, from the time they are admitted by the concurrency limits until they complete, including their retries."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		invalid_record_total: {
 			description:       "The total number of invalid records that have been discarded."
 			type:              "counter"
@@ -747,6 +753,39 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		request_retries_total: {
+			description:       "The total number of times requests were retried, tagged with the number of the attempt made by the retry."
+			type:              "counter"
+			default_namespace: "vector"
+			tags: _component_tags & {
+				attempt_number: {
+					description: "The number of the attempt made by the retry, counting the original request as the first. Attempts from the tenth onwards share the `10+` value."
+					required:    true
+					examples: ["2", "3", "10+"]
+				}
+			}
+		}
+		requests_shed_total: {
+			description:       "The total number of requests dropped to shed load, tagged with the load shedding policy which dropped them."
+			type:              "counter"
+			default_namespace: "vector"
+			tags: _component_tags & {
+				policy: {
+					description: "The load shedding policy which dropped the request."
+					required:    true
+					enum: {
+						pending: "Requests dropped while the sink was unable to send any."
+						sample:  "A random fraction of the requests."
+					}
+				}
+			}
+		}
+		retry_exhausted_total: {
+			description:       "The total number of requests which failed after using up all of their retries."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		send_errors_total: {
 			description:       "The total number of errors sending messages."
 			type:              "counter"